@@ -0,0 +1,178 @@
+use std::net::IpAddr;
+
+use axum::body::{to_bytes, Body};
+use axum::extract::{Request, State};
+use axum::http::Method;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use serde_json::{json, Value};
+use tracing::error;
+
+use crate::auth::ApiIdentity;
+use crate::core::audit::{AuditAction, AuditEntry, AuditOutcome};
+use crate::error::AppError;
+use crate::observability::correlation::RequestContext;
+use crate::observability::sanitize_json;
+use crate::state::AppState;
+
+const MAX_BODY_SIZE: usize = 16 * 1024 * 1024;
+
+/// What a REST request does, if it is audited. Reads never are.
+pub fn audited_action(method: &Method, path: &str) -> Option<AuditAction> {
+    if method == Method::GET {
+        return None;
+    }
+    let path = path.trim_end_matches('/');
+    let action = match path {
+        "/v2/admin/join" => AuditAction::Join,
+        "/v2/admin/leave" => AuditAction::Leave,
+        "/v2/admin/backup" => AuditAction::Backup,
+        "/v2/admin/restore" => AuditAction::Restore,
+        "/v2/ln/pay" => AuditAction::Pay,
+        "/v2/onchain/withdraw" => AuditAction::Withdraw,
+        "/v2/mint/spend" => AuditAction::Spend,
+        "/v2/mint/reissue" => AuditAction::Reissue,
+        "/v2/admin/module" => AuditAction::Module,
+        "/v2/admin/auth/lockouts" => AuditAction::ConfigChange,
+        _ if path.starts_with("/v2/admin/approvals/") => AuditAction::Approval,
+        // Replaying and requeueing deliveries doesn't change the config
+        _ if path.ends_with("/replay") || path.ends_with("/requeue") => return None,
+        _ if path.starts_with("/v2/admin/webhooks") || path.starts_with("/v2/admin/api-keys") => {
+            AuditAction::ConfigChange
+        }
+        _ => return None,
+    };
+    Some(action)
+}
+
+/// Append an entry to the audit log. The request already ran, so failing to
+/// record it is logged rather than returned.
+pub async fn record(state: &AppState, entry: AuditEntry) {
+    let action = entry.action;
+    if let Err(e) = state.core.audit_log.append(entry).await {
+        error!(action = ?action, error = ?e, "Failed to append audit record");
+    }
+}
+
+/// Longest argument recorded as a module subcommand
+const MAX_SUBCOMMAND_LEN: usize = 32;
+
+/// What is recorded of a raw module command: the federation, the module and
+/// the subcommand. Module CLIs take notes, invoices and secrets as plain
+/// positional arguments, so every other argument is redacted, as is a first
+/// argument that doesn't look like a subcommand.
+fn module_summary(request: &Value) -> Value {
+    let args = request
+        .get("args")
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    let subcommand = args.first().and_then(Value::as_str).filter(|arg| {
+        arg.len() <= MAX_SUBCOMMAND_LEN
+            && arg
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+    });
+    let redacted = args.len() - usize::from(subcommand.is_some());
+    json!({
+        "federationId": request.get("federationId"),
+        "module": request.get("module"),
+        "subcommand": subcommand,
+        "args": vec!["[REDACTED]"; redacted],
+    })
+}
+
+/// An audit entry for a request, with its body or params sanitized
+pub fn entry(
+    action: AuditAction,
+    endpoint: String,
+    identity: Option<&ApiIdentity>,
+    source_ip: Option<IpAddr>,
+    context: &RequestContext,
+    request: &Value,
+    outcome: AuditOutcome,
+) -> AuditEntry {
+    AuditEntry {
+        action,
+        endpoint,
+        key_id: identity.map(|identity| identity.key_id.clone()),
+        key_name: identity.map(|identity| identity.name.clone()),
+        source_ip: source_ip.map(|ip| ip.to_string()),
+        correlation_id: context.correlation_id.clone(),
+        request_id: context.request_id.clone(),
+        request: match action {
+            AuditAction::Module => module_summary(request),
+            _ => sanitize_json(request),
+        },
+        outcome,
+    }
+}
+
+/// Records audited REST requests with their outcome. Runs inside the auth
+/// layer, so the caller's API identity is known.
+pub async fn audit_middleware(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(action) = audited_action(request.method(), request.uri().path()) else {
+        return next.run(request).await;
+    };
+    let endpoint = format!("{} {}", request.method(), request.uri().path());
+    let identity = request.extensions().get::<ApiIdentity>().cloned();
+    let source_ip = state.auth_guard.client_ip(&request);
+    let context = request
+        .extensions()
+        .get::<RequestContext>()
+        .cloned()
+        .unwrap_or_else(|| RequestContext::new(None));
+
+    let (parts, body) = request.into_parts();
+    let body = match to_bytes(body, MAX_BODY_SIZE).await {
+        Ok(body) => body,
+        Err(e) => {
+            return AppError::validation_error(format!("Failed to read request body: {}", e))
+                .into_response()
+        }
+    };
+    let summary = serde_json::from_slice::<Value>(&body).unwrap_or(Value::Null);
+    let response = next.run(Request::from_parts(parts, Body::from(body))).await;
+
+    let status = response.status();
+    let (response, error) = if status.is_success() {
+        (response, None)
+    } else {
+        // Error bodies are small, read the message out of it
+        let (parts, body) = response.into_parts();
+        let body = to_bytes(body, MAX_BODY_SIZE).await.unwrap_or_default();
+        let message = serde_json::from_slice::<Value>(&body)
+            .ok()
+            .and_then(|body| {
+                body.pointer("/error/message")
+                    .and_then(Value::as_str)
+                    .map(str::to_string)
+            })
+            .or_else(|| status.canonical_reason().map(str::to_string));
+        (Response::from_parts(parts, Body::from(body)), message)
+    };
+
+    let outcome = AuditOutcome {
+        success: status.is_success(),
+        status: status.as_u16(),
+        error,
+    };
+    record(
+        &state,
+        entry(
+            action,
+            endpoint,
+            identity.as_ref(),
+            source_ip,
+            &context,
+            &summary,
+            outcome,
+        ),
+    )
+    .await;
+    response
+}
@@ -0,0 +1,228 @@
+use std::future::Future;
+
+use axum::body::{to_bytes, Body};
+use axum::extract::{Request, State};
+use axum::http::{HeaderValue, Method, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde_json::{json, Value};
+use tracing::error;
+
+use crate::auth::ApiIdentity;
+use crate::core::idempotency::{request_hash, IdempotencyOutcome, StoredResponse};
+use crate::error::{AppError, ErrorCategory};
+use crate::state::AppState;
+
+/// Header carrying the idempotency key of a REST request
+pub const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+/// Set on responses replayed from a previous request with the same key
+pub const IDEMPOTENT_REPLAYED_HEADER: &str = "Idempotent-Replayed";
+/// JSON-RPC param carrying the idempotency key of a WebSocket request
+pub const IDEMPOTENCY_KEY_PARAM: &str = "idempotencyKey";
+
+const MAX_BODY_SIZE: usize = 16 * 1024 * 1024;
+/// Routes whose responses carry secrets, a created API key and its signing
+/// secret. Their responses must not be stored, so they take no idempotency key.
+const SECRET_RESPONSE_ROUTES: [&str; 1] = ["/v2/admin/api-keys"];
+/// JSON-RPC methods of `SECRET_RESPONSE_ROUTES`
+const SECRET_RESPONSE_METHODS: [&str; 1] = ["admin-api-key-create"];
+
+fn secret_response_error() -> AppError {
+    AppError::validation_error(
+        "Idempotency keys are not accepted for requests whose response carries secrets",
+    )
+}
+
+/// Replays the stored response of mutating requests whose `Idempotency-Key`
+/// was seen before, and stores the response of the first one.
+///
+/// The first request runs detached from the connection, so its response is
+/// stored even when the client gives up waiting. Requests without the header
+/// and `GET` requests pass through untouched.
+pub async fn idempotency_middleware(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if request.method() == Method::GET {
+        return next.run(request).await;
+    }
+    let key = match request.headers().get(IDEMPOTENCY_KEY_HEADER) {
+        Some(key) => match key.to_str() {
+            Ok(key) => key.to_string(),
+            Err(_) => {
+                return AppError::validation_error("Idempotency-Key must be visible ASCII")
+                    .into_response()
+            }
+        },
+        None => return next.run(request).await,
+    };
+    if SECRET_RESPONSE_ROUTES.contains(&request.uri().path().trim_end_matches('/')) {
+        return secret_response_error().into_response();
+    }
+
+    // Runs inside the auth layer, keys are scoped to the caller's API key
+    let owner = request
+        .extensions()
+        .get::<ApiIdentity>()
+        .map(|identity| identity.key_id.clone());
+
+    let (parts, body) = request.into_parts();
+    let body = match to_bytes(body, MAX_BODY_SIZE).await {
+        Ok(body) => body,
+        Err(e) => {
+            return AppError::validation_error(format!("Failed to read request body: {}", e))
+                .into_response()
+        }
+    };
+
+    let scope = format!(
+        "{} {} {}",
+        owner.as_deref().unwrap_or_default(),
+        parts.method,
+        parts.uri.path()
+    );
+    let hash = request_hash(&scope, &json_or_text(&body));
+    let guard = match state
+        .core
+        .idempotency_store
+        .begin(owner.as_deref(), &key, hash)
+        .await
+    {
+        Ok(IdempotencyOutcome::Replay(stored)) => return replay_response(stored),
+        Ok(IdempotencyOutcome::Proceed(guard)) => guard,
+        Err(e) => return e.into_response(),
+    };
+
+    let request = Request::from_parts(parts, Body::from(body));
+    let task = tokio::spawn(async move {
+        let response = next.run(request).await;
+
+        let (parts, body) = response.into_parts();
+        let body = match to_bytes(body, MAX_BODY_SIZE).await {
+            Ok(body) => body,
+            Err(e) => {
+                error!(error = ?e, idempotency_key = %key, "Failed to read response body");
+                return AppError::internal_error("Failed to read response body").into_response();
+            }
+        };
+        let stored = StoredResponse {
+            status: parts.status.as_u16(),
+            body: json_or_text(&body),
+        };
+        if let Err(e) = guard.complete(stored).await {
+            error!(error = ?e, idempotency_key = %key, "Failed to store idempotent response");
+        }
+
+        Response::from_parts(parts, Body::from(body))
+    });
+
+    task.await.unwrap_or_else(|e| {
+        error!(error = ?e, "Idempotent request failed");
+        AppError::internal_error("Request failed").into_response()
+    })
+}
+
+fn json_or_text(bytes: &[u8]) -> Value {
+    serde_json::from_slice(bytes)
+        .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(bytes).into_owned()))
+}
+
+fn replay_response(stored: StoredResponse) -> Response {
+    let status = StatusCode::from_u16(stored.status).unwrap_or(StatusCode::OK);
+    let mut response = (status, Json(stored.body)).into_response();
+    response
+        .headers_mut()
+        .insert(IDEMPOTENT_REPLAYED_HEADER, HeaderValue::from_static("true"));
+    response
+}
+
+/// Remove the idempotency key from the params of a JSON-RPC request, so it is
+/// neither passed to the handler nor part of the request hash
+pub fn take_idempotency_key(params: &mut Value) -> Result<Option<String>, AppError> {
+    let Some(key) = params
+        .as_object_mut()
+        .and_then(|params| params.remove(IDEMPOTENCY_KEY_PARAM))
+    else {
+        return Ok(None);
+    };
+
+    match key {
+        Value::String(key) => Ok(Some(key)),
+        _ => Err(AppError::validation_error(format!(
+            "{} must be a string",
+            IDEMPOTENCY_KEY_PARAM
+        ))),
+    }
+}
+
+/// Run a JSON-RPC request at most once per idempotency key, replaying its
+/// result for later requests of the same API key with the same key. Like REST
+/// requests, the first one runs detached from the connection.
+pub async fn run_idempotent<F>(
+    state: &AppState,
+    owner: Option<&str>,
+    key: &str,
+    method: &str,
+    params: &Value,
+    request: F,
+) -> Result<Value, AppError>
+where
+    F: Future<Output = Result<Value, AppError>> + Send + 'static,
+{
+    if SECRET_RESPONSE_METHODS.contains(&method) {
+        return Err(secret_response_error());
+    }
+    let hash = request_hash(
+        &format!("{} ws {}", owner.unwrap_or_default(), method),
+        params,
+    );
+    let guard = match state.core.idempotency_store.begin(owner, key, hash).await? {
+        IdempotencyOutcome::Replay(stored) => return replayed_result(stored),
+        IdempotencyOutcome::Proceed(guard) => guard,
+    };
+
+    let key = key.to_string();
+    let task = tokio::spawn(async move {
+        let result = request.await;
+
+        let stored = match &result {
+            Ok(value) => StoredResponse {
+                status: StatusCode::OK.as_u16(),
+                body: value.clone(),
+            },
+            Err(e) => StoredResponse {
+                status: e.category.status_code().as_u16(),
+                body: json!({
+                    "error": {
+                        "category": e.category,
+                        "message": e.message,
+                    }
+                }),
+            },
+        };
+        if let Err(e) = guard.complete(stored).await {
+            error!(error = ?e, idempotency_key = %key, "Failed to store idempotent response");
+        }
+
+        result
+    });
+
+    task.await.unwrap_or_else(|e| {
+        error!(error = ?e, "Idempotent request failed");
+        Err(AppError::internal_error("Request failed"))
+    })
+}
+
+fn replayed_result(stored: StoredResponse) -> Result<Value, AppError> {
+    if StatusCode::from_u16(stored.status).is_ok_and(|status| status.is_success()) {
+        return Ok(stored.body);
+    }
+
+    let error = &stored.body["error"];
+    let category = serde_json::from_value::<ErrorCategory>(error["category"].clone())
+        .unwrap_or(ErrorCategory::InternalError);
+    let message = error["message"].as_str().unwrap_or_default().to_string();
+    Err(AppError::with_category(category, message))
+}
@@ -1,7 +1,14 @@
+pub mod audit;
+pub mod idempotency;
+pub mod rate_limit;
 pub mod resolvers;
 pub mod rest;
+pub mod subscriptions;
 pub mod websockets;
 
+#[cfg(test)]
+mod tests;
+
 // Re-export commonly used items
 pub use resolvers::LnurlResolver;
 pub use rest as handlers; // For backward compatibility
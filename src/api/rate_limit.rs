@@ -0,0 +1,286 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use axum::extract::{Request, State};
+use axum::http::{header, HeaderValue};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use serde_json::json;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tracing::warn;
+
+use crate::auth::ApiIdentity;
+use crate::error::{AppError, ErrorCategory};
+use crate::metrics::rate_limit_metrics;
+use crate::observability::correlation::RequestContext;
+use crate::observability::{RateLimitConfig, TokenBucketConfig};
+use crate::state::AppState;
+
+/// Which budget a request is counted against
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RouteClass {
+    /// Anything that doesn't move funds
+    Read,
+    /// Payments, withdrawals and ecash spends
+    Spend,
+}
+
+impl RouteClass {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Read => "read",
+            Self::Spend => "spend",
+        }
+    }
+
+    /// Budget of a REST route
+    pub fn of_path(path: &str) -> Self {
+        let path = path.trim_end_matches('/');
+        match path {
+            "/v2/ln/pay" | "/v2/mint/spend" | "/v2/onchain/withdraw" => Self::Spend,
+            // Approving a parked spend executes it
+            _ if path.starts_with("/v2/admin/approvals/") && path.ends_with("/approve") => {
+                Self::Spend
+            }
+            _ => Self::Read,
+        }
+    }
+}
+
+/// Why a request was refused
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limited {
+    /// The client used up its budget for the class
+    Budget {
+        class: RouteClass,
+        retry_after_secs: u64,
+    },
+    /// Too many spends are running already
+    InFlight,
+}
+
+impl Limited {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Budget { .. } => "budget_exhausted",
+            Self::InFlight => "too_many_in_flight",
+        }
+    }
+
+    pub fn retry_after_secs(&self) -> u64 {
+        match self {
+            Self::Budget {
+                retry_after_secs, ..
+            } => *retry_after_secs,
+            Self::InFlight => 1,
+        }
+    }
+
+    pub fn into_app_error(self) -> AppError {
+        let message = match self {
+            Self::Budget { class, .. } => format!(
+                "Rate limit exceeded for {} requests, retry in {} seconds",
+                class.as_str(),
+                self.retry_after_secs()
+            ),
+            Self::InFlight => "Too many payments and withdrawals in flight".to_string(),
+        };
+        AppError::with_category(ErrorCategory::RateLimited, message).with_details(json!({
+            "reason": self.as_str(),
+            "retryAfterSecs": self.retry_after_secs(),
+        }))
+    }
+}
+
+/// Who a bucket belongs to
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum ClientKey {
+    Identity(String),
+    Ip(IpAddr),
+}
+
+#[derive(Debug, Clone)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn refill(&mut self, config: &TokenBucketConfig, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens =
+            (self.tokens + elapsed * config.requests_per_sec).min(f64::from(config.burst));
+        self.updated = now;
+    }
+
+    /// A full bucket is no different from a new one
+    fn is_full(&self, config: &TokenBucketConfig, now: Instant) -> bool {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens + elapsed * config.requests_per_sec >= f64::from(config.burst)
+    }
+}
+
+/// Token buckets per API identity and per source IP, separate for reads and
+/// spends, and a global cap on spends in flight
+#[derive(Debug)]
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    buckets: Mutex<HashMap<(RouteClass, ClientKey), Bucket>>,
+    in_flight: Arc<Semaphore>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            in_flight: Arc::new(Semaphore::new(config.max_in_flight_spends)),
+            config,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn config(&self) -> &RateLimitConfig {
+        &self.config
+    }
+
+    /// Take a token from the buckets of the identity and the IP. Spends also
+    /// get a permit, which counts them as in flight until dropped.
+    pub fn acquire(
+        &self,
+        identity: Option<&str>,
+        ip: Option<IpAddr>,
+        class: RouteClass,
+        now: Instant,
+    ) -> Result<Option<OwnedSemaphorePermit>, Limited> {
+        if !self.config.enabled {
+            return Ok(None);
+        }
+        let permit = match class {
+            RouteClass::Read => None,
+            RouteClass::Spend => Some(
+                self.in_flight
+                    .clone()
+                    .try_acquire_owned()
+                    .map_err(|_| Limited::InFlight)?,
+            ),
+        };
+
+        let config = self.bucket_config(class);
+        let keys = identity
+            .map(|identity| ClientKey::Identity(identity.to_string()))
+            .into_iter()
+            .chain(ip.map(ClientKey::Ip))
+            .map(|key| (class, key))
+            .collect::<Vec<_>>();
+        let mut buckets = self
+            .buckets
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        // Refused when any bucket is empty, without taking from the others
+        let mut wait_secs = None::<f64>;
+        for key in &keys {
+            if !buckets.contains_key(key) && buckets.len() >= self.config.max_tracked_clients {
+                self.evict(&mut buckets, now);
+            }
+            let bucket = buckets.entry(key.clone()).or_insert(Bucket {
+                tokens: f64::from(config.burst),
+                updated: now,
+            });
+            bucket.refill(&config, now);
+            if bucket.tokens < 1.0 {
+                let wait = (1.0 - bucket.tokens) / config.requests_per_sec;
+                wait_secs = Some(wait_secs.map_or(wait, |max| max.max(wait)));
+            }
+        }
+        if let Some(wait_secs) = wait_secs {
+            return Err(Limited::Budget {
+                class,
+                // Saturates for a zero refill rate
+                retry_after_secs: (wait_secs.ceil() as u64).max(1),
+            });
+        }
+        for key in &keys {
+            if let Some(bucket) = buckets.get_mut(key) {
+                bucket.tokens -= 1.0;
+            }
+        }
+        Ok(permit)
+    }
+
+    fn bucket_config(&self, class: RouteClass) -> TokenBucketConfig {
+        match class {
+            RouteClass::Read => self.config.read,
+            RouteClass::Spend => self.config.spend,
+        }
+    }
+
+    /// Forget full buckets, or the one used longest ago if none is full
+    fn evict(&self, buckets: &mut HashMap<(RouteClass, ClientKey), Bucket>, now: Instant) {
+        buckets.retain(|(class, _), bucket| !bucket.is_full(&self.bucket_config(*class), now));
+        if buckets.len() >= self.config.max_tracked_clients {
+            let oldest = buckets
+                .iter()
+                .min_by_key(|(_, bucket)| bucket.updated)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                buckets.remove(&oldest);
+            }
+        }
+    }
+}
+
+/// Refuses REST requests of clients over their budget, and spends beyond the
+/// in-flight cap. Runs after authentication, so requests are counted against
+/// their API identity as well as their IP.
+pub async fn rate_limit_middleware(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let class = RouteClass::of_path(request.uri().path());
+    let identity = request
+        .extensions()
+        .get::<ApiIdentity>()
+        .map(|identity| identity.key_id.clone());
+    let ip = state.auth_guard.client_ip(&request);
+
+    match state
+        .rate_limiter
+        .acquire(identity.as_deref(), ip, class, Instant::now())
+    {
+        Ok(permit) => {
+            let response = next.run(request).await;
+            drop(permit);
+            response
+        }
+        Err(limited) => {
+            warn!(
+                method = %request.method(),
+                path = %request.uri().path(),
+                class = class.as_str(),
+                reason = limited.as_str(),
+                key_id = identity.as_deref(),
+                remote_addr = ip.map(|ip| ip.to_string()),
+                "Request rate limited"
+            );
+            rate_limit_metrics::record_limited(class.as_str(), limited.as_str());
+
+            let context = request
+                .extensions()
+                .get::<RequestContext>()
+                .cloned()
+                .unwrap_or_else(|| RequestContext::new(None));
+            let mut response = limited
+                .into_app_error()
+                .with_context(context)
+                .into_response();
+            response.headers_mut().insert(
+                header::RETRY_AFTER,
+                HeaderValue::from(limited.retry_after_secs()),
+            );
+            response
+        }
+    }
+}
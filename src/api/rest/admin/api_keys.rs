@@ -0,0 +1,62 @@
+use anyhow::anyhow;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::auth::api_keys::{ApiKeyInfo, CreateApiKeyRequest, CreatedApiKey};
+use crate::error::AppError;
+use crate::state::AppState;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiKeyIdParams {
+    pub key_id: String,
+}
+
+fn parse_params<T: serde::de::DeserializeOwned>(v: Value) -> Result<T, AppError> {
+    serde_json::from_value::<T>(v)
+        .map_err(|e| AppError::new(StatusCode::BAD_REQUEST, anyhow!("Invalid request: {}", e)))
+}
+
+pub async fn handle_ws_list(state: AppState) -> Result<Value, AppError> {
+    Ok(json!(state.api_keys.list()))
+}
+
+pub async fn handle_ws_create(state: AppState, v: Value) -> Result<Value, AppError> {
+    let req = parse_params::<CreateApiKeyRequest>(v)?;
+    let created = state.api_keys.create(req).await?;
+    Ok(json!(created))
+}
+
+pub async fn handle_ws_revoke(state: AppState, v: Value) -> Result<Value, AppError> {
+    let params = parse_params::<ApiKeyIdParams>(v)?;
+    let revoked = state.api_keys.revoke(&params.key_id).await?;
+    Ok(json!(revoked))
+}
+
+#[axum_macros::debug_handler]
+pub async fn handle_rest_list(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<ApiKeyInfo>>, AppError> {
+    Ok(Json(state.api_keys.list()))
+}
+
+#[axum_macros::debug_handler]
+pub async fn handle_rest_create(
+    State(state): State<AppState>,
+    Json(req): Json<CreateApiKeyRequest>,
+) -> Result<Json<CreatedApiKey>, AppError> {
+    let created = state.api_keys.create(req).await?;
+    Ok(Json(created))
+}
+
+#[axum_macros::debug_handler]
+pub async fn handle_rest_revoke(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<ApiKeyInfo>, AppError> {
+    let revoked = state.api_keys.revoke(&id).await?;
+    Ok(Json(revoked))
+}
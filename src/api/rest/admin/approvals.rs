@@ -0,0 +1,104 @@
+use anyhow::anyhow;
+use axum::extract::{Extension, Path, Query, State};
+use axum::http::StatusCode;
+use axum::Json;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::api::LnurlResolver;
+use crate::core::approvals::{ApprovalRequest, ApprovalStatus};
+use crate::error::AppError;
+use crate::observability::correlation::RequestContext;
+use crate::state::AppState;
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ApprovalListQuery {
+    /// Only list requests in this status
+    #[serde(default)]
+    pub status: Option<ApprovalStatus>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApprovalIdParams {
+    pub approval_id: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct RejectRequest {
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+fn parse_params<T: serde::de::DeserializeOwned>(v: Value) -> Result<T, AppError> {
+    serde_json::from_value::<T>(v)
+        .map_err(|e| AppError::new(StatusCode::BAD_REQUEST, anyhow!("Invalid request: {}", e)))
+}
+
+pub async fn handle_ws_list(state: AppState, v: Value) -> Result<Value, AppError> {
+    let query = if v.is_null() {
+        ApprovalListQuery::default()
+    } else {
+        parse_params::<ApprovalListQuery>(v)?
+    };
+    let approvals = state.core.list_approvals(query.status).await;
+    Ok(json!(approvals))
+}
+
+pub async fn handle_ws_approve(state: AppState, v: Value) -> Result<Value, AppError> {
+    let params = parse_params::<ApprovalIdParams>(v)?;
+    let context = RequestContext::new(None);
+    let resolver = LnurlResolver::new();
+    let approval = state
+        .core
+        .approve_request(&params.approval_id, context, Some(&resolver))
+        .await?;
+    Ok(json!(approval))
+}
+
+/// Takes the `approvalId` next to an optional `reason`
+pub async fn handle_ws_reject(state: AppState, v: Value) -> Result<Value, AppError> {
+    let params = parse_params::<ApprovalIdParams>(v.clone())?;
+    let req = parse_params::<RejectRequest>(v)?;
+    let context = RequestContext::new(None);
+    let approval = state
+        .core
+        .reject_request(&params.approval_id, req.reason, context)
+        .await?;
+    Ok(json!(approval))
+}
+
+#[axum_macros::debug_handler]
+pub async fn handle_rest_list(
+    State(state): State<AppState>,
+    Query(query): Query<ApprovalListQuery>,
+) -> Result<Json<Vec<ApprovalRequest>>, AppError> {
+    let approvals = state.core.list_approvals(query.status).await;
+    Ok(Json(approvals))
+}
+
+#[axum_macros::debug_handler]
+pub async fn handle_rest_approve(
+    State(state): State<AppState>,
+    Extension(context): Extension<RequestContext>,
+    Path(id): Path<String>,
+) -> Result<Json<ApprovalRequest>, AppError> {
+    let resolver = LnurlResolver::new();
+    let approval = state
+        .core
+        .approve_request(&id, context, Some(&resolver))
+        .await?;
+    Ok(Json(approval))
+}
+
+#[axum_macros::debug_handler]
+pub async fn handle_rest_reject(
+    State(state): State<AppState>,
+    Extension(context): Extension<RequestContext>,
+    Path(id): Path<String>,
+    req: Option<Json<RejectRequest>>,
+) -> Result<Json<ApprovalRequest>, AppError> {
+    let reason = req.and_then(|Json(req)| req.reason);
+    let approval = state.core.reject_request(&id, reason, context).await?;
+    Ok(Json(approval))
+}
@@ -0,0 +1,84 @@
+use anyhow::anyhow;
+use axum::extract::{Query, State};
+use axum::http::{header, StatusCode};
+use axum::response::IntoResponse;
+use axum::Json;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::core::audit::{AuditPage, AuditVerification};
+use crate::error::AppError;
+use crate::state::AppState;
+
+/// Records exported per read of the log
+const EXPORT_PAGE_SIZE: usize = 1000;
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditQuery {
+    /// Return records after this sequence, from the first when omitted
+    pub after_sequence: Option<u64>,
+    pub limit: Option<usize>,
+}
+
+async fn _records(state: AppState, query: AuditQuery) -> Result<AuditPage, AppError> {
+    let page = state
+        .core
+        .audit_log
+        .records(query.after_sequence, query.limit)
+        .await?;
+    Ok(page)
+}
+
+pub async fn handle_ws(state: AppState, v: Value) -> Result<Value, AppError> {
+    let v = if v.is_null() { json!({}) } else { v };
+    let query = serde_json::from_value::<AuditQuery>(v)
+        .map_err(|e| AppError::new(StatusCode::BAD_REQUEST, anyhow!("Invalid request: {}", e)))?;
+    let page = _records(state, query).await?;
+    Ok(json!(page))
+}
+
+pub async fn handle_ws_verify(state: AppState) -> Result<Value, AppError> {
+    Ok(json!(state.core.audit_log.verify().await))
+}
+
+#[axum_macros::debug_handler]
+pub async fn handle_rest(
+    State(state): State<AppState>,
+    Query(query): Query<AuditQuery>,
+) -> Result<Json<AuditPage>, AppError> {
+    let page = _records(state, query).await?;
+    Ok(Json(page))
+}
+
+/// Every record as newline-delimited JSON, the input of `fmcd verify-audit`
+#[axum_macros::debug_handler]
+pub async fn handle_rest_export(
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, AppError> {
+    let mut body = String::new();
+    let mut after = 0;
+    loop {
+        let page = state
+            .core
+            .audit_log
+            .records(Some(after), Some(EXPORT_PAGE_SIZE))
+            .await?;
+        for record in &page.records {
+            body.push_str(&serde_json::to_string(record)?);
+            body.push('\n');
+        }
+        match page.records.last() {
+            Some(record) if page.records.len() == EXPORT_PAGE_SIZE => after = record.sequence,
+            _ => break,
+        }
+    }
+    Ok(([(header::CONTENT_TYPE, "application/x-ndjson")], body))
+}
+
+#[axum_macros::debug_handler]
+pub async fn handle_rest_verify(
+    State(state): State<AppState>,
+) -> Result<Json<AuditVerification>, AppError> {
+    Ok(Json(state.core.audit_log.verify().await))
+}
@@ -0,0 +1,25 @@
+use anyhow::anyhow;
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::Json;
+use serde_json::{json, Value};
+
+use crate::error::AppError;
+use crate::events::journal::{JournalPage, JournalQuery};
+use crate::state::AppState;
+
+pub async fn handle_ws(state: AppState, v: Value) -> Result<Value, AppError> {
+    let query = serde_json::from_value::<JournalQuery>(v)
+        .map_err(|e| AppError::new(StatusCode::BAD_REQUEST, anyhow!("Invalid request: {}", e)))?;
+    let page = state.core.query_events(query).await?;
+    Ok(json!(page))
+}
+
+#[axum_macros::debug_handler]
+pub async fn handle_rest(
+    State(state): State<AppState>,
+    Query(query): Query<JournalQuery>,
+) -> Result<Json<JournalPage>, AppError> {
+    let page = state.core.query_events(query).await?;
+    Ok(Json(page))
+}
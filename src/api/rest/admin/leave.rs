@@ -0,0 +1,29 @@
+use anyhow::anyhow;
+use axum::extract::{Extension, State};
+use axum::http::StatusCode;
+use axum::Json;
+use serde_json::{json, Value};
+
+use crate::core::{LeaveFederationRequest, LeaveFederationResponse};
+use crate::error::AppError;
+use crate::observability::correlation::RequestContext;
+use crate::state::AppState;
+
+pub async fn handle_ws(state: AppState, v: Value) -> Result<Value, AppError> {
+    let req = serde_json::from_value::<LeaveFederationRequest>(v)
+        .map_err(|e| AppError::new(StatusCode::BAD_REQUEST, anyhow!("Invalid request: {}", e)))?;
+    // TODO: WebSocket requests should get RequestContext from middleware
+    let context = Some(RequestContext::new(None));
+    let response = state.core.leave_federation(req, context).await?;
+    Ok(json!(response))
+}
+
+#[axum_macros::debug_handler]
+pub async fn handle_rest(
+    State(state): State<AppState>,
+    Extension(context): Extension<RequestContext>,
+    Json(req): Json<LeaveFederationRequest>,
+) -> Result<Json<LeaveFederationResponse>, AppError> {
+    let response = state.core.leave_federation(req, Some(context)).await?;
+    Ok(Json(response))
+}
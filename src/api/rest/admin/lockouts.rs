@@ -0,0 +1,30 @@
+use axum::extract::State;
+use axum::Json;
+use chrono::Utc;
+use serde_json::{json, Value};
+
+use crate::auth::lockout::TrackedClient;
+use crate::error::AppError;
+use crate::state::AppState;
+
+pub async fn handle_ws_list(state: AppState) -> Result<Value, AppError> {
+    Ok(json!(state.auth_guard.tracked_clients(Utc::now())))
+}
+
+pub async fn handle_ws_clear(state: AppState) -> Result<Value, AppError> {
+    let cleared = state.auth_guard.clear();
+    Ok(json!({ "cleared": cleared }))
+}
+
+#[axum_macros::debug_handler]
+pub async fn handle_rest_list(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<TrackedClient>>, AppError> {
+    Ok(Json(state.auth_guard.tracked_clients(Utc::now())))
+}
+
+#[axum_macros::debug_handler]
+pub async fn handle_rest_clear(State(state): State<AppState>) -> Result<Json<Value>, AppError> {
+    let cleared = state.auth_guard.clear();
+    Ok(Json(json!({ "cleared": cleared })))
+}
@@ -1,9 +1,16 @@
+pub mod api_keys;
+pub mod approvals;
+pub mod audit;
 pub mod backup;
 pub mod config;
+pub mod events;
 pub mod federations;
 pub mod info;
 pub mod join;
+pub mod leave;
+pub mod lockouts;
 pub mod module;
 pub mod operations;
 pub mod restore;
 pub mod version;
+pub mod webhooks;
@@ -1,3 +1,5 @@
+use std::ffi::OsString;
+
 use anyhow::anyhow;
 use axum::extract::State;
 use axum::http::StatusCode;
@@ -6,14 +8,13 @@ use fedimint_client::ClientHandleArc;
 use fedimint_core::config::FederationId;
 use fedimint_core::core::{ModuleInstanceId, ModuleKind};
 use serde::Deserialize;
-use serde_json::{json, Value};
+use serde_json::Value;
 use tracing::info;
 
-use crate::error::AppError;
+use crate::error::{AppError, ErrorCategory};
 use crate::state::AppState;
 
 #[derive(Debug, Clone, Deserialize)]
-#[allow(unused)]
 pub enum ModuleSelector {
     Id(ModuleInstanceId),
     Kind(ModuleKind),
@@ -27,32 +28,218 @@ pub struct ModuleRequest {
     pub federation_id: FederationId,
 }
 
-async fn _module(_client: ClientHandleArc, req: ModuleRequest) -> Result<(), AppError> {
+/// Resolve the selector to a module instance of the client, for a kind this is
+/// the first instance (the primary module if it has that kind)
+fn resolve_module(
+    client: &ClientHandleArc,
+    selector: &ModuleSelector,
+) -> Result<ModuleInstanceId, AppError> {
+    match selector {
+        ModuleSelector::Id(id) => Ok(*id),
+        ModuleSelector::Kind(kind) => client.get_first_instance(kind).ok_or_else(|| {
+            AppError::with_category(
+                ErrorCategory::NotFound,
+                format!("No module of kind {} found", kind),
+            )
+        }),
+    }
+}
+
+/// Flags clap answers by printing and exiting the process
+const EXITING_FLAGS: &[&str] = &["-h", "--help", "-V", "--version"];
+
+/// Mirrors of the CLI commands of the fedimint 0.8 client modules. The modules
+/// parse their args with `Opts::parse_from`, which exits the process on
+/// anything it can't parse, so args are checked against these first.
+///
+/// Copied from `src/cli.rs` of fedimint-mint-client, fedimint-ln-client and
+/// fedimint-wallet-client 0.8.2. Compare them again whenever those crates are
+/// bumped, a command that drifts is either refused here or parsed differently
+/// by the module.
+mod cli {
+    use bitcoin::address::NetworkUnchecked;
+    use clap::{Parser, Subcommand};
+    use fedimint_core::core::OperationId;
+    use fedimint_core::secp256k1::PublicKey;
+    use fedimint_core::util::SafeUrl;
+    use fedimint_core::Amount;
+    use fedimint_mint_client::OOBNotes;
+    use fedimint_wallet_client::client_db::TweakIdx;
+
+    #[derive(Parser)]
+    pub enum MintOpts {
+        Reissue { notes: OOBNotes },
+    }
+
+    #[derive(Parser)]
+    pub enum LnOpts {
+        Invoice {
+            amount: Amount,
+            #[clap(long, default_value = "")]
+            description: String,
+            #[clap(long)]
+            expiry_time: Option<u64>,
+            #[clap(long)]
+            gateway_id: Option<PublicKey>,
+            #[clap(long)]
+            force_internal: bool,
+        },
+        Pay {
+            payment_info: String,
+            #[clap(long)]
+            amount: Option<Amount>,
+            #[clap(long)]
+            lnurl_comment: Option<String>,
+            #[clap(long)]
+            finish_in_background: bool,
+            #[clap(long)]
+            gateway_id: Option<PublicKey>,
+            #[clap(long)]
+            force_internal: bool,
+        },
+        #[clap(subcommand)]
+        Lnurl(LnurlCommands),
+    }
+
+    #[derive(Subcommand)]
+    pub enum LnurlCommands {
+        Register {
+            server_url: SafeUrl,
+            #[clap(long)]
+            meta: Option<String>,
+            #[clap(long, default_value = "Fedimint LNURL Pay")]
+            description: String,
+        },
+        List,
+        Invoices {
+            payment_code_idx: u64,
+        },
+        InvoiceDetails {
+            operation_id: OperationId,
+        },
+        AwaitInvoicePaid {
+            operation_id: OperationId,
+        },
+    }
+
+    #[derive(Parser)]
+    pub enum WalletOpts {
+        AwaitDeposit {
+            addr: Option<String>,
+            #[clap(long)]
+            operation_id: Option<OperationId>,
+            #[clap(long)]
+            tweak_idx: Option<TweakIdx>,
+            #[clap(long, default_value = "1")]
+            num: usize,
+        },
+        GetConsensusBlockCount,
+        GetBitcoinRpcKind {
+            peer_id: u16,
+        },
+        GetBitcoinRpcConfig,
+        NewDepositAddress,
+        RecheckDepositAddress {
+            addr: Option<bitcoin::Address<NetworkUnchecked>>,
+            #[clap(long)]
+            operation_id: Option<OperationId>,
+            #[clap(long)]
+            tweak_idx: Option<TweakIdx>,
+        },
+    }
+}
+
+/// Check module CLI args before handing them to the module, only commands of
+/// the mint, ln and wallet modules with well-formed arguments are let through,
+/// except for `ln pay`
+pub fn validate_args(kind: &ModuleKind, args: &[String]) -> Result<(), AppError> {
+    if let Some(flag) = args
+        .iter()
+        .find(|arg| EXITING_FLAGS.contains(&arg.as_str()))
+    {
+        return Err(AppError::validation_error(format!(
+            "Argument {} is not supported",
+            flag
+        )));
+    }
+
+    let argv = std::iter::once(kind.as_str()).chain(args.iter().map(String::as_str));
+    let parsed = match kind.as_str() {
+        "mint" => cli::MintOpts::try_parse_from(argv).map(drop),
+        "ln" => match cli::LnOpts::try_parse_from(argv) {
+            // Paying here would skip the spending policy, approvals,
+            // idempotency and the in-flight spend cap
+            Ok(cli::LnOpts::Pay { .. }) => {
+                return Err(AppError::validation_error(
+                    "ln pay is not supported, pay through /v2/ln/pay",
+                ))
+            }
+            parsed => parsed.map(drop),
+        },
+        "wallet" => cli::WalletOpts::try_parse_from(argv).map(drop),
+        other => {
+            return Err(AppError::validation_error(format!(
+                "Module kind {} has no supported commands",
+                other
+            )))
+        }
+    };
+    parsed.map_err(|e| {
+        AppError::validation_error(format!("Invalid {} module command", kind))
+            .with_details(serde_json::json!({ "usage": e.to_string() }))
+    })
+}
+
+async fn _module(client: ClientHandleArc, req: ModuleRequest) -> Result<Value, AppError> {
+    let module_instance_id = resolve_module(&client, &req.module)?;
+    let kind = client
+        .config()
+        .await
+        .modules
+        .get(&module_instance_id)
+        .map(|module| module.kind.clone())
+        .ok_or_else(|| {
+            AppError::with_category(
+                ErrorCategory::NotFound,
+                format!("No module with id {} found", module_instance_id),
+            )
+        })?;
+    validate_args(&kind, &req.args)?;
+
+    // Args carry notes and invoices, only the subcommand is logged once
+    // the args are known to start with one
     info!(
-        "Module request: module: {:?}, args: {:?}, federation_id: {:?}",
-        req.module, req.args, req.federation_id
+        "Module request: module: {:?}, command: {:?}, federation_id: {:?}",
+        req.module,
+        req.args.first(),
+        req.federation_id
     );
-    // TODO: Figure out how to impl this
-    Err(AppError::new(
-        StatusCode::INTERNAL_SERVER_ERROR,
-        anyhow!("Not implemented"),
-    ))
+    let module = client
+        .get_module_client_dyn(module_instance_id)
+        .map_err(|e| AppError::with_category(ErrorCategory::NotFound, e.to_string()))?;
+
+    let args = req.args.into_iter().map(OsString::from).collect::<Vec<_>>();
+    module.handle_cli_command(&args).await.map_err(|e| {
+        AppError::with_category(
+            ErrorCategory::ValidationError,
+            format!("Module {} command failed: {}", module_instance_id, e),
+        )
+    })
 }
 
 pub async fn handle_ws(state: AppState, v: Value) -> Result<Value, AppError> {
     let v = serde_json::from_value::<ModuleRequest>(v)
         .map_err(|e| AppError::new(StatusCode::BAD_REQUEST, anyhow!("Invalid request: {}", e)))?;
     let client = state.get_client(v.federation_id).await?;
-    _module(client, v).await?;
-    Ok(json!(()))
+    _module(client, v).await
 }
 
 #[axum_macros::debug_handler]
 pub async fn handle_rest(
     State(state): State<AppState>,
     Json(req): Json<ModuleRequest>,
-) -> Result<Json<()>, AppError> {
+) -> Result<Json<Value>, AppError> {
     let client = state.get_client(req.federation_id).await?;
-    _module(client, req).await?;
-    Ok(Json(()))
+    let output = _module(client, req).await?;
+    Ok(Json(output))
 }
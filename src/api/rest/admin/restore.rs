@@ -1,33 +1,43 @@
 use anyhow::anyhow;
-use axum::extract::State;
+use axum::extract::{Extension, State};
 use axum::http::StatusCode;
 use axum::Json;
-use fedimint_client::ClientHandleArc;
 use serde_json::{json, Value};
 
+use crate::core::operations::RestoreStatus;
+use crate::core::RestoreRequest;
 use crate::error::AppError;
+use crate::observability::correlation::RequestContext;
 use crate::state::AppState;
 
-async fn _restore(_client: ClientHandleArc, _v: Value) -> Result<(), AppError> {
-    // TODO: unimplemented in cli
-    Err(AppError::new(
-        StatusCode::INTERNAL_SERVER_ERROR,
-        anyhow!("Not implemented"),
-    ))
+pub async fn handle_ws(state: AppState, v: Value) -> Result<Value, AppError> {
+    let req = serde_json::from_value::<RestoreRequest>(v)
+        .map_err(|e| AppError::new(StatusCode::BAD_REQUEST, anyhow!("Invalid request: {}", e)))?;
+    // TODO: WebSocket requests should get RequestContext from middleware
+    let context = Some(RequestContext::new(None));
+    let status = state.core.restore(req, context).await?;
+    Ok(json!(status))
 }
 
-pub async fn handle_ws(_state: AppState, _v: Value) -> Result<Value, AppError> {
-    // let client = state.get_client(v).await?;
-    // _restore(client, v).await?;
-    Ok(json!(null))
+pub async fn handle_ws_status(state: AppState) -> Result<Value, AppError> {
+    let status = state.core.restore_status().await?;
+    Ok(json!(status))
 }
 
 #[axum_macros::debug_handler]
 pub async fn handle_rest(
-    State(_state): State<AppState>,
-    Json(_req): Json<Value>,
-) -> Result<Json<()>, AppError> {
-    // let client = state.get_client(None).await?;
-    // _restore(client, req).await?;
-    Ok(Json(()))
+    State(state): State<AppState>,
+    Extension(context): Extension<RequestContext>,
+    Json(req): Json<RestoreRequest>,
+) -> Result<Json<RestoreStatus>, AppError> {
+    let status = state.core.restore(req, Some(context)).await?;
+    Ok(Json(status))
+}
+
+#[axum_macros::debug_handler]
+pub async fn handle_rest_status(
+    State(state): State<AppState>,
+) -> Result<Json<RestoreStatus>, AppError> {
+    let status = state.core.restore_status().await?;
+    Ok(Json(status))
 }
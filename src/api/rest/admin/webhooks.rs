@@ -0,0 +1,184 @@
+use anyhow::anyhow;
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::Json;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::core::{CreateWebhookRequest, UpdateWebhookRequest};
+use crate::error::AppError;
+use crate::state::AppState;
+use crate::webhooks::{
+    DeliveryLogQuery, ReplaySelector, WebhookDelivery, WebhookDeliveryLogEntry, WebhookEndpointInfo,
+};
+
+#[derive(Debug, Deserialize)]
+pub struct WebhookIdParams {
+    pub id: String,
+}
+
+fn parse_params<T: serde::de::DeserializeOwned>(v: Value) -> Result<T, AppError> {
+    serde_json::from_value::<T>(v)
+        .map_err(|e| AppError::new(StatusCode::BAD_REQUEST, anyhow!("Invalid request: {}", e)))
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RequeueRequest {
+    /// Deliveries to requeue, all dead-lettered deliveries when omitted
+    #[serde(default)]
+    pub delivery_ids: Option<Vec<String>>,
+}
+
+pub async fn handle_ws_list(state: AppState) -> Result<Value, AppError> {
+    let endpoints = state.core.list_webhooks().await?;
+    Ok(json!(endpoints))
+}
+
+pub async fn handle_ws_create(state: AppState, v: Value) -> Result<Value, AppError> {
+    let req = parse_params::<CreateWebhookRequest>(v)?;
+    let endpoint = state.core.create_webhook(req).await?;
+    Ok(json!(endpoint))
+}
+
+/// Takes the endpoint `id` next to the fields to update
+pub async fn handle_ws_update(state: AppState, v: Value) -> Result<Value, AppError> {
+    let params = parse_params::<WebhookIdParams>(v.clone())?;
+    let req = parse_params::<UpdateWebhookRequest>(v)?;
+    let endpoint = state.core.update_webhook(&params.id, req).await?;
+    Ok(json!(endpoint))
+}
+
+pub async fn handle_ws_delete(state: AppState, v: Value) -> Result<Value, AppError> {
+    let params = parse_params::<WebhookIdParams>(v)?;
+    let endpoint = state.core.delete_webhook(&params.id).await?;
+    Ok(json!(endpoint))
+}
+
+/// Takes the endpoint `id` next to the query filters
+pub async fn handle_ws_deliveries(state: AppState, v: Value) -> Result<Value, AppError> {
+    let params = parse_params::<WebhookIdParams>(v.clone())?;
+    let query = parse_params::<DeliveryLogQuery>(v)?;
+    let deliveries = state.core.webhook_deliveries(&params.id, query).await?;
+    Ok(json!(deliveries))
+}
+
+/// Takes the endpoint `id` next to the event id or time window
+pub async fn handle_ws_replay(state: AppState, v: Value) -> Result<Value, AppError> {
+    let params = parse_params::<WebhookIdParams>(v.clone())?;
+    let selector = parse_params::<ReplaySelector>(v)?;
+    let replayed = state.core.replay_webhook(&params.id, selector).await?;
+    Ok(json!(replayed))
+}
+
+pub async fn handle_ws_dead_letters(state: AppState) -> Result<Value, AppError> {
+    let dead_letters = state.core.webhook_dead_letters().await?;
+    Ok(json!(dead_letters))
+}
+
+pub async fn handle_ws_requeue(state: AppState, v: Value) -> Result<Value, AppError> {
+    let req = if v.is_null() {
+        RequeueRequest::default()
+    } else {
+        parse_params::<RequeueRequest>(v)?
+    };
+    let requeued = state
+        .core
+        .requeue_webhook_deliveries(req.delivery_ids)
+        .await?;
+    Ok(json!(requeued))
+}
+
+#[axum_macros::debug_handler]
+pub async fn handle_rest_list(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<WebhookEndpointInfo>>, AppError> {
+    let endpoints = state.core.list_webhooks().await?;
+    Ok(Json(endpoints))
+}
+
+#[axum_macros::debug_handler]
+pub async fn handle_rest_create(
+    State(state): State<AppState>,
+    Json(req): Json<CreateWebhookRequest>,
+) -> Result<Json<WebhookEndpointInfo>, AppError> {
+    let endpoint = state.core.create_webhook(req).await?;
+    Ok(Json(endpoint))
+}
+
+#[axum_macros::debug_handler]
+pub async fn handle_rest_update(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(req): Json<UpdateWebhookRequest>,
+) -> Result<Json<WebhookEndpointInfo>, AppError> {
+    let endpoint = state.core.update_webhook(&id, req).await?;
+    Ok(Json(endpoint))
+}
+
+#[axum_macros::debug_handler]
+pub async fn handle_rest_enable(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<WebhookEndpointInfo>, AppError> {
+    let endpoint = state.core.set_webhook_enabled(&id, true).await?;
+    Ok(Json(endpoint))
+}
+
+#[axum_macros::debug_handler]
+pub async fn handle_rest_disable(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<WebhookEndpointInfo>, AppError> {
+    let endpoint = state.core.set_webhook_enabled(&id, false).await?;
+    Ok(Json(endpoint))
+}
+
+#[axum_macros::debug_handler]
+pub async fn handle_rest_delete(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<WebhookEndpointInfo>, AppError> {
+    let endpoint = state.core.delete_webhook(&id).await?;
+    Ok(Json(endpoint))
+}
+
+#[axum_macros::debug_handler]
+pub async fn handle_rest_deliveries(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(query): Query<DeliveryLogQuery>,
+) -> Result<Json<Vec<WebhookDeliveryLogEntry>>, AppError> {
+    let deliveries = state.core.webhook_deliveries(&id, query).await?;
+    Ok(Json(deliveries))
+}
+
+#[axum_macros::debug_handler]
+pub async fn handle_rest_replay(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(selector): Json<ReplaySelector>,
+) -> Result<Json<Vec<WebhookDelivery>>, AppError> {
+    let replayed = state.core.replay_webhook(&id, selector).await?;
+    Ok(Json(replayed))
+}
+
+#[axum_macros::debug_handler]
+pub async fn handle_rest_dead_letters(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<WebhookDelivery>>, AppError> {
+    let dead_letters = state.core.webhook_dead_letters().await?;
+    Ok(Json(dead_letters))
+}
+
+#[axum_macros::debug_handler]
+pub async fn handle_rest_requeue(
+    State(state): State<AppState>,
+    Json(req): Json<RequeueRequest>,
+) -> Result<Json<Vec<WebhookDelivery>>, AppError> {
+    let requeued = state
+        .core
+        .requeue_webhook_deliveries(req.delivery_ids)
+        .await?;
+    Ok(Json(requeued))
+}
@@ -4,36 +4,60 @@ use axum::http::StatusCode;
 use axum::Json;
 use fedimint_client::ClientHandleArc;
 use fedimint_core::config::FederationId;
+use fedimint_core::Amount;
 use fedimint_ln_client::LightningClientModule;
 use serde::Deserialize;
 use serde_json::{json, Value};
 
+use crate::core::services::{GatewayCandidate, GatewaySelector};
 use crate::error::AppError;
 use crate::state::AppState;
 
+/// Amount the gateway scores are computed for unless the request sets one
+const DEFAULT_SCORE_AMOUNT_MSAT: u64 = 1_000_000;
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ListGatewaysRequest {
     pub federation_id: FederationId,
+    /// Payment amount the fees in the gateway scores are computed for
+    pub amount_msat: Option<Amount>,
 }
 
-async fn _gateways(client: ClientHandleArc) -> Result<Value, AppError> {
+async fn _gateways(
+    selector: &GatewaySelector,
+    client: ClientHandleArc,
+    amount: Amount,
+) -> Result<Value, AppError> {
     let lightning_module = client.get_first_module::<LightningClientModule>()?;
     let gateways = lightning_module.list_gateways().await;
     if gateways.is_empty() {
         return Ok(serde_json::to_value(Vec::<String>::new())?);
     }
 
+    let candidates = gateways
+        .iter()
+        .map(|gateway| GatewayCandidate::from_announcement(gateway, amount))
+        .collect::<Vec<_>>();
+    let scores = selector
+        .rank(&client.federation_id().to_string(), &candidates, amount)
+        .await;
+
     let mut gateways_json = json!(&gateways);
     let gateways_json_array = gateways_json
         .as_array_mut()
         .ok_or_else(|| anyhow!("gateways_json is not an array"))?;
 
-    for gateway in gateways_json_array.iter_mut() {
+    for (gateway, announcement) in gateways_json_array.iter_mut().zip(&gateways) {
         let gateway_obj = gateway
             .as_object_mut()
             .ok_or_else(|| anyhow!("gateway is not an object"))?;
         gateway_obj.insert("federation_id".to_string(), json!(client.federation_id()));
+
+        let gateway_id = announcement.info.gateway_id.to_string();
+        if let Some(score) = scores.iter().find(|score| score.gateway_id == gateway_id) {
+            gateway_obj.insert("selection".to_string(), json!(score));
+        }
     }
 
     Ok(serde_json::to_value(gateways_json_array)?)
@@ -43,7 +67,10 @@ pub async fn handle_ws(state: AppState, v: Value) -> Result<Value, AppError> {
     let v = serde_json::from_value::<ListGatewaysRequest>(v)
         .map_err(|e| AppError::new(StatusCode::BAD_REQUEST, anyhow!("Invalid request: {}", e)))?;
     let client = state.get_client(v.federation_id).await?;
-    let gateways = _gateways(client).await?;
+    let amount = v
+        .amount_msat
+        .unwrap_or(Amount::from_msats(DEFAULT_SCORE_AMOUNT_MSAT));
+    let gateways = _gateways(&state.core.gateway_selector, client, amount).await?;
     let gateways_json = json!(gateways);
     Ok(gateways_json)
 }
@@ -54,6 +81,9 @@ pub async fn handle_rest(
     Json(req): Json<ListGatewaysRequest>,
 ) -> Result<Json<Value>, AppError> {
     let client = state.get_client(req.federation_id).await?;
-    let gateways = _gateways(client).await?;
+    let amount = req
+        .amount_msat
+        .unwrap_or(Amount::from_msats(DEFAULT_SCORE_AMOUNT_MSAT));
+    let gateways = _gateways(&state.core.gateway_selector, client, amount).await?;
     Ok(Json(gateways))
 }
@@ -3,7 +3,7 @@ use std::time::Duration;
 
 use anyhow::anyhow;
 use axum::extract::{Path, Query, State};
-use axum::http::StatusCode;
+use axum::http::{HeaderMap, StatusCode};
 use axum::response::sse::{Event, KeepAlive, Sse};
 use axum::response::{IntoResponse, Response};
 use chrono::Utc;
@@ -11,15 +11,62 @@ use fedimint_client::ClientHandleArc;
 use fedimint_core::config::FederationId;
 use fedimint_core::core::OperationId;
 use fedimint_ln_client::{LightningClientModule, LnReceiveState};
-use futures_util::stream::Stream;
+use futures_util::stream::{BoxStream, Stream, StreamExt};
 use serde::Deserialize;
 use tokio_stream::wrappers::IntervalStream;
 use tracing::{error, info, warn};
 
 use crate::core::{InvoiceStatus, SettlementInfo};
 use crate::error::AppError;
+use crate::events::journal::{CursorStatus, JournaledEvent};
+use crate::events::FmcdEvent;
 use crate::state::AppState;
 
+/// Header an `EventSource` sends on reconnect with the id of the last event it
+/// received
+const LAST_EVENT_ID_HEADER: &str = "last-event-id";
+
+/// Ordinal of the final (claimed or canceled) receive state
+const FINAL_RECEIVE_ORDINAL: u64 = 4;
+
+/// Event types the global stream can send, all of them without an `eventTypes`
+/// filter
+const INVOICE_EVENT_TYPES: [&str; 3] = ["invoice_created", "invoice_paid", "invoice_expired"];
+
+fn last_event_id(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(LAST_EVENT_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+/// Tells the client its `Last-Event-ID` can't be resumed without a gap, the
+/// stream continues from the oldest event still available
+fn cursor_expired_event(last_event_id: &str, oldest_event_id: Option<u64>) -> Event {
+    Event::default().event("cursor_expired").data(
+        serde_json::json!({
+            "lastEventId": last_event_id,
+            "oldestEventId": oldest_event_id,
+        })
+        .to_string(),
+    )
+}
+
+/// Position of a receive state in the invoice lifecycle, used as the SSE event
+/// id. Derived from the state rather than counting updates since fedimint
+/// replays the states on every subscription and only yields the final state
+/// for finished operations.
+fn receive_state_ordinal(state: &LnReceiveState) -> u64 {
+    match state {
+        LnReceiveState::Created => 0,
+        LnReceiveState::WaitingForPayment { .. } => 1,
+        LnReceiveState::Funded => 2,
+        LnReceiveState::AwaitingFunds => 3,
+        LnReceiveState::Claimed | LnReceiveState::Canceled { .. } => FINAL_RECEIVE_ORDINAL,
+    }
+}
+
 /// Invoice status update for streaming
 #[derive(Debug, serde::Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
@@ -41,25 +88,15 @@ pub struct StreamQuery {
     pub timeout_seconds: Option<u64>,
 }
 
-/// Create a unified invoice status stream using fedimint's native
-/// subscribe_ln_receive
-async fn create_unified_invoice_stream(
-    client: ClientHandleArc,
+/// Invoice status updates of a lightning receive operation using fedimint's
+/// native subscribe_ln_receive, each with the ordinal of its receive state
+pub(crate) async fn invoice_status_updates(
+    client: &ClientHandleArc,
     operation_id: OperationId,
-    heartbeat_interval: Duration,
-    timeout: Duration,
-) -> impl Stream<Item = Result<Event, Infallible>> {
-    let lightning_module = match client.get_first_module::<LightningClientModule>() {
-        Ok(module) => module,
-        Err(e) => {
-            error!(
-                operation_id = ?operation_id,
-                error = ?e,
-                "Failed to get lightning module for unified streaming"
-            );
-            return tokio_stream::empty().boxed();
-        }
-    };
+) -> anyhow::Result<BoxStream<'static, (u64, InvoiceStatusUpdate)>> {
+    let lightning_module = client
+        .get_first_module::<LightningClientModule>()
+        .map_err(|e| anyhow!("Failed to get lightning module: {}", e))?;
 
     // Try to get the invoice amount from operation metadata
     let invoice_amount_msat = client
@@ -74,9 +111,42 @@ async fn create_unified_invoice_stream(
         })
         .unwrap_or(0); // Default to 0 if not found
 
-    // Use fedimint's native subscribe_ln_receive for real-time monitoring
-    let updates_stream = match lightning_module.subscribe_ln_receive(operation_id).await {
-        Ok(stream) => stream.into_stream(),
+    let updates_stream = lightning_module
+        .subscribe_ln_receive(operation_id)
+        .await
+        .map_err(|e| anyhow!("Failed to subscribe to invoice updates: {}", e))?
+        .into_stream();
+
+    Ok(updates_stream
+        .map(move |ln_state| {
+            let ordinal = receive_state_ordinal(&ln_state);
+            let updated_at = Utc::now();
+            let (status, settlement_info) =
+                fedimint_state_to_unified_status(ln_state, updated_at, invoice_amount_msat);
+
+            let update = InvoiceStatusUpdate {
+                invoice_id: format!("inv_{:?}", operation_id), // Generate consistent invoice_id
+                operation_id,
+                status,
+                settlement: settlement_info,
+                updated_at,
+            };
+            (ordinal, update)
+        })
+        .boxed())
+}
+
+/// Create a unified invoice status stream using fedimint's native
+/// subscribe_ln_receive
+async fn create_unified_invoice_stream(
+    client: ClientHandleArc,
+    operation_id: OperationId,
+    heartbeat_interval: Duration,
+    timeout: Duration,
+    resume_after: Option<u64>,
+) -> impl Stream<Item = Result<Event, Infallible>> {
+    let updates_stream = match invoice_status_updates(&client, operation_id).await {
+        Ok(stream) => stream,
         Err(e) => {
             error!(
                 operation_id = ?operation_id,
@@ -95,47 +165,45 @@ async fn create_unified_invoice_stream(
     );
 
     // Create heartbeat stream for connection keepalive
-    use futures_util::stream::{self, StreamExt};
+    use futures_util::stream;
     let heartbeat_stream = IntervalStream::new(tokio::time::interval(heartbeat_interval))
         .map(|_| Ok::<_, Infallible>(Event::default().event("heartbeat").data("ping")));
 
-    // Convert fedimint LnReceiveState updates to unified SSE events
-    let invoice_updates_stream = updates_stream.map(move |ln_state| {
-        let updated_at = Utc::now();
-        let (status, settlement_info) =
-            fedimint_state_to_unified_status(ln_state, updated_at, invoice_amount_msat);
-
-        let update = InvoiceStatusUpdate {
-            invoice_id: format!("inv_{:?}", operation_id), // Generate consistent invoice_id
-            operation_id,
-            status,
-            settlement: settlement_info,
-            updated_at,
-        };
-
-        match serde_json::to_string(&update) {
-            Ok(json_data) => {
-                info!(
-                    operation_id = ?operation_id,
-                    status = ?update.status,
-                    "Sending unified invoice status update via native fedimint stream"
-                );
-                Ok::<_, Infallible>(Event::default().event("invoice_update").data(json_data))
-            }
-            Err(e) => {
-                error!(
-                    operation_id = ?operation_id,
-                    error = ?e,
-                    "Failed to serialize unified invoice update"
-                );
-                Ok::<_, Infallible>(
-                    Event::default()
-                        .event("error")
-                        .data(format!("Serialization error: {}", e)),
-                )
-            }
-        }
-    });
+    // Convert invoice updates to unified SSE events, skipping the ones a
+    // reconnecting client already received
+    let invoice_updates_stream = updates_stream
+        .filter(move |(ordinal, _)| {
+            std::future::ready(resume_after.is_none_or(|after| *ordinal > after))
+        })
+        .map(
+            move |(ordinal, update)| match serde_json::to_string(&update) {
+                Ok(json_data) => {
+                    info!(
+                        operation_id = ?operation_id,
+                        status = ?update.status,
+                        "Sending unified invoice status update via native fedimint stream"
+                    );
+                    Ok::<_, Infallible>(
+                        Event::default()
+                            .id(ordinal.to_string())
+                            .event("invoice_update")
+                            .data(json_data),
+                    )
+                }
+                Err(e) => {
+                    error!(
+                        operation_id = ?operation_id,
+                        error = ?e,
+                        "Failed to serialize unified invoice update"
+                    );
+                    Ok::<_, Infallible>(
+                        Event::default()
+                            .event("error")
+                            .data(format!("Serialization error: {}", e)),
+                    )
+                }
+            },
+        );
 
     // Create timeout stream using futures_util for consistency
     let timeout_stream = stream::once(async move {
@@ -217,6 +285,7 @@ pub async fn handle_operation_stream(
     State(state): State<AppState>,
     Path(operation_id_str): Path<String>,
     Query(query): Query<StreamQuery>,
+    headers: HeaderMap,
 ) -> Result<Response, AppError> {
     let operation_id = operation_id_str.parse::<OperationId>().map_err(|e| {
         AppError::new(
@@ -237,8 +306,27 @@ pub async fn handle_operation_stream(
         "Starting unified invoice stream for operation"
     );
 
-    let stream =
-        create_unified_invoice_stream(client, operation_id, heartbeat_interval, timeout).await;
+    // Resume after the receive state the client saw last, an id we didn't hand
+    // out restarts the stream from the first state
+    let last_event_id = last_event_id(&headers);
+    let resume_after = last_event_id
+        .as_deref()
+        .and_then(|id| id.parse::<u64>().ok())
+        .filter(|ordinal| *ordinal <= FINAL_RECEIVE_ORDINAL);
+    let expired = match (&last_event_id, resume_after) {
+        (Some(id), None) => Some(Ok::<_, Infallible>(cursor_expired_event(id, Some(0)))),
+        _ => None,
+    };
+
+    let stream = create_unified_invoice_stream(
+        client,
+        operation_id,
+        heartbeat_interval,
+        timeout,
+        resume_after,
+    )
+    .await;
+    let stream = futures_util::stream::iter(expired).chain(stream);
 
     let sse = Sse::new(stream).keep_alive(
         KeepAlive::new()
@@ -249,35 +337,134 @@ pub async fn handle_operation_stream(
     Ok(sse.into_response())
 }
 
-/// Multi-invoice event stream from the event bus
+/// Multi-invoice event stream from the event journal
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct EventStreamQuery {
     pub federation_id: FederationId,
+    /// Comma separated invoice event types, all of them when omitted
+    pub event_types: Option<String>,
     /// Filter by specific invoice/operation IDs (optional)
     pub filter_ids: Option<Vec<String>>,
     /// Heartbeat interval in seconds (default: 30)
     pub heartbeat_interval: Option<u64>,
 }
 
-/// Global event stream for all invoices in a federation
+/// Which journaled events the global stream sends
+#[derive(Debug, Clone)]
+struct GlobalStreamFilter {
+    federation_id: String,
+    event_types: Vec<String>,
+    filter_ids: Vec<String>,
+}
+
+impl GlobalStreamFilter {
+    /// The stream only needs a read-only key, so it is limited to the invoice
+    /// events of one federation
+    fn new(query: EventStreamQuery) -> Result<Self, AppError> {
+        let event_types: Vec<String> = match query.event_types {
+            Some(event_types) => event_types
+                .split(',')
+                .map(|t| t.trim().to_string())
+                .filter(|t| !t.is_empty())
+                .collect(),
+            None => INVOICE_EVENT_TYPES.iter().map(|t| t.to_string()).collect(),
+        };
+        if let Some(unsupported) = event_types
+            .iter()
+            .find(|t| !INVOICE_EVENT_TYPES.contains(&t.as_str()))
+        {
+            return Err(AppError::validation_error(format!(
+                "Unsupported event type '{}', expected one of {}",
+                unsupported,
+                INVOICE_EVENT_TYPES.join(", ")
+            )));
+        }
+
+        Ok(Self {
+            federation_id: query.federation_id.to_string(),
+            event_types,
+            filter_ids: query.filter_ids.unwrap_or_default(),
+        })
+    }
+
+    fn matches(&self, event: &FmcdEvent) -> bool {
+        self.event_types.iter().any(|t| t == event.event_type())
+            && event.federation_id() == Some(&self.federation_id)
+            && (self.filter_ids.is_empty()
+                || self.filter_ids.iter().any(|id| event.contains_id(id)))
+    }
+}
+
+/// SSE event for a journaled event, its sequence number is the event id
+fn journaled_sse_event(entry: &JournaledEvent) -> Event {
+    let name = match entry.event {
+        FmcdEvent::InvoiceCreated { .. }
+        | FmcdEvent::InvoicePaid { .. }
+        | FmcdEvent::InvoiceExpired { .. } => "invoice_event",
+        _ => entry.event.event_type(),
+    };
+
+    match serde_json::to_string(&entry.event) {
+        Ok(json_data) => Event::default()
+            .id(entry.sequence.to_string())
+            .event(name)
+            .data(json_data),
+        Err(e) => {
+            warn!(error = ?e, "Failed to serialize unified event");
+            Event::default()
+                .event("error")
+                .data(format!("Serialization error: {}", e))
+        }
+    }
+}
+
+/// Global event stream for all invoices in a federation.
+///
+/// Events are read from the event journal, each carries its sequence number as
+/// SSE id. A client reconnecting with `Last-Event-ID` first gets the events it
+/// missed, or a `cursor_expired` event when they were already pruned.
 #[axum_macros::debug_handler]
 pub async fn handle_global_event_stream(
     State(state): State<AppState>,
     Query(query): Query<EventStreamQuery>,
+    headers: HeaderMap,
 ) -> Result<Response, AppError> {
-    let mut event_receiver = state.event_bus().subscribe();
-    let federation_id = query.federation_id.to_string();
+    let journal = state.core.event_journal()?.clone();
     let heartbeat_interval = Duration::from_secs(query.heartbeat_interval.unwrap_or(30));
-    let filter_ids = query.filter_ids.unwrap_or_default();
+    let filter = GlobalStreamFilter::new(query)?;
+    let last_event_id = last_event_id(&headers);
 
     info!(
-        federation_id = %federation_id,
-        filter_count = filter_ids.len(),
+        federation_id = %filter.federation_id,
+        event_types = ?filter.event_types,
+        filter_count = filter.filter_ids.len(),
+        last_event_id = ?last_event_id,
         "Starting unified global invoice event stream"
     );
 
     let stream = async_stream::stream! {
+        let cursor = match last_event_id {
+            None => None,
+            Some(ref id) => {
+                let status = match id.parse::<u64>() {
+                    Ok(sequence) => (sequence, journal.cursor_status(sequence).await),
+                    Err(_) => (0, CursorStatus::Expired {
+                        oldest_sequence: journal.first_sequence().await,
+                    }),
+                };
+                match status {
+                    (sequence, CursorStatus::Valid) => Some(sequence),
+                    (_, CursorStatus::Expired { oldest_sequence }) => {
+                        yield Ok::<_, Infallible>(cursor_expired_event(id, oldest_sequence));
+                        oldest_sequence.map(|oldest| oldest - 1)
+                    }
+                }
+            }
+        };
+
+        let events = journal.follow(cursor);
+        futures_util::pin_mut!(events);
         let mut heartbeat_interval = tokio::time::interval(heartbeat_interval);
 
         loop {
@@ -287,50 +474,22 @@ pub async fn handle_global_event_stream(
                         .event("heartbeat")
                         .data("ping"));
                 }
-                event_result = event_receiver.recv() => {
-                    match event_result {
-                        Ok(event) => {
-                            // Filter events for the requested federation
-                            let should_send = match &event {
-                                crate::events::FmcdEvent::InvoiceCreated { federation_id: fid, .. } => {
-                                    fid == &federation_id && (filter_ids.is_empty() ||
-                                        filter_ids.iter().any(|id| event.contains_id(id)))
-                                },
-                                crate::events::FmcdEvent::InvoicePaid { federation_id: fid, .. } => {
-                                    fid == &federation_id && (filter_ids.is_empty() ||
-                                        filter_ids.iter().any(|id| event.contains_id(id)))
-                                },
-                                crate::events::FmcdEvent::InvoiceExpired { federation_id: fid, .. } => {
-                                    fid == &federation_id && (filter_ids.is_empty() ||
-                                        filter_ids.iter().any(|id| event.contains_id(id)))
-                                },
-                                _ => false,
-                            };
-
-                            if should_send {
-                                match serde_json::to_string(&event) {
-                                    Ok(json_data) => {
-                                        yield Ok::<_, Infallible>(Event::default()
-                                            .event("invoice_event")
-                                            .data(json_data));
-                                    }
-                                    Err(e) => {
-                                        warn!(error = ?e, "Failed to serialize unified event");
-                                        yield Ok::<_, Infallible>(Event::default()
-                                            .event("error")
-                                            .data(format!("Serialization error: {}", e)));
-                                    }
-                                }
+                entry = events.next() => {
+                    match entry {
+                        Some(Ok(entry)) => {
+                            if filter.matches(&entry.event) {
+                                yield Ok::<_, Infallible>(journaled_sse_event(&entry));
                             }
                         }
-                        Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
-                            warn!(skipped = skipped, "Unified event stream lagged");
+                        Some(Err(e)) => {
+                            error!(error = ?e, "Failed to read event journal");
                             yield Ok::<_, Infallible>(Event::default()
-                                .event("warning")
-                                .data(format!("Stream lagged, {} events skipped", skipped)));
+                                .event("error")
+                                .data(format!("Failed to read events: {}", e)));
+                            break;
                         }
-                        Err(tokio::sync::broadcast::error::RecvError::Closed) => {
-                            info!("Unified event bus closed, ending stream");
+                        None => {
+                            info!("Event journal closed, ending stream");
                             break;
                         }
                     }
@@ -353,13 +512,13 @@ trait EventIdFilter {
     fn contains_id(&self, id: &str) -> bool;
 }
 
-impl EventIdFilter for crate::events::FmcdEvent {
+impl EventIdFilter for FmcdEvent {
     fn contains_id(&self, id: &str) -> bool {
         match self {
             // Match invoice_id where available, operation_id for InvoicePaid
-            crate::events::FmcdEvent::InvoiceCreated { invoice_id, .. } => invoice_id == id,
-            crate::events::FmcdEvent::InvoicePaid { operation_id, .. } => operation_id == id,
-            crate::events::FmcdEvent::InvoiceExpired { invoice_id, .. } => invoice_id == id,
+            FmcdEvent::InvoiceCreated { invoice_id, .. } => invoice_id == id,
+            FmcdEvent::InvoicePaid { operation_id, .. } => operation_id == id,
+            FmcdEvent::InvoiceExpired { invoice_id, .. } => invoice_id == id,
             _ => false,
         }
     }
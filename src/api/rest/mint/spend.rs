@@ -15,6 +15,7 @@ use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use tracing::{info, warn};
 
+use crate::core::policy::{SpendIntent, SpendKind};
 use crate::error::AppError;
 use crate::state::AppState;
 
@@ -35,6 +36,49 @@ pub struct SpendResponse {
     pub notes: OOBNotes,
 }
 
+/// Spend notes once the spending policy allows it. With overpay allowed the
+/// notes can be worth more than requested, then their whole amount is checked
+/// again and the spend is canceled when it doesn't pass.
+async fn spend_with_policy(state: &AppState, req: SpendRequest) -> Result<SpendResponse, AppError> {
+    let intent = SpendIntent {
+        federation_id: req.federation_id,
+        kind: SpendKind::EcashSpend,
+        amount_msat: req.amount_msat.msats,
+        destinations: Vec::new(),
+        approved: false,
+    };
+    let mut authorization = state.core.authorize_spend(intent.clone(), None).await?;
+    let client = state.get_client(req.federation_id).await?;
+    let spend = _spend(client.clone(), req).await?;
+
+    let spent_msat = spend.notes.total_amount().msats;
+    if spent_msat > intent.amount_msat {
+        // Released first, so the spend isn't counted twice against the caps
+        drop(authorization);
+        let overpaid = SpendIntent {
+            amount_msat: spent_msat,
+            ..intent
+        };
+        authorization = match state.core.authorize_spend(overpaid, None).await {
+            Ok(authorization) => authorization,
+            Err(e) => {
+                warn!(
+                    operation_id = ?spend.operation,
+                    spent_msat = spent_msat,
+                    "Overpaid spend exceeds the spending policy, canceling it"
+                );
+                client
+                    .get_first_module::<MintClientModule>()?
+                    .try_cancel_spend_notes(spend.operation)
+                    .await;
+                return Err(e);
+            }
+        };
+    }
+    state.core.record_spend(&authorization).await;
+    Ok(spend)
+}
+
 async fn _spend(client: ClientHandleArc, req: SpendRequest) -> Result<SpendResponse, AppError> {
     warn!("The client will try to double-spend these notes after the duration specified by the --timeout option to recover any unclaimed e-cash.");
     let mint_module = client.get_first_module::<MintClientModule>()?;
@@ -77,8 +121,7 @@ async fn _spend(client: ClientHandleArc, req: SpendRequest) -> Result<SpendRespo
 pub async fn handle_ws(state: AppState, v: Value) -> Result<Value, AppError> {
     let v = serde_json::from_value::<SpendRequest>(v)
         .map_err(|e| AppError::new(StatusCode::BAD_REQUEST, anyhow!("Invalid request: {}", e)))?;
-    let client = state.get_client(v.federation_id).await?;
-    let spend = _spend(client, v).await?;
+    let spend = spend_with_policy(&state, v).await?;
     let spend_json = json!(spend);
     Ok(spend_json)
 }
@@ -88,7 +131,6 @@ pub async fn handle_rest(
     State(state): State<AppState>,
     Json(req): Json<SpendRequest>,
 ) -> Result<Json<SpendResponse>, AppError> {
-    let client = state.get_client(req.federation_id).await?;
-    let spend = _spend(client, req).await?;
+    let spend = spend_with_policy(&state, req).await?;
     Ok(Json(spend))
 }
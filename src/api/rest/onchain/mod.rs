@@ -1,3 +1,6 @@
 pub mod await_deposit;
 pub mod deposit_address;
+pub mod status;
+pub mod stream;
 pub mod withdraw;
+pub mod withdraw_quote;
@@ -0,0 +1,56 @@
+use anyhow::anyhow;
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::Json;
+use fedimint_core::config::FederationId;
+use fedimint_core::core::OperationId;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::core::WithdrawStatusResponse;
+use crate::error::AppError;
+use crate::state::AppState;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatusQuery {
+    pub federation_id: FederationId,
+}
+
+pub async fn handle_ws(state: AppState, v: Value) -> Result<Value, AppError> {
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct WSRequest {
+        operation_id: OperationId,
+        federation_id: FederationId,
+    }
+
+    let req = serde_json::from_value::<WSRequest>(v)
+        .map_err(|e| AppError::new(StatusCode::BAD_REQUEST, anyhow!("Invalid request: {}", e)))?;
+    let status = state
+        .core
+        .withdraw_status(req.federation_id, req.operation_id)
+        .await?;
+    Ok(json!(status))
+}
+
+/// Status of an onchain withdrawal by operation ID
+#[axum_macros::debug_handler]
+pub async fn handle_rest(
+    State(state): State<AppState>,
+    Path(operation_id_str): Path<String>,
+    Query(query): Query<StatusQuery>,
+) -> Result<Json<WithdrawStatusResponse>, AppError> {
+    let operation_id = operation_id_str.parse::<OperationId>().map_err(|e| {
+        AppError::new(
+            StatusCode::BAD_REQUEST,
+            anyhow!("Invalid operation ID: {}", e),
+        )
+    })?;
+
+    let status = state
+        .core
+        .withdraw_status(query.federation_id, operation_id)
+        .await?;
+    Ok(Json(status))
+}
@@ -0,0 +1,134 @@
+use std::convert::Infallible;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use fedimint_core::config::FederationId;
+use fedimint_core::core::OperationId;
+use futures_util::StreamExt;
+use serde::Deserialize;
+use tracing::{error, info, warn};
+
+use crate::core::{WithdrawStatus, WithdrawStatusResponse};
+use crate::error::AppError;
+use crate::state::AppState;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StreamQuery {
+    pub federation_id: FederationId,
+    /// Heartbeat interval in seconds (default: 30)
+    pub heartbeat_interval: Option<u64>,
+    /// Stream timeout in seconds (default: 600)
+    pub timeout_seconds: Option<u64>,
+}
+
+/// Position of a withdraw state in the withdrawal lifecycle, used as the SSE
+/// event id
+fn withdraw_status_ordinal(status: &WithdrawStatus) -> u64 {
+    match status {
+        WithdrawStatus::Created => 0,
+        WithdrawStatus::Succeeded { .. } | WithdrawStatus::Failed { .. } => 1,
+    }
+}
+
+fn withdraw_sse_event(update: &WithdrawStatusResponse) -> Event {
+    match serde_json::to_string(update) {
+        Ok(json_data) => Event::default()
+            .id(withdraw_status_ordinal(&update.status).to_string())
+            .event("withdraw_update")
+            .data(json_data),
+        Err(e) => {
+            error!(
+                operation_id = ?update.operation_id,
+                error = ?e,
+                "Failed to serialize withdraw update"
+            );
+            Event::default()
+                .event("error")
+                .data(format!("Serialization error: {}", e))
+        }
+    }
+}
+
+/// Withdraw state transitions of an onchain withdrawal, ending after the final
+/// state
+#[axum_macros::debug_handler]
+pub async fn handle_operation_stream(
+    State(state): State<AppState>,
+    Path(operation_id_str): Path<String>,
+    Query(query): Query<StreamQuery>,
+) -> Result<Response, AppError> {
+    let operation_id = operation_id_str.parse::<OperationId>().map_err(|e| {
+        AppError::new(
+            StatusCode::BAD_REQUEST,
+            anyhow!("Invalid operation ID: {}", e),
+        )
+    })?;
+
+    let heartbeat_interval = Duration::from_secs(query.heartbeat_interval.unwrap_or(30));
+    let timeout = Duration::from_secs(query.timeout_seconds.unwrap_or(600));
+    let updates = state
+        .core
+        .withdraw_updates(query.federation_id, operation_id)
+        .await?;
+
+    info!(
+        operation_id = ?operation_id,
+        federation_id = %query.federation_id,
+        heartbeat_interval_secs = heartbeat_interval.as_secs(),
+        timeout_secs = timeout.as_secs(),
+        "Starting withdraw stream for operation"
+    );
+
+    // Unlike the invoice stream this ends with the final withdraw state instead
+    // of waiting for the timeout
+    let stream = async_stream::stream! {
+        let mut updates = updates;
+        let mut heartbeat_interval = tokio::time::interval(heartbeat_interval);
+        let deadline = tokio::time::sleep(timeout);
+        tokio::pin!(deadline);
+
+        loop {
+            tokio::select! {
+                _ = heartbeat_interval.tick() => {
+                    yield Ok::<_, Infallible>(Event::default()
+                        .event("heartbeat")
+                        .data("ping"));
+                }
+                update = updates.next() => {
+                    match update {
+                        Some(update) => {
+                            yield Ok::<_, Infallible>(withdraw_sse_event(&update));
+                        }
+                        None => break,
+                    }
+                }
+                _ = &mut deadline => {
+                    warn!(
+                        operation_id = ?operation_id,
+                        timeout_secs = timeout.as_secs(),
+                        "Withdraw stream timed out"
+                    );
+                    yield Ok::<_, Infallible>(Event::default().event("timeout").data(format!(
+                        "{{\"message\":\"Stream timed out after {} seconds\",\"timeout_seconds\":{}}}",
+                        timeout.as_secs(),
+                        timeout.as_secs()
+                    )));
+                    break;
+                }
+            }
+        }
+    };
+
+    let sse = Sse::new(stream).keep_alive(
+        KeepAlive::new()
+            .interval(heartbeat_interval)
+            .text("keep-alive"),
+    );
+
+    Ok(sse.into_response())
+}
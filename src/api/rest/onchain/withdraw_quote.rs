@@ -0,0 +1,27 @@
+use anyhow::anyhow;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use serde_json::{json, Value};
+
+use crate::core::withdraw_quote::WithdrawQuote;
+use crate::core::WithdrawQuoteRequest;
+use crate::error::AppError;
+use crate::state::AppState;
+
+pub async fn handle_ws(state: AppState, v: Value) -> Result<Value, AppError> {
+    let req = serde_json::from_value::<WithdrawQuoteRequest>(v)
+        .map_err(|e| AppError::new(StatusCode::BAD_REQUEST, anyhow!("Invalid request: {}", e)))?;
+    let quote = state.core.quote_withdraw(req).await?;
+    let quote_json = json!(quote);
+    Ok(quote_json)
+}
+
+#[axum_macros::debug_handler]
+pub async fn handle_rest(
+    State(state): State<AppState>,
+    Json(req): Json<WithdrawQuoteRequest>,
+) -> Result<Json<WithdrawQuote>, AppError> {
+    let quote = state.core.quote_withdraw(req).await?;
+    Ok(Json(quote))
+}
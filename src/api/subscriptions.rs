@@ -0,0 +1,250 @@
+use std::collections::HashMap;
+
+use anyhow::anyhow;
+use axum::http::StatusCode;
+use fedimint_core::config::FederationId;
+use fedimint_core::core::OperationId;
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use super::handlers::ln::stream::invoice_status_updates;
+use super::websockets::JSONRPC_VERSION;
+use crate::error::{AppError, ErrorCategory};
+use crate::events::journal::CursorStatus;
+use crate::events::FmcdEvent;
+use crate::state::AppState;
+
+/// Method name of the notifications pushed for a subscription
+pub const SUBSCRIPTION_NOTIFICATION_METHOD: &str = "subscription";
+
+const MAX_SUBSCRIPTIONS_PER_CONNECTION: usize = 32;
+const NOTIFICATION_BUFFER: usize = 1000;
+
+/// What to subscribe to, selected by `kind`
+#[derive(Debug, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SubscribeParams {
+    /// Events from the event journal
+    Events(EventSubscription),
+    /// Status updates of a lightning invoice operation
+    Operation(OperationSubscription),
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EventSubscription {
+    pub federation_id: Option<FederationId>,
+    /// Only these event types, all events when omitted
+    pub event_types: Option<Vec<String>>,
+    pub correlation_id: Option<String>,
+    /// Resume after this journal sequence number instead of starting now
+    pub after_sequence: Option<u64>,
+}
+
+impl EventSubscription {
+    pub fn matches(&self, event: &FmcdEvent) -> bool {
+        self.event_types
+            .as_ref()
+            .is_none_or(|types| types.iter().any(|t| t == event.event_type()))
+            && self
+                .federation_id
+                .is_none_or(|id| event.federation_id() == Some(&id.to_string()))
+            && self
+                .correlation_id
+                .as_ref()
+                .is_none_or(|id| event.correlation_id() == Some(id))
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OperationSubscription {
+    pub federation_id: FederationId,
+    pub operation_id: OperationId,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnsubscribeParams {
+    pub subscription: String,
+}
+
+/// JSON-RPC notification, a message without id pushed by the server
+#[derive(Debug, Clone, Serialize)]
+pub struct JsonRpcNotification {
+    pub jsonrpc: String,
+    pub method: String,
+    pub params: SubscriptionNotification,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubscriptionNotification {
+    pub subscription: String,
+    pub result: Value,
+}
+
+impl JsonRpcNotification {
+    pub(crate) fn new(subscription: &str, result: Value) -> Self {
+        Self {
+            jsonrpc: JSONRPC_VERSION.to_string(),
+            method: SUBSCRIPTION_NOTIFICATION_METHOD.to_string(),
+            params: SubscriptionNotification {
+                subscription: subscription.to_string(),
+                result,
+            },
+        }
+    }
+}
+
+/// Subscriptions of a single WebSocket connection.
+///
+/// Every subscription runs as a task pushing notifications into a channel the
+/// connection drains between requests. Tasks are aborted on unsubscribe and
+/// when the connection goes away.
+pub struct Subscriptions {
+    sender: mpsc::Sender<JsonRpcNotification>,
+    tasks: HashMap<String, JoinHandle<()>>,
+}
+
+impl Subscriptions {
+    pub fn new() -> (Self, mpsc::Receiver<JsonRpcNotification>) {
+        let (sender, receiver) = mpsc::channel(NOTIFICATION_BUFFER);
+        (
+            Self {
+                sender,
+                tasks: HashMap::new(),
+            },
+            receiver,
+        )
+    }
+
+    /// Start a subscription, returns its id
+    pub async fn subscribe(&mut self, state: &AppState, params: Value) -> Result<Value, AppError> {
+        let params = serde_json::from_value::<SubscribeParams>(params).map_err(|e| {
+            AppError::new(StatusCode::BAD_REQUEST, anyhow!("Invalid request: {}", e))
+        })?;
+
+        self.tasks.retain(|_, task| !task.is_finished());
+        if self.tasks.len() >= MAX_SUBSCRIPTIONS_PER_CONNECTION {
+            return Err(AppError::validation_error(format!(
+                "At most {} subscriptions per connection",
+                MAX_SUBSCRIPTIONS_PER_CONNECTION
+            )));
+        }
+
+        let subscription_id = Uuid::new_v4().to_string();
+        let task = match params {
+            SubscribeParams::Events(filter) => {
+                self.spawn_events(state, subscription_id.clone(), filter)
+                    .await?
+            }
+            SubscribeParams::Operation(operation) => {
+                self.spawn_operation(state, subscription_id.clone(), operation)
+                    .await?
+            }
+        };
+        self.tasks.insert(subscription_id.clone(), task);
+
+        info!(subscription_id = %subscription_id, "WebSocket subscription started");
+        Ok(json!({ "subscription": subscription_id }))
+    }
+
+    /// Stop a subscription
+    pub fn unsubscribe(&mut self, params: Value) -> Result<Value, AppError> {
+        let params = serde_json::from_value::<UnsubscribeParams>(params).map_err(|e| {
+            AppError::new(StatusCode::BAD_REQUEST, anyhow!("Invalid request: {}", e))
+        })?;
+
+        let task = self.tasks.remove(&params.subscription).ok_or_else(|| {
+            AppError::with_category(
+                ErrorCategory::NotFound,
+                format!("Subscription {} not found", params.subscription),
+            )
+        })?;
+        task.abort();
+
+        info!(subscription_id = %params.subscription, "WebSocket subscription stopped");
+        Ok(json!({ "unsubscribed": true }))
+    }
+
+    async fn spawn_events(
+        &self,
+        state: &AppState,
+        subscription_id: String,
+        filter: EventSubscription,
+    ) -> Result<JoinHandle<()>, AppError> {
+        let journal = state.core.event_journal()?.clone();
+
+        if let Some(cursor) = filter.after_sequence {
+            if let CursorStatus::Expired { oldest_sequence } = journal.cursor_status(cursor).await {
+                return Err(AppError::validation_error(format!(
+                    "Cursor {} expired, oldest available sequence is {}",
+                    cursor,
+                    oldest_sequence.map_or("none".to_string(), |s| s.to_string())
+                )));
+            }
+        }
+
+        let sender = self.sender.clone();
+        let events = journal.follow(filter.after_sequence);
+        Ok(tokio::spawn(async move {
+            futures_util::pin_mut!(events);
+            while let Some(entry) = events.next().await {
+                let entry = match entry {
+                    Ok(entry) => entry,
+                    Err(e) => {
+                        warn!(
+                            subscription_id = %subscription_id,
+                            error = ?e,
+                            "Failed to read event journal, ending subscription"
+                        );
+                        break;
+                    }
+                };
+                if !filter.matches(&entry.event) {
+                    continue;
+                }
+                let notification = JsonRpcNotification::new(&subscription_id, json!(entry));
+                if sender.send(notification).await.is_err() {
+                    break;
+                }
+            }
+        }))
+    }
+
+    async fn spawn_operation(
+        &self,
+        state: &AppState,
+        subscription_id: String,
+        operation: OperationSubscription,
+    ) -> Result<JoinHandle<()>, AppError> {
+        let client = state.get_client(operation.federation_id).await?;
+        let mut updates = invoice_status_updates(&client, operation.operation_id)
+            .await
+            .map_err(|e| AppError::with_category(ErrorCategory::NotFound, e.to_string()))?;
+
+        let sender = self.sender.clone();
+        Ok(tokio::spawn(async move {
+            while let Some((_, update)) = updates.next().await {
+                let notification = JsonRpcNotification::new(&subscription_id, json!(update));
+                if sender.send(notification).await.is_err() {
+                    break;
+                }
+            }
+        }))
+    }
+}
+
+impl Drop for Subscriptions {
+    fn drop(&mut self) {
+        for task in self.tasks.values() {
+            task.abort();
+        }
+    }
+}
@@ -0,0 +1,98 @@
+#![allow(clippy::unwrap_used)]
+
+use serde_json::{json, Value};
+use tempfile::TempDir;
+
+use crate::api::audit::entry;
+use crate::core::audit::{AuditAction, AuditLog, AuditOutcome};
+use crate::core::tests::open_db;
+use crate::observability::correlation::RequestContext;
+
+const NOTES: &str =
+    "AwEEeAmV1JgL3tlP1y2qt6kXlpqH7E2VxXzHFw4Rb0ZpK9jWGcNnUeSc2aQ8rTfB5mYoLdHgZ1uIvCe3wKs7xPq6";
+
+fn outcome() -> AuditOutcome {
+    AuditOutcome {
+        success: true,
+        status: 200,
+        error: None,
+    }
+}
+
+#[test]
+fn test_module_request_keeps_only_module_and_subcommand() {
+    let request = json!({
+        "federationId": "15db8cb4f1ec8e484d73b889372bec94812580f929e8148b7437d359af422cd3",
+        "module": { "Kind": "mint" },
+        "args": ["reissue", NOTES, "--include-invite"],
+    });
+    let entry = entry(
+        AuditAction::Module,
+        "POST /v2/admin/module".to_string(),
+        None,
+        None,
+        &RequestContext::new(None),
+        &request,
+        outcome(),
+    );
+
+    assert_eq!(entry.request["module"], request["module"]);
+    assert_eq!(entry.request["federationId"], request["federationId"]);
+    assert_eq!(entry.request["subcommand"], "reissue");
+    assert_eq!(entry.request["args"], json!(["[REDACTED]", "[REDACTED]"]));
+}
+
+#[test]
+fn test_module_request_without_subcommand_is_redacted() {
+    let request = json!({ "module": { "Id": 1 }, "args": [NOTES] });
+    let entry = entry(
+        AuditAction::Module,
+        "ws admin-module".to_string(),
+        None,
+        None,
+        &RequestContext::new(None),
+        &request,
+        outcome(),
+    );
+
+    assert_eq!(entry.request["subcommand"], Value::Null);
+    assert_eq!(entry.request["args"], json!(["[REDACTED]"]));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_notes_never_reach_audit_records() {
+    let temp_dir = TempDir::new().unwrap();
+    let log = AuditLog::open(open_db(&temp_dir).await).await.unwrap();
+    let context = RequestContext::new(None);
+
+    let requests = [
+        (
+            AuditAction::Module,
+            json!({ "module": { "Kind": "mint" }, "args": ["reissue", NOTES] }),
+        ),
+        (AuditAction::Reissue, json!({ "notes": NOTES })),
+        (
+            AuditAction::Spend,
+            json!({ "notes": NOTES, "amountMsat": 1_000 }),
+        ),
+    ];
+    for (action, request) in requests {
+        log.append(entry(
+            action,
+            "POST /v2/test".to_string(),
+            None,
+            None,
+            &context,
+            &request,
+            outcome(),
+        ))
+        .await
+        .unwrap();
+    }
+
+    let records = log.records(None, None).await.unwrap().records;
+    assert_eq!(records.len(), 3);
+    for record in records {
+        assert!(!serde_json::to_string(&record).unwrap().contains(NOTES));
+    }
+}
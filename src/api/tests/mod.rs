@@ -0,0 +1,3 @@
+mod audit_tests;
+mod subscriptions_tests;
+mod websockets_tests;
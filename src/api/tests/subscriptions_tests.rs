@@ -0,0 +1,144 @@
+#![allow(clippy::unwrap_used)]
+
+use std::time::Duration;
+
+use chrono::Utc;
+use fedimint_core::config::FederationId;
+use serde_json::json;
+use tempfile::TempDir;
+
+use crate::api::subscriptions::{EventSubscription, Subscriptions};
+use crate::error::ErrorCategory;
+use crate::events::journal::JournaledEvent;
+use crate::events::FmcdEvent;
+use crate::state::AppState;
+
+fn invoice_paid(federation_id: &str, correlation_id: Option<&str>) -> FmcdEvent {
+    FmcdEvent::InvoicePaid {
+        operation_id: "op-1".to_string(),
+        federation_id: federation_id.to_string(),
+        amount_msat: 1000,
+        correlation_id: correlation_id.map(str::to_string),
+        timestamp: Utc::now(),
+    }
+}
+
+fn balance_updated(federation_id: &str) -> FmcdEvent {
+    FmcdEvent::FederationBalanceUpdated {
+        federation_id: federation_id.to_string(),
+        balance_msat: 5000,
+        correlation_id: None,
+        timestamp: Utc::now(),
+    }
+}
+
+#[test]
+fn test_event_subscription_matches() {
+    let federation_id = FederationId::dummy();
+    let federation = federation_id.to_string();
+
+    assert!(EventSubscription::default().matches(&balance_updated("other")));
+
+    let by_type = EventSubscription {
+        event_types: Some(vec!["invoice_paid".to_string()]),
+        ..Default::default()
+    };
+    assert!(by_type.matches(&invoice_paid(&federation, None)));
+    assert!(!by_type.matches(&balance_updated(&federation)));
+
+    let by_federation = EventSubscription {
+        federation_id: Some(federation_id),
+        ..Default::default()
+    };
+    assert!(by_federation.matches(&balance_updated(&federation)));
+    assert!(!by_federation.matches(&balance_updated("other")));
+
+    let by_correlation = EventSubscription {
+        correlation_id: Some("req-1".to_string()),
+        ..Default::default()
+    };
+    assert!(by_correlation.matches(&invoice_paid(&federation, Some("req-1"))));
+    assert!(!by_correlation.matches(&invoice_paid(&federation, Some("req-2"))));
+    assert!(!by_correlation.matches(&invoice_paid(&federation, None)));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_subscription_receives_matching_events() {
+    let temp_dir = TempDir::new().unwrap();
+    let state = AppState::new(temp_dir.path().to_path_buf()).await.unwrap();
+    let (mut subscriptions, mut notifications) = Subscriptions::new();
+
+    let subscribed = subscriptions
+        .subscribe(
+            &state,
+            json!({ "kind": "events", "eventTypes": ["invoice_paid"] }),
+        )
+        .await
+        .unwrap();
+    let subscription_id = subscribed["subscription"].as_str().unwrap().to_string();
+
+    state
+        .event_bus()
+        .publish(balance_updated("fed-a"))
+        .await
+        .unwrap();
+    state
+        .event_bus()
+        .publish(invoice_paid("fed-a", None))
+        .await
+        .unwrap();
+
+    let notification = tokio::time::timeout(Duration::from_secs(5), notifications.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(notification.method, "subscription");
+    assert_eq!(notification.params.subscription, subscription_id);
+    let entry: JournaledEvent = serde_json::from_value(notification.params.result).unwrap();
+    assert_eq!(entry.event.event_type(), "invoice_paid");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_subscriptions_per_connection_are_capped() {
+    let temp_dir = TempDir::new().unwrap();
+    let state = AppState::new(temp_dir.path().to_path_buf()).await.unwrap();
+    let (mut subscriptions, _notifications) = Subscriptions::new();
+
+    for _ in 0..32 {
+        subscriptions
+            .subscribe(&state, json!({ "kind": "events" }))
+            .await
+            .unwrap();
+    }
+    let err = subscriptions
+        .subscribe(&state, json!({ "kind": "events" }))
+        .await
+        .unwrap_err();
+    assert!(matches!(err.category, ErrorCategory::ValidationError));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_unsubscribe_stops_subscription() {
+    let temp_dir = TempDir::new().unwrap();
+    let state = AppState::new(temp_dir.path().to_path_buf()).await.unwrap();
+    let (mut subscriptions, mut notifications) = Subscriptions::new();
+
+    let subscribed = subscriptions
+        .subscribe(&state, json!({ "kind": "events" }))
+        .await
+        .unwrap();
+    let params = json!({ "subscription": subscribed["subscription"] });
+
+    subscriptions.unsubscribe(params.clone()).unwrap();
+    let err = subscriptions.unsubscribe(params).unwrap_err();
+    assert!(matches!(err.category, ErrorCategory::NotFound));
+
+    // Nothing is delivered for the stopped subscription
+    state
+        .event_bus()
+        .publish(balance_updated("fed-a"))
+        .await
+        .unwrap();
+    let received = tokio::time::timeout(Duration::from_millis(200), notifications.recv()).await;
+    assert!(!matches!(received, Ok(Some(_))));
+}
@@ -0,0 +1,31 @@
+#![allow(clippy::unwrap_used)]
+
+use serde_json::json;
+
+use crate::api::subscriptions::JsonRpcNotification;
+use crate::api::websockets::notification_text;
+use crate::auth::{AuthenticatedMessage, WebSocketAuth};
+
+#[test]
+fn test_notification_is_signed_when_auth_is_enabled() {
+    let notification = JsonRpcNotification::new("sub-1", json!({ "sequence": 1 }));
+    let auth = WebSocketAuth::new(Some("password".to_string()));
+
+    let text = notification_text(&notification, &auth).unwrap();
+    let message: AuthenticatedMessage = serde_json::from_str(&text).unwrap();
+    assert!(message.verify(&auth));
+    assert_eq!(message.payload["method"], "subscription");
+    assert_eq!(message.payload["params"]["subscription"], "sub-1");
+    assert!(!message.verify(&WebSocketAuth::new(Some("other".to_string()))));
+}
+
+#[test]
+fn test_notification_is_plain_without_auth() {
+    let notification = JsonRpcNotification::new("sub-1", json!({ "sequence": 1 }));
+
+    let text = notification_text(&notification, &WebSocketAuth::new(None)).unwrap();
+    let value: serde_json::Value = serde_json::from_str(&text).unwrap();
+    assert_eq!(value["jsonrpc"], "2.0");
+    assert_eq!(value["params"]["result"]["sequence"], 1);
+    assert!(value.get("signature").is_none());
+}
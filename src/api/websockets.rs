@@ -1,30 +1,45 @@
+use std::net::{IpAddr, SocketAddr};
 use std::sync::Arc;
+use std::time::Instant;
 
 use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
-use axum::extract::{Extension, State};
+use axum::extract::{ConnectInfo, Extension, State};
+use axum::http::HeaderMap;
 use axum::response::IntoResponse;
+use chrono::Utc;
 use futures_util::stream::StreamExt;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use tracing::{info, warn};
 use uuid::Uuid;
 
+use super::audit;
 use super::handlers;
-use crate::auth::{AuthenticatedMessage, WebSocketAuth};
-use crate::error::AppError;
+use super::idempotency::{run_idempotent, take_idempotency_key};
+use super::rate_limit::RouteClass;
+use super::subscriptions::{JsonRpcNotification, Subscriptions};
+use crate::auth::{ApiIdentity, AuthenticatedMessage, WebSocketAuth};
+use crate::core::audit::{AuditAction, AuditOutcome};
+use crate::error::{AppError, ErrorCategory};
+use crate::metrics::{auth_metrics, rate_limit_metrics};
 use crate::observability::correlation::RequestContext;
 use crate::state::AppState;
 
-const JSONRPC_VERSION: &str = "2.0";
+pub(crate) const JSONRPC_VERSION: &str = "2.0";
 const JSONRPC_ERROR_INVALID_REQUEST: i16 = -32600;
 
 pub async fn websocket_handler(
     ws: WebSocketUpgrade,
     State(state): State<AppState>,
     Extension(auth): Extension<Arc<WebSocketAuth>>,
+    headers: HeaderMap,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
 ) -> impl IntoResponse {
+    let client_ip = state
+        .auth_guard
+        .client_ip_from(&headers, connect_info.map(|ConnectInfo(addr)| addr));
     ws.on_upgrade(move |socket| async move {
-        if let Err(e) = handle_socket(socket, state, auth).await {
+        if let Err(e) = handle_socket(socket, state, auth, client_ip).await {
             // Log the error or handle it as needed
             eprintln!("Error handling socket: {}", e);
         }
@@ -58,13 +73,34 @@ pub struct JsonRpcError {
 pub enum JsonRpcMethod {
     AdminBackup,
     AdminConfig,
+    AdminEvents,
     AdminDiscoverVersion,
     AdminFederationIds,
     AdminInfo,
     AdminJoin,
+    AdminLeave,
     AdminModule,
     AdminRestore,
+    AdminRestoreStatus,
     AdminListOperations,
+    AdminWebhookList,
+    AdminWebhookCreate,
+    AdminWebhookUpdate,
+    AdminWebhookDelete,
+    AdminWebhookDeliveries,
+    AdminWebhookReplay,
+    AdminWebhookDeadLetters,
+    AdminWebhookRequeue,
+    AdminApprovalList,
+    AdminApprovalApprove,
+    AdminApprovalReject,
+    AdminApiKeyList,
+    AdminApiKeyCreate,
+    AdminApiKeyRevoke,
+    AdminAuthLockouts,
+    AdminAuthClearLockouts,
+    AdminAudit,
+    AdminAuditVerify,
     MintDecodeNotes,
     MintEncodeNotes,
     MintReissue,
@@ -81,17 +117,41 @@ pub enum JsonRpcMethod {
     WalletDepositAddress,
     WalletAwaitDeposit,
     WalletWithdraw,
+    WalletWithdrawQuote,
+    WalletWithdrawStatus,
+    Subscribe,
+    Unsubscribe,
 }
 
 async fn handle_socket(
     mut socket: WebSocket,
     state: AppState,
     auth: Arc<WebSocketAuth>,
+    client_ip: Option<IpAddr>,
 ) -> Result<(), anyhow::Error> {
     // Create a session-level correlation ID for this WebSocket connection
     let session_correlation_id = Uuid::new_v4().to_string();
+    // Messages are authenticated with the password, so they are rate limited
+    // and audited as its identity
+    let identity = auth.is_enabled().then(ApiIdentity::password);
+    let client = WsClient {
+        identity: identity.as_ref(),
+        ip: client_ip,
+    };
+    let (mut subscriptions, mut notifications) = Subscriptions::new();
+
+    loop {
+        let msg = tokio::select! {
+            msg = socket.next() => match msg {
+                Some(Ok(msg)) => msg,
+                _ => break,
+            },
+            Some(notification) = notifications.recv() => {
+                send_notification(&mut socket, &notification, &auth).await?;
+                continue;
+            }
+        };
 
-    while let Some(Ok(msg)) = socket.next().await {
         if let Message::Text(text) = msg {
             // Create a new RequestContext for each message, using session correlation ID
             let message_context = RequestContext::new(Some(session_correlation_id.clone()));
@@ -112,9 +172,22 @@ async fn handle_socket(
                     }
                 };
 
+                // A connection stays open across a lockout, so every
+                // message is checked
+                if let Err(blocked) = state.auth_guard.check(client_ip, None, Utc::now()) {
+                    warn!(
+                        reason = blocked.as_str(),
+                        "WebSocket connection closed, client is blocked"
+                    );
+                    auth_metrics::record_blocked_request(blocked.as_str());
+                    send_auth_error(&mut socket, "Too many failed authentication attempts").await?;
+                    break;
+                }
+
                 // Verify HMAC signature
                 if !auth_msg.verify(&auth) {
                     warn!("HMAC verification failed for WebSocket message");
+                    state.auth_guard.record_failure(client_ip, None, Utc::now());
                     send_auth_error(&mut socket, "Authentication failed").await?;
                     continue;
                 }
@@ -128,7 +201,14 @@ async fn handle_socket(
                     }
                 };
 
-                let res = match_method(req.clone(), state.clone(), message_context.clone()).await;
+                let res = dispatch(
+                    &mut subscriptions,
+                    req.clone(),
+                    &state,
+                    message_context.clone(),
+                    client,
+                )
+                .await;
                 let res_msg = create_json_rpc_response(res, req.id);
 
                 // Send response as authenticated message
@@ -148,7 +228,14 @@ async fn handle_socket(
                     }
                 };
 
-                let res = match_method(req.clone(), state.clone(), message_context.clone()).await;
+                let res = dispatch(
+                    &mut subscriptions,
+                    req.clone(),
+                    &state,
+                    message_context.clone(),
+                    client,
+                )
+                .await;
                 let res_msg = create_json_rpc_response(res, req.id);
                 let response_text = serde_json::to_string(&res_msg)?;
                 socket.send(Message::Text(response_text)).await?;
@@ -159,6 +246,162 @@ async fn handle_socket(
     Ok(())
 }
 
+/// Who the messages of a connection are rate limited and audited as
+#[derive(Debug, Clone, Copy)]
+struct WsClient<'a> {
+    identity: Option<&'a ApiIdentity>,
+    ip: Option<IpAddr>,
+}
+
+/// Rate limit budget of a method, the same as the REST route it mirrors
+fn method_class(method: &JsonRpcMethod) -> RouteClass {
+    match method {
+        JsonRpcMethod::LnPay
+        | JsonRpcMethod::MintSpend
+        | JsonRpcMethod::WalletWithdraw
+        | JsonRpcMethod::AdminApprovalApprove => RouteClass::Spend,
+        _ => RouteClass::Read,
+    }
+}
+
+/// What a method does for the audit log, the same as the REST route it mirrors
+fn method_action(method: &JsonRpcMethod) -> Option<AuditAction> {
+    let action = match method {
+        JsonRpcMethod::AdminJoin => AuditAction::Join,
+        JsonRpcMethod::AdminLeave => AuditAction::Leave,
+        JsonRpcMethod::AdminBackup => AuditAction::Backup,
+        JsonRpcMethod::AdminRestore => AuditAction::Restore,
+        JsonRpcMethod::AdminWebhookCreate
+        | JsonRpcMethod::AdminWebhookUpdate
+        | JsonRpcMethod::AdminWebhookDelete
+        | JsonRpcMethod::AdminApiKeyCreate
+        | JsonRpcMethod::AdminApiKeyRevoke
+        | JsonRpcMethod::AdminAuthClearLockouts => AuditAction::ConfigChange,
+        JsonRpcMethod::AdminApprovalApprove | JsonRpcMethod::AdminApprovalReject => {
+            AuditAction::Approval
+        }
+        JsonRpcMethod::LnPay => AuditAction::Pay,
+        JsonRpcMethod::WalletWithdraw => AuditAction::Withdraw,
+        JsonRpcMethod::MintSpend => AuditAction::Spend,
+        JsonRpcMethod::MintReissue => AuditAction::Reissue,
+        JsonRpcMethod::AdminModule => AuditAction::Module,
+        _ => return None,
+    };
+    Some(action)
+}
+
+/// Route a request, subscriptions belong to the connection so they are handled
+/// here rather than in `match_method`
+async fn dispatch(
+    subscriptions: &mut Subscriptions,
+    mut req: JsonRpcRequest,
+    state: &AppState,
+    context: RequestContext,
+    client: WsClient<'_>,
+) -> Result<Value, AppError> {
+    let class = method_class(&req.method);
+    // Held until the request completes, counting spends as in flight
+    let _permit = state
+        .rate_limiter
+        .acquire(
+            client.identity.map(|identity| identity.key_id.as_str()),
+            client.ip,
+            class,
+            Instant::now(),
+        )
+        .map_err(|limited| {
+            rate_limit_metrics::record_limited(class.as_str(), limited.as_str());
+            limited.into_app_error().with_context(context.clone())
+        })?;
+    let idempotency_key = take_idempotency_key(&mut req.params)?;
+    let audit = method_action(&req.method).map(|action| {
+        let method = serde_json::to_value(&req.method).unwrap_or_default();
+        let endpoint = format!("ws {}", method.as_str().unwrap_or_default());
+        (action, endpoint, req.params.clone())
+    });
+    let audit_context = context.clone();
+
+    let result = match req.method {
+        JsonRpcMethod::Subscribe => subscriptions.subscribe(state, req.params).await,
+        JsonRpcMethod::Unsubscribe => subscriptions.unsubscribe(req.params),
+        _ => match idempotency_key {
+            Some(key) => {
+                let method = serde_json::to_value(&req.method)?;
+                let method = method.as_str().unwrap_or_default().to_string();
+                let params = req.params.clone();
+                run_idempotent(
+                    state,
+                    client.identity.map(|identity| identity.key_id.as_str()),
+                    &key,
+                    &method,
+                    &params,
+                    match_method(req, state.clone(), context),
+                )
+                .await
+            }
+            None => match_method(req, state.clone(), context).await,
+        },
+    };
+
+    if let Some((action, endpoint, params)) = audit {
+        let outcome = match &result {
+            Ok(_) => AuditOutcome {
+                success: true,
+                status: 200,
+                error: None,
+            },
+            Err(e) => AuditOutcome {
+                success: false,
+                status: e.category.status_code().as_u16(),
+                error: Some(e.message.clone()),
+            },
+        };
+        audit::record(
+            state,
+            audit::entry(
+                action,
+                endpoint,
+                client.identity,
+                client.ip,
+                &audit_context,
+                &params,
+                outcome,
+            ),
+        )
+        .await;
+    }
+    result
+}
+
+async fn send_notification(
+    socket: &mut WebSocket,
+    notification: &JsonRpcNotification,
+    auth: &WebSocketAuth,
+) -> Result<(), anyhow::Error> {
+    let text = notification_text(notification, auth)?;
+    socket
+        .send(Message::Text(text))
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to send notification: {}", e))?;
+
+    Ok(())
+}
+
+/// Wire form of a notification, signed like responses when auth is enabled
+pub(crate) fn notification_text(
+    notification: &JsonRpcNotification,
+    auth: &WebSocketAuth,
+) -> Result<String, anyhow::Error> {
+    let notification = serde_json::to_value(notification)?;
+    if auth.is_enabled() {
+        let auth_notification = AuthenticatedMessage::new(notification, auth)
+            .map_err(|e| anyhow::anyhow!("Failed to create authenticated message: {}", e))?;
+        Ok(serde_json::to_string(&auth_notification)?)
+    } else {
+        Ok(serde_json::to_string(&notification)?)
+    }
+}
+
 fn create_json_rpc_response(res: Result<Value, AppError>, req_id: u64) -> JsonRpcResponse {
     match res {
         Ok(res) => JsonRpcResponse {
@@ -235,6 +478,9 @@ async fn match_method(
             handlers::admin::backup::handle_ws(state.clone(), req.params).await
         }
         JsonRpcMethod::AdminConfig => handlers::admin::config::handle_ws(state.clone()).await,
+        JsonRpcMethod::AdminEvents => {
+            handlers::admin::events::handle_ws(state.clone(), req.params).await
+        }
         JsonRpcMethod::AdminDiscoverVersion => {
             handlers::admin::version::handle_ws(state.clone()).await
         }
@@ -247,15 +493,75 @@ async fn match_method(
         JsonRpcMethod::AdminJoin => {
             handlers::admin::join::handle_ws(state.clone(), req.params).await
         }
+        JsonRpcMethod::AdminLeave => {
+            handlers::admin::leave::handle_ws(state.clone(), req.params).await
+        }
         JsonRpcMethod::AdminModule => {
             handlers::admin::module::handle_ws(state.clone(), req.params).await
         }
         JsonRpcMethod::AdminRestore => {
             handlers::admin::restore::handle_ws(state.clone(), req.params).await
         }
+        JsonRpcMethod::AdminRestoreStatus => {
+            handlers::admin::restore::handle_ws_status(state.clone()).await
+        }
         JsonRpcMethod::AdminListOperations => {
             handlers::admin::operations::handle_ws(state.clone(), req.params).await
         }
+        JsonRpcMethod::AdminWebhookList => {
+            handlers::admin::webhooks::handle_ws_list(state.clone()).await
+        }
+        JsonRpcMethod::AdminWebhookCreate => {
+            handlers::admin::webhooks::handle_ws_create(state.clone(), req.params).await
+        }
+        JsonRpcMethod::AdminWebhookUpdate => {
+            handlers::admin::webhooks::handle_ws_update(state.clone(), req.params).await
+        }
+        JsonRpcMethod::AdminWebhookDelete => {
+            handlers::admin::webhooks::handle_ws_delete(state.clone(), req.params).await
+        }
+        JsonRpcMethod::AdminWebhookDeliveries => {
+            handlers::admin::webhooks::handle_ws_deliveries(state.clone(), req.params).await
+        }
+        JsonRpcMethod::AdminWebhookReplay => {
+            handlers::admin::webhooks::handle_ws_replay(state.clone(), req.params).await
+        }
+        JsonRpcMethod::AdminWebhookDeadLetters => {
+            handlers::admin::webhooks::handle_ws_dead_letters(state.clone()).await
+        }
+        JsonRpcMethod::AdminWebhookRequeue => {
+            handlers::admin::webhooks::handle_ws_requeue(state.clone(), req.params).await
+        }
+        JsonRpcMethod::AdminApprovalList => {
+            handlers::admin::approvals::handle_ws_list(state.clone(), req.params).await
+        }
+        JsonRpcMethod::AdminApprovalApprove => {
+            handlers::admin::approvals::handle_ws_approve(state.clone(), req.params).await
+        }
+        JsonRpcMethod::AdminApprovalReject => {
+            handlers::admin::approvals::handle_ws_reject(state.clone(), req.params).await
+        }
+        JsonRpcMethod::AdminApiKeyList => {
+            handlers::admin::api_keys::handle_ws_list(state.clone()).await
+        }
+        JsonRpcMethod::AdminApiKeyCreate => {
+            handlers::admin::api_keys::handle_ws_create(state.clone(), req.params).await
+        }
+        JsonRpcMethod::AdminApiKeyRevoke => {
+            handlers::admin::api_keys::handle_ws_revoke(state.clone(), req.params).await
+        }
+        JsonRpcMethod::AdminAuthLockouts => {
+            handlers::admin::lockouts::handle_ws_list(state.clone()).await
+        }
+        JsonRpcMethod::AdminAuthClearLockouts => {
+            handlers::admin::lockouts::handle_ws_clear(state.clone()).await
+        }
+        JsonRpcMethod::AdminAudit => {
+            handlers::admin::audit::handle_ws(state.clone(), req.params).await
+        }
+        JsonRpcMethod::AdminAuditVerify => {
+            handlers::admin::audit::handle_ws_verify(state.clone()).await
+        }
         JsonRpcMethod::MintDecodeNotes => handlers::mint::decode_notes::handle_ws(req.params).await,
         JsonRpcMethod::MintEncodeNotes => handlers::mint::encode_notes::handle_ws(req.params).await,
         JsonRpcMethod::MintReissue => {
@@ -298,5 +604,15 @@ async fn match_method(
         JsonRpcMethod::WalletWithdraw => {
             handlers::onchain::withdraw::handle_ws(state.clone(), req.params).await
         }
+        JsonRpcMethod::WalletWithdrawQuote => {
+            handlers::onchain::withdraw_quote::handle_ws(state.clone(), req.params).await
+        }
+        JsonRpcMethod::WalletWithdrawStatus => {
+            handlers::onchain::status::handle_ws(state.clone(), req.params).await
+        }
+        JsonRpcMethod::Subscribe | JsonRpcMethod::Unsubscribe => Err(AppError::with_category(
+            ErrorCategory::ValidationError,
+            "Subscriptions are only available on a WebSocket connection".to_string(),
+        )),
     }
 }
@@ -0,0 +1,469 @@
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use fedimint_core::config::{FederationId, FederationIdPrefix};
+use fedimint_core::db::{Database, IDatabaseTransactionOpsCoreTyped};
+use fedimint_mint_client::OOBNotes;
+use futures_util::StreamExt;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::core::multimint::db::{ApiKeyKey, ApiKeyKeyPrefix, ApiKeyRecord};
+use crate::error::{AppError, ErrorCategory};
+
+/// Prefix of every generated API key, so leaked keys are easy to recognize
+pub const API_KEY_PREFIX: &str = "fmcd_";
+/// Prefix of every generated request signing secret
+pub const SIGNING_SECRET_PREFIX: &str = "fmcd_sig_";
+
+/// What an API key may be used for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ApiScope {
+    /// Balances, statuses and other calls that don't move funds
+    ReadOnly,
+    /// Receive funds: invoices, deposit addresses and reissuing ecash
+    Invoice,
+    /// Pay lightning invoices and spend ecash
+    Pay,
+    /// Withdraw on-chain
+    Withdraw,
+    /// Everything, including the admin endpoints
+    Admin,
+}
+
+impl ApiScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::ReadOnly => "read-only",
+            Self::Invoice => "invoice",
+            Self::Pay => "pay",
+            Self::Withdraw => "withdraw",
+            Self::Admin => "admin",
+        }
+    }
+
+    /// Scope needed to call a REST route. Routes not listed here need admin.
+    pub fn required_for(path: &str) -> Self {
+        let Some(route) = path.trim_end_matches('/').strip_prefix("/v2/") else {
+            return Self::Admin;
+        };
+        match route {
+            "ln/pay" | "mint/spend" => Self::Pay,
+            "onchain/withdraw" | "onchain/withdraw/quote" => Self::Withdraw,
+            "ln/invoice" | "onchain/deposit-address" | "onchain/await-deposit" | "mint/reissue" => {
+                Self::Invoice
+            }
+            "ln/invoice/status/bulk"
+            | "ln/gateways"
+            | "ln/events/stream"
+            | "mint/decode-notes"
+            | "mint/encode-notes"
+            | "mint/validate"
+            | "mint/split"
+            | "mint/combine"
+            | "admin/info"
+            | "admin/federations"
+            | "admin/operations"
+            | "admin/version"
+            | "admin/restore/status" => Self::ReadOnly,
+            _ if route.starts_with("ln/operation/") || route.starts_with("onchain/operation/") => {
+                Self::ReadOnly
+            }
+            _ => Self::Admin,
+        }
+    }
+}
+
+/// Who a request was authenticated as
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiIdentity {
+    pub key_id: String,
+    pub name: String,
+    pub scopes: Vec<ApiScope>,
+    /// Federations the key is restricted to, all when `None`
+    pub federations: Option<Vec<FederationId>>,
+}
+
+impl ApiIdentity {
+    /// The `http-password` from `fmcd.conf`, which has full access
+    pub fn password() -> Self {
+        Self {
+            key_id: "password".to_string(),
+            name: "fmcd".to_string(),
+            scopes: vec![ApiScope::Admin],
+            federations: None,
+        }
+    }
+
+    /// Every key may read, admin keys may do anything
+    pub fn allows(&self, scope: ApiScope) -> bool {
+        scope == ApiScope::ReadOnly
+            || self.scopes.contains(&ApiScope::Admin)
+            || self.scopes.contains(&scope)
+    }
+
+    pub fn allows_federation(&self, federation_id: &FederationId) -> bool {
+        self.federations
+            .as_ref()
+            .is_none_or(|federations| federations.contains(federation_id))
+    }
+
+    /// Whether the key may use the federation of notes with this prefix
+    pub fn allows_federation_prefix(&self, prefix: &FederationIdPrefix) -> bool {
+        self.federations.as_ref().is_none_or(|federations| {
+            federations
+                .iter()
+                .any(|federation_id| federation_id.to_prefix() == *prefix)
+        })
+    }
+}
+
+/// An API key as listed by the admin endpoints, without its hash
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiKeyInfo {
+    pub key_id: String,
+    pub name: String,
+    pub scopes: Vec<ApiScope>,
+    pub federations: Option<Vec<FederationId>>,
+    pub created_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+/// Request to create an API key
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateApiKeyRequest {
+    pub name: String,
+    pub scopes: Vec<ApiScope>,
+    /// Restrict the key to these federations
+    #[serde(default)]
+    pub federations: Option<Vec<FederationId>>,
+}
+
+/// A newly created API key. The key and its signing secret are only ever
+/// returned here.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatedApiKey {
+    #[serde(flatten)]
+    pub info: ApiKeyInfo,
+    pub key: String,
+    /// Secret to sign requests made as this key with, unrelated to the key
+    pub signing_secret: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StoredApiKey {
+    #[serde(flatten)]
+    info: ApiKeyInfo,
+    /// SHA-256 of the key, hex encoded
+    key_hash: String,
+    /// Secret requests signed as this key are signed with. Verifying an HMAC
+    /// needs the secret itself, so unlike the key it is stored as is. Keys
+    /// created before signing secrets existed have none and can't sign.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    signing_secret: Option<String>,
+}
+
+impl StoredApiKey {
+    fn identity(&self) -> ApiIdentity {
+        ApiIdentity {
+            key_id: self.info.key_id.clone(),
+            name: self.info.name.clone(),
+            scopes: self.info.scopes.clone(),
+            federations: self.info.federations.clone(),
+        }
+    }
+}
+
+/// Named API keys, stored hashed in the multimint database. Keys are cached
+/// in memory so authenticating a request doesn't touch the database.
+#[derive(Debug)]
+pub struct ApiKeyStore {
+    db: Database,
+    keys: RwLock<BTreeMap<String, StoredApiKey>>,
+}
+
+impl ApiKeyStore {
+    pub async fn load(db: Database) -> Result<Self> {
+        let mut dbtx = db.begin_transaction_nc().await;
+        let records = dbtx
+            .find_by_prefix(&ApiKeyKeyPrefix)
+            .await
+            .map(|(key, record)| (key.key_id, record))
+            .collect::<Vec<_>>()
+            .await;
+        drop(dbtx);
+
+        let mut keys = BTreeMap::new();
+        for (key_id, record) in records {
+            match serde_json::from_str::<StoredApiKey>(&record.json) {
+                Ok(key) => {
+                    keys.insert(key_id, key);
+                }
+                Err(e) => warn!(key_id = %key_id, error = ?e, "Skipping undecodable API key"),
+            }
+        }
+        Ok(Self {
+            db,
+            keys: RwLock::new(keys),
+        })
+    }
+
+    pub async fn create(&self, req: CreateApiKeyRequest) -> Result<CreatedApiKey, AppError> {
+        let name = req.name.trim().to_string();
+        if name.is_empty() {
+            return Err(AppError::validation_error("API key name must not be empty"));
+        }
+        if req.scopes.is_empty() {
+            return Err(AppError::validation_error(
+                "API key needs at least one scope",
+            ));
+        }
+        if req.federations.as_ref().is_some_and(Vec::is_empty) {
+            return Err(AppError::validation_error(
+                "API key restricted to no federations could never be used",
+            ));
+        }
+        if self
+            .list()
+            .iter()
+            .any(|key| key.revoked_at.is_none() && key.name == name)
+        {
+            return Err(AppError::with_category(
+                ErrorCategory::Conflict,
+                format!("An API key named {} exists already", name),
+            ));
+        }
+
+        let key = generate_secret(API_KEY_PREFIX);
+        let signing_secret = generate_secret(SIGNING_SECRET_PREFIX);
+
+        let mut scopes = req.scopes;
+        scopes.sort();
+        scopes.dedup();
+        let stored = StoredApiKey {
+            info: ApiKeyInfo {
+                key_id: Uuid::new_v4().to_string(),
+                name,
+                scopes,
+                federations: req.federations,
+                created_at: Utc::now(),
+                revoked_at: None,
+            },
+            key_hash: hash_key(&key),
+            signing_secret: Some(signing_secret.clone()),
+        };
+        self.save(&stored).await?;
+
+        info!(
+            key_id = %stored.info.key_id,
+            key_name = %stored.info.name,
+            "API key created"
+        );
+        Ok(CreatedApiKey {
+            info: stored.info,
+            key,
+            signing_secret,
+        })
+    }
+
+    /// All keys, revoked ones included, oldest first
+    pub fn list(&self) -> Vec<ApiKeyInfo> {
+        let mut keys = self
+            .keys
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .values()
+            .map(|key| key.info.clone())
+            .collect::<Vec<_>>();
+        keys.sort_by_key(|key| key.created_at);
+        keys
+    }
+
+    pub async fn revoke(&self, key_id: &str) -> Result<ApiKeyInfo, AppError> {
+        let mut stored = self
+            .keys
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .get(key_id)
+            .cloned()
+            .ok_or_else(|| AppError::not_found(format!("API key {} not found", key_id)))?;
+        if stored.info.revoked_at.is_some() {
+            return Err(AppError::with_category(
+                ErrorCategory::Conflict,
+                format!("API key {} is revoked already", key_id),
+            ));
+        }
+
+        stored.info.revoked_at = Some(Utc::now());
+        self.save(&stored).await?;
+
+        info!(
+            key_id = %stored.info.key_id,
+            key_name = %stored.info.name,
+            "API key revoked"
+        );
+        Ok(stored.info)
+    }
+
+    /// Identity of a key that exists and isn't revoked
+    pub fn authenticate(&self, key: &str) -> Option<ApiIdentity> {
+        if !key.starts_with(API_KEY_PREFIX) {
+            return None;
+        }
+        let key_hash = hash_key(key);
+        self.keys
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .values()
+            .find(|stored| stored.info.revoked_at.is_none() && stored.key_hash == key_hash)
+            .map(StoredApiKey::identity)
+    }
+
+    /// Identity and signing secret of a key that isn't revoked, by key id
+    pub fn signing_key(&self, key_id: &str) -> Option<(ApiIdentity, String)> {
+        self.keys
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .get(key_id)
+            .filter(|stored| stored.info.revoked_at.is_none())
+            .and_then(|stored| {
+                let signing_secret = stored.signing_secret.clone()?;
+                Some((stored.identity(), signing_secret))
+            })
+    }
+
+    async fn save(&self, stored: &StoredApiKey) -> Result<(), AppError> {
+        let record = ApiKeyRecord {
+            json: serde_json::to_string(stored)?,
+        };
+        let mut dbtx = self.db.begin_transaction().await;
+        dbtx.insert_entry(
+            &ApiKeyKey {
+                key_id: stored.info.key_id.clone(),
+            },
+            &record,
+        )
+        .await;
+        dbtx.commit_tx_result()
+            .await
+            .map_err(|e| AppError::internal_error(format!("Failed to store API key: {:?}", e)))?;
+
+        self.keys
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .insert(stored.info.key_id.clone(), stored.clone());
+        Ok(())
+    }
+}
+
+/// 32 random bytes, hex encoded after `prefix`
+fn generate_secret(prefix: &str) -> String {
+    let mut secret = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut secret);
+    format!("{}{}", prefix, hex::encode(secret))
+}
+
+/// SHA-256 of a key or password, hex encoded
+pub fn hash_key(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+/// Routes a key restricted to some federations may call without naming one,
+/// as they touch no federation
+const FEDERATION_FREE_ROUTES: &[&str] = &["admin/version", "mint/encode-notes"];
+
+/// Whether a key restricted to some federations must name one to call a
+/// route
+pub fn route_needs_federation(path: &str) -> bool {
+    path.trim_end_matches('/')
+        .strip_prefix("/v2/")
+        .is_none_or(|route| !FEDERATION_FREE_ROUTES.contains(&route))
+}
+
+/// Federations a request acts on
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RequestedFederations {
+    /// The `federationId` of the query or JSON body
+    pub federation_id: Option<FederationId>,
+    /// Federations of the ecash notes in the JSON body, which name no id
+    pub note_prefixes: Vec<FederationIdPrefix>,
+}
+
+impl RequestedFederations {
+    pub fn is_empty(&self) -> bool {
+        self.federation_id.is_none() && self.note_prefixes.is_empty()
+    }
+}
+
+/// Federations a request names in its `federationId` query parameters and
+/// JSON body field, or through the `notes` and `notesVec` it carries. Naming
+/// different federations in the query and the body is an error, as handlers
+/// only read one of them.
+pub fn requested_federations(query: Option<&str>, body: &[u8]) -> Result<RequestedFederations> {
+    let body = serde_json::from_slice::<serde_json::Value>(body).ok();
+    let from_query = query.into_iter().flat_map(|query| {
+        url::form_urlencoded::parse(query.as_bytes())
+            .filter(|(name, _)| name == "federationId")
+            .map(|(_, value)| value.into_owned())
+            .collect::<Vec<_>>()
+    });
+    let from_body = body
+        .as_ref()
+        .and_then(|body| body.get("federationId"))
+        .and_then(|id| id.as_str())
+        .map(str::to_string);
+
+    let mut federation_id = None;
+    for id in from_query.chain(from_body) {
+        let id = id
+            .parse::<FederationId>()
+            .map_err(|e| anyhow!("Invalid federationId {}: {}", id, e))?;
+        if let Some(named) = federation_id.replace(id) {
+            if named != id {
+                return Err(anyhow!(
+                    "Request names conflicting federations {} and {}",
+                    named,
+                    id
+                ));
+            }
+        }
+    }
+
+    let notes = body
+        .iter()
+        .flat_map(|body| {
+            let notes_vec = body
+                .get("notesVec")
+                .and_then(|notes_vec| notes_vec.as_array())
+                .into_iter()
+                .flatten();
+            body.get("notes").into_iter().chain(notes_vec)
+        })
+        .filter_map(|notes| notes.as_str());
+    let mut note_prefixes = Vec::new();
+    for notes in notes {
+        let prefix = notes
+            .parse::<OOBNotes>()
+            .map_err(|e| anyhow!("Invalid notes: {}", e))?
+            .federation_id_prefix();
+        if !note_prefixes.contains(&prefix) {
+            note_prefixes.push(prefix);
+        }
+    }
+
+    Ok(RequestedFederations {
+        federation_id,
+        note_prefixes,
+    })
+}
@@ -1,22 +1,45 @@
-use std::sync::Arc;
+use std::net::IpAddr;
+use std::sync::{Arc, OnceLock};
 
-use axum::body::Body;
-use axum::extract::Request;
-use axum::http::StatusCode;
+use axum::body::{to_bytes, Body, Bytes};
+use axum::extract::{Request, State};
+use axum::http::{header, HeaderValue, StatusCode};
 use axum::middleware::Next;
-use axum::response::Response;
+use axum::response::{IntoResponse, Response};
 use base64::Engine;
 use chrono::Utc;
+use hmac::digest::Key;
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use serde_json::json;
+use sha2::Sha256;
 use tracing::{info, warn};
 
+use super::api_keys::{
+    requested_federations, route_needs_federation, ApiIdentity, ApiKeyStore, ApiScope,
+};
+use super::lockout::{AuthGuard, Blocked};
+use super::signing::{
+    string_to_sign, RequestSigningConfig, RequestVerifier, SignatureError, KEY_ID_HEADER,
+    NONCE_HEADER, SIGNATURE_HEADER, TIMESTAMP_HEADER,
+};
+use crate::error::{AppError, ErrorCategory};
 use crate::events::{EventBus, FmcdEvent};
+use crate::metrics::auth_metrics;
 use crate::observability::correlation::RequestContext;
 
+/// Largest request body read to verify its signature, or to find the
+/// federation of a request made with a key restricted to some federations
+const MAX_BODY_SIZE: usize = 16 * 1024 * 1024;
+
 #[derive(Clone)]
 pub struct BasicAuth {
     username: String,
     password: String,
     enabled: bool,
+    api_keys: Option<Arc<ApiKeyStore>>,
+    signing: Option<Arc<RequestVerifier>>,
+    guard: Option<Arc<AuthGuard>>,
 }
 
 impl BasicAuth {
@@ -27,31 +50,452 @@ impl BasicAuth {
             username: "fmcd".to_string(),
             password: password.clone().unwrap_or_default(),
             enabled: password.is_some(),
+            api_keys: None,
+            signing: None,
+            guard: None,
         }
     }
 
+    /// Also accept the API keys in `api_keys`, as a bearer token or as the
+    /// Basic Auth password
+    pub fn with_api_keys(mut self, api_keys: Arc<ApiKeyStore>) -> Self {
+        self.api_keys = Some(api_keys);
+        self
+    }
+
+    /// Accept requests signed with an API key or the password, or only those
+    /// when signing is required
+    pub fn with_request_signing(mut self, config: RequestSigningConfig) -> Self {
+        self.signing = config
+            .enabled
+            .then(|| Arc::new(RequestVerifier::new(config)));
+        self
+    }
+
+    /// Refuse denied IPs and lock out clients that keep failing to
+    /// authenticate
+    pub fn with_guard(mut self, guard: Arc<AuthGuard>) -> Self {
+        self.guard = Some(guard);
+        self
+    }
+
     pub fn is_enabled(&self) -> bool {
         self.enabled
     }
 
     pub fn verify(&self, auth_header: &str) -> bool {
-        if !self.enabled {
-            return true;
+        !self.enabled || self.authenticate(auth_header).is_some()
+    }
+
+    /// Identity of the credentials in an `Authorization` header
+    pub fn authenticate(&self, auth_header: &str) -> Option<ApiIdentity> {
+        if let Some(token) = auth_header.strip_prefix("Bearer ") {
+            return self.api_key_identity(token.trim());
+        }
+
+        let credentials = auth_header.strip_prefix("Basic ")?;
+        let decoded = base64::engine::general_purpose::STANDARD
+            .decode(credentials)
+            .ok()?;
+        let decoded_str = String::from_utf8_lossy(&decoded);
+        if decoded_str == format!("{}:{}", self.username, self.password) {
+            return Some(ApiIdentity::password());
+        }
+        // API keys may be sent as the password with any username
+        let (_, password) = decoded_str.split_once(':')?;
+        self.api_key_identity(password)
+    }
+
+    /// Identity of a key id and the secret its requests are signed with
+    fn signing_key(&self, key_id: &str) -> Option<(ApiIdentity, String)> {
+        if key_id == ApiIdentity::password().key_id {
+            let secret = self.signing.as_ref()?.config().password_secret.clone()?;
+            return Some((ApiIdentity::password(), secret));
         }
+        self.api_keys
+            .as_ref()
+            .and_then(|api_keys| api_keys.signing_key(key_id))
+    }
+
+    fn api_key_identity(&self, key: &str) -> Option<ApiIdentity> {
+        self.api_keys
+            .as_ref()
+            .and_then(|api_keys| api_keys.authenticate(key))
+    }
+}
+
+/// Why a request was turned away
+enum AuthFailure {
+    MissingHeader,
+    InvalidCredentials,
+    InsufficientScope(ApiIdentity, ApiScope),
+    FederationNotAllowed(ApiIdentity, String),
+    FederationRequired(ApiIdentity),
+    InvalidFederation(ApiIdentity, String),
+    UnreadableBody(ApiIdentity),
+    Signature(SignatureError),
+    Blocked(Blocked),
+}
+
+impl AuthFailure {
+    fn reason(&self) -> &'static str {
+        match self {
+            Self::MissingHeader => "missing_authorization_header",
+            Self::InvalidCredentials => "invalid_credentials",
+            Self::InsufficientScope(..) => "insufficient_scope",
+            Self::FederationNotAllowed(..) => "federation_not_allowed",
+            Self::FederationRequired(_) => "federation_required",
+            Self::InvalidFederation(..) => "invalid_federation",
+            Self::UnreadableBody(_) => "unreadable_body",
+            Self::Signature(error) => error.as_str(),
+            Self::Blocked(blocked) => blocked.as_str(),
+        }
+    }
+
+    /// Failures that look like guessing credentials, as opposed to a valid
+    /// key calling a route it may not
+    fn counts_against_client(&self) -> bool {
+        matches!(
+            self,
+            Self::InvalidCredentials
+                | Self::Signature(SignatureError::InvalidSignature | SignatureError::UnknownKey)
+        )
+    }
 
-        if !auth_header.starts_with("Basic ") {
-            return false;
+    fn identity(&self) -> Option<&ApiIdentity> {
+        match self {
+            Self::MissingHeader
+            | Self::InvalidCredentials
+            | Self::Signature(_)
+            | Self::Blocked(_) => None,
+            Self::InsufficientScope(identity, _)
+            | Self::FederationNotAllowed(identity, _)
+            | Self::FederationRequired(identity)
+            | Self::InvalidFederation(identity, _)
+            | Self::UnreadableBody(identity) => Some(identity),
         }
+    }
 
-        let credentials = &auth_header[6..];
-        match base64::engine::general_purpose::STANDARD.decode(credentials) {
-            Ok(decoded) => {
-                let decoded_str = String::from_utf8_lossy(&decoded);
-                decoded_str == format!("{}:{}", self.username, self.password)
+    fn into_response(self, context: RequestContext) -> Response {
+        let error = match self {
+            Self::MissingHeader | Self::InvalidCredentials | Self::Signature(_) => {
+                return Response::builder()
+                    .status(StatusCode::UNAUTHORIZED)
+                    .header("WWW-Authenticate", "Basic realm=\"fmcd\"")
+                    .body(Body::from("Unauthorized"))
+                    .unwrap_or_else(|_| Response::new(Body::from("Unauthorized")));
+            }
+            Self::InsufficientScope(identity, scope) => AppError::with_category(
+                ErrorCategory::AuthorizationError,
+                format!(
+                    "API key {} lacks the {} scope",
+                    identity.name,
+                    scope.as_str()
+                ),
+            ),
+            Self::FederationNotAllowed(identity, federation_id) => AppError::with_category(
+                ErrorCategory::AuthorizationError,
+                format!(
+                    "API key {} may not use federation {}",
+                    identity.name, federation_id
+                ),
+            ),
+            Self::FederationRequired(identity) => AppError::with_category(
+                ErrorCategory::AuthorizationError,
+                format!(
+                    "API key {} is restricted to some federations, name one with federationId",
+                    identity.name
+                ),
+            ),
+            Self::InvalidFederation(_, message) => AppError::validation_error(message),
+            Self::UnreadableBody(_) => AppError::validation_error("Failed to read request body"),
+            Self::Blocked(blocked) => {
+                let (message, retry_after_secs) = match blocked {
+                    Blocked::IpDenied | Blocked::IpNotAllowed => {
+                        ("Client IP is not allowed".to_string(), None)
+                    }
+                    Blocked::LockedOut { retry_after_secs } => (
+                        format!(
+                            "Too many failed authentication attempts, retry in {} seconds",
+                            retry_after_secs
+                        ),
+                        Some(retry_after_secs),
+                    ),
+                };
+                let mut response = AppError::with_category(ErrorCategory::RateLimited, message)
+                    .with_details(json!({
+                        "reason": blocked.as_str(),
+                        "retryAfterSecs": retry_after_secs,
+                    }))
+                    .with_context(context)
+                    .into_response();
+                if let Some(retry_after_secs) = retry_after_secs {
+                    response
+                        .headers_mut()
+                        .insert(header::RETRY_AFTER, HeaderValue::from(retry_after_secs));
+                }
+                return response;
             }
-            Err(_) => false,
+        };
+        error.with_context(context).into_response()
+    }
+}
+
+/// Credential to count a request's failures against: the signing key id, or
+/// the hash of the bearer key or Basic Auth password whatever the username,
+/// so neither rotating usernames nor switching to a bearer token escapes it.
+/// The hashes are listed by the lockouts endpoint and logged, so they are
+/// keyed with `secret_hash`.
+fn attempted_credential(request: &Request) -> Option<String> {
+    let headers = request.headers();
+    if let Some(key_id) = headers
+        .get(KEY_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+    {
+        return Some(format!("key:{}", key_id));
+    }
+    let auth_header = headers
+        .get("Authorization")
+        .and_then(|value| value.to_str().ok())?;
+    if let Some(token) = auth_header.strip_prefix("Bearer ") {
+        return Some(format!("secret:{}", secret_hash(token.trim())));
+    }
+    let decoded = base64::engine::general_purpose::STANDARD
+        .decode(auth_header.strip_prefix("Basic ")?)
+        .ok()?;
+    let decoded_str = String::from_utf8_lossy(&decoded);
+    let (_, password) = decoded_str.split_once(':')?;
+    Some(format!("secret:{}", secret_hash(password)))
+}
+
+/// HMAC of an attempted secret under a key drawn once per process, so a
+/// listed hash can't be checked against guessed secrets offline
+fn secret_hash(secret: &str) -> String {
+    static KEY: OnceLock<Key<Hmac<Sha256>>> = OnceLock::new();
+    let key = KEY.get_or_init(|| {
+        let mut key = Key::<Hmac<Sha256>>::default();
+        rand::thread_rng().fill_bytes(&mut key);
+        key
+    });
+    let mut mac = <Hmac<Sha256> as Mac>::new(key);
+    mac.update(secret.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Refuse blocked IPs, then authorize the request and count the outcome
+/// against its IP and credential. Credential lockouts only refuse requests
+/// whose credentials failed, so nobody can lock out a credential they don't
+/// hold.
+async fn authorize_guarded(
+    auth: &BasicAuth,
+    client_ip: Option<IpAddr>,
+    request: Request,
+) -> Result<(ApiIdentity, Request), AuthFailure> {
+    let Some(guard) = &auth.guard else {
+        return authorize_request(auth, request).await;
+    };
+    let credential = attempted_credential(&request);
+    let now = Utc::now();
+    if let Err(blocked) = guard.check(client_ip, None, now) {
+        auth_metrics::record_blocked_request(blocked.as_str());
+        return Err(AuthFailure::Blocked(blocked));
+    }
+
+    let result = authorize_request(auth, request).await;
+    match &result {
+        Ok(_) => {
+            if let Some(credential) = &credential {
+                guard.record_success(credential);
+            }
+        }
+        Err(failure) if failure.counts_against_client() => {
+            let locked_out = credential
+                .as_deref()
+                .and_then(|credential| guard.check_credential(credential, now).err());
+            if let Some(blocked) = locked_out {
+                // Still counted against the IP, without extending the lockout
+                guard.record_failure(client_ip, None, now);
+                auth_metrics::record_blocked_request(blocked.as_str());
+                return Err(AuthFailure::Blocked(blocked));
+            }
+            guard.record_failure(client_ip, credential.as_deref(), now)
         }
+        Err(_) => {}
     }
+    result
+}
+
+/// Refuse denied IPs and locked out clients, whether or not authentication
+/// is enabled. Applied to the REST and WebSocket routes alike.
+pub async fn client_guard_middleware(
+    State(guard): State<Arc<AuthGuard>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let client_ip = guard.client_ip(&request);
+    if let Err(blocked) = guard.check(client_ip, None, Utc::now()) {
+        let context = request
+            .extensions()
+            .get::<RequestContext>()
+            .cloned()
+            .unwrap_or_else(|| RequestContext::new(None));
+        warn!(
+            method = %request.method(),
+            path = %request.uri().path(),
+            remote_addr = %client_addr(client_ip, &request),
+            reason = blocked.as_str(),
+            correlation_id = %context.correlation_id,
+            "Request refused before authentication"
+        );
+        auth_metrics::record_blocked_request(blocked.as_str());
+        return AuthFailure::Blocked(blocked).into_response(context);
+    }
+    next.run(request).await
+}
+
+/// IP to log a request under, `unknown` when it can't be told
+fn client_addr(client_ip: Option<IpAddr>, request: &Request) -> String {
+    client_ip
+        .map(|ip| ip.to_string())
+        .or_else(|| {
+            request
+                .headers()
+                .get("x-forwarded-for")
+                .and_then(|h| h.to_str().ok())
+                .map(str::to_string)
+        })
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Authenticate a request and check its key may call the route, for the
+/// federations it names. Keys restricted to some federations must name them.
+async fn authorize_request(
+    auth: &BasicAuth,
+    request: Request,
+) -> Result<(ApiIdentity, Request), AuthFailure> {
+    let signed = request.headers().contains_key(SIGNATURE_HEADER);
+    let (identity, request, signed_body) = match &auth.signing {
+        Some(verifier) if signed => {
+            let (identity, request, body) = verify_signed_request(auth, verifier, request).await?;
+            (identity, request, Some(body))
+        }
+        Some(verifier) if verifier.config().required => {
+            return Err(AuthFailure::Signature(SignatureError::MissingHeaders));
+        }
+        _ => {
+            let identity = request
+                .headers()
+                .get("Authorization")
+                .and_then(|header| header.to_str().ok())
+                .ok_or(AuthFailure::MissingHeader)
+                .and_then(|header| {
+                    auth.authenticate(header)
+                        .ok_or(AuthFailure::InvalidCredentials)
+                })?;
+            (identity, request, None)
+        }
+    };
+
+    let scope = ApiScope::required_for(request.uri().path());
+    if !identity.allows(scope) {
+        return Err(AuthFailure::InsufficientScope(identity, scope));
+    }
+    if identity.federations.is_none() {
+        return Ok((identity, request));
+    }
+
+    let (parts, body) = request.into_parts();
+    let body = match signed_body {
+        Some(body) => body,
+        None => match to_bytes(body, MAX_BODY_SIZE).await {
+            Ok(body) => body,
+            Err(_) => return Err(AuthFailure::UnreadableBody(identity)),
+        },
+    };
+    let requested = match requested_federations(parts.uri.query(), &body) {
+        Ok(requested) => requested,
+        Err(e) => return Err(AuthFailure::InvalidFederation(identity, e.to_string())),
+    };
+    if requested.is_empty() && route_needs_federation(parts.uri.path()) {
+        return Err(AuthFailure::FederationRequired(identity));
+    }
+    if let Some(federation_id) = requested.federation_id {
+        if !identity.allows_federation(&federation_id) {
+            return Err(AuthFailure::FederationNotAllowed(
+                identity,
+                federation_id.to_string(),
+            ));
+        }
+    }
+    if let Some(prefix) = requested
+        .note_prefixes
+        .iter()
+        .find(|prefix| !identity.allows_federation_prefix(prefix))
+    {
+        let federation = format!("with prefix {}", prefix);
+        return Err(AuthFailure::FederationNotAllowed(identity, federation));
+    }
+    Ok((identity, Request::from_parts(parts, Body::from(body))))
+}
+
+/// Check the signature headers of a request against its method, path, query
+/// and body
+async fn verify_signed_request(
+    auth: &BasicAuth,
+    verifier: &RequestVerifier,
+    request: Request,
+) -> Result<(ApiIdentity, Request, Bytes), AuthFailure> {
+    let header = |name: &str| {
+        request
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
+            .ok_or(AuthFailure::Signature(SignatureError::MissingHeaders))
+    };
+    let key_id = header(KEY_ID_HEADER)?;
+    let nonce = header(NONCE_HEADER)?;
+    let signature = header(SIGNATURE_HEADER)?;
+    let timestamp = header(TIMESTAMP_HEADER)?
+        .parse::<i64>()
+        .map_err(|_| AuthFailure::Signature(SignatureError::StaleTimestamp))?;
+    let (identity, secret) = auth
+        .signing_key(&key_id)
+        .ok_or(AuthFailure::Signature(SignatureError::UnknownKey))?;
+
+    let (parts, body) = request.into_parts();
+    let body = match to_bytes(body, MAX_BODY_SIZE).await {
+        Ok(body) => body,
+        Err(_) => return Err(AuthFailure::UnreadableBody(identity)),
+    };
+    let path_and_query = parts
+        .uri
+        .path_and_query()
+        .map(|path_and_query| path_and_query.as_str())
+        .unwrap_or_else(|| parts.uri.path());
+    let string_to_sign = string_to_sign(
+        timestamp,
+        &nonce,
+        parts.method.as_str(),
+        path_and_query,
+        &body,
+    );
+    verifier
+        .verify(
+            &secret,
+            &string_to_sign,
+            &signature,
+            timestamp,
+            &nonce,
+            Utc::now().timestamp(),
+        )
+        .map_err(AuthFailure::Signature)?;
+
+    Ok((
+        identity,
+        Request::from_parts(parts, Body::from(body.clone())),
+        body,
+    ))
 }
 
 /// Enhanced basic auth middleware with event publishing
@@ -63,12 +507,11 @@ pub async fn basic_auth_middleware_with_events(
 ) -> Result<Response, StatusCode> {
     let method = request.method().to_string();
     let path = request.uri().path().to_string();
-    let remote_addr = request
-        .headers()
-        .get("x-forwarded-for")
-        .and_then(|h| h.to_str().ok())
-        .unwrap_or("unknown")
-        .to_string();
+    let client_ip = auth
+        .guard
+        .as_ref()
+        .and_then(|guard| guard.client_ip(&request));
+    let remote_addr = client_addr(client_ip, &request);
 
     // Get or create request context
     let context = request
@@ -78,32 +521,29 @@ pub async fn basic_auth_middleware_with_events(
         .unwrap_or_else(|| RequestContext::new(None));
 
     // Publish authentication attempt event helper function
-    let publish_auth_event = |success: bool, reason: Option<String>| {
-        let event_bus = event_bus.clone();
-        let remote_addr = remote_addr.clone();
-        let path = path.clone();
-        let correlation_id = context.correlation_id.clone();
-        let user_id = if success {
-            Some(auth.username.clone())
-        } else {
-            None
-        };
+    let publish_auth_event =
+        |identity: Option<&ApiIdentity>, success: bool, reason: Option<String>| {
+            let event_bus = event_bus.clone();
+            let remote_addr = remote_addr.clone();
+            let path = path.clone();
+            let correlation_id = context.correlation_id.clone();
+            let user_id = identity.map(|identity| identity.name.clone());
 
-        tokio::spawn(async move {
-            let event = FmcdEvent::AuthenticationAttempt {
-                user_id,
-                ip_address: remote_addr,
-                endpoint: path,
-                success,
-                reason,
-                correlation_id: Some(correlation_id),
-                timestamp: Utc::now(),
-            };
-            if let Err(e) = event_bus.publish(event).await {
-                warn!("Failed to publish authentication event: {}", e);
-            }
-        });
-    };
+            tokio::spawn(async move {
+                let event = FmcdEvent::AuthenticationAttempt {
+                    user_id,
+                    ip_address: remote_addr,
+                    endpoint: path,
+                    success,
+                    reason,
+                    correlation_id: Some(correlation_id),
+                    timestamp: Utc::now(),
+                };
+                if let Err(e) = event_bus.publish(event).await {
+                    warn!("Failed to publish authentication event: {}", e);
+                }
+            });
+        };
 
     // If authentication is disabled, pass through
     if !auth.enabled {
@@ -117,18 +557,13 @@ pub async fn basic_auth_middleware_with_events(
         );
 
         // Still publish event for disabled auth (for monitoring purposes)
-        publish_auth_event(true, Some("authentication_disabled".to_string()));
+        publish_auth_event(None, true, Some("authentication_disabled".to_string()));
 
         return Ok(next.run(request).await);
     }
 
-    let auth_header = request
-        .headers()
-        .get("Authorization")
-        .and_then(|header| header.to_str().ok());
-
-    match auth_header {
-        Some(header) if auth.verify(header) => {
+    match authorize_guarded(&auth, client_ip, request).await {
+        Ok((identity, mut request)) => {
             info!(
                 method = %method,
                 path = %path,
@@ -136,17 +571,19 @@ pub async fn basic_auth_middleware_with_events(
                 auth_enabled = true,
                 auth_result = "success",
                 auth_type = "basic",
-                username = %auth.username,
+                key_id = %identity.key_id,
+                key_name = %identity.name,
                 correlation_id = %context.correlation_id,
                 "Authentication successful"
             );
 
             // Publish successful authentication event
-            publish_auth_event(true, None);
+            publish_auth_event(Some(&identity), true, None);
 
+            request.extensions_mut().insert(identity);
             Ok(next.run(request).await)
         }
-        Some(_) => {
+        Err(failure) => {
             warn!(
                 method = %method,
                 path = %path,
@@ -154,43 +591,21 @@ pub async fn basic_auth_middleware_with_events(
                 auth_enabled = true,
                 auth_result = "failure",
                 auth_type = "basic",
-                failure_reason = "invalid_credentials",
+                failure_reason = failure.reason(),
+                key_id = failure.identity().map(|identity| identity.key_id.as_str()),
+                key_name = failure.identity().map(|identity| identity.name.as_str()),
                 correlation_id = %context.correlation_id,
-                "Authentication failed - invalid credentials"
+                "Authentication failed"
             );
 
             // Publish failed authentication event
-            publish_auth_event(false, Some("invalid_credentials".to_string()));
-
-            let response = Response::builder()
-                .status(StatusCode::UNAUTHORIZED)
-                .header("WWW-Authenticate", "Basic realm=\"fmcd\"")
-                .body(Body::from("Unauthorized"))
-                .unwrap_or_else(|_| Response::new(Body::from("Unauthorized")));
-            Ok(response)
-        }
-        None => {
-            warn!(
-                method = %method,
-                path = %path,
-                remote_addr = %remote_addr,
-                auth_enabled = true,
-                auth_result = "failure",
-                auth_type = "basic",
-                failure_reason = "missing_authorization_header",
-                correlation_id = %context.correlation_id,
-                "Authentication failed - missing Authorization header"
+            publish_auth_event(
+                failure.identity(),
+                false,
+                Some(failure.reason().to_string()),
             );
 
-            // Publish failed authentication event
-            publish_auth_event(false, Some("missing_authorization_header".to_string()));
-
-            let response = Response::builder()
-                .status(StatusCode::UNAUTHORIZED)
-                .header("WWW-Authenticate", "Basic realm=\"fmcd\"")
-                .body(Body::from("Unauthorized"))
-                .unwrap_or_else(|_| Response::new(Body::from("Unauthorized")));
-            Ok(response)
+            Ok(failure.into_response(context))
         }
     }
 }
@@ -204,11 +619,16 @@ pub async fn basic_auth_middleware(
 ) -> Result<Response, StatusCode> {
     let method = request.method().to_string();
     let path = request.uri().path().to_string();
-    let remote_addr = request
-        .headers()
-        .get("x-forwarded-for")
-        .and_then(|h| h.to_str().ok())
-        .unwrap_or("unknown");
+    let client_ip = auth
+        .guard
+        .as_ref()
+        .and_then(|guard| guard.client_ip(&request));
+    let remote_addr = client_addr(client_ip, &request);
+    let context = request
+        .extensions()
+        .get::<RequestContext>()
+        .cloned()
+        .unwrap_or_else(|| RequestContext::new(None));
 
     // If authentication is disabled, pass through
     if !auth.enabled {
@@ -223,13 +643,8 @@ pub async fn basic_auth_middleware(
         return Ok(next.run(request).await);
     }
 
-    let auth_header = request
-        .headers()
-        .get("Authorization")
-        .and_then(|header| header.to_str().ok());
-
-    match auth_header {
-        Some(header) if auth.verify(header) => {
+    match authorize_guarded(&auth, client_ip, request).await {
+        Ok((identity, mut request)) => {
             info!(
                 method = %method,
                 path = %path,
@@ -237,30 +652,14 @@ pub async fn basic_auth_middleware(
                 auth_enabled = true,
                 auth_result = "success",
                 auth_type = "basic",
-                username = %auth.username,
+                key_id = %identity.key_id,
+                key_name = %identity.name,
                 "Authentication successful"
             );
+            request.extensions_mut().insert(identity);
             Ok(next.run(request).await)
         }
-        Some(_) => {
-            warn!(
-                method = %method,
-                path = %path,
-                remote_addr = %remote_addr,
-                auth_enabled = true,
-                auth_result = "failure",
-                auth_type = "basic",
-                failure_reason = "invalid_credentials",
-                "Authentication failed - invalid credentials"
-            );
-            let response = Response::builder()
-                .status(StatusCode::UNAUTHORIZED)
-                .header("WWW-Authenticate", "Basic realm=\"fmcd\"")
-                .body(Body::from("Unauthorized"))
-                .unwrap_or_else(|_| Response::new(Body::from("Unauthorized")));
-            Ok(response)
-        }
-        None => {
+        Err(failure) => {
             warn!(
                 method = %method,
                 path = %path,
@@ -268,15 +667,12 @@ pub async fn basic_auth_middleware(
                 auth_enabled = true,
                 auth_result = "failure",
                 auth_type = "basic",
-                failure_reason = "missing_authorization_header",
-                "Authentication failed - missing Authorization header"
+                failure_reason = failure.reason(),
+                key_id = failure.identity().map(|identity| identity.key_id.as_str()),
+                key_name = failure.identity().map(|identity| identity.name.as_str()),
+                "Authentication failed"
             );
-            let response = Response::builder()
-                .status(StatusCode::UNAUTHORIZED)
-                .header("WWW-Authenticate", "Basic realm=\"fmcd\"")
-                .body(Body::from("Unauthorized"))
-                .unwrap_or_else(|_| Response::new(Body::from("Unauthorized")));
-            Ok(response)
+            Ok(failure.into_response(context))
         }
     }
 }
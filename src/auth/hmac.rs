@@ -93,7 +93,7 @@ impl WebSocketAuth {
 }
 
 /// WebSocket message format with HMAC authentication
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct AuthenticatedMessage {
     pub timestamp: i64,
     pub signature: String,
@@ -0,0 +1,405 @@
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Mutex;
+
+use anyhow::{anyhow, Result};
+use axum::extract::{ConnectInfo, Request};
+use axum::http::HeaderMap;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::metrics::auth_metrics;
+
+/// Clients tracked at most, the ones that failed longest ago are forgotten
+/// first beyond it
+const MAX_TRACKED_CLIENTS: usize = 100_000;
+
+/// Brute-force protection and IP filtering for authenticated routes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AuthProtectionConfig {
+    /// Lock out clients that keep failing to authenticate
+    pub enabled: bool,
+    /// Failed attempts allowed before a lockout
+    pub max_failures: u32,
+    /// First lockout, doubled with every failure after it
+    pub base_lockout_secs: u64,
+    pub max_lockout_secs: u64,
+    /// Failures are forgotten after this long without another one
+    pub failure_window_secs: u64,
+    /// When not empty, only these IPs or CIDR ranges may connect
+    pub ip_allow_list: Vec<String>,
+    /// IPs or CIDR ranges that may never connect
+    pub ip_deny_list: Vec<String>,
+    /// Take the client IP from the right-most `X-Forwarded-For` entry, only
+    /// safe behind a proxy that appends to it
+    pub trust_forwarded_for: bool,
+}
+
+impl Default for AuthProtectionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_failures: 5,
+            base_lockout_secs: 30,
+            max_lockout_secs: 3600,
+            failure_window_secs: 900,
+            ip_allow_list: Vec::new(),
+            ip_deny_list: Vec::new(),
+            trust_forwarded_for: false,
+        }
+    }
+}
+
+/// An IP address or CIDR range
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct IpRange {
+    addr: IpAddr,
+    prefix_len: u8,
+}
+
+impl IpRange {
+    fn parse(s: &str) -> Result<Self> {
+        let (addr, prefix_len) = match s.trim().split_once('/') {
+            Some((addr, prefix_len)) => (addr, Some(prefix_len)),
+            None => (s.trim(), None),
+        };
+        let addr = addr
+            .parse::<IpAddr>()
+            .map_err(|e| anyhow!("Invalid IP address {}: {}", s, e))?;
+        let max_len = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix_len = match prefix_len {
+            Some(prefix_len) => prefix_len
+                .parse::<u8>()
+                .ok()
+                .filter(|prefix_len| *prefix_len <= max_len)
+                .ok_or_else(|| anyhow!("Invalid CIDR prefix length in {}", s))?,
+            None => max_len,
+        };
+        Ok(Self { addr, prefix_len })
+    }
+
+    fn contains(&self, ip: &IpAddr) -> bool {
+        let (range, ip, bits) = match (self.addr, ip.to_canonical()) {
+            (IpAddr::V4(range), IpAddr::V4(ip)) => {
+                (u128::from(u32::from(range)), u128::from(u32::from(ip)), 32)
+            }
+            (IpAddr::V6(range), IpAddr::V6(ip)) => (u128::from(range), u128::from(ip), 128),
+            _ => return false,
+        };
+        let host_bits = bits - u32::from(self.prefix_len);
+        let mask = u128::MAX.checked_shl(host_bits).unwrap_or(0);
+        range & mask == ip & mask
+    }
+}
+
+/// Who failed to authenticate
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum ClientKey {
+    Ip(IpAddr),
+    /// Hash of the presented secret, or the signing key id
+    Credential(String),
+}
+
+#[derive(Debug, Clone)]
+struct FailureState {
+    failures: u32,
+    last_failure: DateTime<Utc>,
+    locked_until: Option<DateTime<Utc>>,
+}
+
+/// A client with recent authentication failures
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrackedClient {
+    /// `ip` or `credential`
+    pub kind: &'static str,
+    pub client: String,
+    pub failures: u32,
+    pub last_failure: DateTime<Utc>,
+    pub locked_until: Option<DateTime<Utc>>,
+}
+
+/// Why a request was refused before authenticating it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Blocked {
+    IpDenied,
+    IpNotAllowed,
+    LockedOut { retry_after_secs: u64 },
+}
+
+impl Blocked {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::IpDenied => "ip_denied",
+            Self::IpNotAllowed => "ip_not_allowed",
+            Self::LockedOut { .. } => "locked_out",
+        }
+    }
+}
+
+/// Counts authentication failures per IP and per credential, and locks out
+/// clients that keep failing
+#[derive(Debug)]
+pub struct AuthGuard {
+    config: AuthProtectionConfig,
+    allow_list: Vec<IpRange>,
+    deny_list: Vec<IpRange>,
+    clients: Mutex<HashMap<ClientKey, FailureState>>,
+}
+
+impl AuthGuard {
+    pub fn new(config: AuthProtectionConfig) -> Result<Self> {
+        let parse = |list: &[String]| {
+            list.iter()
+                .map(|range| IpRange::parse(range))
+                .collect::<Result<Vec<_>>>()
+        };
+        Ok(Self {
+            allow_list: parse(&config.ip_allow_list)?,
+            deny_list: parse(&config.ip_deny_list)?,
+            config,
+            clients: Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub fn config(&self) -> &AuthProtectionConfig {
+        &self.config
+    }
+
+    /// IP the request came from, `None` when the server wasn't started with
+    /// connect info and no trusted `X-Forwarded-For` is set
+    pub fn client_ip(&self, request: &Request) -> Option<IpAddr> {
+        let peer = request
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| *addr);
+        self.client_ip_from(request.headers(), peer)
+    }
+
+    /// IP a client connected from, given its headers and peer address. Only
+    /// the right-most `X-Forwarded-For` entry, the one the trusted proxy
+    /// appended, is taken; the ones before it are whatever the client sent.
+    pub fn client_ip_from(&self, headers: &HeaderMap, peer: Option<SocketAddr>) -> Option<IpAddr> {
+        let forwarded = self
+            .config
+            .trust_forwarded_for
+            .then(|| {
+                headers
+                    .get_all("x-forwarded-for")
+                    .iter()
+                    .last()
+                    .and_then(|header| header.to_str().ok())
+                    .and_then(|header| header.rsplit(',').next())
+                    .and_then(|ip| ip.trim().parse::<IpAddr>().ok())
+            })
+            .flatten();
+        forwarded.or_else(|| peer.map(|addr| addr.ip()))
+    }
+
+    /// Refuse denied IPs and locked out clients
+    pub fn check(
+        &self,
+        ip: Option<IpAddr>,
+        credential: Option<&str>,
+        now: DateTime<Utc>,
+    ) -> Result<(), Blocked> {
+        if let Some(ip) = ip {
+            if self.deny_list.iter().any(|range| range.contains(&ip)) {
+                return Err(Blocked::IpDenied);
+            }
+        }
+        if !self.allow_list.is_empty()
+            && !ip.is_some_and(|ip| self.allow_list.iter().any(|range| range.contains(&ip)))
+        {
+            return Err(Blocked::IpNotAllowed);
+        }
+        self.check_lockout(Self::keys(ip, credential), now)
+    }
+
+    /// Refuse a locked out credential. Only meant for credentials that failed
+    /// to verify, so a lockout never refuses a request whose credentials are
+    /// right.
+    pub fn check_credential(&self, credential: &str, now: DateTime<Utc>) -> Result<(), Blocked> {
+        self.check_lockout(Self::keys(None, Some(credential)), now)
+    }
+
+    fn check_lockout(
+        &self,
+        keys: impl Iterator<Item = ClientKey>,
+        now: DateTime<Utc>,
+    ) -> Result<(), Blocked> {
+        if !self.config.enabled {
+            return Ok(());
+        }
+
+        let clients = self
+            .clients
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let locked_until = keys
+            .filter_map(|key| clients.get(&key))
+            .filter_map(|state| state.locked_until)
+            .filter(|locked_until| *locked_until > now)
+            .max();
+        match locked_until {
+            Some(locked_until) => {
+                // Rounded up, so clients retrying after it aren't refused again
+                let millis = u64::try_from((locked_until - now).num_milliseconds()).unwrap_or(0);
+                Err(Blocked::LockedOut {
+                    retry_after_secs: millis.div_ceil(1000).max(1),
+                })
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Count a failed authentication, locking the client out once it failed
+    /// `max_failures` times
+    pub fn record_failure(&self, ip: Option<IpAddr>, credential: Option<&str>, now: DateTime<Utc>) {
+        if !self.config.enabled {
+            return;
+        }
+        let window = seconds(self.config.failure_window_secs);
+        let mut clients = self
+            .clients
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        for key in Self::keys(ip, credential) {
+            if !clients.contains_key(&key) && clients.len() >= MAX_TRACKED_CLIENTS {
+                Self::evict(&mut clients, now, window);
+            }
+            let state = clients.entry(key.clone()).or_insert(FailureState {
+                failures: 0,
+                last_failure: now,
+                locked_until: None,
+            });
+            if state.failures > 0
+                && state.locked_until.is_none_or(|until| until <= now)
+                && now - state.last_failure > window
+            {
+                state.failures = 0;
+            }
+            state.failures += 1;
+            state.last_failure = now;
+
+            if let Some(lockout_secs) = self.lockout_secs(state.failures) {
+                state.locked_until = Some(
+                    now.checked_add_signed(seconds(lockout_secs))
+                        .unwrap_or(DateTime::<Utc>::MAX_UTC),
+                );
+                auth_metrics::record_lockout(key.kind());
+                warn!(
+                    client_kind = key.kind(),
+                    client = %key.value(),
+                    failures = state.failures,
+                    lockout_secs = lockout_secs,
+                    "Client locked out after repeated authentication failures"
+                );
+            }
+        }
+    }
+
+    /// Forget the failures of a credential that authenticated. Failures of
+    /// the IP are left to fall out of the window, so holding one valid
+    /// credential doesn't reset guessing at others from the same IP.
+    pub fn record_success(&self, credential: &str) {
+        self.clients
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .remove(&ClientKey::Credential(credential.to_string()));
+    }
+
+    /// Clients with recent failures, locked out ones first
+    pub fn tracked_clients(&self, now: DateTime<Utc>) -> Vec<TrackedClient> {
+        let mut tracked = self
+            .clients
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .iter()
+            .map(|(key, state)| TrackedClient {
+                kind: key.kind(),
+                client: key.value(),
+                failures: state.failures,
+                last_failure: state.last_failure,
+                locked_until: state.locked_until.filter(|until| *until > now),
+            })
+            .collect::<Vec<_>>();
+        tracked.sort_by(|a, b| {
+            b.locked_until
+                .cmp(&a.locked_until)
+                .then(b.last_failure.cmp(&a.last_failure))
+        });
+        tracked
+    }
+
+    /// Lift every lockout and forget all failures, returns how many clients
+    /// were tracked
+    pub fn clear(&self) -> usize {
+        let mut clients = self
+            .clients
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let cleared = clients.len();
+        clients.clear();
+        cleared
+    }
+
+    fn lockout_secs(&self, failures: u32) -> Option<u64> {
+        let over = failures.checked_sub(self.config.max_failures)?;
+        Some(
+            self.config
+                .base_lockout_secs
+                .saturating_mul(2u64.saturating_pow(over))
+                .min(self.config.max_lockout_secs),
+        )
+    }
+
+    fn keys(ip: Option<IpAddr>, credential: Option<&str>) -> impl Iterator<Item = ClientKey> {
+        ip.map(ClientKey::Ip)
+            .into_iter()
+            .chain(credential.map(|credential| ClientKey::Credential(credential.to_string())))
+    }
+
+    /// Forget clients that aren't locked out and failed outside the window,
+    /// or the one that failed longest ago if all are recent
+    fn evict(clients: &mut HashMap<ClientKey, FailureState>, now: DateTime<Utc>, window: Duration) {
+        clients.retain(|_, state| {
+            state.locked_until.is_some_and(|until| until > now)
+                || now - state.last_failure <= window
+        });
+        if clients.len() >= MAX_TRACKED_CLIENTS {
+            let oldest = clients
+                .iter()
+                .min_by_key(|(_, state)| state.last_failure)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                clients.remove(&oldest);
+            }
+        }
+    }
+}
+
+impl ClientKey {
+    fn kind(&self) -> &'static str {
+        match self {
+            Self::Ip(_) => "ip",
+            Self::Credential(_) => "credential",
+        }
+    }
+
+    fn value(&self) -> String {
+        match self {
+            Self::Ip(ip) => ip.to_string(),
+            Self::Credential(credential) => credential.clone(),
+        }
+    }
+}
+
+fn seconds(secs: u64) -> Duration {
+    i64::try_from(secs)
+        .ok()
+        .and_then(Duration::try_seconds)
+        .unwrap_or(Duration::MAX)
+}
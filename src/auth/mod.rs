@@ -1,5 +1,13 @@
+pub mod api_keys;
 pub mod basic;
 pub mod hmac;
+pub mod lockout;
+pub mod signing;
 
-pub use basic::{basic_auth_middleware, basic_auth_middleware_with_events, BasicAuth};
+pub use api_keys::{ApiIdentity, ApiKeyStore, ApiScope};
+pub use basic::{
+    basic_auth_middleware, basic_auth_middleware_with_events, client_guard_middleware, BasicAuth,
+};
 pub use hmac::{AuthenticatedMessage, WebSocketAuth};
+pub use lockout::{AuthGuard, AuthProtectionConfig};
+pub use signing::RequestSigningConfig;
@@ -1,6 +1,6 @@
 use std::future::ready;
-use std::path::PathBuf;
-use std::str::FromStr;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::Instant;
 
@@ -9,48 +9,42 @@ use axum::extract::{MatchedPath, Request};
 use axum::http::Method;
 use axum::middleware::{self, Next};
 use axum::response::IntoResponse;
-use axum::routing::{get, post};
+use axum::routing::{delete, get, post, put};
 use axum::Router;
-use clap::{Parser, Subcommand, ValueEnum};
+use clap::{Parser, Subcommand};
 use console::{style, Term};
 use fedimint_core::invite_code::InviteCode;
+use fmcd::api::audit::audit_middleware;
+use fmcd::api::idempotency::idempotency_middleware;
+use fmcd::api::rate_limit::rate_limit_middleware;
 use fmcd::api::rest::{admin, ln, mint, onchain};
 use fmcd::api::websockets::websocket_handler;
-use fmcd::auth::{basic_auth_middleware, BasicAuth, WebSocketAuth};
-use fmcd::config::Config;
+use fmcd::auth::{
+    basic_auth_middleware_with_events, client_guard_middleware, BasicAuth, WebSocketAuth,
+};
+use fmcd::config::{Config, Mode, CONFIG_FILE_NAME};
+use fmcd::core::audit::{verify_chain, AuditRecord};
 use fmcd::core::FmcdCore;
 use fmcd::health::{health_check, liveness_check, readiness_check};
 use fmcd::metrics::{api_metrics, init_prometheus_metrics};
 use fmcd::observability::correlation::create_request_id_middleware;
 use fmcd::observability::{init_logging, LoggingConfig};
 use fmcd::state::AppState;
-use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
 use tower_http::cors::{Any, CorsLayer};
 use tower_http::trace::TraceLayer;
 use tracing::info;
 
-#[derive(Clone, Debug, ValueEnum, PartialEq)]
-enum Mode {
-    Rest,
-    Ws,
-}
-
-impl FromStr for Mode {
-    type Err = anyhow::Error;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "rest" => Ok(Mode::Rest),
-            "ws" => Ok(Mode::Ws),
-            _ => Err(anyhow::anyhow!("Invalid mode")),
-        }
-    }
-}
-
 #[derive(Subcommand)]
 enum Commands {
     Start,
     Stop,
+    /// Check the hash chain of an audit log exported from
+    /// `/v2/admin/audit/export`
+    VerifyAudit {
+        /// Newline-delimited JSON export
+        #[clap(long)]
+        file: PathBuf,
+    },
 }
 
 #[derive(Parser)]
@@ -76,13 +70,16 @@ struct Cli {
     #[clap(long, env = "FMCD_MANUAL_SECRET")]
     manual_secret: Option<String>,
 
-    /// Mode: ws, rest
+    /// Mode: ws, rest, all
     #[clap(long, env = "FMCD_MODE", default_value = "rest")]
     mode: Mode,
 
     /// Disable authentication
     #[clap(long)]
     no_auth: bool,
+
+    #[clap(subcommand)]
+    command: Option<Commands>,
 }
 
 // const PID_FILE: &str = "/tmp/fedimint_http.pid";
@@ -93,12 +90,18 @@ async fn main() -> Result<()> {
 
     let cli: Cli = Cli::parse();
 
+    match &cli.command {
+        Some(Commands::VerifyAudit { file }) => return verify_audit(file),
+        Some(Commands::Stop) => return Err(anyhow::anyhow!("`stop` is not supported")),
+        Some(Commands::Start) | None => {}
+    }
+
     // Initialize structured logging
     let log_config = LoggingConfig {
         level: std::env::var("LOG_LEVEL").unwrap_or_else(|_| "info".to_string()),
         log_dir: cli.data_dir.join("logs"),
-        console_output: !std::env::var("NO_CONSOLE_LOG").is_ok(),
-        file_output: !std::env::var("NO_FILE_LOG").is_ok(),
+        console_output: std::env::var("NO_CONSOLE_LOG").is_err(),
+        file_output: std::env::var("NO_FILE_LOG").is_err(),
         ..Default::default()
     };
     init_logging(log_config)?;
@@ -109,7 +112,7 @@ async fn main() -> Result<()> {
     std::fs::create_dir_all(&cli.data_dir)?;
 
     // Config file is always in data_dir
-    let config_path = cli.data_dir.join("fmcd.conf");
+    let config_path = cli.data_dir.join(CONFIG_FILE_NAME);
 
     // Load or create configuration file with automatic password generation
     let term = Term::stdout();
@@ -149,7 +152,9 @@ async fn main() -> Result<()> {
     }
 
     // Initialize FmcdCore with the data directory
-    let mut core = FmcdCore::new_with_config(cli.data_dir.clone(), config.webhooks.clone()).await?;
+    let core = FmcdCore::new_with_config(cli.data_dir.clone(), config.webhooks.clone())
+        .await?
+        .with_spending_policy(config.spending_policy.clone());
 
     // Handle federation invite code
     if let Some(invite_code_str) = &config.invite_code {
@@ -179,39 +184,50 @@ async fn main() -> Result<()> {
     }
 
     // Create AppState with the core
-    let state = AppState::new_with_core(core).await?;
+    let state = AppState::new_with_core(core)
+        .await?
+        .with_auth_protection(config.auth_protection.clone())?
+        .with_rate_limiting(config.rate_limiting.clone());
 
     start_main_server(&config, cli.mode, state).await?;
     Ok(())
 }
 
+/// Verify an exported audit log offline, exiting non-zero if the chain is
+/// broken
+fn verify_audit(file: &Path) -> Result<()> {
+    let export = std::fs::read_to_string(file)?;
+    let records = export
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| serde_json::from_str::<AuditRecord>(line).map_err(anyhow::Error::from));
+    let verification = verify_chain(records);
+    println!("{}", serde_json::to_string_pretty(&verification)?);
+    if !verification.valid {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
 async fn start_main_server(config: &Config, mode: Mode, state: AppState) -> anyhow::Result<()> {
     // Create authentication instances
-    let basic_auth = Arc::new(BasicAuth::new(config.http_password.clone()));
+    let basic_auth = Arc::new(
+        BasicAuth::new(config.http_password.clone())
+            .with_api_keys(state.api_keys.clone())
+            .with_request_signing(config.request_signing.clone())
+            .with_guard(state.auth_guard.clone()),
+    );
     let ws_auth = Arc::new(WebSocketAuth::new(config.http_password.clone()));
 
-    // Create the router based on mode
-    let app = match mode {
-        Mode::Rest => {
-            let router = Router::new()
-                .nest("/v2", fedimint_v2_rest())
-                .with_state(state);
-
-            // Apply authentication middleware if enabled
-            if basic_auth.is_enabled() {
-                let auth_clone = basic_auth.clone();
-                router.route_layer(middleware::from_fn(move |request, next| {
-                    basic_auth_middleware(auth_clone.clone(), request, next)
-                }))
-            } else {
-                router
-            }
-        }
-        Mode::Ws => Router::new()
-            .route("/ws", get(websocket_handler))
-            .with_state(state.clone())
-            .layer(axum::Extension(ws_auth)),
-    };
+    let listeners = config.listeners(&mode);
+
+    let mut app = Router::new();
+    if listeners.rest {
+        app = app.merge(rest_router(state.clone(), basic_auth));
+    }
+    if listeners.ws {
+        app = app.merge(ws_router(state.clone(), ws_auth.clone()));
+    }
 
     let auth_status = if config.is_auth_enabled() {
         "enabled"
@@ -220,46 +236,101 @@ async fn start_main_server(config: &Config, mode: Mode, state: AppState) -> anyh
     };
     info!("Starting server in {mode:?} mode with authentication {auth_status}");
 
+    // Initialize comprehensive metrics system
+    let metrics_handle = init_prometheus_metrics().await?;
+
+    let app = with_common_layers(app, config)
+        .route("/health", get(health_check))
+        .route("/health/live", get(liveness_check))
+        .route("/health/ready", get(readiness_check))
+        .route("/metrics", get(move || ready(metrics_handle.render())))
+        .route_layer(middleware::from_fn(track_metrics));
+
+    if let Some(ws_address) = listeners.separate_ws {
+        let ws_app = with_common_layers(ws_router(state, ws_auth), config)
+            .route_layer(middleware::from_fn(track_metrics));
+        tokio::try_join!(serve(listeners.address, app), serve(ws_address, ws_app))?;
+    } else {
+        serve(listeners.address, app).await?;
+    }
+    Ok(())
+}
+
+fn rest_router(state: AppState, basic_auth: Arc<BasicAuth>) -> Router {
+    let event_bus = state.core.event_bus.clone();
+    let auth_guard = state.auth_guard.clone();
+    let router = Router::new()
+        .nest("/v2", fedimint_v2_rest())
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            idempotency_middleware,
+        ))
+        // Outside idempotency, so replayed responses are recorded too
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            audit_middleware,
+        ))
+        // Inside the auth layer, so requests are limited by their API identity
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            rate_limit_middleware,
+        ))
+        .with_state(state);
+
+    // Apply authentication middleware if enabled
+    let router = if basic_auth.is_enabled() {
+        router.route_layer(middleware::from_fn(move |request, next| {
+            basic_auth_middleware_with_events(basic_auth.clone(), event_bus.clone(), request, next)
+        }))
+    } else {
+        router
+    };
+    // Outermost, so IP filters and lockouts hold with authentication disabled
+    router.route_layer(middleware::from_fn_with_state(
+        auth_guard,
+        client_guard_middleware,
+    ))
+}
+
+fn ws_router(state: AppState, ws_auth: Arc<WebSocketAuth>) -> Router {
+    let auth_guard = state.auth_guard.clone();
+    Router::new()
+        .route("/ws", get(websocket_handler))
+        .route_layer(middleware::from_fn_with_state(
+            auth_guard,
+            client_guard_middleware,
+        ))
+        .with_state(state)
+        .layer(axum::Extension(ws_auth))
+}
+
+/// Request id, CORS and tracing layers shared by every listener
+fn with_common_layers(router: Router, config: &Config) -> Router {
     let cors = CorsLayer::new()
-        .allow_methods([Method::GET, Method::POST])
+        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE])
         .allow_origin(Any)
         .allow_headers(Any);
 
-    // Initialize comprehensive metrics system
-    let metrics_handle = init_prometheus_metrics().await?;
-
-    let app = app
+    router
         .layer(middleware::from_fn(create_request_id_middleware(
             config.rate_limiting.clone(),
         )))
         .layer(cors)
         .layer(TraceLayer::new_for_http())
-        .route("/health", get(health_check))
-        .route("/health/live", get(liveness_check))
-        .route("/health/ready", get(readiness_check))
-        .route("/metrics", get(move || ready(metrics_handle.render())))
-        .route_layer(middleware::from_fn(track_metrics));
+}
 
-    let addr = config.http_address();
+async fn serve(addr: String, app: Router) -> anyhow::Result<()> {
     let listener = tokio::net::TcpListener::bind(&addr).await?;
     info!("fmcd listening on {addr}");
-    axum::serve(listener, app).await?;
+    // Connect info gives the auth middleware the peer address to lock out
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await?;
     Ok(())
 }
 
-fn setup_metrics_recorder() -> anyhow::Result<PrometheusHandle> {
-    const EXPONENTIAL_SECONDS: &[f64] = &[
-        0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
-    ];
-
-    Ok(PrometheusBuilder::new()
-        .set_buckets_for_metric(
-            Matcher::Full("http_requests_duration_seconds".to_string()),
-            EXPONENTIAL_SECONDS,
-        )?
-        .install_recorder()?)
-}
-
 async fn track_metrics(req: Request, next: Next) -> impl IntoResponse {
     let start = Instant::now();
     let path = if let Some(matched_path) = req.extensions().get::<MatchedPath>() {
@@ -275,7 +346,7 @@ async fn track_metrics(req: Request, next: Next) -> impl IntoResponse {
     let status_code = response.status().as_u16();
 
     // Use our comprehensive API metrics recording
-    api_metrics::record_api_request(&method.to_string(), &path, status_code, duration);
+    api_metrics::record_api_request(method.as_ref(), &path, status_code, duration);
 
     response
 }
@@ -287,11 +358,41 @@ async fn track_metrics(req: Request, next: Next) -> impl IntoResponse {
 ///   with the federation.
 /// - `/v2/admin/info`: Display wallet info (holdings, tiers).
 /// - `/v2/admin/join`: Join a federation with an invite code.
-/// - `/v2/admin/restore`: Restore the previously created backup of mint notes
-///   (with `backup` command).
+/// - `/v2/admin/leave`: Leave a federation, optionally wiping its client
+///   database.
+/// - `/v2/admin/restore`: Restore a wallet from its mnemonic on a fresh data
+///   dir, rejoining federations and recovering the notes saved with `backup`.
+/// - `/v2/admin/restore/status`: Per-federation progress of the last restore.
 /// - `/v2/admin/operations`: List operations.
 /// - `/v2/admin/module`: Call a module subcommand.
 /// - `/v2/admin/config`: Returns the client config.
+/// - `/v2/admin/events`: Read the event journal from a sequence number, or
+///   filtered by time window, type, federation or correlation id.
+/// - `/v2/admin/webhooks`: List (GET) or create (POST) webhook endpoints.
+/// - `/v2/admin/webhooks/:id`: Update (PUT) or delete (DELETE) a webhook
+///   endpoint.
+/// - `/v2/admin/webhooks/:id/enable`, `/v2/admin/webhooks/:id/disable`: Turn
+///   delivery to a webhook endpoint on or off.
+/// - `/v2/admin/webhooks/:id/deliveries`: Delivery history of a webhook
+///   endpoint.
+/// - `/v2/admin/webhooks/:id/replay`: Re-send one past event, or every event in
+///   a time window, to a webhook endpoint.
+/// - `/v2/admin/webhooks/dead-letters`: List webhook deliveries that exhausted
+///   their retries.
+/// - `/v2/admin/webhooks/dead-letters/requeue`: Requeue dead-lettered webhook
+///   deliveries.
+/// - `/v2/admin/approvals`: List spends parked for approval, optionally by
+///   status.
+/// - `/v2/admin/approvals/:id/approve`: Approve and execute a parked spend.
+/// - `/v2/admin/approvals/:id/reject`: Reject a parked spend.
+/// - `/v2/admin/api-keys`: List (GET) or create (POST) API keys.
+/// - `/v2/admin/api-keys/:id`: Revoke (DELETE) an API key.
+/// - `/v2/admin/auth/lockouts`: List clients with recent authentication
+///   failures (GET) or lift every lockout (DELETE).
+/// - `/v2/admin/audit`: Page through the audit log from a sequence number.
+/// - `/v2/admin/audit/export`: Download the audit log as newline-delimited
+///   JSON.
+/// - `/v2/admin/audit/verify`: Check the hash chain of the audit log.
 ///
 /// Mint related commands:
 /// - `/v2/mint/reissue`: Reissue notes received from a third party to avoid
@@ -314,7 +415,13 @@ async fn track_metrics(req: Request, next: Next) -> impl IntoResponse {
 ///   to it can later be claimed.
 /// - `/v2/onchain/await-deposit`: Wait for deposit on previously generated
 ///   address.
+/// - `/v2/onchain/withdraw/quote`: Quote the fees of a withdrawal, to execute
+///   it later with `quoteId`.
 /// - `/v2/onchain/withdraw`: Withdraw funds from the federation.
+/// - `/v2/onchain/operation/:operation_id/status`: Get the state of a
+///   withdrawal.
+/// - `/v2/onchain/operation/:operation_id/stream`: Stream the state changes of a
+///   withdrawal.
 fn fedimint_v2_rest() -> Router<AppState> {
     let mint_router = Router::new()
         .route("/decode-notes", post(mint::decode_notes::handle_rest))
@@ -351,7 +458,19 @@ fn fedimint_v2_rest() -> Router<AppState> {
             post(onchain::deposit_address::handle_rest),
         )
         .route("/await-deposit", post(onchain::await_deposit::handle_rest))
-        .route("/withdraw", post(onchain::withdraw::handle_rest));
+        .route("/withdraw", post(onchain::withdraw::handle_rest))
+        .route(
+            "/withdraw/quote",
+            post(onchain::withdraw_quote::handle_rest),
+        )
+        .route(
+            "/operation/:operation_id/status",
+            get(onchain::status::handle_rest),
+        )
+        .route(
+            "/operation/:operation_id/stream",
+            get(onchain::stream::handle_operation_stream),
+        );
 
     let admin_router = Router::new()
         .route("/backup", post(admin::backup::handle_rest))
@@ -359,12 +478,68 @@ fn fedimint_v2_rest() -> Router<AppState> {
         .route("/federations", get(admin::federations::handle_rest))
         .route("/info", get(admin::info::handle_rest))
         .route("/join", post(admin::join::handle_rest))
+        .route("/leave", post(admin::leave::handle_rest))
         .route("/restore", post(admin::restore::handle_rest))
+        .route("/restore/status", get(admin::restore::handle_rest_status))
         // .route("/printsecret", get(handle_printsecret)) TODO: should I expose this
         // under admin?
         .route("/operations", post(admin::operations::handle_rest))
         .route("/module", post(admin::module::handle_rest))
-        .route("/config", get(admin::config::handle_rest));
+        .route("/config", get(admin::config::handle_rest))
+        .route("/events", get(admin::events::handle_rest))
+        .route(
+            "/webhooks",
+            get(admin::webhooks::handle_rest_list).post(admin::webhooks::handle_rest_create),
+        )
+        .route(
+            "/webhooks/:id",
+            put(admin::webhooks::handle_rest_update).delete(admin::webhooks::handle_rest_delete),
+        )
+        .route(
+            "/webhooks/:id/enable",
+            post(admin::webhooks::handle_rest_enable),
+        )
+        .route(
+            "/webhooks/:id/disable",
+            post(admin::webhooks::handle_rest_disable),
+        )
+        .route(
+            "/webhooks/:id/deliveries",
+            get(admin::webhooks::handle_rest_deliveries),
+        )
+        .route(
+            "/webhooks/:id/replay",
+            post(admin::webhooks::handle_rest_replay),
+        )
+        .route(
+            "/webhooks/dead-letters",
+            get(admin::webhooks::handle_rest_dead_letters),
+        )
+        .route(
+            "/webhooks/dead-letters/requeue",
+            post(admin::webhooks::handle_rest_requeue),
+        )
+        .route("/approvals", get(admin::approvals::handle_rest_list))
+        .route(
+            "/approvals/:id/approve",
+            post(admin::approvals::handle_rest_approve),
+        )
+        .route(
+            "/approvals/:id/reject",
+            post(admin::approvals::handle_rest_reject),
+        )
+        .route(
+            "/api-keys",
+            get(admin::api_keys::handle_rest_list).post(admin::api_keys::handle_rest_create),
+        )
+        .route("/api-keys/:id", delete(admin::api_keys::handle_rest_revoke))
+        .route(
+            "/auth/lockouts",
+            get(admin::lockouts::handle_rest_list).delete(admin::lockouts::handle_rest_clear),
+        )
+        .route("/audit", get(admin::audit::handle_rest))
+        .route("/audit/export", get(admin::audit::handle_rest_export))
+        .route("/audit/verify", get(admin::audit::handle_rest_verify));
 
     Router::new()
         .nest("/admin", admin_router)
@@ -1,12 +1,54 @@
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
 use anyhow::Result;
 use rand::RngCore;
 use serde::{Deserialize, Serialize};
 
+use crate::auth::{AuthProtectionConfig, RequestSigningConfig};
+use crate::core::policy::SpendingPolicyConfig;
 use crate::observability::correlation::RateLimitConfig;
 use crate::webhooks::WebhookConfig;
 
+/// Name of the configuration file, always kept in the data dir
+pub const CONFIG_FILE_NAME: &str = "fmcd.conf";
+
+/// Which APIs the daemon serves
+#[derive(Clone, Debug, clap::ValueEnum, PartialEq)]
+pub enum Mode {
+    Rest,
+    Ws,
+    /// REST and, when `websocket-enabled`, WebSocket together
+    All,
+}
+
+impl FromStr for Mode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "rest" => Ok(Mode::Rest),
+            "ws" => Ok(Mode::Ws),
+            "all" => Ok(Mode::All),
+            _ => Err(anyhow::anyhow!("Invalid mode")),
+        }
+    }
+}
+
+/// Where the daemon listens in a mode
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Listeners {
+    /// Address of the main listener
+    pub address: String,
+    /// REST is served on the main listener
+    pub rest: bool,
+    /// WebSocket is served on the main listener
+    pub ws: bool,
+    /// Address of a WebSocket-only listener, used when REST and WebSocket are
+    /// both served and `websocket-port` differs from the HTTP port
+    pub separate_ws: Option<String>,
+}
+
 /// Configuration structure
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Config {
@@ -50,6 +92,18 @@ pub struct Config {
     /// Rate limiting configuration for correlation IDs
     #[serde(rename = "rate-limiting", default)]
     pub rate_limiting: RateLimitConfig,
+
+    /// Spending limits applied to payments, withdrawals and ecash spends
+    #[serde(rename = "spending-policy", default)]
+    pub spending_policy: SpendingPolicyConfig,
+
+    /// HMAC signed REST requests
+    #[serde(rename = "request-signing", default)]
+    pub request_signing: RequestSigningConfig,
+
+    /// Brute-force protection and IP filtering for authenticated routes
+    #[serde(rename = "auth-protection", default)]
+    pub auth_protection: AuthProtectionConfig,
 }
 
 impl Default for Config {
@@ -65,6 +119,9 @@ impl Default for Config {
             manual_secret: None,
             webhooks: WebhookConfig::default(),
             rate_limiting: RateLimitConfig::default(),
+            spending_policy: SpendingPolicyConfig::default(),
+            request_signing: RequestSigningConfig::default(),
+            auth_protection: AuthProtectionConfig::default(),
         }
     }
 }
@@ -122,6 +179,29 @@ impl Config {
         format!("{}:{}", self.http_bind_ip, port)
     }
 
+    /// Listeners for the mode. A WebSocket-only daemon binds the WebSocket
+    /// address, which is the HTTP address unless `websocket-port` is set.
+    pub fn listeners(&self, mode: &Mode) -> Listeners {
+        match mode {
+            Mode::Ws => Listeners {
+                address: self.websocket_address(),
+                rest: false,
+                ws: true,
+                separate_ws: None,
+            },
+            Mode::Rest | Mode::All => {
+                let serve_ws = *mode == Mode::All && self.websocket_enabled;
+                let separate_ws = serve_ws && self.websocket_address() != self.http_address();
+                Listeners {
+                    address: self.http_address(),
+                    rest: true,
+                    ws: serve_ws && !separate_ws,
+                    separate_ws: separate_ws.then(|| self.websocket_address()),
+                }
+            }
+        }
+    }
+
     /// Check if authentication is enabled
     pub fn is_auth_enabled(&self) -> bool {
         self.http_password.is_some()
@@ -176,6 +256,12 @@ impl Config {
             config.save_to_file(path)?;
         }
 
+        // Signing as the password needs a secret of its own
+        if config.request_signing.enabled && config.request_signing.password_secret.is_none() {
+            config.request_signing.password_secret = Some(Self::generate_password());
+            config.save_to_file(path)?;
+        }
+
         Ok((config, password_generated))
     }
 }
@@ -0,0 +1,413 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use fedimint_core::config::FederationId;
+use fedimint_core::db::{Database, IDatabaseTransactionOpsCoreTyped};
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::core::multimint::db::{ApprovalKey, ApprovalKeyPrefix, ApprovalRecord};
+use crate::core::policy::SpendKind;
+use crate::core::{FmcdCore, LnPayRequest, PaymentInfoResolver, WithdrawRequest};
+use crate::error::{AppError, ErrorCategory};
+use crate::observability::correlation::RequestContext;
+
+/// Where an approval request is in its lifecycle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApprovalStatus {
+    Pending,
+    /// Approved and executed
+    Approved,
+    Rejected,
+    /// Not decided on before it expired
+    Expired,
+    /// Approved, but executing it failed
+    Failed,
+}
+
+impl ApprovalStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Approved => "approved",
+            Self::Rejected => "rejected",
+            Self::Expired => "expired",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+/// The parked request, executed as it was submitted once approved
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "request", rename_all = "snake_case")]
+pub enum ApprovalAction {
+    LightningPayment(LnPayRequest),
+    OnchainWithdrawal(WithdrawRequest),
+}
+
+impl ApprovalAction {
+    pub fn spend_kind(&self) -> SpendKind {
+        match self {
+            Self::LightningPayment(_) => SpendKind::LightningPayment,
+            Self::OnchainWithdrawal(_) => SpendKind::OnchainWithdrawal,
+        }
+    }
+}
+
+/// A spend above the approval threshold, waiting for an operator
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApprovalRequest {
+    pub approval_id: String,
+    pub federation_id: FederationId,
+    pub amount_msat: u64,
+    /// Invoice, lightning address or LNURL paid, or address withdrawn to
+    pub destination: String,
+    /// Gateway the payment was requested through
+    pub gateway_id: Option<String>,
+    /// Correlation id of the request that asked for the spend
+    pub requester: String,
+    pub status: ApprovalStatus,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub decided_at: Option<DateTime<Utc>>,
+    /// Why it was rejected, or why executing it failed
+    pub reason: Option<String>,
+    /// Response of the executed payment or withdrawal
+    pub result: Option<serde_json::Value>,
+    pub action: ApprovalAction,
+}
+
+impl ApprovalRequest {
+    pub fn new(
+        action: ApprovalAction,
+        amount_msat: u64,
+        destination: String,
+        requester: String,
+        expires_at: DateTime<Utc>,
+    ) -> Self {
+        let (federation_id, gateway_id) = match &action {
+            ApprovalAction::LightningPayment(req) => {
+                (req.federation_id, req.gateway_id.map(|id| id.to_string()))
+            }
+            ApprovalAction::OnchainWithdrawal(req) => (req.federation_id, None),
+        };
+        Self {
+            approval_id: Uuid::new_v4().to_string(),
+            federation_id,
+            amount_msat,
+            destination,
+            gateway_id,
+            requester,
+            status: ApprovalStatus::Pending,
+            created_at: Utc::now(),
+            expires_at,
+            decided_at: None,
+            reason: None,
+            result: None,
+            action,
+        }
+    }
+
+    /// Pending requests past their expiry are reported as expired
+    fn with_expiry(mut self, now: DateTime<Utc>) -> Self {
+        if self.status == ApprovalStatus::Pending && self.expires_at <= now {
+            self.status = ApprovalStatus::Expired;
+        }
+        self
+    }
+}
+
+/// Approval requests, kept in the multimint database so pending ones survive
+/// a restart
+#[derive(Debug)]
+pub struct ApprovalStore {
+    db: Database,
+}
+
+impl ApprovalStore {
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+
+    /// Store a new request, or the outcome of an approved one
+    pub async fn save(&self, request: &ApprovalRequest) -> Result<()> {
+        let record = ApprovalRecord {
+            json: serde_json::to_string(request)?,
+        };
+        let mut dbtx = self.db.begin_transaction().await;
+        dbtx.insert_entry(
+            &ApprovalKey {
+                approval_id: request.approval_id.clone(),
+            },
+            &record,
+        )
+        .await;
+        dbtx.commit_tx_result()
+            .await
+            .map_err(|e| anyhow!("Failed to store approval request: {:?}", e))
+    }
+
+    pub async fn get(&self, approval_id: &str) -> Option<ApprovalRequest> {
+        let record = self
+            .db
+            .begin_transaction_nc()
+            .await
+            .get_value(&ApprovalKey {
+                approval_id: approval_id.to_string(),
+            })
+            .await;
+        record
+            .as_ref()
+            .and_then(decode_request)
+            .map(|request| request.with_expiry(Utc::now()))
+    }
+
+    /// Approval requests oldest first, only those in `status` if given
+    pub async fn list(&self, status: Option<ApprovalStatus>) -> Vec<ApprovalRequest> {
+        let now = Utc::now();
+        let mut dbtx = self.db.begin_transaction_nc().await;
+        let records = dbtx
+            .find_by_prefix(&ApprovalKeyPrefix)
+            .await
+            .map(|(_, record)| record)
+            .collect::<Vec<_>>()
+            .await;
+
+        let mut requests = records
+            .iter()
+            .filter_map(decode_request)
+            .map(|request| request.with_expiry(now))
+            .filter(|request| status.is_none_or(|status| request.status == status))
+            .collect::<Vec<_>>();
+        requests.sort_by_key(|request| request.created_at);
+        requests
+    }
+
+    /// Move a pending request to `status`, fails if it was decided on
+    /// already or expired. Concurrent decisions on the same request conflict
+    /// in the database, so only one of them succeeds.
+    pub async fn decide(
+        &self,
+        approval_id: &str,
+        status: ApprovalStatus,
+        reason: Option<String>,
+    ) -> Result<ApprovalRequest, AppError> {
+        let key = ApprovalKey {
+            approval_id: approval_id.to_string(),
+        };
+        let now = Utc::now();
+
+        let mut dbtx = self.db.begin_transaction().await;
+        let mut request = dbtx
+            .get_value(&key)
+            .await
+            .as_ref()
+            .and_then(decode_request)
+            .ok_or_else(|| {
+                AppError::not_found(format!("Approval request {} not found", approval_id))
+            })?
+            .with_expiry(now);
+        if request.status != ApprovalStatus::Pending {
+            return Err(AppError::with_category(
+                ErrorCategory::Conflict,
+                format!(
+                    "Approval request {} is {} already",
+                    approval_id,
+                    request.status.as_str()
+                ),
+            ));
+        }
+
+        request.status = status;
+        request.decided_at = Some(now);
+        request.reason = reason;
+        let record = ApprovalRecord {
+            json: serde_json::to_string(&request)?,
+        };
+        dbtx.insert_entry(&key, &record).await;
+        dbtx.commit_tx_result().await.map_err(|e| {
+            AppError::with_category(
+                ErrorCategory::Conflict,
+                format!(
+                    "Approval request {} was decided on concurrently: {:?}",
+                    approval_id, e
+                ),
+            )
+        })?;
+        Ok(request)
+    }
+}
+
+fn decode_request(record: &ApprovalRecord) -> Option<ApprovalRequest> {
+    match serde_json::from_str::<ApprovalRequest>(&record.json) {
+        Ok(request) => Some(request),
+        Err(e) => {
+            warn!(error = ?e, "Skipping undecodable approval request");
+            None
+        }
+    }
+}
+
+impl FmcdCore {
+    /// Park a spend until an operator approves it. The returned error tells
+    /// the caller the spend was accepted for approval.
+    pub(super) async fn request_approval(
+        &self,
+        action: ApprovalAction,
+        amount_msat: u64,
+        destination: String,
+        context: &RequestContext,
+    ) -> AppError {
+        use crate::events::FmcdEvent;
+
+        let now = chrono::Utc::now();
+        let expires_at = i64::try_from(self.spending_policy.config().approval_ttl_secs)
+            .ok()
+            .and_then(chrono::Duration::try_seconds)
+            .and_then(|ttl| now.checked_add_signed(ttl))
+            .unwrap_or(chrono::DateTime::<chrono::Utc>::MAX_UTC);
+        let request = ApprovalRequest::new(
+            action,
+            amount_msat,
+            destination,
+            context.correlation_id.clone(),
+            expires_at,
+        );
+        if let Err(e) = self.approvals.save(&request).await {
+            return AppError::internal_error(format!("Failed to park spend for approval: {}", e))
+                .with_context(context.clone());
+        }
+
+        info!(
+            approval_id = %request.approval_id,
+            federation_id = %request.federation_id,
+            amount_msat = amount_msat,
+            "Spend parked for approval"
+        );
+        let event = FmcdEvent::ApprovalRequested {
+            approval_id: request.approval_id.clone(),
+            federation_id: request.federation_id.to_string(),
+            spend_kind: request.action.spend_kind().as_str().to_string(),
+            amount_msat,
+            destination: request.destination.clone(),
+            expires_at: request.expires_at,
+            correlation_id: Some(context.correlation_id.clone()),
+            timestamp: chrono::Utc::now(),
+        };
+        if let Err(e) = self.event_bus.publish(event).await {
+            error!(
+                approval_id = %request.approval_id,
+                error = ?e,
+                "Failed to publish approval requested event"
+            );
+        }
+
+        AppError::with_category(
+            ErrorCategory::ApprovalRequired,
+            format!(
+                "Spend of {} msat needs approval, parked as {}",
+                amount_msat, request.approval_id
+            ),
+        )
+        .with_details(serde_json::json!(request))
+        .with_context(context.clone())
+    }
+
+    /// Approval requests oldest first, only those in `status` if given
+    pub async fn list_approvals(&self, status: Option<ApprovalStatus>) -> Vec<ApprovalRequest> {
+        self.approvals.list(status).await
+    }
+
+    /// Approve a parked payment or withdrawal and execute it. Execution
+    /// failures are recorded on the returned request rather than returned.
+    pub async fn approve_request(
+        &self,
+        approval_id: &str,
+        context: RequestContext,
+        resolver: Option<&dyn PaymentInfoResolver>,
+    ) -> Result<ApprovalRequest, AppError> {
+        use crate::events::FmcdEvent;
+
+        let mut request = self
+            .approvals
+            .decide(approval_id, ApprovalStatus::Approved, None)
+            .await?;
+        let event = FmcdEvent::ApprovalApproved {
+            approval_id: request.approval_id.clone(),
+            federation_id: request.federation_id.to_string(),
+            correlation_id: Some(context.correlation_id.clone()),
+            timestamp: chrono::Utc::now(),
+        };
+        if let Err(e) = self.event_bus.publish(event).await {
+            error!(
+                approval_id = %approval_id,
+                error = ?e,
+                "Failed to publish approval approved event"
+            );
+        }
+
+        let result = match request.action.clone() {
+            ApprovalAction::LightningPayment(req) => self
+                .pay_invoice_checked(req, context, resolver, true)
+                .await
+                .map(|response| serde_json::json!(response)),
+            ApprovalAction::OnchainWithdrawal(req) => self
+                .withdraw_onchain_checked(req, context, true)
+                .await
+                .map(|response| serde_json::json!(response)),
+        };
+        match result {
+            Ok(response) => request.result = Some(response),
+            Err(e) => {
+                warn!(
+                    approval_id = %approval_id,
+                    error = %e,
+                    "Approved spend failed"
+                );
+                request.status = ApprovalStatus::Failed;
+                request.reason = Some(e.message);
+            }
+        }
+        if let Err(e) = self.approvals.save(&request).await {
+            error!(
+                approval_id = %approval_id,
+                error = ?e,
+                "Failed to store outcome of approved spend"
+            );
+        }
+        Ok(request)
+    }
+
+    /// Reject a parked payment or withdrawal
+    pub async fn reject_request(
+        &self,
+        approval_id: &str,
+        reason: Option<String>,
+        context: RequestContext,
+    ) -> Result<ApprovalRequest, AppError> {
+        use crate::events::FmcdEvent;
+
+        let request = self
+            .approvals
+            .decide(approval_id, ApprovalStatus::Rejected, reason)
+            .await?;
+        let event = FmcdEvent::ApprovalRejected {
+            approval_id: request.approval_id.clone(),
+            federation_id: request.federation_id.to_string(),
+            reason: request.reason.clone(),
+            correlation_id: Some(context.correlation_id.clone()),
+            timestamp: chrono::Utc::now(),
+        };
+        if let Err(e) = self.event_bus.publish(event).await {
+            error!(
+                approval_id = %approval_id,
+                error = ?e,
+                "Failed to publish approval rejected event"
+            );
+        }
+        Ok(request)
+    }
+}
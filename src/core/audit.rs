@@ -0,0 +1,312 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use fedimint_core::db::{Database, IDatabaseTransactionOpsCoreTyped};
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use tokio::sync::Mutex;
+
+use crate::core::multimint::db::{AuditLogKey, AuditLogKeyPrefix, AuditLogRecord};
+
+/// `prevHash` of the first record
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+const DEFAULT_PAGE_LIMIT: usize = 100;
+const MAX_PAGE_LIMIT: usize = 1000;
+
+/// What an audited request did
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    Join,
+    Leave,
+    Backup,
+    Restore,
+    /// Webhooks, API keys and lockouts
+    ConfigChange,
+    /// Approving or rejecting a parked spend
+    Approval,
+    Pay,
+    Withdraw,
+    Spend,
+    Reissue,
+    /// Raw module commands, which can move funds
+    Module,
+}
+
+/// How an audited request ended
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditOutcome {
+    pub success: bool,
+    /// HTTP status, or the status of the error category over WebSocket
+    pub status: u16,
+    pub error: Option<String>,
+}
+
+/// An audited request, as handed to the log
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEntry {
+    pub action: AuditAction,
+    /// `POST /v2/ln/pay`, or `ws ln-pay` for WebSocket requests
+    pub endpoint: String,
+    /// API key the caller authenticated with, `None` without authentication
+    pub key_id: Option<String>,
+    pub key_name: Option<String>,
+    pub source_ip: Option<String>,
+    pub correlation_id: String,
+    pub request_id: String,
+    /// Request body or params with secrets redacted
+    pub request: Value,
+    pub outcome: AuditOutcome,
+}
+
+/// An entry in the log, chained to the one before it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditRecord {
+    pub sequence: u64,
+    pub recorded_at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub entry: AuditEntry,
+    /// `hash` of the previous record, `GENESIS_HASH` for the first
+    pub prev_hash: String,
+    /// Hex SHA-256 over every other field
+    pub hash: String,
+}
+
+impl AuditRecord {
+    fn new(sequence: u64, entry: AuditEntry, prev_hash: String) -> Result<Self> {
+        let mut record = Self {
+            sequence,
+            recorded_at: Utc::now(),
+            entry,
+            prev_hash,
+            hash: String::new(),
+        };
+        record.hash = record.compute_hash()?;
+        Ok(record)
+    }
+
+    /// Hash of the record as it should be, to compare with `hash`
+    pub fn compute_hash(&self) -> Result<String> {
+        let content = serde_json::to_string(&HashedContent {
+            sequence: self.sequence,
+            recorded_at: &self.recorded_at,
+            entry: &self.entry,
+            prev_hash: &self.prev_hash,
+        })?;
+        Ok(hex::encode(Sha256::digest(content.as_bytes())))
+    }
+}
+
+/// What the hash of a record is computed over
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct HashedContent<'a> {
+    sequence: u64,
+    recorded_at: &'a DateTime<Utc>,
+    entry: &'a AuditEntry,
+    prev_hash: &'a str,
+}
+
+/// A page of audit records, oldest first
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditPage {
+    pub records: Vec<AuditRecord>,
+    pub last_sequence: u64,
+    pub last_hash: String,
+}
+
+/// Result of checking a chain of records
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditVerification {
+    pub valid: bool,
+    /// Records checked, up to the first broken one
+    pub records: u64,
+    /// Sequence and hash of the last intact record. Keeping `lastHash`
+    /// somewhere else makes rewriting the whole chain detectable too.
+    pub last_sequence: u64,
+    pub last_hash: String,
+    /// Sequence of the first record that doesn't chain
+    pub broken_at: Option<u64>,
+    pub error: Option<String>,
+}
+
+/// Check that records starting from the first one follow each other and
+/// haven't been altered
+pub fn verify_chain(records: impl IntoIterator<Item = Result<AuditRecord>>) -> AuditVerification {
+    let mut verification = AuditVerification {
+        valid: true,
+        records: 0,
+        last_sequence: 0,
+        last_hash: GENESIS_HASH.to_string(),
+        broken_at: None,
+        error: None,
+    };
+
+    for record in records {
+        let expected_sequence = verification.last_sequence + 1;
+        let error = match record {
+            Err(e) => Some(format!("Undecodable record: {}", e)),
+            Ok(record) if record.sequence != expected_sequence => Some(format!(
+                "Expected record {}, found {}",
+                expected_sequence, record.sequence
+            )),
+            Ok(record) if record.prev_hash != verification.last_hash => {
+                Some("prevHash doesn't match the previous record".to_string())
+            }
+            Ok(record) => match record.compute_hash() {
+                Ok(hash) if hash == record.hash => {
+                    verification.records += 1;
+                    verification.last_sequence = record.sequence;
+                    verification.last_hash = record.hash;
+                    None
+                }
+                Ok(_) => Some("hash doesn't match the record".to_string()),
+                Err(e) => Some(format!("Failed to hash record: {}", e)),
+            },
+        };
+        if let Some(error) = error {
+            verification.valid = false;
+            verification.broken_at = Some(expected_sequence);
+            verification.error = Some(error);
+            break;
+        }
+    }
+    verification
+}
+
+/// Append-only log of money-moving and administrative requests in the
+/// multimint database. Every record carries the hash of the one before it, so
+/// altering, removing or reordering records breaks the chain.
+pub struct AuditLog {
+    db: Database,
+    /// Sequence and hash of the last record, held while appending so records
+    /// chain in commit order
+    last: Mutex<(u64, String)>,
+}
+
+impl std::fmt::Debug for AuditLog {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AuditLog").finish()
+    }
+}
+
+impl AuditLog {
+    /// Open the log, continuing the chain of a previous run
+    pub async fn open(db: Database) -> Result<Self> {
+        let last = {
+            let mut dbtx = db.begin_transaction_nc().await;
+            dbtx.find_by_prefix_sorted_descending(&AuditLogKeyPrefix)
+                .await
+                .next()
+                .await
+        };
+        let last = match last {
+            Some((key, record)) => {
+                let record = serde_json::from_str::<AuditRecord>(&record.json).map_err(|e| {
+                    anyhow!("Failed to decode audit record {}: {}", key.sequence, e)
+                })?;
+                (record.sequence, record.hash)
+            }
+            None => (0, GENESIS_HASH.to_string()),
+        };
+
+        Ok(Self {
+            db,
+            last: Mutex::new(last),
+        })
+    }
+
+    /// Append an entry, returns it chained to the previous record
+    pub async fn append(&self, entry: AuditEntry) -> Result<AuditRecord> {
+        let mut last = self.last.lock().await;
+        let record = AuditRecord::new(last.0 + 1, entry, last.1.clone())?;
+
+        let mut dbtx = self.db.begin_transaction().await;
+        dbtx.insert_entry(
+            &AuditLogKey {
+                sequence: record.sequence,
+            },
+            &AuditLogRecord {
+                json: serde_json::to_string(&record)?,
+            },
+        )
+        .await;
+        dbtx.commit_tx_result()
+            .await
+            .map_err(|e| anyhow!("Failed to append audit record: {:?}", e))?;
+
+        *last = (record.sequence, record.hash.clone());
+        Ok(record)
+    }
+
+    /// Records after `after_sequence`, oldest first
+    pub async fn records(
+        &self,
+        after_sequence: Option<u64>,
+        limit: Option<usize>,
+    ) -> Result<AuditPage> {
+        let limit = limit.unwrap_or(DEFAULT_PAGE_LIMIT).min(MAX_PAGE_LIMIT);
+        let records = self
+            .read_after(after_sequence.unwrap_or(0), limit)
+            .await
+            .into_iter()
+            .collect::<Result<Vec<_>>>()?;
+        let (last_sequence, last_hash) = self.last.lock().await.clone();
+        Ok(AuditPage {
+            records,
+            last_sequence,
+            last_hash,
+        })
+    }
+
+    /// Check the whole chain as stored
+    pub async fn verify(&self) -> AuditVerification {
+        let mut records = Vec::new();
+        let mut after = 0;
+        loop {
+            let page = self.read_after(after, MAX_PAGE_LIMIT).await;
+            // Undecodable records end the page, reading on past them is
+            // pointless as the chain is broken there
+            let next = match page.last() {
+                Some(Ok(record)) => Some(record.sequence),
+                _ => None,
+            };
+            records.extend(page);
+            match next {
+                Some(sequence) => after = sequence,
+                None => break,
+            }
+        }
+        verify_chain(records)
+    }
+
+    async fn read_after(&self, after: u64, limit: usize) -> Vec<Result<AuditRecord>> {
+        let mut dbtx = self.db.begin_transaction_nc().await;
+        let records = dbtx
+            .find_by_range(
+                AuditLogKey {
+                    sequence: after.saturating_add(1),
+                }..AuditLogKey { sequence: u64::MAX },
+            )
+            .await
+            .take(limit)
+            .map(|(key, record)| {
+                serde_json::from_str::<AuditRecord>(&record.json)
+                    .map_err(|e| anyhow!("Failed to decode audit record {}: {}", key.sequence, e))
+            })
+            .collect::<Vec<_>>()
+            .await;
+
+        // Stop at the first undecodable record so callers see it last
+        match records.iter().position(Result::is_err) {
+            Some(index) => records.into_iter().take(index + 1).collect(),
+            None => records,
+        }
+    }
+}
@@ -0,0 +1,314 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use fedimint_core::db::{Database, IDatabaseTransactionOpsCoreTyped};
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use tokio::sync::broadcast;
+use tokio::time::interval;
+use tracing::{error, info, warn};
+
+use crate::core::multimint::db::{IdempotencyKey, IdempotencyKeyPrefix, IdempotencyRecord};
+use crate::error::{AppError, ErrorCategory};
+
+const IDEMPOTENCY_PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const MAX_KEY_LENGTH: usize = 255;
+
+/// Response of a request, replayed when its idempotency key is used again
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StoredResponse {
+    pub status: u16,
+    pub body: Value,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct IdempotencyEntry {
+    request_hash: String,
+    /// Not set while the request executes
+    response: Option<StoredResponse>,
+    created_at: DateTime<Utc>,
+}
+
+/// Result of looking up an idempotency key
+#[derive(Debug)]
+pub enum IdempotencyOutcome {
+    /// The key was used for the same request before, this is its response
+    Replay(StoredResponse),
+    /// First use of the key, execute the request and complete the guard
+    Proceed(IdempotencyGuard),
+}
+
+/// Hash identifying a request, `scope` names the endpoint so a key can't be
+/// replayed against a different one
+pub fn request_hash(scope: &str, request: &Value) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(scope.as_bytes());
+    hasher.update([0]);
+    hasher.update(request.to_string().as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+/// Stores the responses of requests carrying an idempotency key in the
+/// multimint database, so a client retrying after a timeout gets the original
+/// result instead of moving funds twice.
+///
+/// Keys are kept for `max_age`. A key is reserved in memory while its request
+/// executes, concurrent uses of the key are rejected. The key is also stored
+/// before its request executes, so a request interrupted by a restart is not
+/// executed again.
+pub struct IdempotencyStore {
+    db: Database,
+    max_age: chrono::Duration,
+    in_flight: Arc<std::sync::Mutex<HashSet<String>>>,
+    shutdown_tx: std::sync::Mutex<Option<broadcast::Sender<()>>>,
+}
+
+impl std::fmt::Debug for IdempotencyStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("IdempotencyStore")
+            .field("max_age", &self.max_age)
+            .finish()
+    }
+}
+
+impl IdempotencyStore {
+    pub fn new(db: Database, max_age: chrono::Duration) -> Self {
+        Self {
+            db,
+            max_age,
+            in_flight: Arc::new(std::sync::Mutex::new(HashSet::new())),
+            shutdown_tx: std::sync::Mutex::new(None),
+        }
+    }
+
+    /// Look up a key before executing its request. Keys belong to the API key
+    /// `owner` that sent them, so callers can neither replay nor block the
+    /// requests of another.
+    ///
+    /// Fails with `Conflict` when the key was used for a different request, its
+    /// request is still executing or its request was interrupted without a
+    /// response.
+    pub async fn begin(
+        &self,
+        owner: Option<&str>,
+        key: &str,
+        request_hash: String,
+    ) -> Result<IdempotencyOutcome, AppError> {
+        if key.is_empty() || key.len() > MAX_KEY_LENGTH {
+            return Err(AppError::validation_error(format!(
+                "Idempotency key must be 1 to {} characters",
+                MAX_KEY_LENGTH
+            )));
+        }
+        let stored_key = match owner {
+            Some(owner) => format!("{}/{}", owner, key),
+            None => key.to_string(),
+        };
+
+        let inserted = self
+            .in_flight
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .insert(stored_key.clone());
+        if !inserted {
+            return Err(AppError::with_category(
+                ErrorCategory::Conflict,
+                format!(
+                    "A request with idempotency key {} is still in progress",
+                    key
+                ),
+            ));
+        }
+        // Releases the reservation on every early return below
+        let guard = IdempotencyGuard {
+            db: self.db.clone(),
+            in_flight: self.in_flight.clone(),
+            key: stored_key,
+            request_hash,
+        };
+
+        let entry = self
+            .get(&guard.key)
+            .await
+            .map_err(|e| AppError::with_category(ErrorCategory::DatabaseError, e.to_string()))?;
+        match entry {
+            Some(entry) if entry.created_at + self.max_age > Utc::now() => {
+                if entry.request_hash != guard.request_hash {
+                    return Err(AppError::with_category(
+                        ErrorCategory::Conflict,
+                        format!("Idempotency key {} was used for a different request", key),
+                    ));
+                }
+                match entry.response {
+                    Some(response) => Ok(IdempotencyOutcome::Replay(response)),
+                    // Not reserved in memory, so fmcd stopped while it ran
+                    None => Err(AppError::with_category(
+                        ErrorCategory::Conflict,
+                        format!(
+                            "The request with idempotency key {} was interrupted, its outcome \
+                             is unknown",
+                            key
+                        ),
+                    )),
+                }
+            }
+            _ => {
+                guard.store(None).await.map_err(|e| {
+                    AppError::with_category(ErrorCategory::DatabaseError, e.to_string())
+                })?;
+                Ok(IdempotencyOutcome::Proceed(guard))
+            }
+        }
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<IdempotencyEntry>> {
+        let mut dbtx = self.db.begin_transaction_nc().await;
+        let record = dbtx
+            .get_value(&IdempotencyKey {
+                key: key.to_string(),
+            })
+            .await;
+
+        Ok(record.and_then(|record| decode_entry(&record)))
+    }
+
+    /// Remove keys older than `max_age`
+    pub async fn prune(&self) -> Result<usize> {
+        let cutoff = Utc::now() - self.max_age;
+
+        let mut dbtx = self.db.begin_transaction().await;
+        let entries = dbtx
+            .find_by_prefix(&IdempotencyKeyPrefix)
+            .await
+            .collect::<Vec<_>>()
+            .await;
+
+        let mut removed = 0;
+        for (key, record) in entries {
+            if decode_entry(&record).is_none_or(|entry| entry.created_at < cutoff) {
+                dbtx.remove_entry(&key).await;
+                removed += 1;
+            }
+        }
+        dbtx.commit_tx_result()
+            .await
+            .map_err(|e| anyhow!("Failed to prune idempotency keys: {:?}", e))?;
+
+        Ok(removed)
+    }
+
+    /// Start the background task removing expired keys
+    pub fn start(self: &Arc<Self>) {
+        let (shutdown_tx, _) = broadcast::channel(1);
+        *self
+            .shutdown_tx
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(shutdown_tx.clone());
+
+        info!(
+            max_age_hours = self.max_age.num_hours(),
+            "Starting idempotency key retention task"
+        );
+
+        let store = self.clone();
+        tokio::spawn(async move {
+            let mut shutdown_rx = shutdown_tx.subscribe();
+            let mut prune_timer = interval(IDEMPOTENCY_PRUNE_INTERVAL);
+
+            loop {
+                tokio::select! {
+                    _ = prune_timer.tick() => {
+                        match store.prune().await {
+                            Ok(removed) if removed > 0 => {
+                                info!(removed = removed, "Pruned idempotency keys");
+                            }
+                            Ok(_) => {}
+                            Err(e) => error!(error = ?e, "Failed to prune idempotency keys"),
+                        }
+                    }
+                    _ = shutdown_rx.recv() => {
+                        info!("Idempotency key retention task received shutdown signal");
+                        break;
+                    }
+                }
+            }
+        });
+    }
+
+    /// Stop the retention task
+    pub fn stop(&self) {
+        let tx_guard = self
+            .shutdown_tx
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(shutdown_tx) = tx_guard.as_ref() {
+            let _ = shutdown_tx.send(());
+        }
+    }
+}
+
+/// Reservation of an idempotency key while its request executes, released
+/// when dropped
+#[derive(Debug)]
+pub struct IdempotencyGuard {
+    db: Database,
+    in_flight: Arc<std::sync::Mutex<HashSet<String>>>,
+    key: String,
+    request_hash: String,
+}
+
+impl IdempotencyGuard {
+    /// Store the final response of the request under its key
+    pub async fn complete(self, response: StoredResponse) -> Result<()> {
+        self.store(Some(response)).await
+    }
+
+    async fn store(&self, response: Option<StoredResponse>) -> Result<()> {
+        let entry = IdempotencyEntry {
+            request_hash: self.request_hash.clone(),
+            response,
+            created_at: Utc::now(),
+        };
+        let record = IdempotencyRecord {
+            json: serde_json::to_string(&entry)?,
+        };
+
+        let mut dbtx = self.db.begin_transaction().await;
+        dbtx.insert_entry(
+            &IdempotencyKey {
+                key: self.key.clone(),
+            },
+            &record,
+        )
+        .await;
+        dbtx.commit_tx_result()
+            .await
+            .map_err(|e| anyhow!("Failed to store idempotent response: {:?}", e))
+    }
+}
+
+impl Drop for IdempotencyGuard {
+    fn drop(&mut self) {
+        self.in_flight
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .remove(&self.key);
+    }
+}
+
+fn decode_entry(record: &IdempotencyRecord) -> Option<IdempotencyEntry> {
+    match serde_json::from_str::<IdempotencyEntry>(&record.json) {
+        Ok(entry) => Some(entry),
+        Err(e) => {
+            warn!(error = ?e, "Skipping undecodable idempotency key");
+            None
+        }
+    }
+}
@@ -1,6 +1,17 @@
+pub mod approvals;
+pub mod audit;
+pub mod idempotency;
 pub mod multimint;
 pub mod operations;
+pub mod policy;
+mod restore;
 pub mod services;
+mod webhooks;
+pub mod withdraw_address;
+pub mod withdraw_quote;
+
+#[cfg(test)]
+pub(crate) mod tests;
 
 use std::collections::{BTreeMap, HashMap};
 use std::path::PathBuf;
@@ -15,27 +26,44 @@ use fedimint_core::core::OperationId;
 use fedimint_core::invite_code::InviteCode;
 use fedimint_core::secp256k1::PublicKey;
 use fedimint_core::{Amount, BitcoinAmountOrAll, TieredCounts};
-use fedimint_ln_client::{LightningClientModule, OutgoingLightningPayment, PayType};
+use fedimint_ln_client::{
+    LightningClientModule, LightningOperationMeta, LightningOperationMetaVariant,
+    OutgoingLightningPayment, PayType,
+};
+use fedimint_ln_common::config::FeeToAmount;
 use fedimint_ln_common::lightning_invoice::{Bolt11InvoiceDescription, Description};
+use fedimint_ln_common::LightningGateway;
 use fedimint_mint_client::MintClientModule;
 use fedimint_wallet_client::client_db::TweakIdx;
 use fedimint_wallet_client::{WalletClientModule, WithdrawState};
+use futures_util::StreamExt;
 use serde::{Deserialize, Serialize};
 use tracing::{error, info, warn};
 
 // Use local module imports
+use self::approvals::{ApprovalAction, ApprovalStore};
+use self::audit::AuditLog;
+use self::idempotency::IdempotencyStore;
 use self::multimint::MultiMint;
 use self::operations::payment::InvoiceTracker;
-use self::operations::PaymentTracker;
+use self::operations::{PaymentTracker, RestoreTracker};
+use self::policy::{SpendIntent, SpendKind, SpendingPolicy, SpendingPolicyConfig};
 use self::services::{
-    BalanceMonitor, BalanceMonitorConfig, DepositMonitor, DepositMonitorConfig,
-    PaymentLifecycleConfig, PaymentLifecycleManager,
+    BalanceMonitor, BalanceMonitorConfig, DepositMonitor, DepositMonitorConfig, GatewayCandidate,
+    GatewaySelector, GatewaySelectorConfig, PaymentLifecycleConfig, PaymentLifecycleManager,
+    PaymentType,
 };
+use self::withdraw_address::{check_dust_limit, parse_withdraw_address};
+use self::withdraw_quote::{WithdrawQuote, WithdrawQuoteStore};
 use crate::error::{AppError, ErrorCategory};
 use crate::events::handlers::{LoggingEventHandler, MetricsEventHandler};
+use crate::events::journal::{EventJournal, JournalPage, JournalQuery, JournalRetention};
 use crate::events::EventBus;
 use crate::observability::correlation::RequestContext;
-use crate::webhooks::{WebhookConfig, WebhookNotifier};
+use crate::webhooks::invoice::InvoiceCallback;
+use crate::webhooks::{
+    RetryConfig, WebhookConfig, WebhookEndpoint, WebhookNotifier, WebhookOutbox,
+};
 
 /// Trait for resolving payment information into Bolt11 invoices
 /// This allows the core to remain agnostic about web protocols like LNURL
@@ -60,19 +88,25 @@ pub struct LnInvoiceRequest {
     pub amount_msat: Amount,
     pub description: String,
     pub expiry_time: Option<u64>,
-    pub gateway_id: PublicKey,
+    /// Gateway to receive through, the best scored gateway when omitted
+    pub gateway_id: Option<PublicKey>,
     pub federation_id: FederationId,
     pub metadata: Option<serde_json::Value>,
+    /// Callback notified once when this invoice settles or expires, with the
+    /// metadata echoed back
+    pub webhook_url: Option<String>,
 }
 
 /// Lightning payment request
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct LnPayRequest {
     pub payment_info: String,
     pub amount_msat: Option<Amount>,
     pub lnurl_comment: Option<String>,
-    pub gateway_id: PublicKey,
+    /// Gateway to pay through. When omitted gateways are tried best scored
+    /// first, falling back to the next one if a gateway refunds the payment
+    pub gateway_id: Option<PublicKey>,
     pub federation_id: FederationId,
 }
 
@@ -153,21 +187,167 @@ pub struct JoinFederationResponse {
     pub federation_ids: Vec<FederationId>,
 }
 
-/// Onchain withdraw request
+/// Leave federation request
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
+pub struct LeaveFederationRequest {
+    pub federation_id: FederationId,
+    /// Leave even if the federation still holds a balance
+    #[serde(default)]
+    pub force: bool,
+    /// Also delete the client's database, discarding its notes and history
+    #[serde(default)]
+    pub wipe_db: bool,
+}
+
+/// Leave federation response
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LeaveFederationResponse {
+    pub federation_id: FederationId,
+    pub balance_msat: Amount,
+    pub wiped_db: bool,
+    pub federation_ids: Vec<FederationId>,
+}
+
+/// Refuse to leave a federation that still holds funds, unless forced
+pub(crate) fn ensure_can_leave(
+    federation_id: FederationId,
+    balance: Amount,
+    force: bool,
+) -> Result<(), AppError> {
+    if balance.msats > 0 && !force {
+        return Err(AppError::with_category(
+            ErrorCategory::Conflict,
+            format!(
+                "Federation {} still holds {} msat, set force to leave anyway",
+                federation_id, balance.msats
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Create webhook endpoint request
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateWebhookRequest {
+    /// Generated when omitted
+    pub id: Option<String>,
+    pub url: String,
+    pub secret: Option<String>,
+    /// Event types to deliver, all events when empty
+    #[serde(default)]
+    pub events: Vec<String>,
+    pub retry_config: Option<RetryConfig>,
+    pub enabled: Option<bool>,
+    pub description: Option<String>,
+}
+
+/// Update webhook endpoint request, fields that are omitted are left as they
+/// are
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateWebhookRequest {
+    pub url: Option<String>,
+    pub secret: Option<String>,
+    pub events: Option<Vec<String>>,
+    pub retry_config: Option<RetryConfig>,
+    pub enabled: Option<bool>,
+    pub description: Option<String>,
+}
+
+/// Wallet restore request
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoreRequest {
+    /// BIP39 mnemonic of the wallet being restored, words separated by spaces
+    pub mnemonic: String,
+    pub invite_codes: Vec<InviteCode>,
+}
+
+/// How long a withdraw quote can be executed
+const WITHDRAW_QUOTE_TTL_SECS: i64 = 5 * 60;
+/// Default for how far the fee rate may fall between quote and withdrawal
+const DEFAULT_FEE_TOLERANCE_PERCENT: u64 = 10;
+
+/// Onchain withdraw request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct WithdrawRequest {
     pub address: String,
     pub amount_sat: BitcoinAmountOrAll,
     pub federation_id: FederationId,
+    /// Return once the withdrawal is submitted instead of waiting for the
+    /// transaction to be broadcast
+    #[serde(default, rename = "async")]
+    pub async_mode: bool,
+    /// Execute with the fees of this quote
+    pub quote_id: Option<String>,
+    /// How far the fee rate may have fallen since the quote, in percent
+    /// (default: 10). A rate that rose always rejects the quote.
+    pub fee_tolerance_percent: Option<u64>,
+}
+
+/// Onchain withdraw quote request
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WithdrawQuoteRequest {
+    pub address: String,
+    pub amount_sat: BitcoinAmountOrAll,
+    pub federation_id: FederationId,
 }
 
 /// Onchain withdraw response
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct WithdrawResponse {
-    pub txid: Txid,
+    pub operation_id: OperationId,
+    /// Broadcast transaction, not set for async withdrawals
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub txid: Option<Txid>,
+    pub amount_sat: u64,
+    pub fees_sat: u64,
+    pub fee_rate_sats_per_kvb: u64,
+}
+
+/// State of an onchain withdrawal
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum WithdrawStatus {
+    Created,
+    Succeeded { txid: Txid },
+    Failed { reason: String },
+}
+
+impl From<WithdrawState> for WithdrawStatus {
+    fn from(state: WithdrawState) -> Self {
+        match state {
+            WithdrawState::Created => WithdrawStatus::Created,
+            WithdrawState::Succeeded(txid) => WithdrawStatus::Succeeded { txid },
+            WithdrawState::Failed(reason) => WithdrawStatus::Failed { reason },
+        }
+    }
+}
+
+/// What a withdrawal was submitted with
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct WithdrawDetails {
+    pub address: String,
+    pub amount_sat: u64,
     pub fees_sat: u64,
+    pub fee_rate_sats_per_kvb: u64,
+}
+
+/// Onchain withdrawal status response
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct WithdrawStatusResponse {
+    pub operation_id: OperationId,
+    pub status: WithdrawStatus,
+    #[serde(flatten)]
+    pub details: WithdrawDetails,
 }
 
 /// Deposit address request
@@ -186,14 +366,41 @@ pub struct DepositAddressResponse {
     pub tweak_idx: TweakIdx,
 }
 
+/// How an outgoing lightning payment ended
+enum PaymentOutcome {
+    Paid {
+        preimage: String,
+    },
+    /// The funds were refunded. When a gateway gave up another gateway may
+    /// still succeed, a failed internal payment is not retried.
+    Refunded {
+        error: String,
+        retry: bool,
+    },
+}
+
 /// Main entry point for library consumers
 pub struct FmcdCore {
+    pub data_dir: PathBuf,
     pub multimint: Arc<MultiMint>,
     pub start_time: Instant,
     pub event_bus: Arc<EventBus>,
+    pub event_journal: Option<Arc<EventJournal>>,
     pub deposit_monitor: Option<Arc<DepositMonitor>>,
     pub balance_monitor: Option<Arc<BalanceMonitor>>,
     pub payment_lifecycle_manager: Option<Arc<PaymentLifecycleManager>>,
+    pub webhook_notifier: Option<Arc<WebhookNotifier>>,
+    pub gateway_selector: Arc<GatewaySelector>,
+    pub idempotency_store: Arc<IdempotencyStore>,
+    pub withdraw_quotes: WithdrawQuoteStore,
+    pub spending_policy: SpendingPolicy,
+    pub approvals: ApprovalStore,
+    /// Hash-chained record of money-moving and administrative requests
+    pub audit_log: Arc<AuditLog>,
+    pub restore_tracker: RestoreTracker,
+    /// Serializes webhook config changes so concurrent requests don't
+    /// overwrite each other
+    webhook_config_lock: tokio::sync::Mutex<()>,
 }
 
 impl FmcdCore {
@@ -202,7 +409,7 @@ impl FmcdCore {
     }
 
     pub async fn new_with_config(data_dir: PathBuf, webhook_config: WebhookConfig) -> Result<Self> {
-        let multimint = MultiMint::new(data_dir).await?;
+        let multimint = MultiMint::new(data_dir.clone()).await?;
         multimint.update_gateway_caches().await?;
         let multimint = Arc::new(multimint);
 
@@ -216,22 +423,66 @@ impl FmcdCore {
         event_bus.register_handler(logging_handler).await;
         event_bus.register_handler(metrics_handler).await;
 
-        // Register webhook notifier if webhooks are configured
-        if webhook_config.enabled && !webhook_config.endpoints.is_empty() {
-            match WebhookNotifier::new(webhook_config) {
-                Ok(webhook_notifier) => {
-                    let webhook_handler = Arc::new(webhook_notifier);
-                    event_bus.register_handler(webhook_handler).await;
-                    info!("Webhook notifier registered successfully");
+        // Register the event journal so consumers can resume from a sequence
+        // number instead of relying on the broadcast channel
+        let event_journal =
+            match EventJournal::open(multimint.db().clone(), JournalRetention::default()).await {
+                Ok(event_journal) => {
+                    let event_journal = Arc::new(event_journal);
+                    event_bus.register_handler(event_journal.clone()).await;
+                    info!(
+                        last_sequence = event_journal.last_sequence().await,
+                        "Event journal registered successfully"
+                    );
+                    Some(event_journal)
                 }
                 Err(e) => {
-                    warn!("Failed to initialize webhook notifier: {}", e);
+                    warn!("Failed to open event journal: {}", e);
+                    None
                 }
+            };
+
+        // Register the webhook notifier, deliveries go through a persistent
+        // outbox in the multimint database
+        let webhook_notifier = match WebhookNotifier::new(webhook_config) {
+            Ok(webhook_notifier) => {
+                let webhook_notifier = Arc::new(
+                    webhook_notifier.with_outbox(WebhookOutbox::new(multimint.db().clone())),
+                );
+                event_bus.register_handler(webhook_notifier.clone()).await;
+                info!("Webhook notifier registered successfully");
+                Some(webhook_notifier)
             }
-        }
+            Err(e) => {
+                warn!("Failed to initialize webhook notifier: {}", e);
+                None
+            }
+        };
+
+        // Register the gateway selector so it learns from payment outcomes
+        let gateway_selector = Arc::new(GatewaySelector::new(GatewaySelectorConfig::default()));
+        event_bus.register_handler(gateway_selector.clone()).await;
 
         info!("Event bus initialized with all handlers");
 
+        // Responses of requests carrying an idempotency key are kept for a day
+        let idempotency_store = Arc::new(IdempotencyStore::new(
+            multimint.db().clone(),
+            chrono::Duration::hours(24),
+        ));
+
+        // Quoted withdraw fees are honoured for a few minutes
+        let withdraw_quotes = WithdrawQuoteStore::new(
+            multimint.db().clone(),
+            chrono::Duration::seconds(WITHDRAW_QUOTE_TTL_SECS),
+        );
+
+        // No spending limits until configured with `with_spending_policy`
+        let spending_policy =
+            SpendingPolicy::new(multimint.db().clone(), SpendingPolicyConfig::default());
+        let approvals = ApprovalStore::new(multimint.db().clone());
+        let audit_log = Arc::new(AuditLog::open(multimint.db().clone()).await?);
+
         // Initialize monitoring services
         let deposit_monitor = Arc::new(DepositMonitor::new(
             event_bus.clone(),
@@ -252,15 +503,32 @@ impl FmcdCore {
         ));
 
         Ok(Self {
+            data_dir,
             multimint,
             start_time: Instant::now(),
             event_bus,
+            event_journal,
             deposit_monitor: Some(deposit_monitor),
             balance_monitor: Some(balance_monitor),
             payment_lifecycle_manager: Some(payment_lifecycle_manager),
+            webhook_notifier,
+            gateway_selector,
+            idempotency_store,
+            withdraw_quotes,
+            spending_policy,
+            approvals,
+            audit_log,
+            restore_tracker: RestoreTracker::new(),
+            webhook_config_lock: tokio::sync::Mutex::new(()),
         })
     }
 
+    /// Apply spending limits to payments, withdrawals and ecash spends
+    pub fn with_spending_policy(mut self, config: SpendingPolicyConfig) -> Self {
+        self.spending_policy = SpendingPolicy::new(self.multimint.db().clone(), config);
+        self
+    }
+
     /// Get uptime since core was initialized
     pub fn uptime(&self) -> Duration {
         self.start_time.elapsed()
@@ -282,11 +550,153 @@ impl FmcdCore {
         if let Some(ref payment_lifecycle_manager) = self.payment_lifecycle_manager {
             payment_lifecycle_manager.start().await?;
             info!("Payment lifecycle manager started successfully");
+            self.resume_invoice_callbacks(payment_lifecycle_manager)
+                .await;
+            self.resume_withdrawals(payment_lifecycle_manager).await;
+        }
+
+        if let Some(ref webhook_notifier) = self.webhook_notifier {
+            webhook_notifier.start().await?;
+            info!("Webhook outbox worker started successfully");
         }
 
+        if let Some(ref event_journal) = self.event_journal {
+            event_journal.start();
+            info!("Event journal retention task started successfully");
+        }
+
+        self.idempotency_store.start();
+        info!("Idempotency key retention task started successfully");
+
         Ok(())
     }
 
+    /// Monitor the invoices that were created with a callback URL before a
+    /// restart again, until their bolt11 expiry. Only the callback is notified,
+    /// the lifecycle manager publishes their events.
+    async fn resume_invoice_callbacks(&self, payment_lifecycle_manager: &PaymentLifecycleManager) {
+        let Some(ref notifier) = self.webhook_notifier else {
+            return;
+        };
+
+        for operation in payment_lifecycle_manager
+            .operations(PaymentType::LightningReceive)
+            .await
+        {
+            let (Some(webhook_url), Some(invoice_id)) =
+                (operation.callback_url, operation.invoice_id)
+            else {
+                continue;
+            };
+            let Some(client) = self.multimint.get(&operation.federation_id).await else {
+                warn!(
+                    operation_id = ?operation.operation_id,
+                    federation_id = %operation.federation_id,
+                    "Federation client not available, invoice callback not resumed"
+                );
+                continue;
+            };
+
+            // Invoices that expired while fmcd was down get a zero timeout, the
+            // biased select still delivers a settlement the stream replays
+            let Some(until_expiry) =
+                Self::invoice_until_expiry(&client, operation.operation_id).await
+            else {
+                warn!(
+                    operation_id = ?operation.operation_id,
+                    "Invoice not found in the operation log, invoice callback not resumed"
+                );
+                continue;
+            };
+            let callback = InvoiceCallback {
+                webhook_url,
+                federation_id: operation.federation_id,
+                metadata: operation.metadata,
+                notifier: notifier.clone(),
+            };
+            info!(
+                operation_id = ?operation.operation_id,
+                invoice_id = %invoice_id,
+                "Resuming invoice callback monitoring"
+            );
+            Self::start_invoice_monitoring(
+                client,
+                operation.operation_id,
+                invoice_id,
+                operation.amount_msat.map_or(0, |amount| amount.msats),
+                until_expiry,
+                None,
+                Some(callback),
+            );
+        }
+    }
+
+    /// Time left until the bolt11 invoice of a receive operation expires
+    async fn invoice_until_expiry(
+        client: &ClientHandleArc,
+        operation_id: OperationId,
+    ) -> Option<Duration> {
+        let operation = client.operation_log().get_operation(operation_id).await?;
+        match operation.meta::<LightningOperationMeta>().variant {
+            LightningOperationMetaVariant::Receive { invoice, .. } => {
+                Some(invoice.duration_until_expiry())
+            }
+            _ => None,
+        }
+    }
+
+    /// Follow the withdrawals that were in flight before a restart again, so
+    /// their outcome is cached in the operation log and published
+    async fn resume_withdrawals(&self, payment_lifecycle_manager: &PaymentLifecycleManager) {
+        for operation in payment_lifecycle_manager
+            .operations(PaymentType::OnchainWithdraw)
+            .await
+        {
+            let operation_id = operation.operation_id;
+            let Some(client) = self.multimint.get(&operation.federation_id).await else {
+                warn!(
+                    operation_id = ?operation_id,
+                    federation_id = %operation.federation_id,
+                    "Federation client not available, withdrawal not resumed"
+                );
+                continue;
+            };
+            let amount = match Self::withdraw_operation(&client, operation_id).await {
+                Ok((details, _)) => bitcoin::Amount::from_sat(details.amount_sat),
+                Err(e) => {
+                    warn!(
+                        operation_id = ?operation_id,
+                        error = ?e,
+                        "Withdrawal not found in operation log, not resumed"
+                    );
+                    continue;
+                }
+            };
+
+            info!(operation_id = ?operation_id, "Resuming withdrawal follow-up");
+            let event_bus = self.event_bus.clone();
+            let correlation_id = operation.correlation_id.unwrap_or_default();
+            tokio::spawn(async move {
+                if let Err(e) = Self::await_withdraw(
+                    event_bus,
+                    client,
+                    operation.federation_id,
+                    operation_id,
+                    amount,
+                    correlation_id,
+                )
+                .await
+                {
+                    error!(
+                        operation_id = ?operation_id,
+                        error = ?e,
+                        "Failed to follow withdrawal"
+                    );
+                }
+            });
+        }
+    }
+
     /// Stop the monitoring services (deposit and balance monitors)
     pub async fn stop_monitoring_services(&self) -> Result<()> {
         if let Some(ref deposit_monitor) = self.deposit_monitor {
@@ -299,6 +709,19 @@ impl FmcdCore {
             info!("Balance monitor stopped successfully");
         }
 
+        if let Some(ref webhook_notifier) = self.webhook_notifier {
+            webhook_notifier.stop();
+            info!("Webhook outbox worker stopped successfully");
+        }
+
+        if let Some(ref event_journal) = self.event_journal {
+            event_journal.stop();
+            info!("Event journal retention task stopped successfully");
+        }
+
+        self.idempotency_store.stop();
+        info!("Idempotency key retention task stopped successfully");
+
         Ok(())
     }
 
@@ -389,29 +812,26 @@ impl FmcdCore {
         // Clone multimint which is cheap due to Arc
         let mut multimint = (*self.multimint).clone();
 
-        let this_federation_id =
-            multimint
-                .register_new(invite_code.clone())
-                .await
-                .map_err(|e| {
-                    // Emit federation connection failed event
-                    let event_bus = self.event_bus.clone();
-                    let federation_id_str = federation_id.to_string();
-                    let correlation_id = context.as_ref().map(|c| c.correlation_id.clone());
-                    let error_msg = e.to_string();
-
-                    tokio::spawn(async move {
-                        let event = FmcdEvent::FederationDisconnected {
-                            federation_id: federation_id_str,
-                            reason: format!("Failed to join: {}", error_msg),
-                            correlation_id,
-                            timestamp: Utc::now(),
-                        };
-                        let _ = event_bus.publish(event).await;
-                    });
-
-                    e
-                })?;
+        let this_federation_id = multimint
+            .register_new(invite_code.clone())
+            .await
+            .inspect_err(|e| {
+                // Emit federation connection failed event
+                let event_bus = self.event_bus.clone();
+                let federation_id_str = federation_id.to_string();
+                let correlation_id = context.as_ref().map(|c| c.correlation_id.clone());
+                let error_msg = e.to_string();
+
+                tokio::spawn(async move {
+                    let event = FmcdEvent::FederationDisconnected {
+                        federation_id: federation_id_str,
+                        reason: format!("Failed to join: {}", error_msg),
+                        correlation_id,
+                        timestamp: Utc::now(),
+                    };
+                    let _ = event_bus.publish(event).await;
+                });
+            })?;
 
         // Emit federation connection success event
         let event_bus = self.event_bus.clone();
@@ -442,6 +862,147 @@ impl FmcdCore {
         })
     }
 
+    /// Leave a federation.
+    ///
+    /// Refuses while the federation holds a balance unless `force` is set.
+    /// Stops monitoring the federation, removes its client and saved config
+    /// from the multimint and, with `wipe_db`, deletes the client's database.
+    pub async fn leave_federation(
+        &self,
+        req: LeaveFederationRequest,
+        context: Option<RequestContext>,
+    ) -> Result<LeaveFederationResponse, AppError> {
+        use chrono::Utc;
+
+        use crate::events::FmcdEvent;
+
+        let federation_id = req.federation_id;
+        let client = self.get_client(federation_id).await?;
+        let balance = client.get_balance().await;
+        drop(client);
+        ensure_can_leave(federation_id, balance, req.force)?;
+
+        info!(
+            federation_id = %federation_id,
+            balance_msat = balance.msats,
+            force = req.force,
+            wipe_db = req.wipe_db,
+            "Leaving federation"
+        );
+
+        // The client is shut down first, monitors can't pick it up again once
+        // it's out of the multimint
+        if let Err(e) = self.multimint.remove(&federation_id, req.wipe_db).await {
+            // The client is put back when it is still in use and can't be wiped
+            let category = if self.multimint.get(&federation_id).await.is_some() {
+                ErrorCategory::Conflict
+            } else {
+                ErrorCategory::DatabaseError
+            };
+            return Err(AppError::with_category(
+                category,
+                format!("Failed to remove federation: {}", e),
+            ));
+        }
+
+        if let Some(ref deposit_monitor) = self.deposit_monitor {
+            deposit_monitor.remove_federation(&federation_id).await;
+        }
+        if let Some(ref balance_monitor) = self.balance_monitor {
+            balance_monitor.remove_federation(&federation_id).await;
+        }
+        if let Some(ref payment_lifecycle_manager) = self.payment_lifecycle_manager {
+            payment_lifecycle_manager
+                .remove_federation(&federation_id)
+                .await;
+        }
+        self.gateway_selector
+            .remove_federation(&federation_id.to_string())
+            .await;
+
+        let _ = self
+            .event_bus
+            .publish(FmcdEvent::FederationDisconnected {
+                federation_id: federation_id.to_string(),
+                reason: "Left federation".to_string(),
+                correlation_id: context.as_ref().map(|c| c.correlation_id.clone()),
+                timestamp: Utc::now(),
+            })
+            .await;
+
+        Ok(LeaveFederationResponse {
+            federation_id,
+            balance_msat: balance,
+            wiped_db: req.wipe_db,
+            federation_ids: self.multimint.ids().await,
+        })
+    }
+
+    pub fn event_journal(&self) -> Result<&Arc<EventJournal>, AppError> {
+        self.event_journal.as_ref().ok_or_else(|| {
+            AppError::with_category(
+                ErrorCategory::ServiceUnavailable,
+                "Event journal is not available".to_string(),
+            )
+        })
+    }
+
+    /// Read journaled events in sequence order, from a consumer's cursor or
+    /// filtered by time, type, federation or correlation id
+    pub async fn query_events(&self, query: JournalQuery) -> Result<JournalPage, AppError> {
+        if let (Some(from), Some(to)) = (query.from, query.to) {
+            if from > to {
+                return Err(AppError::validation_error("from must not be after to"));
+            }
+        }
+
+        self.event_journal()?
+            .query(&query)
+            .await
+            .map_err(|e| AppError::with_category(ErrorCategory::DatabaseError, e.to_string()))
+    }
+
+    /// Gateways to try for a lightning operation without a requested gateway,
+    /// best scored first
+    async fn ranked_gateways(
+        &self,
+        lightning_module: &LightningClientModule,
+        federation_id: FederationId,
+        amount: Amount,
+    ) -> Result<Vec<LightningGateway>, AppError> {
+        let announcements = lightning_module.list_gateways().await;
+        let candidates = GatewaySelector::eligible(
+            announcements
+                .iter()
+                .map(|announcement| GatewayCandidate::from_announcement(announcement, amount))
+                .collect(),
+        );
+
+        let gateways = self
+            .gateway_selector
+            .rank(&federation_id.to_string(), &candidates, amount)
+            .await
+            .iter()
+            .filter_map(|score| {
+                announcements.iter().find(|announcement| {
+                    announcement.info.gateway_id.to_string() == score.gateway_id
+                })
+            })
+            .map(|announcement| announcement.info.clone())
+            .collect::<Vec<_>>();
+
+        if gateways.is_empty() {
+            return Err(AppError::with_category(
+                ErrorCategory::GatewayUnavailable,
+                format!(
+                    "No lightning gateway available for federation {}",
+                    federation_id
+                ),
+            ));
+        }
+        Ok(gateways)
+    }
+
     /// Get wallet info for all federations
     pub async fn get_info(&self) -> Result<HashMap<FederationId, InfoResponse>> {
         let mut info = HashMap::new();
@@ -479,6 +1040,21 @@ impl FmcdCore {
         use chrono::Utc;
         use uuid::Uuid;
 
+        let callback = match req.webhook_url.clone() {
+            Some(webhook_url) => {
+                WebhookEndpoint::validate_webhook_url(&webhook_url).map_err(|e| {
+                    AppError::validation_error(format!("Invalid webhookUrl: {}", e))
+                })?;
+                Some(InvoiceCallback {
+                    webhook_url,
+                    federation_id: req.federation_id,
+                    metadata: req.metadata.clone(),
+                    notifier: self.webhook_notifier()?.clone(),
+                })
+            }
+            None => None,
+        };
+
         let client = self.get_client(req.federation_id).await?;
 
         let lightning_module = client
@@ -495,20 +1071,26 @@ impl FmcdCore {
                 )
             })?;
 
-        let gateway = lightning_module
-            .select_gateway(&req.gateway_id)
-            .await
-            .ok_or_else(|| {
-                error!(
-                    gateway_id = %req.gateway_id,
-                    federation_id = %req.federation_id,
-                    "Failed to select gateway - gateway may be offline or not registered"
-                );
-                AppError::new(
-                    axum::http::StatusCode::BAD_REQUEST,
-                    anyhow!("Failed to select gateway with ID {}. Gateway may be offline or not registered with this federation.", req.gateway_id),
-                )
-            })?;
+        let gateway = match req.gateway_id {
+            Some(gateway_id) => lightning_module
+                .select_gateway(&gateway_id)
+                .await
+                .ok_or_else(|| {
+                    error!(
+                        gateway_id = %gateway_id,
+                        federation_id = %req.federation_id,
+                        "Failed to select gateway - gateway may be offline or not registered"
+                    );
+                    AppError::new(
+                        axum::http::StatusCode::BAD_REQUEST,
+                        anyhow!("Failed to select gateway with ID {}. Gateway may be offline or not registered with this federation.", gateway_id),
+                    )
+                })?,
+            None => self
+                .ranked_gateways(&lightning_module, req.federation_id, req.amount_msat)
+                .await?
+                .remove(0),
+        };
 
         info!(
             gateway_id = %gateway.gateway_id,
@@ -597,6 +1179,8 @@ impl FmcdCore {
                     req.amount_msat,
                     req.metadata.clone(),
                     Some(context.correlation_id.clone()),
+                    invoice_id.clone(),
+                    req.webhook_url.clone(),
                 )
                 .await
             {
@@ -616,14 +1200,15 @@ impl FmcdCore {
         }
 
         // Start automatic monitoring for the invoice
-        self.start_invoice_monitoring(
+        Self::start_invoice_monitoring(
             client,
             operation_id,
             invoice_id.clone(),
             req.amount_msat.msats,
-            invoice_tracker,
-        )
-        .await;
+            invoice.duration_until_expiry(),
+            Some(invoice_tracker),
+            callback,
+        );
 
         info!(
             operation_id = ?operation_id,
@@ -761,16 +1346,23 @@ impl FmcdCore {
         })
     }
 
-    /// Withdraw funds to an on-chain Bitcoin address
+    /// Withdraw funds to an on-chain Bitcoin address. Withdrawals above the
+    /// spending policy's approval threshold are parked for approval.
     pub async fn withdraw_onchain(
         &self,
         req: WithdrawRequest,
         context: RequestContext,
     ) -> Result<WithdrawResponse, AppError> {
-        use std::str::FromStr;
+        self.withdraw_onchain_checked(req, context, false).await
+    }
 
+    async fn withdraw_onchain_checked(
+        &self,
+        req: WithdrawRequest,
+        context: RequestContext,
+        approved: bool,
+    ) -> Result<WithdrawResponse, AppError> {
         use chrono::Utc;
-        use futures_util::StreamExt;
 
         use crate::events::FmcdEvent;
 
@@ -789,41 +1381,79 @@ impl FmcdCore {
                 )
             })?;
 
-        // Parse the address - from_str gives us Address<NetworkUnchecked>
-        let address_unchecked = Address::from_str(&req.address)
-            .map_err(|e| AppError::validation_error(format!("Invalid Bitcoin address: {}", e)))?;
-
-        // TODO: Properly validate network - for now assuming valid
-        let address = address_unchecked.assume_checked();
-        let (amount, fees) = match req.amount_sat {
-            // If the amount is "all", then we need to subtract the fees from
-            // the amount we are withdrawing
-            BitcoinAmountOrAll::All => {
-                let balance = bitcoin::Amount::from_sat(client.get_balance().await.msats / 1000);
-                let fees = wallet_module.get_withdraw_fees(&address, balance).await?;
-                let amount = balance.checked_sub(fees.amount());
-                let amount = match amount {
-                    Some(amount) => amount,
-                    None => {
-                        return Err(AppError::new(
-                            axum::http::StatusCode::BAD_REQUEST,
-                            anyhow!("Insufficient balance to pay fees"),
-                        ))
-                    }
-                };
+        let address = parse_withdraw_address(&req.address, wallet_module.get_network())?;
+        let mut reserved_quote = None;
+        let (amount, fees) = match req.quote_id {
+            Some(ref quote_id) => {
+                let reserved = self.withdraw_quotes.reserve(quote_id).await?;
+                let quote = &reserved.quote;
+                let matches_request = quote.federation_id == req.federation_id
+                    && quote.address == req.address
+                    && match req.amount_sat {
+                        BitcoinAmountOrAll::All => quote.all,
+                        BitcoinAmountOrAll::Amount(amount) => {
+                            !quote.all && quote.amount() == amount
+                        }
+                    };
+                if !matches_request {
+                    return Err(AppError::validation_error(format!(
+                        "Quote {} was issued for a different withdrawal",
+                        quote_id
+                    )));
+                }
 
-                (amount, fees)
+                let current_fees = wallet_module
+                    .get_withdraw_fees(&address, quote.amount())
+                    .await?;
+                quote.check_fee_rate(
+                    current_fees.fee_rate.sats_per_kvb,
+                    req.fee_tolerance_percent
+                        .unwrap_or(DEFAULT_FEE_TOLERANCE_PERCENT),
+                )?;
+
+                let amount_and_fees = (quote.amount(), quote.fees());
+                reserved_quote = Some(reserved);
+                amount_and_fees
+            }
+            None => {
+                Self::withdraw_amount_and_fees(&client, &wallet_module, &address, req.amount_sat)
+                    .await?
             }
-            BitcoinAmountOrAll::Amount(amount) => (
-                amount,
-                wallet_module.get_withdraw_fees(&address, amount).await?,
-            ),
         };
         let absolute_fees = fees.amount();
 
+        let authorization = self
+            .authorize_or_request_approval(
+                SpendIntent {
+                    federation_id: req.federation_id,
+                    kind: SpendKind::OnchainWithdrawal,
+                    amount_msat: (amount + absolute_fees).to_sat() * 1000,
+                    destinations: vec![address.to_string()],
+                    approved,
+                },
+                ApprovalAction::OnchainWithdrawal(req.clone()),
+                req.address.clone(),
+                &context,
+            )
+            .await?;
+
         info!("Attempting withdraw with fees: {fees:?}");
 
         let operation_id = wallet_module.withdraw(&address, amount, fees, ()).await?;
+        self.record_spend(&authorization).await;
+
+        // The withdrawal is submitted, the quote can't be used a second time
+        if let Some(reserved) = reserved_quote {
+            let quote_id = reserved.quote.quote_id.clone();
+            if let Err(e) = reserved.consume().await {
+                error!(
+                    quote_id = %quote_id,
+                    operation_id = ?operation_id,
+                    error = ?e,
+                    "Failed to remove used withdraw quote"
+                );
+            }
+        }
 
         // Emit withdrawal initiated event
         let withdrawal_initiated_event = FmcdEvent::WithdrawalInitiated {
@@ -855,7 +1485,12 @@ impl FmcdCore {
         // Register with payment lifecycle manager for comprehensive monitoring
         if let Some(ref payment_lifecycle_manager) = self.payment_lifecycle_manager {
             if let Err(e) = payment_lifecycle_manager
-                .track_onchain_withdraw(operation_id, req.federation_id, amount.to_sat())
+                .track_onchain_withdraw(
+                    operation_id,
+                    req.federation_id,
+                    amount.to_sat(),
+                    Some(context.correlation_id.clone()),
+                )
                 .await
             {
                 error!(
@@ -871,6 +1506,105 @@ impl FmcdCore {
             }
         }
 
+        let mut response = WithdrawResponse {
+            operation_id,
+            txid: None,
+            amount_sat: amount.to_sat(),
+            fees_sat: absolute_fees.to_sat(),
+            fee_rate_sats_per_kvb: fees.fee_rate.sats_per_kvb,
+        };
+
+        let event_bus = self.event_bus.clone();
+        let federation_id = req.federation_id;
+        if req.async_mode {
+            // Follow the withdrawal in the background, its progress is
+            // reported by the operation status endpoints and events
+            tokio::spawn(async move {
+                if let Err(e) = Self::await_withdraw(
+                    event_bus,
+                    client,
+                    federation_id,
+                    operation_id,
+                    amount,
+                    context.correlation_id,
+                )
+                .await
+                {
+                    error!(
+                        operation_id = ?operation_id,
+                        error = ?e,
+                        "Failed to follow withdrawal"
+                    );
+                }
+            });
+            return Ok(response);
+        }
+
+        let txid = Self::await_withdraw(
+            event_bus,
+            client,
+            federation_id,
+            operation_id,
+            amount,
+            context.correlation_id,
+        )
+        .await?;
+        response.txid = Some(txid);
+        Ok(response)
+    }
+
+    /// Amount a withdrawal sends and the fees it pays, for `All` the balance
+    /// minus the fees. Fails when the amount sent would be dust.
+    async fn withdraw_amount_and_fees(
+        client: &ClientHandleArc,
+        wallet_module: &WalletClientModule,
+        address: &Address,
+        amount: BitcoinAmountOrAll,
+    ) -> Result<(bitcoin::Amount, fedimint_wallet_client::PegOutFees), AppError> {
+        let (amount, fees) = match amount {
+            // If the amount is "all", then we need to subtract the fees from
+            // the amount we are withdrawing
+            BitcoinAmountOrAll::All => {
+                let balance = bitcoin::Amount::from_sat(client.get_balance().await.msats / 1000);
+                let fees = wallet_module.get_withdraw_fees(address, balance).await?;
+                let amount = balance.checked_sub(fees.amount());
+                let amount = match amount {
+                    Some(amount) => amount,
+                    None => {
+                        return Err(AppError::new(
+                            axum::http::StatusCode::BAD_REQUEST,
+                            anyhow!("Insufficient balance to pay fees"),
+                        ))
+                    }
+                };
+
+                (amount, fees)
+            }
+            BitcoinAmountOrAll::Amount(amount) => (
+                amount,
+                wallet_module.get_withdraw_fees(address, amount).await?,
+            ),
+        };
+        check_dust_limit(address, amount)?;
+
+        Ok((amount, fees))
+    }
+
+    /// Wait until a withdrawal transaction is broadcast or the withdrawal
+    /// fails, publishing the outcome
+    async fn await_withdraw(
+        event_bus: Arc<EventBus>,
+        client: ClientHandleArc,
+        federation_id: FederationId,
+        operation_id: OperationId,
+        amount: bitcoin::Amount,
+        correlation_id: String,
+    ) -> Result<Txid, AppError> {
+        use chrono::Utc;
+
+        use crate::events::FmcdEvent;
+
+        let wallet_module = client.get_first_module::<WalletClientModule>()?;
         let mut updates = wallet_module
             .subscribe_withdraw_updates(operation_id)
             .await?
@@ -884,15 +1618,15 @@ impl FmcdCore {
                     // Emit withdrawal succeeded event
                     let withdrawal_succeeded_event = FmcdEvent::WithdrawalSucceeded {
                         operation_id: format!("{:?}", operation_id),
-                        federation_id: req.federation_id.to_string(),
+                        federation_id: federation_id.to_string(),
                         amount_sat: amount.to_sat(),
                         txid: txid.to_string(),
                         timestamp: Utc::now(),
                     };
-                    if let Err(e) = self.event_bus.publish(withdrawal_succeeded_event).await {
+                    if let Err(e) = event_bus.publish(withdrawal_succeeded_event).await {
                         error!(
                             operation_id = ?operation_id,
-                            correlation_id = %context.correlation_id,
+                            correlation_id = %correlation_id,
                             txid = %txid,
                             error = ?e,
                             "Failed to publish withdrawal completed event"
@@ -905,10 +1639,7 @@ impl FmcdCore {
                         "Withdrawal completed successfully"
                     );
 
-                    return Ok(WithdrawResponse {
-                        txid: txid,
-                        fees_sat: absolute_fees.to_sat(),
-                    });
+                    return Ok(txid);
                 }
                 WithdrawState::Failed(e) => {
                     let error_reason = format!("Withdraw failed: {:?}", e);
@@ -916,15 +1647,15 @@ impl FmcdCore {
                     // Emit withdrawal failed event
                     let withdrawal_failed_event = FmcdEvent::WithdrawalFailed {
                         operation_id: format!("{:?}", operation_id),
-                        federation_id: req.federation_id.to_string(),
+                        federation_id: federation_id.to_string(),
                         reason: error_reason.clone(),
-                        correlation_id: Some(context.correlation_id.clone()),
+                        correlation_id: Some(correlation_id.clone()),
                         timestamp: Utc::now(),
                     };
-                    if let Err(event_err) = self.event_bus.publish(withdrawal_failed_event).await {
+                    if let Err(event_err) = event_bus.publish(withdrawal_failed_event).await {
                         error!(
                             operation_id = ?operation_id,
-                            correlation_id = %context.correlation_id,
+                            correlation_id = %correlation_id,
                             error = ?event_err,
                             "Failed to publish withdrawal failed event"
                         );
@@ -949,15 +1680,15 @@ impl FmcdCore {
         let error_reason = "Update stream ended without outcome".to_string();
         let withdrawal_failed_event = FmcdEvent::WithdrawalFailed {
             operation_id: format!("{:?}", operation_id),
-            federation_id: req.federation_id.to_string(),
+            federation_id: federation_id.to_string(),
             reason: error_reason.clone(),
-            correlation_id: Some(context.correlation_id.clone()),
+            correlation_id: Some(correlation_id.clone()),
             timestamp: Utc::now(),
         };
-        if let Err(e) = self.event_bus.publish(withdrawal_failed_event).await {
+        if let Err(e) = event_bus.publish(withdrawal_failed_event).await {
             error!(
                 operation_id = ?operation_id,
-                correlation_id = %context.correlation_id,
+                correlation_id = %correlation_id,
                 error = ?e,
                 "Failed to publish withdrawal failed event for stream timeout"
             );
@@ -974,6 +1705,92 @@ impl FmcdCore {
         ))
     }
 
+    /// Look up a withdrawal in the operation log, with its final state once
+    /// known
+    async fn withdraw_operation(
+        client: &ClientHandleArc,
+        operation_id: OperationId,
+    ) -> Result<(WithdrawDetails, Option<WithdrawState>), AppError> {
+        use fedimint_wallet_client::{WalletOperationMeta, WalletOperationMetaVariant};
+
+        let operation = client
+            .operation_log()
+            .get_operation(operation_id)
+            .await
+            .ok_or_else(|| {
+                AppError::not_found(format!("Operation {} not found", operation_id.fmt_short()))
+            })?;
+
+        let details = match operation.operation_module_kind() {
+            "wallet" => match operation.meta::<WalletOperationMeta>().variant {
+                WalletOperationMetaVariant::Withdraw {
+                    address,
+                    amount,
+                    fee,
+                    ..
+                } => Some(WithdrawDetails {
+                    address: address.assume_checked().to_string(),
+                    amount_sat: amount.to_sat(),
+                    fees_sat: fee.amount().to_sat(),
+                    fee_rate_sats_per_kvb: fee.fee_rate.sats_per_kvb,
+                }),
+                _ => None,
+            },
+            _ => None,
+        }
+        .ok_or_else(|| {
+            AppError::validation_error(format!(
+                "Operation {} is not a withdrawal",
+                operation_id.fmt_short()
+            ))
+        })?;
+
+        // The outcome is cached once the update stream of the withdrawal ran
+        // to its end, until then it is still in flight
+        Ok((details, operation.outcome::<WithdrawState>()))
+    }
+
+    /// Current state of a withdrawal
+    pub async fn withdraw_status(
+        &self,
+        federation_id: FederationId,
+        operation_id: OperationId,
+    ) -> Result<WithdrawStatusResponse, AppError> {
+        let client = self.get_client(federation_id).await?;
+        let (details, outcome) = Self::withdraw_operation(&client, operation_id).await?;
+        let status = outcome.map_or(WithdrawStatus::Created, WithdrawStatus::from);
+
+        Ok(WithdrawStatusResponse {
+            operation_id,
+            status,
+            details,
+        })
+    }
+
+    /// State transitions of a withdrawal, ending with its final state
+    pub async fn withdraw_updates(
+        &self,
+        federation_id: FederationId,
+        operation_id: OperationId,
+    ) -> Result<futures_util::stream::BoxStream<'static, WithdrawStatusResponse>, AppError> {
+        let client = self.get_client(federation_id).await?;
+        let (details, _) = Self::withdraw_operation(&client, operation_id).await?;
+
+        let updates = client
+            .get_first_module::<WalletClientModule>()?
+            .subscribe_withdraw_updates(operation_id)
+            .await?
+            .into_stream();
+
+        Ok(updates
+            .map(move |state| WithdrawStatusResponse {
+                operation_id,
+                status: WithdrawStatus::from(state),
+                details: details.clone(),
+            })
+            .boxed())
+    }
+
     /// Pay a lightning invoice
     pub async fn pay_invoice(
         &self,
@@ -983,16 +1800,34 @@ impl FmcdCore {
         self.pay_invoice_with_resolver(req, context, None).await
     }
 
-    /// Pay a lightning invoice with optional payment info resolver
+    /// Pay a lightning invoice with optional payment info resolver. Payments
+    /// above the spending policy's approval threshold are parked for approval.
     pub async fn pay_invoice_with_resolver(
+        &self,
+        req: LnPayRequest,
+        context: RequestContext,
+        resolver: Option<&dyn PaymentInfoResolver>,
+    ) -> Result<LnPayResponse, AppError> {
+        self.pay_invoice_checked(req, context, resolver, false)
+            .await
+    }
+
+    async fn pay_invoice_checked(
         &self,
         mut req: LnPayRequest,
         context: RequestContext,
         resolver: Option<&dyn PaymentInfoResolver>,
+        approved: bool,
     ) -> Result<LnPayResponse, AppError> {
         use crate::observability::{sanitize_invoice, sanitize_preimage};
 
         let client = self.get_client(req.federation_id).await?;
+        // Parked as submitted if it needs approval, so the payment info is
+        // resolved again when it is executed
+        let submitted = req.clone();
+        // What the caller asked to pay, e.g. a lightning address, before it
+        // is resolved to an invoice
+        let requested_destination = req.payment_info.trim().to_string();
 
         // Use resolver if provided to handle non-Bolt11 payment info
         if let Some(resolver) = resolver {
@@ -1031,6 +1866,81 @@ impl FmcdCore {
             );
         }
 
+        let amount = req
+            .amount_msat
+            .or_else(|| bolt11.amount_milli_satoshis().map(Amount::from_msats))
+            .unwrap_or(Amount::ZERO);
+
+        // Get lightning module
+        let lightning_module = client
+            .get_first_module::<LightningClientModule>()
+            .map_err(|e| {
+                let error_msg = "Lightning module not available".to_string();
+                error!(
+                    error = ?e,
+                    federation_id = %req.federation_id,
+                    "Lightning module not available"
+                );
+                AppError::with_category(ErrorCategory::PaymentTimeout, error_msg)
+                    .with_context(context.clone())
+            })?;
+
+        // Use the requested gateway, or try the best scored gateways in turn
+        let gateways = match req.gateway_id {
+            Some(gateway_id) => {
+                let gateway = lightning_module
+                    .select_gateway(&gateway_id)
+                    .await
+                    .ok_or_else(|| {
+                        let error_msg = format!("Gateway {} not available", gateway_id);
+                        error!(
+                            gateway_id = %gateway_id,
+                            federation_id = %req.federation_id,
+                            "Gateway not available"
+                        );
+                        AppError::with_category(ErrorCategory::GatewayError, error_msg)
+                            .with_context(context.clone())
+                    })?;
+                vec![gateway]
+            }
+            None => {
+                let mut gateways = self
+                    .ranked_gateways(&lightning_module, req.federation_id, amount)
+                    .await
+                    .map_err(|e| e.with_context(context.clone()))?;
+                gateways.truncate(self.gateway_selector.config().max_attempts);
+                gateways
+            }
+        };
+
+        // Gateways charge their fee on top of the invoice, the spend is
+        // authorized for the most expensive candidate
+        let max_gateway_fee = gateways
+            .iter()
+            .map(|gateway| gateway.fees.to_amount(&amount))
+            .max()
+            .unwrap_or(Amount::ZERO);
+
+        // Checked against the payee node and what was requested to be paid
+        let mut destinations = vec![bolt11.get_payee_pub_key().to_string()];
+        if requested_destination != bolt11.to_string() {
+            destinations.push(requested_destination.clone());
+        }
+        let authorization = self
+            .authorize_or_request_approval(
+                SpendIntent {
+                    federation_id: req.federation_id,
+                    kind: SpendKind::LightningPayment,
+                    amount_msat: (amount + max_gateway_fee).msats,
+                    destinations,
+                    approved,
+                },
+                ApprovalAction::LightningPayment(submitted),
+                requested_destination,
+                &context,
+            )
+            .await?;
+
         // Initialize payment tracker
         let mut payment_tracker = PaymentTracker::new(
             req.federation_id,
@@ -1054,76 +1964,130 @@ impl FmcdCore {
             )
             .await;
 
-        // Get lightning module
-        let lightning_module = client
-            .get_first_module::<LightningClientModule>()
-            .map_err(|e| {
-                let error_msg = "Lightning module not available".to_string();
-                error!(
-                    error = ?e,
-                    payment_id = %payment_tracker.payment_id(),
-                    "Lightning module not available"
-                );
-                // Note: Can't update tracker in non-async error closure
-                AppError::with_category(ErrorCategory::PaymentTimeout, error_msg)
-                    .with_context(context.clone())
-            })?;
+        let mut last_error = None;
+        for gateway in gateways {
+            let gateway_id = gateway.gateway_id.to_string();
+            payment_tracker.gateway_selected(gateway_id.clone()).await;
+
+            // Create outgoing payment, a gateway that can't take it is skipped
+            let OutgoingLightningPayment {
+                payment_type,
+                contract_id,
+                fee,
+            } = match lightning_module
+                .pay_bolt11_invoice(Some(gateway), bolt11.clone(), req.amount_msat)
+                .await
+            {
+                Ok(payment) => payment,
+                Err(e) => {
+                    let error_msg = format!("Payment failed: {}", e);
+                    warn!(
+                        gateway_id = %gateway_id,
+                        payment_id = %payment_tracker.payment_id(),
+                        error = ?e,
+                        "Payment failed on gateway"
+                    );
+                    payment_tracker
+                        .gateway_unavailable(gateway_id, error_msg.clone())
+                        .await;
+                    last_error = Some(
+                        AppError::with_category(ErrorCategory::PaymentTimeout, error_msg)
+                            .with_context(context.clone()),
+                    );
+                    continue;
+                }
+            };
 
-        // Select gateway
-        let gateway = lightning_module
-            .select_gateway(&req.gateway_id)
-            .await
-            .ok_or_else(|| {
-                let error_msg = format!("Gateway {} not available", req.gateway_id);
-                error!(
-                    gateway_id = %req.gateway_id,
-                    payment_id = %payment_tracker.payment_id(),
-                    "Gateway not available"
-                );
-                // Note: Can't update tracker in non-async error closure
-                AppError::with_category(ErrorCategory::GatewayError, error_msg)
-                    .with_context(context.clone())
-            })?;
+            // The contract is funded, the spend counts until it is refunded
+            self.record_spend(&authorization).await;
 
-        // Create outgoing payment
-        let OutgoingLightningPayment {
-            payment_type,
-            contract_id,
-            fee,
-        } = lightning_module
-            .pay_bolt11_invoice(Some(gateway), bolt11, req.amount_msat)
-            .await
-            .map_err(|e| {
-                let error_msg = format!("Payment failed: {}", e);
-                error!(
-                    error = ?e,
-                    payment_id = %payment_tracker.payment_id(),
-                    "Payment failed during execution"
-                );
-                // Note: Can't update tracker in non-async error closure
-                AppError::with_category(ErrorCategory::PaymentTimeout, error_msg)
-                    .with_context(context.clone())
-            })?;
+            // Extract the operation_id from the payment_type
+            let operation_id = match &payment_type {
+                PayType::Internal(op_id) => *op_id,
+                PayType::Lightning(op_id) => *op_id,
+            };
 
-        // Extract the operation_id from the payment_type
-        let operation_id = match &payment_type {
-            PayType::Internal(op_id) => *op_id,
-            PayType::Lightning(op_id) => *op_id,
-        };
+            let preimage = match Self::await_outgoing_payment(
+                &lightning_module,
+                &payment_type,
+                payment_tracker.payment_id(),
+                &context,
+            )
+            .await?
+            {
+                PaymentOutcome::Paid { preimage } => preimage,
+                PaymentOutcome::Refunded { error, retry } => {
+                    self.release_spend(&authorization).await;
+                    if !retry {
+                        payment_tracker.fail(error.clone()).await;
+                        return Err(AppError::validation_error(error).with_context(context));
+                    }
+                    warn!(
+                        gateway_id = %gateway_id,
+                        payment_id = %payment_tracker.payment_id(),
+                        error = %error,
+                        "Gateway refunded payment"
+                    );
+                    payment_tracker
+                        .gateway_unavailable(gateway_id, error.clone())
+                        .await;
+                    last_error = Some(
+                        AppError::validation_error(format!("Payment refunded: {}", error))
+                            .with_context(context.clone()),
+                    );
+                    continue;
+                }
+            };
 
-        // Wait for payment completion - inline the logic from wait_for_ln_payment
+            // Track successful payment
+            payment_tracker
+                .succeed(
+                    preimage.clone(),
+                    req.amount_msat.map(|a| a.msats).unwrap_or(0),
+                    0,
+                )
+                .await;
+
+            info!(
+                payment_id = %payment_tracker.payment_id(),
+                preimage = %sanitize_preimage(&preimage),
+                "Payment completed successfully"
+            );
+
+            return Ok(LnPayResponse {
+                operation_id,
+                payment_type,
+                contract_id: contract_id.to_string(),
+                fee,
+                preimage,
+            });
+        }
+
+        let error = last_error.unwrap_or_else(|| {
+            AppError::with_category(ErrorCategory::GatewayUnavailable, "No gateway available")
+                .with_context(context)
+        });
+        payment_tracker.fail(error.message.clone()).await;
+        Err(error)
+    }
+
+    /// Wait for an outgoing payment to settle or be refunded by its gateway
+    async fn await_outgoing_payment(
+        lightning_module: &LightningClientModule,
+        payment_type: &PayType,
+        payment_id: &str,
+        context: &RequestContext,
+    ) -> Result<PaymentOutcome, AppError> {
         use fedimint_ln_client::{InternalPayState, LnPayState};
-        use futures_util::StreamExt;
 
-        let preimage = match payment_type.clone() {
+        let preimage = match *payment_type {
             PayType::Internal(op_id) => {
                 let mut updates = lightning_module
                     .subscribe_internal_pay(op_id)
                     .await
                     .map_err(|e| {
                         let error_msg = format!("Failed to subscribe to payment: {}", e);
-                        error!(error = ?e, payment_id = %payment_tracker.payment_id(), "Subscribe failed");
-                        // Note: Can't update tracker in non-async error closure
+                        error!(error = ?e, payment_id = %payment_id, "Subscribe failed");
                         AppError::validation_error(error_msg).with_context(context.clone())
                     })?
                     .into_stream();
@@ -1139,17 +2103,21 @@ impl FmcdCore {
                             out_points: _,
                             error,
                         } => {
-                            let error_msg =
-                                format!("Internal payment failed with refund. Error: {}", error);
-                            error!(payment_id = %payment_tracker.payment_id(), "Payment refunded");
-                            // Note: Can't update tracker in non-async error closure
-                            return Err(AppError::validation_error(error_msg).with_context(context));
+                            error!(payment_id = %payment_id, "Payment refunded");
+                            return Ok(PaymentOutcome::Refunded {
+                                error: format!(
+                                    "Internal payment failed with refund. Error: {}",
+                                    error
+                                ),
+                                retry: false,
+                            });
                         }
                         InternalPayState::UnexpectedError(e) => {
                             let error_msg = format!("Unexpected payment error: {}", e);
-                            error!(payment_id = %payment_tracker.payment_id(), "Unexpected error");
-                            // Note: Can't update tracker in non-async error closure
-                            return Err(AppError::validation_error(error_msg).with_context(context));
+                            error!(payment_id = %payment_id, "Unexpected error");
+                            return Err(
+                                AppError::validation_error(error_msg).with_context(context.clone())
+                            );
                         }
                         _ => continue,
                     }
@@ -1162,8 +2130,7 @@ impl FmcdCore {
                     .await
                     .map_err(|e| {
                         let error_msg = format!("Failed to subscribe to payment: {}", e);
-                        error!(error = ?e, payment_id = %payment_tracker.payment_id(), "Subscribe failed");
-                        // Note: Can't update tracker in non-async error closure
+                        error!(error = ?e, payment_id = %payment_id, "Subscribe failed");
                         AppError::validation_error(error_msg).with_context(context.clone())
                     })?
                     .into_stream();
@@ -1176,10 +2143,10 @@ impl FmcdCore {
                             break;
                         }
                         LnPayState::Refunded { gateway_error } => {
-                            let error_msg = format!("Payment refunded: {}", gateway_error);
-                            error!(payment_id = %payment_tracker.payment_id(), "Payment refunded");
-                            // Note: Can't update tracker in non-async error closure
-                            return Err(AppError::validation_error(error_msg).with_context(context));
+                            return Ok(PaymentOutcome::Refunded {
+                                error: gateway_error.to_string(),
+                                retry: true,
+                            });
                         }
                         _ => continue,
                     }
@@ -1189,50 +2156,27 @@ impl FmcdCore {
         };
 
         let preimage = preimage.ok_or_else(|| {
-            let error_msg = "Payment completed but no preimage returned".to_string();
             error!(
-                payment_id = %payment_tracker.payment_id(),
+                payment_id = %payment_id,
                 "Payment completed but no preimage returned"
             );
-            // Note: Can't update tracker in non-async error closure
-            AppError::validation_error(error_msg).with_context(context.clone())
+            AppError::validation_error("Payment completed but no preimage returned")
+                .with_context(context.clone())
         })?;
 
-        // Track successful payment
-        payment_tracker
-            .succeed(
-                preimage.clone(),
-                req.amount_msat.map(|a| a.msats).unwrap_or(0),
-                0,
-            )
-            .await;
-
-        info!(
-            payment_id = %payment_tracker.payment_id(),
-            preimage = %sanitize_preimage(&preimage),
-            "Payment completed successfully"
-        );
-
-        Ok(LnPayResponse {
-            operation_id,
-            payment_type,
-            contract_id: contract_id.to_string(),
-            fee,
-            preimage,
-        })
+        Ok(PaymentOutcome::Paid { preimage })
     }
 
     /// Start automatic monitoring for an invoice
-    async fn start_invoice_monitoring(
-        &self,
+    fn start_invoice_monitoring(
         client: ClientHandleArc,
         operation_id: OperationId,
         invoice_id: String,
         amount_msat: u64,
-        invoice_tracker: InvoiceTracker,
+        timeout: Duration,
+        invoice_tracker: Option<InvoiceTracker>,
+        callback: Option<InvoiceCallback>,
     ) {
-        let timeout = Duration::from_secs(24 * 60 * 60); // 24 hours max timeout
-
         tokio::spawn(async move {
             if let Err(e) = Self::monitor_invoice_settlement(
                 client,
@@ -1241,6 +2185,7 @@ impl FmcdCore {
                 amount_msat,
                 timeout,
                 invoice_tracker,
+                callback,
             )
             .await
             {
@@ -1261,7 +2206,8 @@ impl FmcdCore {
         invoice_id: String,
         amount_msat: u64,
         timeout: Duration,
-        invoice_tracker: InvoiceTracker,
+        invoice_tracker: Option<InvoiceTracker>,
+        callback: Option<InvoiceCallback>,
     ) -> anyhow::Result<()> {
         use fedimint_ln_client::LnReceiveState;
         use futures_util::StreamExt;
@@ -1284,7 +2230,11 @@ impl FmcdCore {
         tokio::pin!(timeout_future);
 
         loop {
+            // Updates first, so a settlement that is already known wins over a
+            // timeout that is already due
             tokio::select! {
+                biased;
+
                 update = updates.next() => {
                     match update {
                         Some(LnReceiveState::Claimed) => {
@@ -1295,7 +2245,14 @@ impl FmcdCore {
                             );
 
                             // Publish invoice paid event to event bus
-                            invoice_tracker.paid(amount_msat).await;
+                            if let Some(ref invoice_tracker) = invoice_tracker {
+                                invoice_tracker.paid(amount_msat).await;
+                            }
+                            if let Some(ref callback) = callback {
+                                callback
+                                    .notify("invoice_paid", operation_id, &invoice_id, amount_msat)
+                                    .await;
+                            }
                             break;
                         }
                         Some(LnReceiveState::Canceled { reason }) => {
@@ -1307,7 +2264,14 @@ impl FmcdCore {
                             );
 
                             // Publish invoice expiration/cancellation event to event bus
-                            invoice_tracker.expired().await;
+                            if let Some(ref invoice_tracker) = invoice_tracker {
+                                invoice_tracker.expired().await;
+                            }
+                            if let Some(ref callback) = callback {
+                                callback
+                                    .notify("invoice_expired", operation_id, &invoice_id, amount_msat)
+                                    .await;
+                            }
                             break;
                         }
                         Some(state) => {
@@ -1336,6 +2300,11 @@ impl FmcdCore {
                         timeout_secs = timeout.as_secs(),
                         "Invoice settlement monitoring timed out"
                     );
+                    if let Some(ref callback) = callback {
+                        callback
+                            .notify("invoice_expired", operation_id, &invoice_id, amount_msat)
+                            .await;
+                    }
                     break;
                 }
             }
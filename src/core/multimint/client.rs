@@ -3,14 +3,14 @@
 
 use std::collections::BTreeMap;
 use std::fmt::Debug;
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 
 use anyhow::Result;
 use bip39::Mnemonic;
 use fedimint_bip39::Bip39RootSecretStrategy;
 use fedimint_client::db::ClientConfigKey;
 use fedimint_client::secret::RootSecretStrategy;
-use fedimint_client::{Client, ClientBuilder, RootSecret};
+use fedimint_client::{Client, ClientBuilder, ClientHandle, RootSecret};
 use fedimint_core::config::FederationId;
 use fedimint_core::db::{
     Committable, Database, DatabaseTransaction, IDatabaseTransactionOpsCoreTyped,
@@ -26,12 +26,31 @@ use super::db::{FederationConfig, FederationIdKey, FederationIdKeyPrefix};
 
 #[derive(Debug, Clone)]
 pub struct LocalClientBuilder {
-    mnemonic: Mnemonic,
+    /// Shared between clones so that a restored mnemonic is picked up by every
+    /// handle to the multimint
+    mnemonic: Arc<RwLock<Mnemonic>>,
 }
 
 impl LocalClientBuilder {
     pub fn new(mnemonic: Mnemonic) -> Self {
-        Self { mnemonic }
+        Self {
+            mnemonic: Arc::new(RwLock::new(mnemonic)),
+        }
+    }
+
+    /// Replace the mnemonic used to derive federation secrets
+    pub fn set_mnemonic(&self, mnemonic: Mnemonic) {
+        *self
+            .mnemonic
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = mnemonic;
+    }
+
+    fn mnemonic(&self) -> Mnemonic {
+        self.mnemonic
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
     }
 }
 
@@ -60,6 +79,27 @@ impl LocalClientBuilder {
         Ok(Arc::new(client_res))
     }
 
+    /// Join a federation in recovery mode, restoring module state from the
+    /// latest backup the federation holds for this client's secret.
+    ///
+    /// The returned client still has recoveries in progress, callers should
+    /// wait for them to finish and restart the client before using it.
+    pub async fn recover(&self, db: &Database, config: FederationConfig) -> Result<ClientHandle> {
+        let federation_id = config.invite_code.federation_id();
+        let db = db.with_prefix(federation_id.consensus_encode_to_vec());
+        let secret = self.derive_federation_secret(&federation_id);
+
+        if Client::is_initialized(&db).await {
+            anyhow::bail!("Client for federation {federation_id} is already initialized")
+        }
+
+        let client_builder = self.create_client_builder(db).await?;
+        let client_preview = client_builder.preview(&config.invite_code).await?;
+        client_preview
+            .recover(RootSecret::Custom(secret), None)
+            .await
+    }
+
     /// Save the federation config to the database
     pub async fn save_config(
         &self,
@@ -84,7 +124,7 @@ impl LocalClientBuilder {
     }
 
     pub fn derive_federation_secret(&self, federation_id: &FederationId) -> DerivableSecret {
-        let global_root_secret = Bip39RootSecretStrategy::<12>::to_root_secret(&self.mnemonic);
+        let global_root_secret = Bip39RootSecretStrategy::<12>::to_root_secret(&self.mnemonic());
         let multi_federation_root_secret = global_root_secret.child_key(ChildId(0));
         let federation_root_secret = multi_federation_root_secret.federation_key(federation_id);
         let federation_wallet_root_secret = federation_root_secret.child_key(ChildId(0));
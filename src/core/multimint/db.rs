@@ -1,4 +1,5 @@
 use fedimint_core::config::FederationId;
+use fedimint_core::core::OperationId;
 use fedimint_core::encoding::{Decodable, Encodable};
 use fedimint_core::invite_code::InviteCode;
 use fedimint_core::{impl_db_lookup, impl_db_record};
@@ -8,6 +9,17 @@ use serde::{Deserialize, Serialize};
 #[derive(Clone, Debug)]
 pub enum DbKeyPrefix {
     FederationConfig = 0x04,
+    PaymentOperation = 0x05,
+    WebhookOutbox = 0x06,
+    WebhookDeadLetter = 0x07,
+    WebhookDeliveryLog = 0x08,
+    EventJournal = 0x09,
+    IdempotencyKey = 0x0A,
+    WithdrawQuote = 0x0B,
+    PolicySpend = 0x0C,
+    ApprovalRequest = 0x0D,
+    ApiKey = 0x0E,
+    AuditLog = 0x0F,
 }
 
 impl std::fmt::Display for DbKeyPrefix {
@@ -36,3 +48,283 @@ impl_db_record!(
 );
 
 impl_db_lookup!(key = FederationIdKey, query_prefix = FederationIdKeyPrefix);
+
+#[derive(Debug, Clone, Encodable, Decodable, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct PaymentOperationKey {
+    pub federation_id: FederationId,
+    pub operation_id: OperationId,
+}
+
+#[derive(Debug, Encodable, Decodable)]
+pub struct PaymentOperationKeyPrefix;
+
+#[derive(Debug, Encodable, Decodable)]
+pub struct PaymentOperationFederationPrefix {
+    pub federation_id: FederationId,
+}
+
+/// A payment operation tracked by the payment lifecycle manager, stored as
+/// JSON since its metadata is free-form
+#[derive(Debug, Clone, Eq, PartialEq, Encodable, Decodable)]
+pub struct PaymentOperationRecord {
+    pub json: String,
+}
+
+impl_db_record!(
+    key = PaymentOperationKey,
+    value = PaymentOperationRecord,
+    db_prefix = DbKeyPrefix::PaymentOperation,
+);
+
+impl_db_lookup!(
+    key = PaymentOperationKey,
+    query_prefix = PaymentOperationKeyPrefix,
+    query_prefix = PaymentOperationFederationPrefix
+);
+
+#[derive(Debug, Clone, Encodable, Decodable, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct WebhookOutboxKey {
+    pub delivery_id: String,
+}
+
+#[derive(Debug, Encodable, Decodable)]
+pub struct WebhookOutboxKeyPrefix;
+
+#[derive(Debug, Clone, Encodable, Decodable, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct WebhookDeadLetterKey {
+    pub delivery_id: String,
+}
+
+#[derive(Debug, Encodable, Decodable)]
+pub struct WebhookDeadLetterKeyPrefix;
+
+/// A webhook delivery waiting in the outbox or the dead-letter list, stored as
+/// JSON like payment operations
+#[derive(Debug, Clone, Eq, PartialEq, Encodable, Decodable)]
+pub struct WebhookDeliveryRecord {
+    pub json: String,
+}
+
+impl_db_record!(
+    key = WebhookOutboxKey,
+    value = WebhookDeliveryRecord,
+    db_prefix = DbKeyPrefix::WebhookOutbox,
+);
+
+impl_db_lookup!(
+    key = WebhookOutboxKey,
+    query_prefix = WebhookOutboxKeyPrefix
+);
+
+impl_db_record!(
+    key = WebhookDeadLetterKey,
+    value = WebhookDeliveryRecord,
+    db_prefix = DbKeyPrefix::WebhookDeadLetter,
+);
+
+impl_db_lookup!(
+    key = WebhookDeadLetterKey,
+    query_prefix = WebhookDeadLetterKeyPrefix
+);
+
+#[derive(Debug, Clone, Encodable, Decodable, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct WebhookDeliveryLogKey {
+    pub endpoint_id: String,
+    pub delivery_id: String,
+}
+
+#[derive(Debug, Encodable, Decodable)]
+pub struct WebhookDeliveryLogKeyPrefix;
+
+#[derive(Debug, Encodable, Decodable)]
+pub struct WebhookDeliveryLogEndpointPrefix {
+    pub endpoint_id: String,
+}
+
+impl_db_record!(
+    key = WebhookDeliveryLogKey,
+    value = WebhookDeliveryRecord,
+    db_prefix = DbKeyPrefix::WebhookDeliveryLog,
+);
+
+impl_db_lookup!(
+    key = WebhookDeliveryLogKey,
+    query_prefix = WebhookDeliveryLogKeyPrefix,
+    query_prefix = WebhookDeliveryLogEndpointPrefix
+);
+
+/// Journal entries are keyed by sequence number, the variable length integer
+/// encoding keeps the keys ordered by sequence
+#[derive(Debug, Clone, Copy, Encodable, Decodable, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct EventJournalKey {
+    pub sequence: u64,
+}
+
+#[derive(Debug, Encodable, Decodable)]
+pub struct EventJournalKeyPrefix;
+
+/// A journaled event, stored as JSON like webhook deliveries
+#[derive(Debug, Clone, Eq, PartialEq, Encodable, Decodable)]
+pub struct EventJournalRecord {
+    pub json: String,
+}
+
+impl_db_record!(
+    key = EventJournalKey,
+    value = EventJournalRecord,
+    db_prefix = DbKeyPrefix::EventJournal,
+);
+
+impl_db_lookup!(key = EventJournalKey, query_prefix = EventJournalKeyPrefix);
+
+/// Requests are remembered by their client supplied idempotency key
+#[derive(Debug, Clone, Encodable, Decodable, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct IdempotencyKey {
+    pub key: String,
+}
+
+#[derive(Debug, Encodable, Decodable)]
+pub struct IdempotencyKeyPrefix;
+
+/// Hash and response of an idempotent request, stored as JSON
+#[derive(Debug, Clone, Eq, PartialEq, Encodable, Decodable)]
+pub struct IdempotencyRecord {
+    pub json: String,
+}
+
+impl_db_record!(
+    key = IdempotencyKey,
+    value = IdempotencyRecord,
+    db_prefix = DbKeyPrefix::IdempotencyKey,
+);
+
+impl_db_lookup!(key = IdempotencyKey, query_prefix = IdempotencyKeyPrefix);
+
+/// Withdraw quotes are looked up by the id handed out to the client
+#[derive(Debug, Clone, Encodable, Decodable, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct WithdrawQuoteKey {
+    pub quote_id: String,
+}
+
+#[derive(Debug, Encodable, Decodable)]
+pub struct WithdrawQuoteKeyPrefix;
+
+/// A withdraw quote not used yet, stored as JSON
+#[derive(Debug, Clone, Eq, PartialEq, Encodable, Decodable)]
+pub struct WithdrawQuoteRecord {
+    pub json: String,
+}
+
+impl_db_record!(
+    key = WithdrawQuoteKey,
+    value = WithdrawQuoteRecord,
+    db_prefix = DbKeyPrefix::WithdrawQuote,
+);
+
+impl_db_lookup!(
+    key = WithdrawQuoteKey,
+    query_prefix = WithdrawQuoteKeyPrefix
+);
+
+/// Spends counted against a federation's rolling spending caps
+#[derive(Debug, Clone, Encodable, Decodable, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct PolicySpendKey {
+    pub federation_id: FederationId,
+    pub spend_id: String,
+}
+
+#[derive(Debug, Encodable, Decodable)]
+pub struct PolicySpendKeyPrefix;
+
+#[derive(Debug, Encodable, Decodable)]
+pub struct PolicySpendFederationPrefix {
+    pub federation_id: FederationId,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Encodable, Decodable)]
+pub struct PolicySpendRecord {
+    pub amount_msat: u64,
+    /// Unix time of the spend in seconds
+    pub timestamp: u64,
+}
+
+impl_db_record!(
+    key = PolicySpendKey,
+    value = PolicySpendRecord,
+    db_prefix = DbKeyPrefix::PolicySpend,
+);
+
+impl_db_lookup!(
+    key = PolicySpendKey,
+    query_prefix = PolicySpendKeyPrefix,
+    query_prefix = PolicySpendFederationPrefix
+);
+
+/// Payments and withdrawals parked for approval, by approval id
+#[derive(Debug, Clone, Encodable, Decodable, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct ApprovalKey {
+    pub approval_id: String,
+}
+
+#[derive(Debug, Encodable, Decodable)]
+pub struct ApprovalKeyPrefix;
+
+/// An approval request with the parked request, stored as JSON
+#[derive(Debug, Clone, Eq, PartialEq, Encodable, Decodable)]
+pub struct ApprovalRecord {
+    pub json: String,
+}
+
+impl_db_record!(
+    key = ApprovalKey,
+    value = ApprovalRecord,
+    db_prefix = DbKeyPrefix::ApprovalRequest,
+);
+
+impl_db_lookup!(key = ApprovalKey, query_prefix = ApprovalKeyPrefix);
+
+/// API keys by key id
+#[derive(Debug, Clone, Encodable, Decodable, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct ApiKeyKey {
+    pub key_id: String,
+}
+
+#[derive(Debug, Encodable, Decodable)]
+pub struct ApiKeyKeyPrefix;
+
+/// An API key with the hash of its secret, stored as JSON
+#[derive(Debug, Clone, Eq, PartialEq, Encodable, Decodable)]
+pub struct ApiKeyRecord {
+    pub json: String,
+}
+
+impl_db_record!(
+    key = ApiKeyKey,
+    value = ApiKeyRecord,
+    db_prefix = DbKeyPrefix::ApiKey,
+);
+
+impl_db_lookup!(key = ApiKeyKey, query_prefix = ApiKeyKeyPrefix);
+
+/// Audit records are keyed by sequence number like journal entries
+#[derive(Debug, Clone, Copy, Encodable, Decodable, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct AuditLogKey {
+    pub sequence: u64,
+}
+
+#[derive(Debug, Encodable, Decodable)]
+pub struct AuditLogKeyPrefix;
+
+/// A hash-chained audit record, stored as JSON
+#[derive(Debug, Clone, Eq, PartialEq, Encodable, Decodable)]
+pub struct AuditLogRecord {
+    pub json: String,
+}
+
+impl_db_record!(
+    key = AuditLogKey,
+    value = AuditLogRecord,
+    db_prefix = DbKeyPrefix::AuditLog,
+);
+
+impl_db_lookup!(key = AuditLogKey, query_prefix = AuditLogKeyPrefix);
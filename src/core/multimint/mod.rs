@@ -26,7 +26,7 @@
 //!
 //! Example:
 //!
-//! ```rust
+//! ```rust,ignore
 //! use crate::core::multimint::MultiMint;
 //! use std::path::PathBuf;
 //!
@@ -64,28 +64,38 @@
 use std::collections::BTreeMap;
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::Result;
 use bip39::Mnemonic;
 use fedimint_bip39::Bip39RootSecretStrategy;
+use fedimint_client::db::EncodedClientSecretKey;
 use fedimint_client::secret::RootSecretStrategy;
-use fedimint_client::{Client, ClientHandleArc};
+use fedimint_client::{Client, ClientHandle, ClientHandleArc};
 use fedimint_core::config::{FederationId, FederationIdPrefix};
-use fedimint_core::db::Database;
+use fedimint_core::db::{Database, IDatabaseTransactionOpsCore, IDatabaseTransactionOpsCoreTyped};
+use fedimint_core::encoding::Encodable;
 use fedimint_core::invite_code::InviteCode;
 use fedimint_ln_client::LightningClientModule;
 use rand::thread_rng;
 use tokio::sync::Mutex;
+use tokio::time::{sleep, Instant};
 use tracing::{info, warn};
 
 pub mod client;
 pub mod db;
 
 #[cfg(test)]
-mod tests;
+#[path = "tests.rs"]
+mod multimint_tests;
 
 use self::client::LocalClientBuilder;
-use self::db::FederationConfig;
+use self::db::{FederationConfig, FederationIdKey};
+
+/// How long `remove` waits for other users of a client to release it, e.g.
+/// monitors in the middle of a poll
+pub const CLIENT_RELEASE_TIMEOUT: Duration = Duration::from_secs(10);
+const CLIENT_RELEASE_POLL_INTERVAL: Duration = Duration::from_millis(100);
 
 /// `MultiMint` is a struct for managing Fedimint Clients across multiple
 /// federations.
@@ -105,7 +115,7 @@ impl MultiMint {
     ///
     /// # Example
     ///
-    /// ```rust
+    /// ```rust,ignore
     /// use crate::core::multimint::MultiMint;
     /// use std::path::PathBuf;
     ///
@@ -169,7 +179,7 @@ impl MultiMint {
         for config in configs {
             let federation_id = config.invite_code.federation_id();
 
-            if let Ok(client) = client_builder.build(&db, config.clone()).await {
+            if let Ok(client) = client_builder.build(db, config.clone()).await {
                 clients.insert(federation_id, client);
             } else {
                 warn!("Failed to load client for federation: {federation_id}");
@@ -219,6 +229,139 @@ impl MultiMint {
         Ok(federation_id)
     }
 
+    /// Replace the stored mnemonic with a previously used one, so that clients
+    /// derive the same secrets they had on the original data dir.
+    ///
+    /// Only allowed while no federations are registered, since existing
+    /// clients were initialized with secrets from the current mnemonic.
+    pub async fn replace_mnemonic(&self, mnemonic: Mnemonic) -> Result<()> {
+        let dbtx = self.db.begin_transaction().await;
+        let configs = self.client_builder.load_configs(dbtx.into_nc()).await;
+        if !configs.is_empty() || !self.clients.lock().await.is_empty() {
+            anyhow::bail!("Cannot replace the mnemonic while federations are registered");
+        }
+
+        let mut dbtx = self.db.begin_transaction().await;
+        dbtx.insert_entry(
+            &EncodedClientSecretKey,
+            &mnemonic.to_entropy().consensus_encode_to_vec(),
+        )
+        .await;
+        dbtx.commit_tx_result()
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to store mnemonic: {:?}", e))?;
+
+        self.client_builder.set_mnemonic(mnemonic);
+        info!("Replaced multimint mnemonic");
+        Ok(())
+    }
+
+    /// Join a federation in recovery mode and persist its config, so that the
+    /// recovery resumes on the next start if the process stops before it
+    /// completes.
+    ///
+    /// The client is not added to the multimint, use `insert_recovered` once
+    /// its recoveries are done.
+    pub async fn recover(&self, invite_code: InviteCode) -> Result<ClientHandle> {
+        let federation_id = invite_code.federation_id();
+        if self.clients.lock().await.contains_key(&federation_id) {
+            anyhow::bail!("Federation already registered: {federation_id}");
+        }
+
+        let client_cfg = FederationConfig { invite_code };
+        let client = self
+            .client_builder
+            .recover(&self.db, client_cfg.clone())
+            .await?;
+
+        let dbtx = self.db.begin_transaction().await;
+        self.client_builder.save_config(client_cfg, dbtx).await?;
+
+        Ok(client)
+    }
+
+    /// Undo a recovery that failed: shut its client down and delete its saved
+    /// config and database, so restoring the federation can be retried from
+    /// scratch instead of being refused for a config that exists already.
+    pub async fn abandon_recovery(
+        &self,
+        federation_id: FederationId,
+        client: Option<ClientHandle>,
+    ) -> Result<()> {
+        if let Some(client) = client {
+            client.shutdown().await;
+        }
+
+        let mut dbtx = self.db.begin_transaction().await;
+        dbtx.remove_entry(&FederationIdKey { id: federation_id })
+            .await;
+        dbtx.raw_remove_by_prefix(&federation_id.consensus_encode_to_vec())
+            .await?;
+        dbtx.commit_tx_result()
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to remove failed recovery: {:?}", e))?;
+
+        info!("Removed failed recovery of federation {federation_id}");
+        Ok(())
+    }
+
+    /// The top level multimint database, shared by the services that persist
+    /// their own state next to the federation configs.
+    pub fn db(&self) -> &Database {
+        &self.db
+    }
+
+    /// Add a client that finished recovery to the multimint.
+    pub async fn insert_recovered(&self, federation_id: FederationId, client: ClientHandleArc) {
+        self.clients.lock().await.insert(federation_id, client);
+    }
+
+    /// Remove a client from the multimint and delete its saved federation
+    /// config so it is not loaded again on the next start.
+    ///
+    /// The client is taken out of the multimint first, so no new users can get
+    /// it, and shut down once the users it already has release it. With
+    /// `wipe_db` the client's database prefix is removed as well, which
+    /// discards its notes and operation history for good. Wiping is refused,
+    /// leaving the client registered, when it isn't released within
+    /// `CLIENT_RELEASE_TIMEOUT`. Returns whether a client was registered for
+    /// the federation.
+    pub async fn remove(&self, federation_id: &FederationId, wipe_db: bool) -> Result<bool> {
+        let client = self.clients.lock().await.remove(federation_id);
+        let had_client = client.is_some();
+
+        if let Some(client) = client {
+            if let Err(client) = shutdown_when_released(client, CLIENT_RELEASE_TIMEOUT).await {
+                if wipe_db {
+                    self.clients.lock().await.insert(*federation_id, client);
+                    anyhow::bail!(
+                        "Client for federation {federation_id} is still in use, refusing to wipe its database"
+                    );
+                }
+                warn!(
+                    "Client for federation {federation_id} is still in use, it will shut down once released"
+                );
+            }
+        }
+
+        let mut dbtx = self.db.begin_transaction().await;
+        let had_config = dbtx
+            .remove_entry(&FederationIdKey { id: *federation_id })
+            .await
+            .is_some();
+        if wipe_db {
+            dbtx.raw_remove_by_prefix(&federation_id.consensus_encode_to_vec())
+                .await?;
+        }
+        dbtx.commit_tx_result()
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to remove federation: {:?}", e))?;
+
+        info!("Removed federation {federation_id} from multimint (wiped database: {wipe_db})");
+
+        Ok(had_client || had_config)
+    }
+
     /// Get all the clients in the multimint.
     pub async fn all(&self) -> Vec<ClientHandleArc> {
         self.clients.lock().await.values().cloned().collect()
@@ -290,6 +433,28 @@ impl MultiMint {
     }
 }
 
+/// Shut a client down once every other handle to it is dropped, giving it
+/// back when that doesn't happen within `timeout`
+async fn shutdown_when_released(
+    mut client: ClientHandleArc,
+    timeout: Duration,
+) -> std::result::Result<(), ClientHandleArc> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        match Arc::try_unwrap(client) {
+            Ok(client) => {
+                client.shutdown().await;
+                return Ok(());
+            }
+            Err(shared) if Instant::now() >= deadline => return Err(shared),
+            Err(shared) => {
+                client = shared;
+                sleep(CLIENT_RELEASE_POLL_INTERVAL).await;
+            }
+        }
+    }
+}
+
 async fn load_or_generate_mnemonic(db: &Database) -> Result<Mnemonic> {
     Ok(
         if let Ok(entropy) = Client::load_decodable_client_secret::<Vec<u8>>(db).await {
@@ -303,7 +468,7 @@ async fn load_or_generate_mnemonic(db: &Database) -> Result<Mnemonic> {
                 Bip39RootSecretStrategy::<12>::random(&mut thread_rng())
             };
 
-            Client::store_encodable_client_secret(&db, mnemonic.to_entropy()).await?;
+            Client::store_encodable_client_secret(db, mnemonic.to_entropy()).await?;
             mnemonic
         },
     )
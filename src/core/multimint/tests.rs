@@ -43,8 +43,8 @@ mod tests {
         let temp_dir = TempDir::new().unwrap();
         let work_dir = temp_dir.path().to_path_buf();
 
-        // Create first instance
-        let _multimint1 = MultiMint::new(work_dir.clone()).await.unwrap();
+        // Create first instance, closing its database before reopening it
+        drop(MultiMint::new(work_dir.clone()).await.unwrap());
 
         // Create second instance with same work_dir
         let _multimint2 = MultiMint::new(work_dir.clone()).await.unwrap();
@@ -81,6 +81,76 @@ mod tests {
             "Mnemonic should be persisted"
         );
     }
+
+    /// Save a federation config and a key under the federation's client
+    /// prefix, as joining would
+    async fn save_federation(multimint: &MultiMint, federation_id: FederationId) -> Vec<u8> {
+        use fedimint_core::PeerId;
+
+        let invite_code = InviteCode::new(
+            "wss://fedimint.example.com".parse().unwrap(),
+            PeerId::from(0),
+            federation_id,
+            None,
+        );
+        let mut client_key = federation_id.consensus_encode_to_vec();
+        client_key.push(0x01);
+
+        let mut dbtx = multimint.db().begin_transaction().await;
+        dbtx.insert_entry(
+            &FederationIdKey { id: federation_id },
+            &FederationConfig { invite_code },
+        )
+        .await;
+        dbtx.raw_insert_bytes(&client_key, b"notes").await.unwrap();
+        dbtx.commit_tx().await;
+        client_key
+    }
+
+    async fn saved_state(
+        multimint: &MultiMint,
+        federation_id: FederationId,
+        client_key: &[u8],
+    ) -> (bool, bool) {
+        let mut dbtx = multimint.db().begin_transaction_nc().await;
+        let has_config = dbtx
+            .get_value(&FederationIdKey { id: federation_id })
+            .await
+            .is_some();
+        let has_client_data = dbtx.raw_get_bytes(client_key).await.unwrap().is_some();
+        (has_config, has_client_data)
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_remove_deletes_config_and_keeps_client_data() {
+        let temp_dir = TempDir::new().unwrap();
+        let multimint = MultiMint::new(temp_dir.path().to_path_buf()).await.unwrap();
+        let federation_id = FederationId::dummy();
+        let client_key = save_federation(&multimint, federation_id).await;
+
+        assert!(multimint.remove(&federation_id, false).await.unwrap());
+        assert_eq!(
+            saved_state(&multimint, federation_id, &client_key).await,
+            (false, true)
+        );
+
+        // Nothing left to remove
+        assert!(!multimint.remove(&federation_id, false).await.unwrap());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_remove_wipes_client_data() {
+        let temp_dir = TempDir::new().unwrap();
+        let multimint = MultiMint::new(temp_dir.path().to_path_buf()).await.unwrap();
+        let federation_id = FederationId::dummy();
+        let client_key = save_federation(&multimint, federation_id).await;
+
+        assert!(multimint.remove(&federation_id, true).await.unwrap());
+        assert_eq!(
+            saved_state(&multimint, federation_id, &client_key).await,
+            (false, false)
+        );
+    }
 }
 
 #[cfg(test)]
@@ -167,4 +237,48 @@ mod db_tests {
         // We can't fully test insert without a valid InviteCode
         // This would require more complex mocking
     }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_payment_operation_records_by_federation() {
+        use fedimint_core::core::OperationId;
+        use fedimint_core::db::IDatabaseTransactionOpsCoreTyped;
+        use futures_util::StreamExt;
+
+        let temp_dir = TempDir::new().unwrap();
+        let db = Database::new(
+            fedimint_rocksdb::RocksDb::open(temp_dir.path().join("test.db"))
+                .await
+                .unwrap(),
+            Default::default(),
+        );
+
+        let federation_id = FederationId::dummy();
+        let key = PaymentOperationKey {
+            federation_id,
+            operation_id: OperationId::new_random(),
+        };
+        let record = PaymentOperationRecord {
+            json: r#"{"metadata":{"order":42}}"#.to_string(),
+        };
+
+        let mut dbtx = db.begin_transaction().await;
+        dbtx.insert_entry(&key, &record).await;
+        dbtx.commit_tx_result().await.unwrap();
+
+        let mut dbtx = db.begin_transaction_nc().await;
+        let stored = dbtx
+            .find_by_prefix(&PaymentOperationFederationPrefix { federation_id })
+            .await
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(stored, vec![(key.clone(), record)]);
+
+        let mut dbtx = db.begin_transaction().await;
+        dbtx.remove_by_prefix(&PaymentOperationFederationPrefix { federation_id })
+            .await;
+        dbtx.commit_tx_result().await.unwrap();
+
+        let mut dbtx = db.begin_transaction_nc().await;
+        assert!(dbtx.get_value(&key).await.is_none());
+    }
 }
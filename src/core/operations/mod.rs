@@ -1,6 +1,8 @@
 pub mod payment;
+pub mod restore;
 
 pub use payment::{InvoiceTracker, PaymentState, PaymentTracker};
+pub use restore::{RestoreState, RestoreStatus, RestoreTracker};
 
 #[cfg(test)]
 mod tests;
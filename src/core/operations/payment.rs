@@ -175,6 +175,26 @@ impl PaymentTracker {
         }
     }
 
+    /// Report that the selected gateway could not complete the payment
+    #[instrument(skip(self), fields(payment_id = %self.payment_id))]
+    pub async fn gateway_unavailable(&self, gateway_id: String, reason: String) {
+        let event = FmcdEvent::GatewayUnavailable {
+            gateway_id,
+            federation_id: self.federation_id.clone(),
+            reason,
+            correlation_id: self.correlation_id.clone(),
+            timestamp: Utc::now(),
+        };
+
+        if let Err(e) = self.event_bus.publish(event).await {
+            tracing::error!(
+                payment_id = %self.payment_id,
+                error = ?e,
+                "Failed to publish gateway unavailable event"
+            );
+        }
+    }
+
     /// Mark payment as executing
     #[instrument(skip(self), fields(payment_id = %self.payment_id))]
     pub async fn start_execution(&mut self) {
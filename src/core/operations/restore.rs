@@ -0,0 +1,170 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
+use fedimint_core::config::FederationId;
+use serde::Serialize;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RestoreState {
+    Recovering,
+    Completed,
+    Failed,
+}
+
+/// Progress of a single client module recovery, as reported by the fedimint
+/// client
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModuleRecoveryProgress {
+    pub complete: u32,
+    pub total: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FederationRestoreStatus {
+    pub federation_id: FederationId,
+    pub state: RestoreState,
+    pub modules: BTreeMap<u16, ModuleRecoveryProgress>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub balance_msat: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoreStatus {
+    pub restore_id: String,
+    pub state: RestoreState,
+    pub started_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<DateTime<Utc>>,
+    pub federations: Vec<FederationRestoreStatus>,
+}
+
+impl RestoreStatus {
+    fn federation_mut(
+        &mut self,
+        federation_id: &FederationId,
+    ) -> Option<&mut FederationRestoreStatus> {
+        self.federations
+            .iter_mut()
+            .find(|f| f.federation_id == *federation_id)
+    }
+
+    /// Move the whole restore to a terminal state once every federation has
+    /// finished, failed if any of them did
+    fn refresh_state(&mut self) {
+        if self
+            .federations
+            .iter()
+            .any(|f| f.state == RestoreState::Recovering)
+        {
+            return;
+        }
+
+        self.state = if self
+            .federations
+            .iter()
+            .any(|f| f.state == RestoreState::Failed)
+        {
+            RestoreState::Failed
+        } else {
+            RestoreState::Completed
+        };
+        self.finished_at = Some(Utc::now());
+    }
+}
+
+/// Tracks the progress of the most recent wallet restore so it can be polled
+/// while recoveries run in the background
+#[derive(Debug, Clone, Default)]
+pub struct RestoreTracker {
+    status: Arc<RwLock<Option<RestoreStatus>>>,
+}
+
+impl RestoreTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start tracking a new restore, fails if one is still running
+    pub async fn begin(&self, federation_ids: Vec<FederationId>) -> Result<RestoreStatus> {
+        let mut status = self.status.write().await;
+        if let Some(current) = status.as_ref() {
+            if current.state == RestoreState::Recovering {
+                bail!("Restore {} is already in progress", current.restore_id);
+            }
+        }
+
+        let restore = RestoreStatus {
+            restore_id: Uuid::new_v4().to_string(),
+            state: RestoreState::Recovering,
+            started_at: Utc::now(),
+            finished_at: None,
+            federations: federation_ids
+                .into_iter()
+                .map(|federation_id| FederationRestoreStatus {
+                    federation_id,
+                    state: RestoreState::Recovering,
+                    modules: BTreeMap::new(),
+                    balance_msat: None,
+                    error: None,
+                })
+                .collect(),
+        };
+        *status = Some(restore.clone());
+        Ok(restore)
+    }
+
+    /// Record module progress, returns whether it changed since the last
+    /// update
+    pub async fn update_module(
+        &self,
+        federation_id: &FederationId,
+        module_id: u16,
+        progress: ModuleRecoveryProgress,
+    ) -> bool {
+        let mut status = self.status.write().await;
+        let Some(federation) = status
+            .as_mut()
+            .and_then(|s| s.federation_mut(federation_id))
+        else {
+            return false;
+        };
+        federation.modules.insert(module_id, progress) != Some(progress)
+    }
+
+    pub async fn complete_federation(&self, federation_id: &FederationId, balance_msat: u64) {
+        let mut status = self.status.write().await;
+        if let Some(status) = status.as_mut() {
+            if let Some(federation) = status.federation_mut(federation_id) {
+                federation.state = RestoreState::Completed;
+                federation.balance_msat = Some(balance_msat);
+            }
+            status.refresh_state();
+        }
+    }
+
+    pub async fn fail_federation(&self, federation_id: &FederationId, error: String) {
+        let mut status = self.status.write().await;
+        if let Some(status) = status.as_mut() {
+            if let Some(federation) = status.federation_mut(federation_id) {
+                federation.state = RestoreState::Failed;
+                federation.error = Some(error);
+            }
+            status.refresh_state();
+        }
+    }
+
+    /// Get the status of the most recent restore
+    pub async fn current(&self) -> Option<RestoreStatus> {
+        self.status.read().await.clone()
+    }
+}
@@ -1 +1,2 @@
 mod payment_tests;
+mod restore_tests;
@@ -0,0 +1,80 @@
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::str::FromStr;
+
+    use fedimint_core::config::FederationId;
+
+    use crate::core::operations::restore::*;
+
+    fn federation_id(byte: char) -> FederationId {
+        FederationId::from_str(&byte.to_string().repeat(64)).expect("Valid federation ID")
+    }
+
+    #[tokio::test]
+    async fn test_restore_tracker_completes_when_all_federations_finish() {
+        let tracker = RestoreTracker::new();
+        let (fed_a, fed_b) = (federation_id('a'), federation_id('b'));
+
+        let status = tracker.begin(vec![fed_a, fed_b]).await.unwrap();
+        assert_eq!(status.state, RestoreState::Recovering);
+        assert_eq!(status.federations.len(), 2);
+
+        tracker.complete_federation(&fed_a, 1000).await;
+        let status = tracker.current().await.unwrap();
+        assert_eq!(status.state, RestoreState::Recovering);
+        assert!(status.finished_at.is_none());
+
+        tracker.complete_federation(&fed_b, 2000).await;
+        let status = tracker.current().await.unwrap();
+        assert_eq!(status.state, RestoreState::Completed);
+        assert!(status.finished_at.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_restore_tracker_fails_if_any_federation_fails() {
+        let tracker = RestoreTracker::new();
+        let (fed_a, fed_b) = (federation_id('a'), federation_id('b'));
+
+        tracker.begin(vec![fed_a, fed_b]).await.unwrap();
+        tracker
+            .fail_federation(&fed_a, "no backup".to_string())
+            .await;
+        tracker.complete_federation(&fed_b, 0).await;
+
+        let status = tracker.current().await.unwrap();
+        assert_eq!(status.state, RestoreState::Failed);
+        assert_eq!(status.federations[0].error.as_deref(), Some("no backup"));
+    }
+
+    #[tokio::test]
+    async fn test_restore_tracker_rejects_concurrent_restore() {
+        let tracker = RestoreTracker::new();
+        let fed_a = federation_id('a');
+
+        tracker.begin(vec![fed_a]).await.unwrap();
+        assert!(tracker.begin(vec![fed_a]).await.is_err());
+
+        tracker.complete_federation(&fed_a, 0).await;
+        assert!(tracker.begin(vec![fed_a]).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_restore_tracker_module_progress_dedup() {
+        let tracker = RestoreTracker::new();
+        let fed_a = federation_id('a');
+        tracker.begin(vec![fed_a]).await.unwrap();
+
+        let progress = ModuleRecoveryProgress {
+            complete: 1,
+            total: 10,
+        };
+        assert!(tracker.update_module(&fed_a, 0, progress).await);
+        assert!(!tracker.update_module(&fed_a, 0, progress).await);
+        assert!(
+            !tracker
+                .update_module(&federation_id('b'), 0, progress)
+                .await
+        );
+    }
+}
@@ -0,0 +1,570 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use fedimint_core::config::FederationId;
+use fedimint_core::db::{Database, IDatabaseTransactionOpsCoreTyped};
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::{error, warn};
+use uuid::Uuid;
+
+use crate::core::approvals::ApprovalAction;
+use crate::core::multimint::db::{PolicySpendFederationPrefix, PolicySpendKey, PolicySpendRecord};
+use crate::core::FmcdCore;
+use crate::error::{AppError, ErrorCategory};
+use crate::observability::correlation::RequestContext;
+
+const DAY_SECS: u64 = 24 * 60 * 60;
+const WEEK_SECS: u64 = 7 * DAY_SECS;
+
+/// Limits on what a federation's funds may be spent on. Limits left unset
+/// don't apply.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpendingLimits {
+    /// Largest amount a single payment, withdrawal or ecash spend may move
+    pub max_per_transaction_msat: Option<u64>,
+    /// Cap on the amount spent in the last 24 hours
+    pub daily_cap_msat: Option<u64>,
+    /// Cap on the amount spent in the last 7 days
+    pub weekly_cap_msat: Option<u64>,
+    /// Lightning payments and withdrawals of at least this amount are parked
+    /// until approved, ecash spends are rejected
+    pub approval_threshold_msat: Option<u64>,
+    /// When not empty, only these destinations can be paid. Ecash spends
+    /// have no destination and are rejected then.
+    #[serde(default)]
+    pub allowed_destinations: Vec<String>,
+    /// Destinations that can never be paid
+    #[serde(default)]
+    pub denied_destinations: Vec<String>,
+}
+
+/// Spending policy configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpendingPolicyConfig {
+    /// Limits of federations without an entry of their own
+    #[serde(default)]
+    pub default: SpendingLimits,
+    /// Limits by federation id, replacing the default limits
+    #[serde(default)]
+    pub federations: BTreeMap<String, SpendingLimits>,
+    /// How long a parked payment or withdrawal can be approved
+    #[serde(default = "default_approval_ttl_secs")]
+    pub approval_ttl_secs: u64,
+}
+
+impl Default for SpendingPolicyConfig {
+    fn default() -> Self {
+        Self {
+            default: SpendingLimits::default(),
+            federations: BTreeMap::new(),
+            approval_ttl_secs: default_approval_ttl_secs(),
+        }
+    }
+}
+
+fn default_approval_ttl_secs() -> u64 {
+    DAY_SECS
+}
+
+impl SpendingPolicyConfig {
+    pub fn limits(&self, federation_id: &FederationId) -> &SpendingLimits {
+        self.federations
+            .get(&federation_id.to_string())
+            .unwrap_or(&self.default)
+    }
+}
+
+/// Kind of spend a policy decision is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SpendKind {
+    LightningPayment,
+    OnchainWithdrawal,
+    EcashSpend,
+}
+
+impl SpendKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::LightningPayment => "lightning_payment",
+            Self::OnchainWithdrawal => "onchain_withdrawal",
+            Self::EcashSpend => "ecash_spend",
+        }
+    }
+}
+
+/// A spend checked against the policy before it is executed
+#[derive(Debug, Clone)]
+pub struct SpendIntent {
+    pub federation_id: FederationId,
+    pub kind: SpendKind,
+    pub amount_msat: u64,
+    /// Where the funds go, e.g. the address, the payee node id or the
+    /// lightning address paid. Empty for ecash spends.
+    pub destinations: Vec<String>,
+    /// Whether an operator approved the spend, so the approval threshold
+    /// doesn't apply anymore
+    pub approved: bool,
+}
+
+/// Policy rule a spend broke
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyRule {
+    MaxPerTransaction,
+    DailyCap,
+    WeeklyCap,
+    DestinationDenied,
+    DestinationNotAllowed,
+    ApprovalRequired,
+}
+
+impl PolicyRule {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::MaxPerTransaction => "max_per_transaction",
+            Self::DailyCap => "daily_cap",
+            Self::WeeklyCap => "weekly_cap",
+            Self::DestinationDenied => "destination_denied",
+            Self::DestinationNotAllowed => "destination_not_allowed",
+            Self::ApprovalRequired => "approval_required",
+        }
+    }
+}
+
+/// Why a spend was rejected
+#[derive(Debug, Clone)]
+pub struct PolicyViolation {
+    pub rule: PolicyRule,
+    pub message: String,
+    /// The limit that was exceeded, for amount rules
+    pub limit_msat: Option<u64>,
+}
+
+impl PolicyViolation {
+    fn new(rule: PolicyRule, message: impl Into<String>, limit_msat: Option<u64>) -> Self {
+        Self {
+            rule,
+            message: message.into(),
+            limit_msat,
+        }
+    }
+}
+
+impl From<PolicyViolation> for AppError {
+    fn from(violation: PolicyViolation) -> Self {
+        AppError::with_category(ErrorCategory::PolicyViolation, violation.message).with_details(
+            json!({
+                "rule": violation.rule.as_str(),
+                "limitMsat": violation.limit_msat,
+            }),
+        )
+    }
+}
+
+impl SpendingLimits {
+    /// Check the rules that don't depend on earlier spends
+    pub fn check(&self, intent: &SpendIntent) -> Result<(), PolicyViolation> {
+        let listed = |list: &[String], destination: &String| {
+            list.iter()
+                .any(|entry| entry.eq_ignore_ascii_case(destination))
+        };
+
+        if let Some(denied) = intent
+            .destinations
+            .iter()
+            .find(|destination| listed(&self.denied_destinations, destination))
+        {
+            return Err(PolicyViolation::new(
+                PolicyRule::DestinationDenied,
+                format!("Destination {} is denied by the spending policy", denied),
+                None,
+            ));
+        }
+        if !self.allowed_destinations.is_empty()
+            && !intent
+                .destinations
+                .iter()
+                .any(|destination| listed(&self.allowed_destinations, destination))
+        {
+            return Err(PolicyViolation::new(
+                PolicyRule::DestinationNotAllowed,
+                format!(
+                    "Destination of the {} is not allowed by the spending policy",
+                    intent.kind.as_str().replace('_', " ")
+                ),
+                None,
+            ));
+        }
+
+        if let Some(max) = self.max_per_transaction_msat {
+            if intent.amount_msat > max {
+                return Err(PolicyViolation::new(
+                    PolicyRule::MaxPerTransaction,
+                    format!(
+                        "Amount of {} msat exceeds the maximum of {} msat per transaction",
+                        intent.amount_msat, max
+                    ),
+                    Some(max),
+                ));
+            }
+        }
+        if let Some(threshold) = self.approval_threshold_msat {
+            if !intent.approved && intent.amount_msat >= threshold {
+                return Err(PolicyViolation::new(
+                    PolicyRule::ApprovalRequired,
+                    format!(
+                        "Amount of {} msat needs approval, the threshold is {} msat",
+                        intent.amount_msat, threshold
+                    ),
+                    Some(threshold),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Check the rolling caps, given what was spent in the last day and week
+    pub fn check_caps(
+        &self,
+        amount_msat: u64,
+        spent_day_msat: u64,
+        spent_week_msat: u64,
+    ) -> Result<(), PolicyViolation> {
+        for (cap, spent, rule, period) in [
+            (
+                self.daily_cap_msat,
+                spent_day_msat,
+                PolicyRule::DailyCap,
+                "24 hours",
+            ),
+            (
+                self.weekly_cap_msat,
+                spent_week_msat,
+                PolicyRule::WeeklyCap,
+                "7 days",
+            ),
+        ] {
+            if let Some(cap) = cap {
+                if spent.saturating_add(amount_msat) > cap {
+                    return Err(PolicyViolation::new(
+                        rule,
+                        format!(
+                            "Amount of {} msat exceeds the cap of {} msat per {}, {} msat were spent already",
+                            amount_msat, cap, period, spent
+                        ),
+                        Some(cap),
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+struct PendingSpend {
+    federation_id: FederationId,
+    amount_msat: u64,
+}
+
+/// Checks payments, withdrawals and ecash spends against the configured
+/// limits before they are executed.
+///
+/// Executed spends are kept in the multimint database for a week, so the
+/// rolling caps survive a restart. A spend is counted as soon as it is
+/// authorized, and stops counting if it is dropped without being committed.
+pub struct SpendingPolicy {
+    db: Database,
+    config: SpendingPolicyConfig,
+    pending: Arc<std::sync::Mutex<HashMap<String, PendingSpend>>>,
+    /// Serializes cap checks, so concurrent spends can't both take the rest of
+    /// a cap
+    cap_lock: tokio::sync::Mutex<()>,
+}
+
+impl std::fmt::Debug for SpendingPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SpendingPolicy")
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+impl SpendingPolicy {
+    pub fn new(db: Database, config: SpendingPolicyConfig) -> Self {
+        Self {
+            db,
+            config,
+            pending: Arc::new(std::sync::Mutex::new(HashMap::new())),
+            cap_lock: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    pub fn config(&self) -> &SpendingPolicyConfig {
+        &self.config
+    }
+
+    /// Check a spend against the federation's limits. The returned
+    /// authorization has to be committed once the spend was executed.
+    pub async fn authorize(
+        &self,
+        intent: &SpendIntent,
+    ) -> Result<SpendAuthorization, PolicyViolation> {
+        let limits = self.config.limits(&intent.federation_id);
+        limits.check(intent)?;
+
+        let _guard = self.cap_lock.lock().await;
+        if limits.daily_cap_msat.is_some() || limits.weekly_cap_msat.is_some() {
+            let (spent_day, spent_week) = self.spent(&intent.federation_id).await;
+            limits.check_caps(intent.amount_msat, spent_day, spent_week)?;
+        }
+
+        let spend_id = Uuid::new_v4().to_string();
+        self.pending
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .insert(
+                spend_id.clone(),
+                PendingSpend {
+                    federation_id: intent.federation_id,
+                    amount_msat: intent.amount_msat,
+                },
+            );
+        Ok(SpendAuthorization {
+            db: self.db.clone(),
+            pending: self.pending.clone(),
+            spend_id,
+            federation_id: intent.federation_id,
+            amount_msat: intent.amount_msat,
+        })
+    }
+
+    /// Amount spent by a federation in the last day and the last week,
+    /// including authorized spends not committed yet
+    pub async fn spent(&self, federation_id: &FederationId) -> (u64, u64) {
+        let now = Utc::now().timestamp().max(0) as u64;
+        let mut dbtx = self.db.begin_transaction_nc().await;
+        let spends = dbtx
+            .find_by_prefix(&PolicySpendFederationPrefix {
+                federation_id: *federation_id,
+            })
+            .await
+            .map(|(_, record)| record)
+            .collect::<Vec<_>>()
+            .await;
+
+        let pending = self
+            .pending
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .values()
+            .filter(|spend| spend.federation_id == *federation_id)
+            .map(|spend| spend.amount_msat)
+            .fold(0u64, u64::saturating_add);
+
+        let (mut spent_day, mut spent_week) = (pending, pending);
+        for spend in spends {
+            let age = now.saturating_sub(spend.timestamp);
+            if age < WEEK_SECS {
+                spent_week = spent_week.saturating_add(spend.amount_msat);
+            }
+            if age < DAY_SECS {
+                spent_day = spent_day.saturating_add(spend.amount_msat);
+            }
+        }
+        (spent_day, spent_week)
+    }
+}
+
+/// A spend allowed by the policy, counted against the caps as pending until
+/// it is committed or dropped
+#[derive(Debug)]
+pub struct SpendAuthorization {
+    db: Database,
+    pending: Arc<std::sync::Mutex<HashMap<String, PendingSpend>>>,
+    spend_id: String,
+    federation_id: FederationId,
+    amount_msat: u64,
+}
+
+impl SpendAuthorization {
+    /// Change the amount that is counted, for spends that turned out to move
+    /// more than was authorized
+    pub fn set_amount_msat(&mut self, amount_msat: u64) {
+        self.amount_msat = amount_msat;
+        if let Some(spend) = self
+            .pending
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .get_mut(&self.spend_id)
+        {
+            spend.amount_msat = amount_msat;
+        }
+    }
+
+    /// Record the spend once its funds left the wallet, dropping the
+    /// federation's spends older than the longest cap period
+    pub async fn commit(&self) -> Result<()> {
+        let now = Utc::now().timestamp().max(0) as u64;
+
+        let mut dbtx = self.db.begin_transaction().await;
+        let expired = dbtx
+            .find_by_prefix(&PolicySpendFederationPrefix {
+                federation_id: self.federation_id,
+            })
+            .await
+            .filter_map(|(key, record)| async move {
+                (now.saturating_sub(record.timestamp) >= WEEK_SECS).then_some(key)
+            })
+            .collect::<Vec<_>>()
+            .await;
+        for key in expired {
+            dbtx.remove_entry(&key).await;
+        }
+        dbtx.insert_entry(
+            &PolicySpendKey {
+                federation_id: self.federation_id,
+                spend_id: self.spend_id.clone(),
+            },
+            &PolicySpendRecord {
+                amount_msat: self.amount_msat,
+                timestamp: now,
+            },
+        )
+        .await;
+        dbtx.commit_tx_result()
+            .await
+            .map_err(|e| anyhow!("Failed to record spend: {:?}", e))?;
+
+        self.pending
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .remove(&self.spend_id);
+        Ok(())
+    }
+
+    /// Undo `commit` for a spend whose funds came back, it counts as pending
+    /// again until it is committed again or dropped
+    pub async fn release(&self) -> Result<()> {
+        self.pending
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .insert(
+                self.spend_id.clone(),
+                PendingSpend {
+                    federation_id: self.federation_id,
+                    amount_msat: self.amount_msat,
+                },
+            );
+
+        let mut dbtx = self.db.begin_transaction().await;
+        dbtx.remove_entry(&PolicySpendKey {
+            federation_id: self.federation_id,
+            spend_id: self.spend_id.clone(),
+        })
+        .await;
+        dbtx.commit_tx_result()
+            .await
+            .map_err(|e| anyhow!("Failed to release spend: {:?}", e))
+    }
+}
+
+impl Drop for SpendAuthorization {
+    fn drop(&mut self) {
+        self.pending
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .remove(&self.spend_id);
+    }
+}
+
+impl FmcdCore {
+    /// Check a spend against the federation's spending policy, publishing a
+    /// policy violation event when it is rejected
+    pub async fn authorize_spend(
+        &self,
+        intent: SpendIntent,
+        context: Option<&RequestContext>,
+    ) -> Result<SpendAuthorization, AppError> {
+        match self.spending_policy.authorize(&intent).await {
+            Ok(authorization) => Ok(authorization),
+            Err(violation) => Err(self.reject_spend(&intent, violation, context).await),
+        }
+    }
+
+    /// Like `authorize_spend`, but a spend above the approval threshold is
+    /// parked as an approval request instead of being rejected
+    pub(super) async fn authorize_or_request_approval(
+        &self,
+        intent: SpendIntent,
+        action: ApprovalAction,
+        destination: String,
+        context: &RequestContext,
+    ) -> Result<SpendAuthorization, AppError> {
+        match self.spending_policy.authorize(&intent).await {
+            Ok(authorization) => Ok(authorization),
+            Err(violation) if violation.rule == PolicyRule::ApprovalRequired => Err(self
+                .request_approval(action, intent.amount_msat, destination, context)
+                .await),
+            Err(violation) => Err(self.reject_spend(&intent, violation, Some(context)).await),
+        }
+    }
+
+    async fn reject_spend(
+        &self,
+        intent: &SpendIntent,
+        violation: PolicyViolation,
+        context: Option<&RequestContext>,
+    ) -> AppError {
+        use crate::events::FmcdEvent;
+
+        warn!(
+            federation_id = %intent.federation_id,
+            spend_kind = intent.kind.as_str(),
+            rule = violation.rule.as_str(),
+            amount_msat = intent.amount_msat,
+            "Spend rejected by spending policy"
+        );
+        let event = FmcdEvent::PolicyViolation {
+            federation_id: intent.federation_id.to_string(),
+            spend_kind: intent.kind.as_str().to_string(),
+            rule: violation.rule.as_str().to_string(),
+            amount_msat: intent.amount_msat,
+            reason: violation.message.clone(),
+            correlation_id: context.map(|c| c.correlation_id.clone()),
+            timestamp: chrono::Utc::now(),
+        };
+        if let Err(e) = self.event_bus.publish(event).await {
+            error!(
+                federation_id = %intent.federation_id,
+                error = ?e,
+                "Failed to publish policy violation event"
+            );
+        }
+
+        let error = AppError::from(violation);
+        match context {
+            Some(context) => error.with_context(context.clone()),
+            None => error,
+        }
+    }
+
+    /// Count an executed spend against the federation's spending caps
+    pub async fn record_spend(&self, authorization: &SpendAuthorization) {
+        if let Err(e) = authorization.commit().await {
+            error!(error = ?e, "Failed to record spend for the spending policy");
+        }
+    }
+
+    /// Stop counting a recorded spend whose funds were refunded
+    pub(super) async fn release_spend(&self, authorization: &SpendAuthorization) {
+        if let Err(e) = authorization.release().await {
+            error!(error = ?e, "Failed to release refunded spend for the spending policy");
+        }
+    }
+}
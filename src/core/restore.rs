@@ -0,0 +1,249 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use anyhow::Result;
+use bip39::Mnemonic;
+use fedimint_client::ClientHandle;
+use fedimint_core::config::FederationId;
+use fedimint_core::invite_code::InviteCode;
+use futures_util::StreamExt;
+use tracing::{error, info};
+
+use crate::core::multimint::MultiMint;
+use crate::core::operations::restore::ModuleRecoveryProgress;
+use crate::core::operations::{RestoreStatus, RestoreTracker};
+use crate::core::{FmcdCore, RestoreRequest};
+use crate::error::{AppError, ErrorCategory};
+use crate::events::EventBus;
+use crate::observability::correlation::RequestContext;
+
+impl FmcdCore {
+    /// Restore a wallet from its mnemonic on a fresh data dir.
+    ///
+    /// Replaces the generated mnemonic with the given one, rejoins every
+    /// federation in recovery mode and recovers ecash from the latest backup
+    /// each federation holds. Recoveries run in the background, progress can
+    /// be polled with `restore_status` and is published on the event bus.
+    pub async fn restore(
+        &self,
+        req: RestoreRequest,
+        context: Option<RequestContext>,
+    ) -> Result<RestoreStatus, AppError> {
+        use chrono::Utc;
+
+        use crate::events::FmcdEvent;
+
+        let mnemonic = Mnemonic::parse_in_normalized(bip39::Language::English, req.mnemonic.trim())
+            .map_err(|e| AppError::validation_error(format!("Invalid mnemonic: {}", e)))?;
+
+        let mut invite_codes = BTreeMap::new();
+        for invite_code in req.invite_codes {
+            invite_codes.insert(invite_code.federation_id(), invite_code);
+        }
+        if invite_codes.is_empty() {
+            return Err(AppError::validation_error(
+                "At least one invite code is required to restore",
+            ));
+        }
+
+        if !self.multimint.ids().await.is_empty() {
+            return Err(AppError::with_category(
+                ErrorCategory::Conflict,
+                "Restore requires a data directory that has not joined any federation",
+            ));
+        }
+
+        let status = self
+            .restore_tracker
+            .begin(invite_codes.keys().copied().collect())
+            .await
+            .map_err(|e| AppError::with_category(ErrorCategory::Conflict, e.to_string()))?;
+
+        if let Err(e) = self.multimint.replace_mnemonic(mnemonic).await {
+            for federation_id in invite_codes.keys() {
+                self.restore_tracker
+                    .fail_federation(federation_id, e.to_string())
+                    .await;
+            }
+            return Err(AppError::with_category(
+                ErrorCategory::Conflict,
+                e.to_string(),
+            ));
+        }
+
+        let correlation_id = context.as_ref().map(|c| c.correlation_id.clone());
+
+        info!(
+            restore_id = %status.restore_id,
+            federation_count = invite_codes.len(),
+            "Starting wallet restore"
+        );
+
+        let _ = self
+            .event_bus
+            .publish(FmcdEvent::RestoreStarted {
+                restore_id: status.restore_id.clone(),
+                federation_ids: invite_codes.keys().map(|id| id.to_string()).collect(),
+                correlation_id: correlation_id.clone(),
+                timestamp: Utc::now(),
+            })
+            .await;
+
+        for invite_code in invite_codes.into_values() {
+            tokio::spawn(Self::recover_federation(
+                self.multimint.clone(),
+                self.event_bus.clone(),
+                self.restore_tracker.clone(),
+                status.restore_id.clone(),
+                invite_code,
+                correlation_id.clone(),
+            ));
+        }
+
+        Ok(status)
+    }
+
+    /// Get the status of the most recent restore
+    pub async fn restore_status(&self) -> Result<RestoreStatus, AppError> {
+        self.restore_tracker
+            .current()
+            .await
+            .ok_or_else(|| AppError::not_found("No restore has been started"))
+    }
+
+    /// Recover a single federation, reporting module progress until all
+    /// recoveries are done, then restart the client so the recovered modules
+    /// become available and add it to the multimint
+    async fn recover_federation(
+        multimint: Arc<MultiMint>,
+        event_bus: Arc<EventBus>,
+        tracker: RestoreTracker,
+        restore_id: String,
+        invite_code: InviteCode,
+        correlation_id: Option<String>,
+    ) {
+        use chrono::Utc;
+
+        use crate::events::FmcdEvent;
+
+        let federation_id = invite_code.federation_id();
+
+        let result: Result<u64> = async {
+            let client = multimint.recover(invite_code).await?;
+
+            let recovered: Result<()> = async {
+                let mut progress_updates = client.subscribe_to_recovery_progress();
+                let recoveries = client.wait_for_all_recoveries();
+                tokio::pin!(recoveries);
+
+                loop {
+                    tokio::select! {
+                        res = &mut recoveries => {
+                            res?;
+                            break;
+                        }
+                        Some((module_id, progress)) = progress_updates.next() => {
+                            let progress = ModuleRecoveryProgress {
+                                complete: progress.complete,
+                                total: progress.total,
+                            };
+                            if tracker.update_module(&federation_id, module_id, progress).await {
+                                let _ = event_bus
+                                    .publish(FmcdEvent::FederationRecoveryProgress {
+                                        restore_id: restore_id.clone(),
+                                        federation_id: federation_id.to_string(),
+                                        module_id,
+                                        complete: progress.complete,
+                                        total: progress.total,
+                                        correlation_id: correlation_id.clone(),
+                                        timestamp: Utc::now(),
+                                    })
+                                    .await;
+                            }
+                        }
+                    }
+                }
+                Ok(())
+            }
+            .await;
+            // The config was saved to resume an interrupted recovery, a failed
+            // one is removed so the restore can be retried
+            if let Err(e) = recovered {
+                Self::abandon_recovery(&multimint, federation_id, Some(client)).await;
+                return Err(e);
+            }
+
+            let client = match client.restart().await {
+                Ok(client) => client,
+                Err(e) => {
+                    Self::abandon_recovery(&multimint, federation_id, None).await;
+                    return Err(e);
+                }
+            };
+            let balance_msat = client.get_balance().await.msats;
+            multimint
+                .insert_recovered(federation_id, Arc::new(client))
+                .await;
+
+            Ok(balance_msat)
+        }
+        .await;
+
+        match result {
+            Ok(balance_msat) => {
+                info!(
+                    restore_id = %restore_id,
+                    federation_id = %federation_id,
+                    balance_msat = balance_msat,
+                    "Federation recovery completed"
+                );
+                tracker
+                    .complete_federation(&federation_id, balance_msat)
+                    .await;
+                let _ = event_bus
+                    .publish(FmcdEvent::FederationRecoveryCompleted {
+                        restore_id,
+                        federation_id: federation_id.to_string(),
+                        balance_msat,
+                        correlation_id,
+                        timestamp: Utc::now(),
+                    })
+                    .await;
+            }
+            Err(e) => {
+                error!(
+                    restore_id = %restore_id,
+                    federation_id = %federation_id,
+                    error = ?e,
+                    "Federation recovery failed"
+                );
+                tracker.fail_federation(&federation_id, e.to_string()).await;
+                let _ = event_bus
+                    .publish(FmcdEvent::FederationRecoveryFailed {
+                        restore_id,
+                        federation_id: federation_id.to_string(),
+                        reason: e.to_string(),
+                        correlation_id,
+                        timestamp: Utc::now(),
+                    })
+                    .await;
+            }
+        }
+    }
+
+    /// Remove what a failed recovery left behind, failures to do so are only
+    /// logged as the recovery failed already
+    async fn abandon_recovery(
+        multimint: &MultiMint,
+        federation_id: FederationId,
+        client: Option<ClientHandle>,
+    ) {
+        if let Err(e) = multimint.abandon_recovery(federation_id, client).await {
+            error!(
+                federation_id = %federation_id,
+                error = ?e,
+                "Failed to remove failed recovery, restoring it again is refused until it is left"
+            );
+        }
+    }
+}
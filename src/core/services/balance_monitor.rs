@@ -135,21 +135,24 @@ impl BalanceMonitor {
 
         for federation_id in federation_ids {
             if let Some(client) = self.multimint.get(&federation_id).await {
-                match client.get_balance().await {
-                    balance => {
-                        balances.push(FederationBalance {
-                            federation_id,
-                            balance_msat: balance.msats,
-                            last_updated: Utc::now(),
-                        });
-                    }
-                }
+                let balance = client.get_balance().await;
+                balances.push(FederationBalance {
+                    federation_id,
+                    balance_msat: balance.msats,
+                    last_updated: Utc::now(),
+                });
             }
         }
 
         Ok(balances)
     }
 
+    /// Forget the last known balance of a federation that is no longer
+    /// monitored
+    pub async fn remove_federation(&self, federation_id: &FederationId) {
+        self.last_balances.write().await.remove(federation_id);
+    }
+
     /// Get statistics about the balance monitor
     pub async fn get_stats(&self) -> BalanceMonitorStats {
         let last_balances = self.last_balances.read().await;
@@ -268,11 +271,7 @@ impl BalanceMonitor {
 
         let balance_change = match last_balance_msat {
             Some(last_balance) => {
-                let change = if current_balance_msat > last_balance {
-                    current_balance_msat - last_balance
-                } else {
-                    last_balance - current_balance_msat
-                };
+                let change = current_balance_msat.abs_diff(last_balance);
 
                 if change >= min_change_threshold {
                     Some(BalanceChange {
@@ -0,0 +1,321 @@
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use fedimint_core::Amount;
+use fedimint_ln_common::config::FeeToAmount;
+use fedimint_ln_common::LightningGatewayAnnouncement;
+use serde::Serialize;
+use tokio::sync::RwLock;
+use tracing::debug;
+
+use crate::events::{EventHandler, FmcdEvent};
+
+/// Configuration for gateway scoring
+#[derive(Debug, Clone)]
+pub struct GatewaySelectorConfig {
+    /// Number of most recent attempts the success rate is computed over
+    /// (default: 20)
+    pub recent_window: usize,
+    /// Weight of the newest latency sample in the moving average (default:
+    /// 0.3)
+    pub latency_smoothing: f64,
+    /// Latency at which the latency factor halves the score (default: 10
+    /// seconds)
+    pub latency_half_score: Duration,
+    /// How many gateways a payment tries before giving up (default: 3)
+    pub max_attempts: usize,
+    /// Attempts without outcome are forgotten after this long (default: 1
+    /// hour)
+    pub pending_timeout: Duration,
+}
+
+impl Default for GatewaySelectorConfig {
+    fn default() -> Self {
+        Self {
+            recent_window: 20,
+            latency_smoothing: 0.3,
+            latency_half_score: Duration::from_secs(10),
+            max_attempts: 3,
+            pending_timeout: Duration::from_secs(60 * 60),
+        }
+    }
+}
+
+/// What fmcd has observed about a gateway
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GatewayStats {
+    pub successes: u64,
+    pub failures: u64,
+    /// Share of successful attempts within the recent window, none without
+    /// attempts
+    pub recent_success_rate: Option<f64>,
+    /// Moving average of the time from gateway selection to settlement
+    pub avg_latency_ms: Option<u64>,
+    pub last_success_at: Option<DateTime<Utc>>,
+    pub last_failure_at: Option<DateTime<Utc>>,
+    #[serde(skip)]
+    recent: VecDeque<bool>,
+}
+
+impl GatewayStats {
+    fn record(&mut self, success: bool, window: usize) {
+        if success {
+            self.successes += 1;
+            self.last_success_at = Some(Utc::now());
+        } else {
+            self.failures += 1;
+            self.last_failure_at = Some(Utc::now());
+        }
+
+        self.recent.push_back(success);
+        while self.recent.len() > window {
+            self.recent.pop_front();
+        }
+        let recent_successes = self.recent.iter().filter(|s| **s).count();
+        self.recent_success_rate = Some(recent_successes as f64 / self.recent.len() as f64);
+    }
+
+    /// Success rate with a uniform prior, so unknown gateways start at 0.5 and
+    /// a single failure doesn't rule a gateway out
+    fn estimated_success_rate(&self) -> f64 {
+        let attempts = self.recent.len() as f64;
+        let successes = self.recent.iter().filter(|s| **s).count() as f64;
+        (successes + 1.0) / (attempts + 2.0)
+    }
+}
+
+/// A gateway that can be used for a payment
+#[derive(Debug, Clone)]
+pub struct GatewayCandidate {
+    pub gateway_id: String,
+    /// Fee the gateway charges for the payment amount
+    pub fee_msat: u64,
+    pub vetted: bool,
+}
+
+impl GatewayCandidate {
+    pub fn from_announcement(announcement: &LightningGatewayAnnouncement, amount: Amount) -> Self {
+        Self {
+            gateway_id: announcement.info.gateway_id.to_string(),
+            fee_msat: announcement.info.fees.to_amount(&amount).msats,
+            vetted: announcement.vetted,
+        }
+    }
+}
+
+/// Score of a gateway for a payment amount, higher is better
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GatewayScore {
+    pub gateway_id: String,
+    pub vetted: bool,
+    pub fee_msat: u64,
+    pub score: f64,
+    pub stats: GatewayStats,
+}
+
+#[derive(Debug)]
+struct PendingAttempt {
+    federation_id: String,
+    gateway_id: String,
+    started_at: Instant,
+}
+
+/// Ranks lightning gateways by fees, recent success rate and latency.
+///
+/// Outcomes are learned from the payment events on the event bus: a
+/// `GatewaySelected` starts an attempt, `PaymentSucceeded` completes it and
+/// `GatewayUnavailable` counts as a failure of the gateway.
+#[derive(Debug)]
+pub struct GatewaySelector {
+    config: GatewaySelectorConfig,
+    stats: RwLock<HashMap<(String, String), GatewayStats>>,
+    pending: RwLock<HashMap<String, PendingAttempt>>,
+}
+
+impl GatewaySelector {
+    pub fn new(config: GatewaySelectorConfig) -> Self {
+        Self {
+            config,
+            stats: RwLock::new(HashMap::new()),
+            pending: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub fn config(&self) -> &GatewaySelectorConfig {
+        &self.config
+    }
+
+    /// Candidates eligible for automatic selection: vetted gateways, or all of
+    /// them when the federation has not vetted any
+    pub fn eligible(candidates: Vec<GatewayCandidate>) -> Vec<GatewayCandidate> {
+        if candidates.iter().any(|c| c.vetted) {
+            candidates.into_iter().filter(|c| c.vetted).collect()
+        } else {
+            candidates
+        }
+    }
+
+    /// Score the candidates of a federation, best first
+    pub async fn rank(
+        &self,
+        federation_id: &str,
+        candidates: &[GatewayCandidate],
+        amount: Amount,
+    ) -> Vec<GatewayScore> {
+        let stats = self.stats.read().await;
+
+        let mut scores = candidates
+            .iter()
+            .map(|candidate| {
+                let stats = stats
+                    .get(&(federation_id.to_string(), candidate.gateway_id.clone()))
+                    .cloned()
+                    .unwrap_or_default();
+                GatewayScore {
+                    gateway_id: candidate.gateway_id.clone(),
+                    vetted: candidate.vetted,
+                    fee_msat: candidate.fee_msat,
+                    score: self.score(&stats, candidate.fee_msat, amount),
+                    stats,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        scores.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| a.fee_msat.cmp(&b.fee_msat))
+        });
+        scores
+    }
+
+    fn score(&self, stats: &GatewayStats, fee_msat: u64, amount: Amount) -> f64 {
+        let fee_ratio = fee_msat as f64 / amount.msats.max(1) as f64;
+        // A 1% fee halves the score
+        let fee_factor = 1.0 / (1.0 + 100.0 * fee_ratio);
+        let latency_factor = stats.avg_latency_ms.map_or(1.0, |latency_ms| {
+            1.0 / (1.0 + latency_ms as f64 / self.config.latency_half_score.as_millis() as f64)
+        });
+        stats.estimated_success_rate() * fee_factor * latency_factor
+    }
+
+    /// Record a settled payment through a gateway
+    pub async fn record_success(&self, federation_id: &str, gateway_id: &str, latency: Duration) {
+        let mut stats = self.stats.write().await;
+        let entry = stats
+            .entry((federation_id.to_string(), gateway_id.to_string()))
+            .or_default();
+        entry.record(true, self.config.recent_window);
+
+        let latency_ms = latency.as_millis() as f64;
+        entry.avg_latency_ms = Some(match entry.avg_latency_ms {
+            Some(avg) => {
+                let smoothing = self.config.latency_smoothing;
+                (smoothing * latency_ms + (1.0 - smoothing) * avg as f64).round() as u64
+            }
+            None => latency_ms.round() as u64,
+        });
+    }
+
+    /// Record a payment the gateway could not complete
+    pub async fn record_failure(&self, federation_id: &str, gateway_id: &str) {
+        self.stats
+            .write()
+            .await
+            .entry((federation_id.to_string(), gateway_id.to_string()))
+            .or_default()
+            .record(false, self.config.recent_window);
+    }
+
+    /// Stats of all gateways of a federation
+    pub async fn federation_stats(&self, federation_id: &str) -> HashMap<String, GatewayStats> {
+        self.stats
+            .read()
+            .await
+            .iter()
+            .filter(|((federation, _), _)| federation == federation_id)
+            .map(|((_, gateway), stats)| (gateway.clone(), stats.clone()))
+            .collect()
+    }
+
+    /// Forget everything learned about a federation's gateways
+    pub async fn remove_federation(&self, federation_id: &str) {
+        self.stats
+            .write()
+            .await
+            .retain(|(federation, _), _| federation != federation_id);
+        self.pending
+            .write()
+            .await
+            .retain(|_, attempt| attempt.federation_id != federation_id);
+    }
+}
+
+#[async_trait]
+impl EventHandler for GatewaySelector {
+    async fn handle(&self, event: FmcdEvent) -> anyhow::Result<()> {
+        match event {
+            FmcdEvent::GatewaySelected {
+                gateway_id,
+                federation_id,
+                payment_id: Some(payment_id),
+                ..
+            } => {
+                let mut pending = self.pending.write().await;
+                let timeout = self.config.pending_timeout;
+                pending.retain(|_, attempt| attempt.started_at.elapsed() < timeout);
+                pending.insert(
+                    payment_id,
+                    PendingAttempt {
+                        federation_id,
+                        gateway_id,
+                        started_at: Instant::now(),
+                    },
+                );
+            }
+            FmcdEvent::PaymentSucceeded { operation_id, .. } => {
+                let attempt = self.pending.write().await.remove(&operation_id);
+                if let Some(attempt) = attempt {
+                    debug!(
+                        gateway_id = %attempt.gateway_id,
+                        federation_id = %attempt.federation_id,
+                        "Recording gateway success"
+                    );
+                    self.record_success(
+                        &attempt.federation_id,
+                        &attempt.gateway_id,
+                        attempt.started_at.elapsed(),
+                    )
+                    .await;
+                }
+            }
+            FmcdEvent::PaymentFailed { payment_id, .. } => {
+                // Failures the gateway is responsible for arrive as
+                // GatewayUnavailable, this only ends the attempt
+                self.pending.write().await.remove(&payment_id);
+            }
+            FmcdEvent::GatewayUnavailable {
+                gateway_id,
+                federation_id,
+                ..
+            } => {
+                debug!(
+                    gateway_id = %gateway_id,
+                    federation_id = %federation_id,
+                    "Recording gateway failure"
+                );
+                self.record_failure(&federation_id, &gateway_id).await;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "gateway_selector"
+    }
+}
@@ -1,7 +1,14 @@
 pub mod balance_monitor;
 pub mod deposit_monitor;
+pub mod gateway_selector;
 pub mod payment_lifecycle;
 
 pub use balance_monitor::{BalanceMonitor, BalanceMonitorConfig};
 pub use deposit_monitor::{DepositMonitor, DepositMonitorConfig};
-pub use payment_lifecycle::{PaymentLifecycleConfig, PaymentLifecycleManager};
+pub use gateway_selector::{
+    GatewayCandidate, GatewayScore, GatewaySelector, GatewaySelectorConfig, GatewayStats,
+};
+pub use payment_lifecycle::{PaymentLifecycleConfig, PaymentLifecycleManager, PaymentType};
+
+#[cfg(test)]
+mod tests;
@@ -7,6 +7,7 @@ use chrono::Utc;
 use fedimint_client::ClientHandleArc;
 use fedimint_core::config::FederationId;
 use fedimint_core::core::OperationId;
+use fedimint_core::db::{Database, IDatabaseTransactionOpsCoreTyped};
 use fedimint_core::Amount;
 use fedimint_ln_client::{LightningClientModule, LnPayState, LnReceiveState};
 use fedimint_wallet_client::{DepositStateV2, WalletClientModule, WithdrawState};
@@ -16,6 +17,10 @@ use tokio::sync::{broadcast, Mutex, RwLock};
 use tokio::time::interval;
 use tracing::{debug, error, info, instrument, warn};
 
+use crate::core::multimint::db::{
+    PaymentOperationFederationPrefix, PaymentOperationKey, PaymentOperationKeyPrefix,
+    PaymentOperationRecord,
+};
 use crate::core::multimint::MultiMint;
 use crate::events::{EventBus, FmcdEvent};
 
@@ -42,6 +47,12 @@ pub struct PaymentOperation {
     pub claim_attempted: bool,
     /// Track if ecash was successfully claimed
     pub ecash_claimed: bool,
+    /// Invoice id handed out when a receive was created
+    #[serde(default)]
+    pub invoice_id: Option<String>,
+    /// Per-invoice callback URL notified when a receive settles or expires
+    #[serde(default)]
+    pub callback_url: Option<String>,
 }
 
 /// Configuration for the payment lifecycle manager
@@ -70,6 +81,9 @@ impl Default for PaymentLifecycleConfig {
 }
 
 /// Service that manages the complete lifecycle of payment operations
+///
+/// Tracked operations are persisted in the multimint database, so their
+/// metadata and correlation IDs survive a restart.
 #[derive(Debug)]
 pub struct PaymentLifecycleManager {
     event_bus: Arc<EventBus>,
@@ -173,6 +187,8 @@ impl PaymentLifecycleManager {
         amount_msat: Amount,
         metadata: Option<serde_json::Value>,
         correlation_id: Option<String>,
+        invoice_id: String,
+        callback_url: Option<String>,
     ) -> Result<()> {
         let operation = PaymentOperation {
             operation_id,
@@ -184,6 +200,8 @@ impl PaymentLifecycleManager {
             correlation_id,
             claim_attempted: false,
             ecash_claimed: false,
+            invoice_id: Some(invoice_id),
+            callback_url,
         };
 
         self.add_operation(operation).await
@@ -197,6 +215,7 @@ impl PaymentLifecycleManager {
         federation_id: FederationId,
         amount_msat: Amount,
         metadata: Option<serde_json::Value>,
+        correlation_id: Option<String>,
     ) -> Result<()> {
         let operation = PaymentOperation {
             operation_id,
@@ -205,9 +224,11 @@ impl PaymentLifecycleManager {
             amount_msat: Some(amount_msat),
             created_at: Utc::now(),
             metadata,
-            correlation_id: None,
+            correlation_id,
             claim_attempted: false,
             ecash_claimed: false,
+            invoice_id: None,
+            callback_url: None,
         };
 
         self.add_operation(operation).await
@@ -231,6 +252,8 @@ impl PaymentLifecycleManager {
             correlation_id,
             claim_attempted: false,
             ecash_claimed: false,
+            invoice_id: None,
+            callback_url: None,
         };
 
         self.add_operation(operation).await
@@ -243,6 +266,7 @@ impl PaymentLifecycleManager {
         operation_id: OperationId,
         federation_id: FederationId,
         amount_sat: u64,
+        correlation_id: Option<String>,
     ) -> Result<()> {
         let operation = PaymentOperation {
             operation_id,
@@ -251,14 +275,28 @@ impl PaymentLifecycleManager {
             amount_msat: Some(Amount::from_sats(amount_sat)),
             created_at: Utc::now(),
             metadata: None,
-            correlation_id: None,
+            correlation_id,
             claim_attempted: false,
             ecash_claimed: false,
+            invoice_id: None,
+            callback_url: None,
         };
 
         self.add_operation(operation).await
     }
 
+    /// Tracked operations of a payment type, so work that followed them can
+    /// be resumed after a restart
+    pub async fn operations(&self, payment_type: PaymentType) -> Vec<PaymentOperation> {
+        self.active_operations
+            .read()
+            .await
+            .values()
+            .filter(|op| op.payment_type == payment_type)
+            .cloned()
+            .collect()
+    }
+
     /// Add an operation to track
     async fn add_operation(&self, operation: PaymentOperation) -> Result<()> {
         let operation_id = operation.operation_id;
@@ -282,6 +320,8 @@ impl PaymentLifecycleManager {
             }
         }
 
+        Self::persist_operation(self.multimint.db(), &operation).await?;
+
         // Add to active operations
         {
             let mut operations = self.active_operations.write().await;
@@ -303,8 +343,25 @@ impl PaymentLifecycleManager {
     async fn recover_pending_operations(&self) -> Result<()> {
         info!("Recovering pending operations from all federations");
 
+        // Operations we were tracking before the restart keep their metadata and
+        // correlation IDs, the operation log only fills in what is missing
+        let persisted = Self::load_operations(self.multimint.db()).await?;
+        let mut total_recovered = persisted.len();
+        {
+            let mut operations = self.active_operations.write().await;
+            for operation in persisted {
+                operations.insert(operation.operation_id, operation);
+            }
+        }
+
+        if total_recovered > 0 {
+            info!(
+                restored_operations = total_recovered,
+                "Restored persisted payment operations"
+            );
+        }
+
         let clients = self.multimint.clients.lock().await.clone();
-        let mut total_recovered = 0;
 
         for (federation_id, client) in clients.iter() {
             match self
@@ -354,6 +411,15 @@ impl PaymentLifecycleManager {
 
         for (key, value) in operations {
             let operation_id = key.operation_id;
+            if self
+                .active_operations
+                .read()
+                .await
+                .contains_key(&operation_id)
+            {
+                continue;
+            }
+
             let operation_kind = value.operation_module_kind();
             let created_at = chrono::DateTime::<Utc>::from(key.creation_time);
 
@@ -374,7 +440,7 @@ impl PaymentLifecycleManager {
             let variant = meta.get("variant").and_then(|v| v.as_str());
             let op_type = meta.get("type").and_then(|v| v.as_str());
 
-            let payment_type = match operation_kind.as_ref() {
+            let payment_type = match operation_kind {
                 "ln" => {
                     // Check multiple metadata fields to determine operation type
                     if variant == Some("receive")
@@ -461,8 +527,18 @@ impl PaymentLifecycleManager {
                     correlation_id: None,
                     claim_attempted: false,
                     ecash_claimed: false,
+                    invoice_id: None,
+                    callback_url: None,
                 };
 
+                if let Err(e) = Self::persist_operation(self.multimint.db(), &operation).await {
+                    warn!(
+                        operation_id = ?operation_id,
+                        error = ?e,
+                        "Failed to persist recovered operation"
+                    );
+                }
+
                 // Add to active operations
                 let mut operations = self.active_operations.write().await;
                 operations.insert(operation_id, operation);
@@ -558,12 +634,23 @@ impl PaymentLifecycleManager {
 
                         // Update the operation in active_operations with the new state
                         let mut operations = active_operations.write().await;
-                        if let Some(active_op) = operations.get_mut(&operation_mut.operation_id) {
-                            active_op.claim_attempted = operation_mut.claim_attempted;
-                            active_op.ecash_claimed = operation_mut.ecash_claimed;
-                        }
+                        let changed = match operations.get_mut(&operation_mut.operation_id) {
+                            Some(active_op) => {
+                                let changed = active_op.claim_attempted
+                                    != operation_mut.claim_attempted
+                                    || active_op.ecash_claimed != operation_mut.ecash_claimed;
+                                active_op.claim_attempted = operation_mut.claim_attempted;
+                                active_op.ecash_claimed = operation_mut.ecash_claimed;
+                                changed
+                            }
+                            None => false,
+                        };
                         drop(operations);
 
+                        if changed && !operation_mut.ecash_claimed {
+                            Self::persist_operation_logged(multimint.db(), &operation_mut).await;
+                        }
+
                         if operation_mut.ecash_claimed {
                             completed_operations.push(operation_mut.operation_id);
                         }
@@ -598,26 +685,32 @@ impl PaymentLifecycleManager {
 
                         // Update the operation in active_operations with the new state
                         let mut operations = active_operations.write().await;
-                        if let Some(active_op) = operations.get_mut(&operation_mut.operation_id) {
-                            active_op.claim_attempted = operation_mut.claim_attempted;
-                            active_op.ecash_claimed = operation_mut.ecash_claimed;
-                            active_op.amount_msat = operation_mut.amount_msat;
-                        }
+                        let changed = match operations.get_mut(&operation_mut.operation_id) {
+                            Some(active_op) => {
+                                let changed = active_op.claim_attempted
+                                    != operation_mut.claim_attempted
+                                    || active_op.ecash_claimed != operation_mut.ecash_claimed
+                                    || active_op.amount_msat != operation_mut.amount_msat;
+                                active_op.claim_attempted = operation_mut.claim_attempted;
+                                active_op.ecash_claimed = operation_mut.ecash_claimed;
+                                active_op.amount_msat = operation_mut.amount_msat;
+                                changed
+                            }
+                            None => false,
+                        };
                         drop(operations);
 
+                        if changed && !operation_mut.ecash_claimed {
+                            Self::persist_operation_logged(multimint.db(), &operation_mut).await;
+                        }
+
                         if operation_mut.ecash_claimed {
                             completed_operations.push(operation_mut.operation_id);
                         }
                     }
                     PaymentType::OnchainWithdraw => {
-                        if let Err(e) =
-                            Self::process_onchain_withdraw(&client, &operation, event_bus).await
-                        {
-                            error!(
-                                operation_id = ?operation.operation_id,
-                                error = ?e,
-                                "Failed to process onchain withdrawal"
-                            );
+                        if Self::process_onchain_withdraw(&client, &operation).await {
+                            completed_operations.push(operation.operation_id);
                         }
                     }
                 }
@@ -627,16 +720,26 @@ impl PaymentLifecycleManager {
         // Remove completed and timed out operations
         if !completed_operations.is_empty() || !timed_out_operations.is_empty() {
             let mut operations = active_operations.write().await;
+            let mut finished = Vec::new();
 
             for operation_id in &completed_operations {
-                operations.remove(operation_id);
+                if let Some(operation) = operations.remove(operation_id) {
+                    finished.push(operation);
+                }
                 info!(operation_id = ?operation_id, "Payment operation completed successfully");
             }
 
             for operation_id in &timed_out_operations {
-                operations.remove(operation_id);
+                if let Some(operation) = operations.remove(operation_id) {
+                    finished.push(operation);
+                }
                 warn!(operation_id = ?operation_id, "Payment operation timed out");
             }
+            drop(operations);
+
+            if let Err(e) = Self::delete_operations(multimint.db(), &finished).await {
+                error!(error = ?e, "Failed to delete finished payment operations");
+            }
         }
 
         Ok(())
@@ -966,73 +1069,152 @@ impl PaymentLifecycleManager {
         Ok(())
     }
 
-    /// Process an onchain withdrawal operation
+    /// Check whether an onchain withdrawal reached its final state. The
+    /// outcome is published by the task following the withdrawal, which also
+    /// caches it in the operation log, so this only stops tracking it.
     async fn process_onchain_withdraw(
         client: &ClientHandleArc,
         operation: &PaymentOperation,
-        event_bus: &Arc<EventBus>,
-    ) -> Result<()> {
-        let wallet_module = client.get_first_module::<WalletClientModule>()?;
-
-        // Check current state
-        let mut updates = wallet_module
-            .subscribe_withdraw_updates(operation.operation_id)
-            .await?
-            .into_stream();
-
-        let current_state = match updates.next().await {
-            Some(state) => state,
-            None => return Ok(()),
-        };
+    ) -> bool {
+        let outcome = client
+            .operation_log()
+            .get_operation(operation.operation_id)
+            .await
+            .and_then(|entry| entry.outcome::<WithdrawState>());
 
-        match current_state {
-            WithdrawState::Succeeded(txid) => {
+        match outcome {
+            Some(WithdrawState::Succeeded(txid)) => {
                 info!(
                     operation_id = ?operation.operation_id,
                     txid = %txid,
                     "Onchain withdrawal succeeded"
                 );
-
-                // Publish success event
-                if let Some(amount) = operation.amount_msat {
-                    let event = FmcdEvent::WithdrawalSucceeded {
-                        operation_id: format!("{:?}", operation.operation_id),
-                        federation_id: operation.federation_id.to_string(),
-                        amount_sat: amount.sats_round_down(),
-                        txid: txid.to_string(),
-                        timestamp: Utc::now(),
-                    };
-                    let _ = event_bus.publish(event).await;
-                }
+                true
             }
-            WithdrawState::Failed(reason) => {
+            Some(WithdrawState::Failed(reason)) => {
                 error!(
                     operation_id = ?operation.operation_id,
                     reason = %reason,
                     "Onchain withdrawal failed"
                 );
-
-                // Publish failure event
-                let event = FmcdEvent::WithdrawalFailed {
-                    operation_id: format!("{:?}", operation.operation_id),
-                    federation_id: operation.federation_id.to_string(),
-                    reason,
-                    correlation_id: None,
-                    timestamp: Utc::now(),
-                };
-                let _ = event_bus.publish(event).await;
+                true
             }
             _ => {
-                // Still in progress
                 debug!(
                     operation_id = ?operation.operation_id,
-                    state = ?current_state,
                     "Onchain withdrawal still in progress"
                 );
+                false
             }
         }
+    }
 
-        Ok(())
+    /// Stop tracking every operation of a federation, returns how many were
+    /// removed
+    #[instrument(skip(self))]
+    pub async fn remove_federation(&self, federation_id: &FederationId) -> usize {
+        let mut operations = self.active_operations.write().await;
+        let before = operations.len();
+        operations.retain(|_, op| op.federation_id != *federation_id);
+        let removed = before - operations.len();
+        drop(operations);
+
+        let mut dbtx = self.multimint.db().begin_transaction().await;
+        dbtx.remove_by_prefix(&PaymentOperationFederationPrefix {
+            federation_id: *federation_id,
+        })
+        .await;
+        if let Err(e) = dbtx.commit_tx_result().await {
+            error!(
+                federation_id = %federation_id,
+                error = ?e,
+                "Failed to delete persisted payment operations"
+            );
+        }
+
+        info!(
+            federation_id = %federation_id,
+            removed = removed,
+            "Removed federation from payment lifecycle tracking"
+        );
+
+        removed
+    }
+
+    /// Store an operation in the multimint database, replacing any previous
+    /// version of it
+    async fn persist_operation(db: &Database, operation: &PaymentOperation) -> Result<()> {
+        let record = PaymentOperationRecord {
+            json: serde_json::to_string(operation)?,
+        };
+
+        let mut dbtx = db.begin_transaction().await;
+        dbtx.insert_entry(
+            &PaymentOperationKey {
+                federation_id: operation.federation_id,
+                operation_id: operation.operation_id,
+            },
+            &record,
+        )
+        .await;
+        dbtx.commit_tx_result()
+            .await
+            .map_err(|e| anyhow!("Failed to persist payment operation: {:?}", e))
+    }
+
+    async fn persist_operation_logged(db: &Database, operation: &PaymentOperation) {
+        if let Err(e) = Self::persist_operation(db, operation).await {
+            error!(
+                operation_id = ?operation.operation_id,
+                error = ?e,
+                "Failed to persist payment operation"
+            );
+        }
+    }
+
+    async fn delete_operations(db: &Database, operations: &[PaymentOperation]) -> Result<()> {
+        if operations.is_empty() {
+            return Ok(());
+        }
+
+        let mut dbtx = db.begin_transaction().await;
+        for operation in operations {
+            dbtx.remove_entry(&PaymentOperationKey {
+                federation_id: operation.federation_id,
+                operation_id: operation.operation_id,
+            })
+            .await;
+        }
+        dbtx.commit_tx_result()
+            .await
+            .map_err(|e| anyhow!("Failed to delete payment operations: {:?}", e))
+    }
+
+    /// Load all persisted operations, skipping records that no longer decode
+    async fn load_operations(db: &Database) -> Result<Vec<PaymentOperation>> {
+        let mut dbtx = db.begin_transaction_nc().await;
+        let records = dbtx
+            .find_by_prefix(&PaymentOperationKeyPrefix)
+            .await
+            .collect::<Vec<_>>()
+            .await;
+
+        Ok(records
+            .into_iter()
+            .filter_map(|(key, record)| {
+                match serde_json::from_str::<PaymentOperation>(&record.json) {
+                    Ok(operation) => Some(operation),
+                    Err(e) => {
+                        warn!(
+                            operation_id = ?key.operation_id,
+                            error = ?e,
+                            "Skipping undecodable persisted payment operation"
+                        );
+                        None
+                    }
+                }
+            })
+            .collect())
     }
 
     /// Get statistics about active operations
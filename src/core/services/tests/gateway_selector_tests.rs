@@ -0,0 +1,161 @@
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::time::Duration;
+
+    use chrono::Utc;
+    use fedimint_core::Amount;
+
+    use crate::core::services::gateway_selector::*;
+    use crate::events::{EventHandler, FmcdEvent};
+
+    const FEDERATION: &str = "federation";
+
+    fn candidate(gateway_id: &str, fee_msat: u64, vetted: bool) -> GatewayCandidate {
+        GatewayCandidate {
+            gateway_id: gateway_id.to_string(),
+            fee_msat,
+            vetted,
+        }
+    }
+
+    fn ranked_ids(scores: &[GatewayScore]) -> Vec<&str> {
+        scores.iter().map(|s| s.gateway_id.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_unknown_gateways_are_ranked_by_fee() {
+        let selector = GatewaySelector::new(GatewaySelectorConfig::default());
+        let candidates = vec![
+            candidate("expensive", 5_000, true),
+            candidate("cheap", 1_000, true),
+        ];
+
+        let scores = selector
+            .rank(FEDERATION, &candidates, Amount::from_msats(1_000_000))
+            .await;
+
+        assert_eq!(ranked_ids(&scores), vec!["cheap", "expensive"]);
+    }
+
+    #[test]
+    fn test_unvetted_gateways_only_eligible_without_vetted_ones() {
+        let eligible = GatewaySelector::eligible(vec![
+            candidate("vetted", 5_000, true),
+            candidate("free", 0, false),
+        ]);
+        let ids = eligible
+            .iter()
+            .map(|c| c.gateway_id.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec!["vetted"]);
+
+        let eligible = GatewaySelector::eligible(vec![candidate("free", 0, false)]);
+        assert_eq!(eligible.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_failures_demote_a_gateway() {
+        let selector = GatewaySelector::new(GatewaySelectorConfig::default());
+        let candidates = vec![candidate("a", 1_000, true), candidate("b", 1_500, true)];
+
+        for _ in 0..3 {
+            selector.record_failure(FEDERATION, "a").await;
+        }
+        selector
+            .record_success(FEDERATION, "b", Duration::from_secs(1))
+            .await;
+
+        let scores = selector
+            .rank(FEDERATION, &candidates, Amount::from_msats(1_000_000))
+            .await;
+        assert_eq!(ranked_ids(&scores), vec!["b", "a"]);
+        assert_eq!(scores[1].stats.failures, 3);
+        assert_eq!(scores[1].stats.recent_success_rate, Some(0.0));
+
+        // Stats of other federations are independent
+        let scores = selector
+            .rank("other", &candidates, Amount::from_msats(1_000_000))
+            .await;
+        assert_eq!(ranked_ids(&scores), vec!["a", "b"]);
+    }
+
+    #[tokio::test]
+    async fn test_success_rate_uses_recent_window() {
+        let selector = GatewaySelector::new(GatewaySelectorConfig {
+            recent_window: 4,
+            ..Default::default()
+        });
+
+        for _ in 0..4 {
+            selector.record_failure(FEDERATION, "a").await;
+        }
+        for _ in 0..4 {
+            selector
+                .record_success(FEDERATION, "a", Duration::from_millis(500))
+                .await;
+        }
+
+        let stats = selector.federation_stats(FEDERATION).await;
+        let stats = stats.get("a").unwrap();
+        assert_eq!(stats.failures, 4);
+        assert_eq!(stats.successes, 4);
+        assert_eq!(stats.recent_success_rate, Some(1.0));
+        assert_eq!(stats.avg_latency_ms, Some(500));
+    }
+
+    #[tokio::test]
+    async fn test_learns_from_payment_events() {
+        let selector = GatewaySelector::new(GatewaySelectorConfig::default());
+
+        selector
+            .handle(FmcdEvent::GatewaySelected {
+                gateway_id: "a".to_string(),
+                federation_id: FEDERATION.to_string(),
+                payment_id: Some("payment".to_string()),
+                correlation_id: None,
+                timestamp: Utc::now(),
+            })
+            .await
+            .unwrap();
+        selector
+            .handle(FmcdEvent::GatewayUnavailable {
+                gateway_id: "a".to_string(),
+                federation_id: FEDERATION.to_string(),
+                reason: "refunded".to_string(),
+                correlation_id: None,
+                timestamp: Utc::now(),
+            })
+            .await
+            .unwrap();
+        selector
+            .handle(FmcdEvent::GatewaySelected {
+                gateway_id: "b".to_string(),
+                federation_id: FEDERATION.to_string(),
+                payment_id: Some("payment".to_string()),
+                correlation_id: None,
+                timestamp: Utc::now(),
+            })
+            .await
+            .unwrap();
+        selector
+            .handle(FmcdEvent::PaymentSucceeded {
+                operation_id: "payment".to_string(),
+                federation_id: FEDERATION.to_string(),
+                amount_msat: 1_000,
+                preimage: "00".to_string(),
+                timestamp: Utc::now(),
+            })
+            .await
+            .unwrap();
+
+        let stats = selector.federation_stats(FEDERATION).await;
+        assert_eq!(stats.get("a").unwrap().failures, 1);
+        assert_eq!(stats.get("a").unwrap().successes, 0);
+        assert_eq!(stats.get("b").unwrap().successes, 1);
+        assert!(stats.get("b").unwrap().avg_latency_ms.is_some());
+
+        selector.remove_federation(FEDERATION).await;
+        assert!(selector.federation_stats(FEDERATION).await.is_empty());
+    }
+}
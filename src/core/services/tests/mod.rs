@@ -0,0 +1 @@
+mod gateway_selector_tests;
@@ -0,0 +1,101 @@
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use chrono::{Duration, Utc};
+    use fedimint_core::config::FederationId;
+    use fedimint_core::BitcoinAmountOrAll;
+    use tempfile::TempDir;
+
+    use crate::core::approvals::*;
+    use crate::core::tests::open_db;
+    use crate::core::WithdrawRequest;
+    use crate::error::ErrorCategory;
+
+    const ADDRESS: &str = "bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq";
+
+    async fn open_store(temp_dir: &TempDir) -> ApprovalStore {
+        ApprovalStore::new(open_db(temp_dir).await)
+    }
+
+    fn withdrawal(expires_in: Duration) -> ApprovalRequest {
+        let action = ApprovalAction::OnchainWithdrawal(WithdrawRequest {
+            address: ADDRESS.to_string(),
+            amount_sat: BitcoinAmountOrAll::Amount(bitcoin::Amount::from_sat(50_000)),
+            federation_id: FederationId::dummy(),
+            async_mode: false,
+            quote_id: None,
+            fee_tolerance_percent: None,
+        });
+        ApprovalRequest::new(
+            action,
+            51_000_000,
+            ADDRESS.to_string(),
+            "requester".to_string(),
+            Utc::now() + expires_in,
+        )
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_decide_pending_request_once() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = open_store(&temp_dir).await;
+        let request = withdrawal(Duration::hours(1));
+        store.save(&request).await.unwrap();
+
+        let rejected = store
+            .decide(
+                &request.approval_id,
+                ApprovalStatus::Rejected,
+                Some("not today".to_string()),
+            )
+            .await
+            .unwrap();
+        assert_eq!(rejected.status, ApprovalStatus::Rejected);
+        assert_eq!(rejected.reason.as_deref(), Some("not today"));
+        assert!(rejected.decided_at.is_some());
+
+        let err = store
+            .decide(&request.approval_id, ApprovalStatus::Approved, None)
+            .await
+            .unwrap_err();
+        assert!(matches!(err.category, ErrorCategory::Conflict));
+
+        let err = store
+            .decide("missing", ApprovalStatus::Approved, None)
+            .await
+            .unwrap_err();
+        assert!(matches!(err.category, ErrorCategory::NotFound));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_expired_request_cannot_be_approved() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = open_store(&temp_dir).await;
+        let request = withdrawal(Duration::seconds(-1));
+        store.save(&request).await.unwrap();
+
+        let stored = store.get(&request.approval_id).await.unwrap();
+        assert_eq!(stored.status, ApprovalStatus::Expired);
+        let err = store
+            .decide(&request.approval_id, ApprovalStatus::Approved, None)
+            .await
+            .unwrap_err();
+        assert!(matches!(err.category, ErrorCategory::Conflict));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_list_by_status() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = open_store(&temp_dir).await;
+        let pending = withdrawal(Duration::hours(1));
+        let expired = withdrawal(Duration::seconds(-1));
+        store.save(&pending).await.unwrap();
+        store.save(&expired).await.unwrap();
+
+        assert_eq!(store.list(None).await.len(), 2);
+        let listed = store.list(Some(ApprovalStatus::Pending)).await;
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].approval_id, pending.approval_id);
+        assert_eq!(listed[0].action.spend_kind().as_str(), "onchain_withdrawal");
+    }
+}
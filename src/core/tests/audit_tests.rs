@@ -0,0 +1,116 @@
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use fedimint_core::db::IDatabaseTransactionOpsCoreTyped;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    use crate::core::audit::*;
+    use crate::core::multimint::db::{AuditLogKey, AuditLogRecord};
+    use crate::core::tests::open_db;
+
+    fn entry(action: AuditAction, amount_msat: u64) -> AuditEntry {
+        AuditEntry {
+            action,
+            endpoint: "POST /v2/ln/pay".to_string(),
+            key_id: Some("password".to_string()),
+            key_name: Some("fmcd".to_string()),
+            source_ip: Some("127.0.0.1".to_string()),
+            correlation_id: "correlation".to_string(),
+            request_id: "request".to_string(),
+            request: json!({ "amountMsat": amount_msat }),
+            outcome: AuditOutcome {
+                success: true,
+                status: 200,
+                error: None,
+            },
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_append_chains_records() {
+        let temp_dir = TempDir::new().unwrap();
+        let log = AuditLog::open(open_db(&temp_dir).await).await.unwrap();
+
+        let first = log.append(entry(AuditAction::Pay, 1_000)).await.unwrap();
+        let second = log.append(entry(AuditAction::Join, 0)).await.unwrap();
+        assert_eq!(first.sequence, 1);
+        assert_eq!(first.prev_hash, GENESIS_HASH);
+        assert_eq!(second.sequence, 2);
+        assert_eq!(second.prev_hash, first.hash);
+
+        let page = log.records(None, None).await.unwrap();
+        assert_eq!(page.records, vec![first.clone(), second.clone()]);
+        assert_eq!(page.last_sequence, 2);
+        assert_eq!(page.last_hash, second.hash);
+
+        let page = log.records(Some(1), None).await.unwrap();
+        assert_eq!(page.records, vec![second.clone()]);
+
+        let verification = log.verify().await;
+        assert!(verification.valid);
+        assert_eq!(verification.records, 2);
+        assert_eq!(verification.last_hash, second.hash);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_reopened_log_continues_chain() {
+        let temp_dir = TempDir::new().unwrap();
+        let first = {
+            let log = AuditLog::open(open_db(&temp_dir).await).await.unwrap();
+            log.append(entry(AuditAction::Pay, 1_000)).await.unwrap()
+        };
+
+        let log = AuditLog::open(open_db(&temp_dir).await).await.unwrap();
+        let second = log.append(entry(AuditAction::Spend, 2_000)).await.unwrap();
+        assert_eq!(second.sequence, 2);
+        assert_eq!(second.prev_hash, first.hash);
+        assert!(log.verify().await.valid);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_verify_detects_altered_record() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = open_db(&temp_dir).await;
+        let log = AuditLog::open(db.clone()).await.unwrap();
+        log.append(entry(AuditAction::Pay, 1_000)).await.unwrap();
+        let mut altered = log.append(entry(AuditAction::Pay, 2_000)).await.unwrap();
+        log.append(entry(AuditAction::Pay, 3_000)).await.unwrap();
+
+        altered.entry.request = json!({ "amountMsat": 20_000 });
+        let mut dbtx = db.begin_transaction().await;
+        dbtx.insert_entry(
+            &AuditLogKey { sequence: 2 },
+            &AuditLogRecord {
+                json: serde_json::to_string(&altered).unwrap(),
+            },
+        )
+        .await;
+        dbtx.commit_tx().await;
+
+        let verification = log.verify().await;
+        assert!(!verification.valid);
+        assert_eq!(verification.records, 1);
+        assert_eq!(verification.last_sequence, 1);
+        assert_eq!(verification.broken_at, Some(2));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_verify_chain_detects_removed_record() {
+        let temp_dir = TempDir::new().unwrap();
+        let log = AuditLog::open(open_db(&temp_dir).await).await.unwrap();
+        for amount_msat in [1_000, 2_000, 3_000] {
+            log.append(entry(AuditAction::Withdraw, amount_msat))
+                .await
+                .unwrap();
+        }
+        let mut records = log.records(None, None).await.unwrap().records;
+        assert!(verify_chain(records.clone().into_iter().map(Ok)).valid);
+
+        records.remove(1);
+        let verification = verify_chain(records.into_iter().map(Ok));
+        assert!(!verification.valid);
+        assert_eq!(verification.broken_at, Some(2));
+        assert!(verification.error.unwrap().contains("found 3"));
+    }
+}
@@ -0,0 +1,154 @@
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    use crate::core::idempotency::*;
+    use crate::core::tests::open_db;
+    use crate::error::ErrorCategory;
+
+    async fn open_store(temp_dir: &TempDir) -> IdempotencyStore {
+        IdempotencyStore::new(open_db(temp_dir).await, chrono::Duration::hours(24))
+    }
+
+    fn response() -> StoredResponse {
+        StoredResponse {
+            status: 200,
+            body: json!({ "operationId": "op-1" }),
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_replays_response_for_same_request() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = open_store(&temp_dir).await;
+        let hash = request_hash("POST /v2/ln/pay", &json!({ "amountMsat": 1000 }));
+
+        match store
+            .begin(Some("shop"), "key-1", hash.clone())
+            .await
+            .unwrap()
+        {
+            IdempotencyOutcome::Proceed(guard) => guard.complete(response()).await.unwrap(),
+            IdempotencyOutcome::Replay(_) => panic!("first use must proceed"),
+        }
+
+        match store.begin(Some("shop"), "key-1", hash).await.unwrap() {
+            IdempotencyOutcome::Replay(stored) => assert_eq!(stored, response()),
+            IdempotencyOutcome::Proceed(_) => panic!("second use must replay"),
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_rejects_key_reused_for_different_request() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = open_store(&temp_dir).await;
+
+        let hash = request_hash("POST /v2/ln/pay", &json!({ "amountMsat": 1000 }));
+        match store.begin(Some("shop"), "key-1", hash).await.unwrap() {
+            IdempotencyOutcome::Proceed(guard) => guard.complete(response()).await.unwrap(),
+            IdempotencyOutcome::Replay(_) => panic!("first use must proceed"),
+        }
+
+        let other_body = request_hash("POST /v2/ln/pay", &json!({ "amountMsat": 2000 }));
+        let err = store
+            .begin(Some("shop"), "key-1", other_body)
+            .await
+            .unwrap_err();
+        assert!(matches!(err.category, ErrorCategory::Conflict));
+
+        let other_endpoint =
+            request_hash("POST /v2/onchain/withdraw", &json!({ "amountMsat": 1000 }));
+        let err = store
+            .begin(Some("shop"), "key-1", other_endpoint)
+            .await
+            .unwrap_err();
+        assert!(matches!(err.category, ErrorCategory::Conflict));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_rejects_key_while_request_in_progress() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = open_store(&temp_dir).await;
+        let hash = request_hash("POST /v2/mint/spend", &json!({}));
+
+        let guard = match store
+            .begin(Some("shop"), "key-1", hash.clone())
+            .await
+            .unwrap()
+        {
+            IdempotencyOutcome::Proceed(guard) => guard,
+            IdempotencyOutcome::Replay(_) => panic!("first use must proceed"),
+        };
+        let err = store
+            .begin(Some("shop"), "key-1", hash.clone())
+            .await
+            .unwrap_err();
+        assert!(matches!(err.category, ErrorCategory::Conflict));
+
+        // A request that never completed may have moved funds, its key is not
+        // executed again
+        drop(guard);
+        let err = store.begin(Some("shop"), "key-1", hash).await.unwrap_err();
+        assert!(matches!(err.category, ErrorCategory::Conflict));
+        assert!(err.message.contains("interrupted"));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_key_is_stored_before_request_executes() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = open_db(&temp_dir).await;
+        let hash = request_hash("POST /v2/ln/pay", &json!({ "amountMsat": 1000 }));
+
+        let store = IdempotencyStore::new(db.clone(), chrono::Duration::hours(24));
+        let guard = store
+            .begin(Some("shop"), "key-1", hash.clone())
+            .await
+            .unwrap();
+        // fmcd stops while the request runs, the next run has no reservation
+        std::mem::forget(guard);
+
+        let store = IdempotencyStore::new(db, chrono::Duration::hours(24));
+        let err = store.begin(Some("shop"), "key-1", hash).await.unwrap_err();
+        assert!(matches!(err.category, ErrorCategory::Conflict));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_prune_removes_expired_keys() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = IdempotencyStore::new(open_db(&temp_dir).await, chrono::Duration::zero());
+        let hash = request_hash("POST /v2/mint/spend", &json!({}));
+
+        match store.begin(Some("shop"), "key-1", hash).await.unwrap() {
+            IdempotencyOutcome::Proceed(guard) => guard.complete(response()).await.unwrap(),
+            IdempotencyOutcome::Replay(_) => panic!("first use must proceed"),
+        }
+
+        assert_eq!(store.prune().await.unwrap(), 1);
+        assert_eq!(store.prune().await.unwrap(), 0);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_keys_are_scoped_to_their_owner() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = open_store(&temp_dir).await;
+        let hash = request_hash("POST /v2/mint/spend", &json!({}));
+
+        match store
+            .begin(Some("shop"), "key-1", hash.clone())
+            .await
+            .unwrap()
+        {
+            IdempotencyOutcome::Proceed(guard) => guard.complete(response()).await.unwrap(),
+            IdempotencyOutcome::Replay(_) => panic!("first use must proceed"),
+        }
+
+        // Another API key using the same key neither gets the stored response
+        // nor a conflict
+        assert!(matches!(
+            store.begin(Some("other"), "key-1", hash).await.unwrap(),
+            IdempotencyOutcome::Proceed(_)
+        ));
+    }
+}
@@ -0,0 +1,27 @@
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use fedimint_core::config::FederationId;
+    use fedimint_core::Amount;
+
+    use crate::core::ensure_can_leave;
+    use crate::error::ErrorCategory;
+
+    #[test]
+    fn test_leave_refused_with_balance() {
+        let err =
+            ensure_can_leave(FederationId::dummy(), Amount::from_msats(1_000), false).unwrap_err();
+        assert!(matches!(err.category, ErrorCategory::Conflict));
+        assert!(err.message.contains("1000 msat"));
+    }
+
+    #[test]
+    fn test_leave_with_balance_when_forced() {
+        assert!(ensure_can_leave(FederationId::dummy(), Amount::from_msats(1_000), true).is_ok());
+    }
+
+    #[test]
+    fn test_leave_empty_federation() {
+        assert!(ensure_can_leave(FederationId::dummy(), Amount::ZERO, false).is_ok());
+    }
+}
@@ -0,0 +1,23 @@
+use fedimint_core::db::Database;
+use tempfile::TempDir;
+
+mod approval_tests;
+mod audit_tests;
+mod idempotency_tests;
+mod leave_tests;
+mod policy_tests;
+mod withdraw_address_tests;
+mod withdraw_quote_tests;
+mod withdraw_tests;
+
+/// Open the RocksDB database in the temp dir, later calls with the same dir
+/// reopen it
+#[allow(clippy::unwrap_used)]
+pub(crate) async fn open_db(temp_dir: &TempDir) -> Database {
+    Database::new(
+        fedimint_rocksdb::RocksDb::open(temp_dir.path().join("fmcd.db"))
+            .await
+            .unwrap(),
+        Default::default(),
+    )
+}
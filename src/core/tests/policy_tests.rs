@@ -0,0 +1,148 @@
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use fedimint_core::config::FederationId;
+    use tempfile::TempDir;
+
+    use crate::core::policy::*;
+    use crate::core::tests::open_db;
+    use crate::error::{AppError, ErrorCategory};
+
+    fn payment(amount_msat: u64, destination: &str) -> SpendIntent {
+        SpendIntent {
+            federation_id: FederationId::dummy(),
+            kind: SpendKind::LightningPayment,
+            amount_msat,
+            destinations: vec![destination.to_string()],
+            approved: false,
+        }
+    }
+
+    #[test]
+    fn test_per_transaction_and_approval_limits() {
+        let limits = SpendingLimits {
+            max_per_transaction_msat: Some(10_000),
+            approval_threshold_msat: Some(5_000),
+            ..Default::default()
+        };
+
+        assert!(limits.check(&payment(4_999, "alice")).is_ok());
+        let violation = limits.check(&payment(5_000, "alice")).unwrap_err();
+        assert_eq!(violation.rule, PolicyRule::ApprovalRequired);
+        let approved = SpendIntent {
+            approved: true,
+            ..payment(5_000, "alice")
+        };
+        assert!(limits.check(&approved).is_ok());
+        let violation = limits.check(&payment(10_001, "alice")).unwrap_err();
+        assert_eq!(violation.rule, PolicyRule::MaxPerTransaction);
+
+        let err = AppError::from(violation);
+        assert!(matches!(err.category, ErrorCategory::PolicyViolation));
+        assert_eq!(err.details.unwrap()["rule"], "max_per_transaction");
+    }
+
+    #[test]
+    fn test_destination_lists() {
+        let limits = SpendingLimits {
+            allowed_destinations: vec!["alice@example.com".to_string()],
+            denied_destinations: vec!["mallory@example.com".to_string()],
+            ..Default::default()
+        };
+
+        assert!(limits.check(&payment(1_000, "Alice@Example.com")).is_ok());
+        let violation = limits
+            .check(&payment(1_000, "mallory@example.com"))
+            .unwrap_err();
+        assert_eq!(violation.rule, PolicyRule::DestinationDenied);
+        let violation = limits
+            .check(&payment(1_000, "bob@example.com"))
+            .unwrap_err();
+        assert_eq!(violation.rule, PolicyRule::DestinationNotAllowed);
+
+        // Ecash has no destination to match against the allow list
+        let spend = SpendIntent {
+            kind: SpendKind::EcashSpend,
+            destinations: Vec::new(),
+            ..payment(1_000, "")
+        };
+        let violation = limits.check(&spend).unwrap_err();
+        assert_eq!(violation.rule, PolicyRule::DestinationNotAllowed);
+    }
+
+    #[test]
+    fn test_federation_limits_replace_default() {
+        let config = SpendingPolicyConfig {
+            default: SpendingLimits {
+                max_per_transaction_msat: Some(1_000),
+                ..Default::default()
+            },
+            federations: [(FederationId::dummy().to_string(), SpendingLimits::default())]
+                .into_iter()
+                .collect(),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            config.limits(&FederationId::dummy()),
+            &SpendingLimits::default()
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_daily_cap_counts_pending_and_committed_spends() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = SpendingPolicyConfig {
+            default: SpendingLimits {
+                daily_cap_msat: Some(10_000),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let policy = SpendingPolicy::new(open_db(&temp_dir).await, config.clone());
+
+        let first = policy.authorize(&payment(6_000, "alice")).await.unwrap();
+        // The first spend counts while it is being executed
+        let violation = policy
+            .authorize(&payment(6_000, "alice"))
+            .await
+            .unwrap_err();
+        assert_eq!(violation.rule, PolicyRule::DailyCap);
+        first.commit().await.unwrap();
+
+        // A spend that was never executed doesn't count
+        drop(policy.authorize(&payment(4_000, "alice")).await.unwrap());
+        assert_eq!(policy.spent(&FederationId::dummy()).await, (6_000, 6_000));
+
+        // Committed spends survive a restart
+        drop(policy);
+        let policy = SpendingPolicy::new(open_db(&temp_dir).await, config);
+        assert!(policy.authorize(&payment(4_000, "alice")).await.is_ok());
+        let violation = policy
+            .authorize(&payment(4_001, "alice"))
+            .await
+            .unwrap_err();
+        assert_eq!(violation.rule, PolicyRule::DailyCap);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_released_spend_stops_counting_once_dropped() {
+        let temp_dir = TempDir::new().unwrap();
+        let policy = SpendingPolicy::new(open_db(&temp_dir).await, SpendingPolicyConfig::default());
+
+        let mut spend = policy.authorize(&payment(5_000, "alice")).await.unwrap();
+        spend.set_amount_msat(6_000);
+        assert_eq!(policy.spent(&FederationId::dummy()).await, (6_000, 6_000));
+
+        // Committed once funded, counted once rather than also as pending
+        spend.commit().await.unwrap();
+        assert_eq!(policy.spent(&FederationId::dummy()).await, (6_000, 6_000));
+
+        // Refunded: pending again while a retry may still use it
+        spend.release().await.unwrap();
+        assert_eq!(policy.spent(&FederationId::dummy()).await, (6_000, 6_000));
+
+        drop(spend);
+        assert_eq!(policy.spent(&FederationId::dummy()).await, (0, 0));
+    }
+}
@@ -0,0 +1,59 @@
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use bitcoin::Network;
+
+    use crate::core::withdraw_address::*;
+    use crate::error::{AppError, ErrorCategory};
+
+    const MAINNET_P2WPKH: &str = "bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq";
+    const TESTNET_P2WPKH: &str = "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx";
+
+    fn reason(err: &AppError) -> &str {
+        assert!(matches!(err.category, ErrorCategory::ValidationError));
+        err.details.as_ref().unwrap()["reason"].as_str().unwrap()
+    }
+
+    #[test]
+    fn test_accepts_address_for_federation_network() {
+        let address = parse_withdraw_address(MAINNET_P2WPKH, Network::Bitcoin).unwrap();
+        assert_eq!(address.to_string(), MAINNET_P2WPKH);
+        assert!(parse_withdraw_address(TESTNET_P2WPKH, Network::Testnet).is_ok());
+        // Testnet addresses are valid on signet and regtest as well
+        assert!(parse_withdraw_address(TESTNET_P2WPKH, Network::Signet).is_ok());
+    }
+
+    #[test]
+    fn test_rejects_address_for_other_network() {
+        let err = parse_withdraw_address(TESTNET_P2WPKH, Network::Bitcoin).unwrap_err();
+        assert_eq!(reason(&err), "NETWORK_MISMATCH");
+
+        let err = parse_withdraw_address(MAINNET_P2WPKH, Network::Regtest).unwrap_err();
+        assert_eq!(reason(&err), "NETWORK_MISMATCH");
+    }
+
+    #[test]
+    fn test_rejects_invalid_address() {
+        let err = parse_withdraw_address("not-an-address", Network::Bitcoin).unwrap_err();
+        assert_eq!(reason(&err), "INVALID_ADDRESS");
+    }
+
+    #[test]
+    fn test_rejects_unsupported_witness_version() {
+        // Segwit v2 outputs are not spendable today
+        let err = parse_withdraw_address("bc1zw508d6qejxtdg4y5r3zarvaryvaxxpcs", Network::Bitcoin)
+            .unwrap_err();
+        assert_eq!(reason(&err), "UNSUPPORTED_SCRIPT_TYPE");
+    }
+
+    #[test]
+    fn test_dust_limit() {
+        let address = parse_withdraw_address(MAINNET_P2WPKH, Network::Bitcoin).unwrap();
+        let dust_limit = address.script_pubkey().minimal_non_dust();
+
+        assert!(check_dust_limit(&address, dust_limit).is_ok());
+        let err =
+            check_dust_limit(&address, dust_limit - bitcoin::Amount::from_sat(1)).unwrap_err();
+        assert_eq!(reason(&err), "AMOUNT_BELOW_DUST");
+    }
+}
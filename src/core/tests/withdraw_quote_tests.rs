@@ -0,0 +1,98 @@
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use fedimint_core::config::FederationId;
+    use fedimint_wallet_client::PegOutFees;
+    use tempfile::TempDir;
+
+    use crate::core::tests::open_db;
+    use crate::core::withdraw_quote::*;
+    use crate::error::ErrorCategory;
+
+    fn quote(expires_in: chrono::Duration) -> WithdrawQuote {
+        WithdrawQuote::new(
+            FederationId::dummy(),
+            "bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq".to_string(),
+            false,
+            bitcoin::Amount::from_sat(50_000),
+            PegOutFees::new(2_000, 4_000),
+            chrono::Utc::now() + expires_in,
+        )
+    }
+
+    #[test]
+    fn test_fee_rate_fallen_within_tolerance() {
+        let quote = quote(chrono::Duration::minutes(5));
+        assert_eq!(quote.fees_sat, 2_000);
+        assert!(quote.check_fee_rate(2_000, 10).is_ok());
+        assert!(quote.check_fee_rate(1_800, 10).is_ok());
+
+        let err = quote.check_fee_rate(1_799, 10).unwrap_err();
+        assert!(matches!(err.category, ErrorCategory::Conflict));
+    }
+
+    #[test]
+    fn test_fee_rate_rise_is_rejected() {
+        let quote = quote(chrono::Duration::minutes(5));
+        let err = quote.check_fee_rate(2_001, 10).unwrap_err();
+        assert!(matches!(err.category, ErrorCategory::Conflict));
+        assert!(err.message.contains("rose"));
+
+        // The tolerance only covers a fallen rate
+        assert!(quote.check_fee_rate(2_200, 100).is_err());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_quote_survives_restart_until_consumed() {
+        let temp_dir = TempDir::new().unwrap();
+        let quote = quote(chrono::Duration::minutes(5));
+        {
+            let store =
+                WithdrawQuoteStore::new(open_db(&temp_dir).await, chrono::Duration::minutes(5));
+            store.insert(&quote).await.unwrap();
+        }
+
+        let store = WithdrawQuoteStore::new(open_db(&temp_dir).await, chrono::Duration::minutes(5));
+        let reserved = store.reserve(&quote.quote_id).await.unwrap();
+        assert_eq!(reserved.quote.fees_sat, quote.fees_sat);
+        reserved.consume().await.unwrap();
+
+        let err = store.reserve(&quote.quote_id).await.unwrap_err();
+        assert!(matches!(err.category, ErrorCategory::NotFound));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_released_reservation_keeps_quote_usable() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = WithdrawQuoteStore::new(open_db(&temp_dir).await, chrono::Duration::minutes(5));
+        let quote = quote(chrono::Duration::minutes(5));
+        store.insert(&quote).await.unwrap();
+
+        let reserved = store.reserve(&quote.quote_id).await.unwrap();
+        let err = store.reserve(&quote.quote_id).await.unwrap_err();
+        assert!(matches!(err.category, ErrorCategory::Conflict));
+
+        // A withdrawal failing validation drops its reservation unused
+        drop(reserved);
+        assert!(store.reserve(&quote.quote_id).await.is_ok());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_rejects_expired_quote() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = WithdrawQuoteStore::new(open_db(&temp_dir).await, chrono::Duration::minutes(5));
+        let expired = quote(chrono::Duration::seconds(-1));
+        store.insert(&expired).await.unwrap();
+
+        let err = store.reserve(&expired.quote_id).await.unwrap_err();
+        assert!(matches!(err.category, ErrorCategory::ValidationError));
+
+        // Expired quotes are dropped when the next one is added
+        store
+            .insert(&quote(chrono::Duration::minutes(5)))
+            .await
+            .unwrap();
+        let err = store.reserve(&expired.quote_id).await.unwrap_err();
+        assert!(matches!(err.category, ErrorCategory::NotFound));
+    }
+}
@@ -0,0 +1,96 @@
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use bitcoin::Txid;
+    use fedimint_core::config::FederationId;
+    use fedimint_core::core::OperationId;
+    use fedimint_wallet_client::WithdrawState;
+    use tempfile::TempDir;
+
+    use crate::core::services::payment_lifecycle::PaymentType;
+    use crate::core::{FmcdCore, WithdrawResponse, WithdrawStatus};
+    use crate::error::ErrorCategory;
+
+    const TXID: &str = "f4184fc596403b9d638783cf57adfe4c75c605f6356fbc91338530e9831e9e16";
+
+    #[test]
+    fn test_async_withdraw_response_carries_operation_and_fees() {
+        let operation_id = OperationId::new_random();
+        let response = WithdrawResponse {
+            operation_id,
+            txid: None,
+            amount_sat: 50_000,
+            fees_sat: 420,
+            fee_rate_sats_per_kvb: 2_000,
+        };
+
+        let json = serde_json::to_value(&response).unwrap();
+        assert_eq!(
+            json["operationId"],
+            serde_json::to_value(operation_id).unwrap()
+        );
+        assert_eq!(json["amountSat"], 50_000);
+        assert_eq!(json["feesSat"], 420);
+        assert_eq!(json["feeRateSatsPerKvb"], 2_000);
+        assert!(json.get("txid").is_none());
+    }
+
+    #[test]
+    fn test_withdraw_status_from_state() {
+        let txid = TXID.parse::<Txid>().unwrap();
+
+        assert_eq!(
+            WithdrawStatus::from(WithdrawState::Created),
+            WithdrawStatus::Created
+        );
+        assert_eq!(
+            WithdrawStatus::from(WithdrawState::Succeeded(txid)),
+            WithdrawStatus::Succeeded { txid }
+        );
+        assert_eq!(
+            WithdrawStatus::from(WithdrawState::Failed("no funds".to_string())),
+            WithdrawStatus::Failed {
+                reason: "no funds".to_string()
+            }
+        );
+
+        let json = serde_json::to_value(WithdrawStatus::Succeeded { txid }).unwrap();
+        assert_eq!(json["succeeded"]["txid"], TXID);
+        assert_eq!(
+            serde_json::to_value(WithdrawStatus::Created).unwrap(),
+            "created"
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_withdraw_status_of_unknown_federation() {
+        let temp_dir = TempDir::new().unwrap();
+        let core = FmcdCore::new(temp_dir.path().to_path_buf()).await.unwrap();
+
+        let err = core
+            .withdraw_status(FederationId::dummy(), OperationId::new_random())
+            .await
+            .unwrap_err();
+        assert!(matches!(err.category, ErrorCategory::FederationNotFound));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_resume_withdrawals_keeps_withdrawals_of_missing_federations() {
+        let temp_dir = TempDir::new().unwrap();
+        let core = FmcdCore::new(temp_dir.path().to_path_buf()).await.unwrap();
+        let manager = core.payment_lifecycle_manager.clone().unwrap();
+
+        let operation_id = OperationId::new_random();
+        manager
+            .track_onchain_withdraw(operation_id, FederationId::dummy(), 50_000, None)
+            .await
+            .unwrap();
+
+        // Without a client the withdrawal can't be followed, it stays tracked
+        // so a later start resumes it
+        core.resume_withdrawals(&manager).await;
+        let withdrawals = manager.operations(PaymentType::OnchainWithdraw).await;
+        assert_eq!(withdrawals.len(), 1);
+        assert_eq!(withdrawals[0].operation_id, operation_id);
+    }
+}
@@ -0,0 +1,245 @@
+use std::sync::Arc;
+
+use crate::config::{Config, CONFIG_FILE_NAME};
+use crate::core::{CreateWebhookRequest, FmcdCore, UpdateWebhookRequest};
+use crate::error::{AppError, ErrorCategory};
+use crate::webhooks::{
+    DeliveryLogQuery, ReplaySelector, WebhookConfig, WebhookDelivery, WebhookDeliveryLogEntry,
+    WebhookEndpoint, WebhookEndpointInfo, WebhookNotifier, CALLBACK_ENDPOINT_ID,
+};
+
+impl FmcdCore {
+    pub(super) fn webhook_notifier(&self) -> Result<&Arc<WebhookNotifier>, AppError> {
+        self.webhook_notifier.as_ref().ok_or_else(|| {
+            AppError::with_category(
+                ErrorCategory::ServiceUnavailable,
+                "Webhook notifier is not available".to_string(),
+            )
+        })
+    }
+
+    /// List the configured webhook endpoints
+    pub async fn list_webhooks(&self) -> Result<Vec<WebhookEndpointInfo>, AppError> {
+        let config = self.webhook_notifier()?.config();
+        Ok(config
+            .endpoints
+            .iter()
+            .map(WebhookEndpointInfo::from)
+            .collect())
+    }
+
+    /// Add a webhook endpoint
+    pub async fn create_webhook(
+        &self,
+        req: CreateWebhookRequest,
+    ) -> Result<WebhookEndpointInfo, AppError> {
+        let endpoint = WebhookEndpoint {
+            id: req.id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
+            url: req.url,
+            secret: req.secret,
+            events: req.events,
+            retry_config: req.retry_config.unwrap_or_default(),
+            enabled: req.enabled.unwrap_or(true),
+            description: req.description,
+        };
+        endpoint
+            .validate()
+            .map_err(|e| AppError::validation_error(e.to_string()))?;
+        if endpoint.id == CALLBACK_ENDPOINT_ID {
+            return Err(AppError::validation_error(format!(
+                "Webhook endpoint id {} is reserved for invoice callbacks",
+                CALLBACK_ENDPOINT_ID
+            )));
+        }
+
+        self.modify_webhook_config(|config| {
+            if config.endpoint(&endpoint.id).is_some() {
+                return Err(AppError::with_category(
+                    ErrorCategory::Conflict,
+                    format!("Webhook endpoint {} already exists", endpoint.id),
+                ));
+            }
+            let info = WebhookEndpointInfo::from(&endpoint);
+            config.endpoints.push(endpoint);
+            Ok(info)
+        })
+        .await
+    }
+
+    /// Update a webhook endpoint
+    pub async fn update_webhook(
+        &self,
+        id: &str,
+        req: UpdateWebhookRequest,
+    ) -> Result<WebhookEndpointInfo, AppError> {
+        self.modify_webhook_config(|config| {
+            let endpoint = config.endpoint_mut(id).ok_or_else(|| {
+                AppError::with_category(
+                    ErrorCategory::NotFound,
+                    format!("Webhook endpoint {} not found", id),
+                )
+            })?;
+
+            let mut updated = endpoint.clone();
+            if let Some(url) = req.url {
+                updated.url = url;
+            }
+            if let Some(secret) = req.secret {
+                updated.secret = Some(secret);
+            }
+            if let Some(events) = req.events {
+                updated.events = events;
+            }
+            if let Some(retry_config) = req.retry_config {
+                updated.retry_config = retry_config;
+            }
+            if let Some(enabled) = req.enabled {
+                updated.enabled = enabled;
+            }
+            if let Some(description) = req.description {
+                updated.description = Some(description);
+            }
+            updated
+                .validate()
+                .map_err(|e| AppError::validation_error(e.to_string()))?;
+
+            *endpoint = updated;
+            Ok(WebhookEndpointInfo::from(&*endpoint))
+        })
+        .await
+    }
+
+    /// Enable or disable a webhook endpoint
+    pub async fn set_webhook_enabled(
+        &self,
+        id: &str,
+        enabled: bool,
+    ) -> Result<WebhookEndpointInfo, AppError> {
+        self.update_webhook(
+            id,
+            UpdateWebhookRequest {
+                enabled: Some(enabled),
+                ..Default::default()
+            },
+        )
+        .await
+    }
+
+    /// Remove a webhook endpoint, its pending deliveries end up in the
+    /// dead-letter list
+    pub async fn delete_webhook(&self, id: &str) -> Result<WebhookEndpointInfo, AppError> {
+        self.modify_webhook_config(|config| {
+            config
+                .remove_endpoint(id)
+                .map(|endpoint| WebhookEndpointInfo::from(&endpoint))
+                .ok_or_else(|| {
+                    AppError::with_category(
+                        ErrorCategory::NotFound,
+                        format!("Webhook endpoint {} not found", id),
+                    )
+                })
+        })
+        .await
+    }
+
+    /// Apply a change to the webhook config, save it to the config file and
+    /// hand it to the live notifier
+    async fn modify_webhook_config<T>(
+        &self,
+        modify: impl FnOnce(&mut WebhookConfig) -> Result<T, AppError>,
+    ) -> Result<T, AppError> {
+        let notifier = self.webhook_notifier()?;
+        let _guard = self.webhook_config_lock.lock().await;
+
+        let mut webhook_config = notifier.config();
+        let output = modify(&mut webhook_config)?;
+
+        // Only the webhooks section is written back, CLI and environment
+        // overrides must not end up in the file
+        let config_path = self.data_dir.join(CONFIG_FILE_NAME);
+        let mut config = if config_path.exists() {
+            Config::load_from_file(&config_path).map_err(|e| {
+                AppError::with_category(
+                    ErrorCategory::InternalError,
+                    format!("Failed to load config file: {}", e),
+                )
+            })?
+        } else {
+            Config::default()
+        };
+        config.webhooks = webhook_config.clone();
+        config.save_to_file(&config_path).map_err(|e| {
+            AppError::with_category(
+                ErrorCategory::InternalError,
+                format!("Failed to save config file: {}", e),
+            )
+        })?;
+
+        notifier.update_config(webhook_config);
+        Ok(output)
+    }
+
+    /// Delivery history of a webhook endpoint, newest first
+    pub async fn webhook_deliveries(
+        &self,
+        id: &str,
+        query: DeliveryLogQuery,
+    ) -> Result<Vec<WebhookDeliveryLogEntry>, AppError> {
+        self.webhook_notifier()?
+            .delivery_log(id, &query)
+            .await
+            .map_err(|e| AppError::with_category(ErrorCategory::DatabaseError, e.to_string()))
+    }
+
+    /// Send one past event, or every event in a time window, to a webhook
+    /// endpoint again
+    pub async fn replay_webhook(
+        &self,
+        id: &str,
+        selector: ReplaySelector,
+    ) -> Result<Vec<WebhookDelivery>, AppError> {
+        if selector.event_id.is_none() && selector.from.is_none() && selector.to.is_none() {
+            return Err(AppError::validation_error(
+                "Either eventId or a from/to time window is required",
+            ));
+        }
+        if let (Some(from), Some(to)) = (selector.from, selector.to) {
+            if from > to {
+                return Err(AppError::validation_error("from must not be after to"));
+            }
+        }
+
+        let notifier = self.webhook_notifier()?;
+        if notifier.config().endpoint(id).is_none() {
+            return Err(AppError::not_found(format!(
+                "Webhook endpoint {} not found",
+                id
+            )));
+        }
+
+        notifier
+            .replay(id, &selector)
+            .await
+            .map_err(|e| AppError::with_category(ErrorCategory::DatabaseError, e.to_string()))
+    }
+
+    /// List webhook deliveries that exhausted their retries
+    pub async fn webhook_dead_letters(&self) -> Result<Vec<WebhookDelivery>, AppError> {
+        self.webhook_notifier()?
+            .dead_letters()
+            .await
+            .map_err(|e| AppError::with_category(ErrorCategory::DatabaseError, e.to_string()))
+    }
+
+    /// Requeue dead-lettered webhook deliveries, all of them if no ids are
+    /// given
+    pub async fn requeue_webhook_deliveries(
+        &self,
+        delivery_ids: Option<Vec<String>>,
+    ) -> Result<Vec<WebhookDelivery>, AppError> {
+        self.webhook_notifier()?
+            .requeue(delivery_ids.as_deref())
+            .await
+            .map_err(|e| AppError::with_category(ErrorCategory::DatabaseError, e.to_string()))
+    }
+}
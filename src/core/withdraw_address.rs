@@ -0,0 +1,95 @@
+use std::str::FromStr;
+
+use bitcoin::address::AddressType;
+use bitcoin::{Address, Network};
+use serde_json::json;
+
+use crate::error::AppError;
+
+/// Why a withdrawal address or amount was rejected, sent to the client as
+/// `details.reason`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddressRejection {
+    InvalidAddress,
+    NetworkMismatch,
+    UnsupportedScriptType,
+    AmountBelowDust,
+}
+
+impl AddressRejection {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::InvalidAddress => "INVALID_ADDRESS",
+            Self::NetworkMismatch => "NETWORK_MISMATCH",
+            Self::UnsupportedScriptType => "UNSUPPORTED_SCRIPT_TYPE",
+            Self::AmountBelowDust => "AMOUNT_BELOW_DUST",
+        }
+    }
+
+    fn error(self, message: String, details: serde_json::Value) -> AppError {
+        let mut details = details;
+        details["reason"] = json!(self.code());
+        AppError::validation_error(message).with_details(details)
+    }
+}
+
+/// Parse a withdrawal address for a federation on `network`, rejecting
+/// addresses for another network and script types the federation can't pay
+/// to
+pub fn parse_withdraw_address(address: &str, network: Network) -> Result<Address, AppError> {
+    let unchecked = Address::from_str(address).map_err(|e| {
+        AddressRejection::InvalidAddress.error(
+            format!("Invalid Bitcoin address: {}", e),
+            json!({ "address": address }),
+        )
+    })?;
+
+    if !unchecked.is_valid_for_network(network) {
+        return Err(AddressRejection::NetworkMismatch.error(
+            format!(
+                "Address {} is not valid on the federation's network {}",
+                address, network
+            ),
+            json!({ "address": address, "network": network.to_string() }),
+        ));
+    }
+    let address = unchecked.assume_checked();
+
+    match address.address_type() {
+        Some(
+            AddressType::P2pkh
+            | AddressType::P2sh
+            | AddressType::P2wpkh
+            | AddressType::P2wsh
+            | AddressType::P2tr,
+        ) => Ok(address),
+        other => Err(AddressRejection::UnsupportedScriptType.error(
+            format!("Withdrawals to address {} are not supported", address),
+            json!({
+                "address": address.to_string(),
+                "scriptType": other.map(|t| t.to_string()),
+            }),
+        )),
+    }
+}
+
+/// Reject withdrawals whose output would be dust, which bitcoin nodes don't
+/// relay
+pub fn check_dust_limit(address: &Address, amount: bitcoin::Amount) -> Result<(), AppError> {
+    let dust_limit = address.script_pubkey().minimal_non_dust();
+    if amount < dust_limit {
+        return Err(AddressRejection::AmountBelowDust.error(
+            format!(
+                "Withdrawal of {} sat is below the dust limit of {} sat for address {}",
+                amount.to_sat(),
+                dust_limit.to_sat(),
+                address
+            ),
+            json!({
+                "amountSat": amount.to_sat(),
+                "dustLimitSat": dust_limit.to_sat(),
+            }),
+        ));
+    }
+    Ok(())
+}
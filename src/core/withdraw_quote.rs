@@ -0,0 +1,303 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use fedimint_core::config::FederationId;
+use fedimint_core::db::{Database, IDatabaseTransactionOpsCoreTyped};
+use fedimint_core::BitcoinAmountOrAll;
+use fedimint_wallet_client::{PegOutFees, WalletClientModule};
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::core::multimint::db::{WithdrawQuoteKey, WithdrawQuoteKeyPrefix, WithdrawQuoteRecord};
+use crate::core::withdraw_address::parse_withdraw_address;
+use crate::core::{FmcdCore, WithdrawQuoteRequest};
+use crate::error::{AppError, ErrorCategory};
+
+/// Fee a quoted withdrawal executes with, and what it leaves to the recipient
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WithdrawQuote {
+    pub quote_id: String,
+    pub federation_id: FederationId,
+    pub address: String,
+    /// Whether the quote withdraws the whole balance
+    pub all: bool,
+    /// Amount the address receives, the balance minus fees for `all`
+    pub amount_sat: u64,
+    pub fees_sat: u64,
+    pub fee_rate_sats_per_kvb: u64,
+    pub total_weight: u64,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl WithdrawQuote {
+    pub fn new(
+        federation_id: FederationId,
+        address: String,
+        all: bool,
+        amount: bitcoin::Amount,
+        fees: PegOutFees,
+        expires_at: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            quote_id: Uuid::new_v4().to_string(),
+            federation_id,
+            address,
+            all,
+            amount_sat: amount.to_sat(),
+            fees_sat: fees.amount().to_sat(),
+            fee_rate_sats_per_kvb: fees.fee_rate.sats_per_kvb,
+            total_weight: fees.total_weight,
+            expires_at,
+        }
+    }
+
+    pub fn amount(&self) -> bitcoin::Amount {
+        bitcoin::Amount::from_sat(self.amount_sat)
+    }
+
+    /// The quoted fees, exactly as the withdrawal is executed with
+    pub fn fees(&self) -> PegOutFees {
+        PegOutFees::new(self.fee_rate_sats_per_kvb, self.total_weight)
+    }
+
+    /// Reject the quote when the federation's current fee rate rose above
+    /// the quoted one, since the quoted fees would no longer be enough, or
+    /// fell more than `tolerance_percent` below it, so they would overpay
+    pub fn check_fee_rate(
+        &self,
+        current_sats_per_kvb: u64,
+        tolerance_percent: u64,
+    ) -> Result<(), AppError> {
+        let quoted = self.fee_rate_sats_per_kvb;
+        if current_sats_per_kvb > quoted {
+            return Err(AppError::with_category(
+                ErrorCategory::Conflict,
+                format!(
+                    "Fee rate rose from {} to {} sats/kvB since quote {}",
+                    quoted, current_sats_per_kvb, self.quote_id
+                ),
+            ));
+        }
+
+        let allowed = quoted.saturating_mul(tolerance_percent) / 100;
+        if quoted - current_sats_per_kvb > allowed {
+            return Err(AppError::with_category(
+                ErrorCategory::Conflict,
+                format!(
+                    "Fee rate fell from {} to {} sats/kvB since quote {}, beyond the {}% tolerance",
+                    quoted, current_sats_per_kvb, self.quote_id, tolerance_percent
+                ),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Withdraw quotes handed out and not yet used, kept in the multimint
+/// database so they survive a restart.
+///
+/// A quote is reserved in memory while a withdrawal validates and executes it,
+/// and only removed once the withdrawal was submitted. A request that fails
+/// validation leaves the quote usable. Expired quotes are dropped whenever a
+/// new one is added.
+pub struct WithdrawQuoteStore {
+    db: Database,
+    ttl: chrono::Duration,
+    in_use: Arc<std::sync::Mutex<HashSet<String>>>,
+}
+
+impl std::fmt::Debug for WithdrawQuoteStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WithdrawQuoteStore")
+            .field("ttl", &self.ttl)
+            .finish()
+    }
+}
+
+impl WithdrawQuoteStore {
+    pub fn new(db: Database, ttl: chrono::Duration) -> Self {
+        Self {
+            db,
+            ttl,
+            in_use: Arc::new(std::sync::Mutex::new(HashSet::new())),
+        }
+    }
+
+    pub fn ttl(&self) -> chrono::Duration {
+        self.ttl
+    }
+
+    pub async fn insert(&self, quote: &WithdrawQuote) -> Result<()> {
+        let now = Utc::now();
+        let record = WithdrawQuoteRecord {
+            json: serde_json::to_string(quote)?,
+        };
+
+        let mut dbtx = self.db.begin_transaction().await;
+        let expired = dbtx
+            .find_by_prefix(&WithdrawQuoteKeyPrefix)
+            .await
+            .filter_map(|(key, record)| async move {
+                decode_quote(&record)
+                    .is_none_or(|quote| quote.expires_at <= now)
+                    .then_some(key)
+            })
+            .collect::<Vec<_>>()
+            .await;
+        for key in expired {
+            dbtx.remove_entry(&key).await;
+        }
+        dbtx.insert_entry(
+            &WithdrawQuoteKey {
+                quote_id: quote.quote_id.clone(),
+            },
+            &record,
+        )
+        .await;
+        dbtx.commit_tx_result()
+            .await
+            .map_err(|e| anyhow!("Failed to store withdraw quote: {:?}", e))
+    }
+
+    /// Reserve a quote for a withdrawal, fails if it is unknown, expired or
+    /// already being used
+    pub async fn reserve(&self, quote_id: &str) -> Result<ReservedQuote, AppError> {
+        let inserted = self
+            .in_use
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .insert(quote_id.to_string());
+        if !inserted {
+            return Err(AppError::with_category(
+                ErrorCategory::Conflict,
+                format!("Quote {} is already being used", quote_id),
+            ));
+        }
+        // Releases the reservation on every early return below
+        let reservation = QuoteReservation {
+            db: self.db.clone(),
+            in_use: self.in_use.clone(),
+            quote_id: quote_id.to_string(),
+        };
+
+        let record = self
+            .db
+            .begin_transaction_nc()
+            .await
+            .get_value(&WithdrawQuoteKey {
+                quote_id: quote_id.to_string(),
+            })
+            .await;
+        let quote = record
+            .as_ref()
+            .and_then(decode_quote)
+            .ok_or_else(|| AppError::not_found(format!("Quote {} not found", quote_id)))?;
+
+        if quote.expires_at <= Utc::now() {
+            return Err(AppError::validation_error(format!(
+                "Quote {} expired at {}",
+                quote_id, quote.expires_at
+            )));
+        }
+        Ok(reservation.with_quote(quote))
+    }
+}
+
+/// A quote reserved for one withdrawal, released when dropped
+#[derive(Debug)]
+pub struct QuoteReservation {
+    db: Database,
+    in_use: Arc<std::sync::Mutex<HashSet<String>>>,
+    quote_id: String,
+}
+
+/// A reserved quote
+#[derive(Debug)]
+pub struct ReservedQuote {
+    pub quote: WithdrawQuote,
+    reservation: QuoteReservation,
+}
+
+impl QuoteReservation {
+    fn with_quote(self, quote: WithdrawQuote) -> ReservedQuote {
+        ReservedQuote {
+            quote,
+            reservation: self,
+        }
+    }
+}
+
+impl ReservedQuote {
+    /// Remove the quote once its withdrawal was submitted
+    pub async fn consume(self) -> Result<()> {
+        let mut dbtx = self.reservation.db.begin_transaction().await;
+        dbtx.remove_entry(&WithdrawQuoteKey {
+            quote_id: self.reservation.quote_id.clone(),
+        })
+        .await;
+        dbtx.commit_tx_result()
+            .await
+            .map_err(|e| anyhow!("Failed to remove withdraw quote: {:?}", e))
+    }
+}
+
+impl Drop for QuoteReservation {
+    fn drop(&mut self) {
+        self.in_use
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .remove(&self.quote_id);
+    }
+}
+
+fn decode_quote(record: &WithdrawQuoteRecord) -> Option<WithdrawQuote> {
+    match serde_json::from_str::<WithdrawQuote>(&record.json) {
+        Ok(quote) => Some(quote),
+        Err(e) => {
+            warn!(error = ?e, "Skipping undecodable withdraw quote");
+            None
+        }
+    }
+}
+
+impl FmcdCore {
+    /// Quote the fees of a withdrawal without executing it. Passing the quote
+    /// id to `withdraw_onchain` executes the withdrawal with exactly these
+    /// fees.
+    pub async fn quote_withdraw(
+        &self,
+        req: WithdrawQuoteRequest,
+    ) -> Result<WithdrawQuote, AppError> {
+        let client = self.get_client(req.federation_id).await?;
+        let wallet_module = client.get_first_module::<WalletClientModule>()?;
+
+        let address = parse_withdraw_address(&req.address, wallet_module.get_network())?;
+        let (amount, fees) =
+            Self::withdraw_amount_and_fees(&client, &wallet_module, &address, req.amount_sat)
+                .await?;
+
+        let quote = WithdrawQuote::new(
+            req.federation_id,
+            req.address,
+            matches!(req.amount_sat, BitcoinAmountOrAll::All),
+            amount,
+            fees,
+            chrono::Utc::now() + self.withdraw_quotes.ttl(),
+        );
+        info!(
+            quote_id = %quote.quote_id,
+            federation_id = %quote.federation_id,
+            amount_sat = quote.amount_sat,
+            fee_sat = quote.fees_sat,
+            "Withdraw quoted"
+        );
+        self.withdraw_quotes.insert(&quote).await?;
+
+        Ok(quote)
+    }
+}
@@ -50,7 +50,7 @@ impl DatabaseStats {
             successful_operations: successful,
             failed_operations: failed,
             slow_operations: slow,
-            average_duration_ms: if total > 0 { total_duration / total } else { 0 },
+            average_duration_ms: total_duration.checked_div(total).unwrap_or(0),
             success_rate: if total > 0 {
                 (successful as f64 / total as f64) * 100.0
             } else {
@@ -103,7 +103,7 @@ impl<T: DatabaseInterface> InstrumentedDatabase<T> {
     }
 
     /// Get key prefix for logging (first 8 bytes, hex encoded)
-    fn get_key_prefix(&self, key: &[u8]) -> String {
+    pub(crate) fn get_key_prefix(&self, key: &[u8]) -> String {
         let prefix_len = std::cmp::min(key.len(), 8);
         hex::encode(&key[..prefix_len])
     }
@@ -344,6 +344,3 @@ impl<T: DatabaseInterface> DatabaseWithContext<T> for InstrumentedDatabase<T> {
         DatabaseContextWrapper::new(self, context)
     }
 }
-
-#[cfg(test)]
-mod tests;
@@ -13,6 +13,8 @@ pub enum ErrorCategory {
     NotFound,
     Conflict,
     RateLimited,
+    PolicyViolation,
+    ApprovalRequired,
 
     // Payment errors
     InsufficientFunds,
@@ -46,6 +48,8 @@ impl ErrorCategory {
             Self::NotFound => StatusCode::NOT_FOUND,
             Self::Conflict => StatusCode::CONFLICT,
             Self::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+            Self::PolicyViolation => StatusCode::FORBIDDEN,
+            Self::ApprovalRequired => StatusCode::ACCEPTED,
             Self::InsufficientFunds => StatusCode::PAYMENT_REQUIRED,
             Self::PaymentTimeout | Self::InvoiceExpired => StatusCode::REQUEST_TIMEOUT,
             Self::RouteNotFound => StatusCode::NOT_FOUND,
@@ -71,6 +75,8 @@ impl ErrorCategory {
             Self::NotFound => "NOT_FOUND",
             Self::Conflict => "CONFLICT",
             Self::RateLimited => "RATE_LIMITED",
+            Self::PolicyViolation => "POLICY_VIOLATION",
+            Self::ApprovalRequired => "APPROVAL_REQUIRED",
             Self::InsufficientFunds => "INSUFFICIENT_FUNDS",
             Self::PaymentTimeout => "PAYMENT_TIMEOUT",
             Self::InvoiceExpired => "INVOICE_EXPIRED",
@@ -97,6 +103,8 @@ impl ErrorCategory {
                 | Self::NotFound
                 | Self::Conflict
                 | Self::RateLimited
+                | Self::PolicyViolation
+                | Self::ApprovalRequired
                 | Self::InsufficientFunds
                 | Self::PaymentTimeout
                 | Self::InvoiceExpired
@@ -142,6 +150,10 @@ mod tests {
             ErrorCategory::ServiceUnavailable.status_code(),
             StatusCode::SERVICE_UNAVAILABLE
         );
+        assert_eq!(
+            ErrorCategory::PolicyViolation.status_code(),
+            StatusCode::FORBIDDEN
+        );
     }
 
     #[test]
@@ -159,6 +171,10 @@ mod tests {
             "INSUFFICIENT_FUNDS"
         );
         assert_eq!(ErrorCategory::GatewayError.error_code(), "GATEWAY_ERROR");
+        assert_eq!(
+            ErrorCategory::PolicyViolation.error_code(),
+            "POLICY_VIOLATION"
+        );
     }
 
     #[test]
@@ -14,7 +14,7 @@ use crate::observability::correlation::RequestContext;
 
 #[cfg(test)]
 #[path = "tests.rs"]
-mod tests;
+mod error_tests;
 
 #[derive(Debug)]
 pub struct AppError {
@@ -22,7 +22,7 @@ pub struct AppError {
     pub message: String,
     pub details: Option<serde_json::Value>,
     pub source: Option<Box<dyn std::error::Error + Send + Sync>>,
-    pub request_context: Option<RequestContext>,
+    pub request_context: Option<Box<RequestContext>>,
 }
 
 impl AppError {
@@ -37,7 +37,7 @@ impl AppError {
     }
 
     pub fn with_context(mut self, context: RequestContext) -> Self {
-        self.request_context = Some(context);
+        self.request_context = Some(Box::new(context));
         self
     }
 
@@ -170,6 +170,78 @@ impl EventHandler for LoggingEventHandler {
                     );
                 }
             }
+            FmcdEvent::RestoreStarted {
+                restore_id,
+                federation_ids,
+                correlation_id,
+                timestamp,
+            } => {
+                info!(
+                    event_type = "restore_started",
+                    restore_id = %restore_id,
+                    federation_count = federation_ids.len(),
+                    correlation_id = ?correlation_id,
+                    timestamp = %timestamp,
+                    "Restore started"
+                );
+            }
+            FmcdEvent::FederationRecoveryProgress {
+                restore_id,
+                federation_id,
+                module_id,
+                complete,
+                total,
+                correlation_id,
+                timestamp,
+            } => {
+                if self.include_debug_events {
+                    debug!(
+                        event_type = "federation_recovery_progress",
+                        restore_id = %restore_id,
+                        federation_id = %federation_id,
+                        module_id = module_id,
+                        complete = complete,
+                        total = total,
+                        correlation_id = ?correlation_id,
+                        timestamp = %timestamp,
+                        "Federation recovery progress"
+                    );
+                }
+            }
+            FmcdEvent::FederationRecoveryCompleted {
+                restore_id,
+                federation_id,
+                balance_msat,
+                correlation_id,
+                timestamp,
+            } => {
+                info!(
+                    event_type = "federation_recovery_completed",
+                    restore_id = %restore_id,
+                    federation_id = %federation_id,
+                    balance_msat = balance_msat,
+                    correlation_id = ?correlation_id,
+                    timestamp = %timestamp,
+                    "Federation recovery completed"
+                );
+            }
+            FmcdEvent::FederationRecoveryFailed {
+                restore_id,
+                federation_id,
+                reason,
+                correlation_id,
+                timestamp,
+            } => {
+                error!(
+                    event_type = "federation_recovery_failed",
+                    restore_id = %restore_id,
+                    federation_id = %federation_id,
+                    reason = %reason,
+                    correlation_id = ?correlation_id,
+                    timestamp = %timestamp,
+                    "Federation recovery failed"
+                );
+            }
             FmcdEvent::GatewaySelected {
                 gateway_id,
                 federation_id,
@@ -204,6 +276,81 @@ impl EventHandler for LoggingEventHandler {
                     "Gateway unavailable"
                 );
             }
+            FmcdEvent::PolicyViolation {
+                federation_id,
+                spend_kind,
+                rule,
+                amount_msat,
+                reason,
+                correlation_id,
+                timestamp,
+            } => {
+                warn!(
+                    event_type = "policy_violation",
+                    federation_id = %federation_id,
+                    spend_kind = %spend_kind,
+                    rule = %rule,
+                    amount_msat = amount_msat,
+                    reason = %reason,
+                    correlation_id = ?correlation_id,
+                    timestamp = %timestamp,
+                    "Spend rejected by spending policy"
+                );
+            }
+            FmcdEvent::ApprovalRequested {
+                approval_id,
+                federation_id,
+                spend_kind,
+                amount_msat,
+                destination: _,
+                expires_at,
+                correlation_id,
+                timestamp,
+            } => {
+                info!(
+                    event_type = "approval_requested",
+                    approval_id = %approval_id,
+                    federation_id = %federation_id,
+                    spend_kind = %spend_kind,
+                    amount_msat = amount_msat,
+                    expires_at = %expires_at,
+                    correlation_id = ?correlation_id,
+                    timestamp = %timestamp,
+                    "Spend parked for approval"
+                );
+            }
+            FmcdEvent::ApprovalApproved {
+                approval_id,
+                federation_id,
+                correlation_id,
+                timestamp,
+            } => {
+                info!(
+                    event_type = "approval_approved",
+                    approval_id = %approval_id,
+                    federation_id = %federation_id,
+                    correlation_id = ?correlation_id,
+                    timestamp = %timestamp,
+                    "Parked spend approved"
+                );
+            }
+            FmcdEvent::ApprovalRejected {
+                approval_id,
+                federation_id,
+                reason,
+                correlation_id,
+                timestamp,
+            } => {
+                info!(
+                    event_type = "approval_rejected",
+                    approval_id = %approval_id,
+                    federation_id = %federation_id,
+                    reason = ?reason,
+                    correlation_id = ?correlation_id,
+                    timestamp = %timestamp,
+                    "Parked spend rejected"
+                );
+            }
             FmcdEvent::DatabaseQueryExecuted {
                 operation,
                 key_prefix,
@@ -457,12 +604,11 @@ mod tests {
         let handler = LoggingEventHandler::new(false);
 
         let event = FmcdEvent::PaymentSucceeded {
-            payment_id: "test_payment_id".to_string(),
+            operation_id: "test_payment_id".to_string(),
             federation_id: "test_federation_id".to_string(),
+            amount_msat: 1000,
             preimage: "a1b2c3d4e5f6789012345678901234567890123456789012345678901234567890"
                 .to_string(),
-            fee_msat: 100,
-            correlation_id: Some("test_correlation".to_string()),
             timestamp: Utc::now(),
         };
 
@@ -491,17 +637,14 @@ mod tests {
             timestamp: Utc::now(),
         };
 
-        // Handler with debug should log the event
-        let result = handler_with_debug.handle(event.clone()).await;
-        assert!(result.is_ok());
-
-        // Reset logs for second test
-        tracing_test::internal::logs_clear();
-
         // Handler without debug should not log this event
-        let result = handler_without_debug.handle(event).await;
+        let result = handler_without_debug.handle(event.clone()).await;
         assert!(result.is_ok());
         assert!(!logs_contain("Federation balance updated"));
+
+        // Handler with debug should log the event
+        let result = handler_with_debug.handle(event).await;
+        assert!(result.is_ok());
     }
 
     #[tokio::test]
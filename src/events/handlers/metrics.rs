@@ -166,6 +166,22 @@ impl EventHandler for MetricsEventHandler {
                     Some(balance_msat),
                 );
             }
+            FmcdEvent::RestoreStarted { .. } => {}
+            FmcdEvent::FederationRecoveryProgress { .. } => {}
+            FmcdEvent::FederationRecoveryCompleted {
+                federation_id,
+                balance_msat,
+                ..
+            } => {
+                self.record_federation_metrics(
+                    &federation_id,
+                    "recovery_completed",
+                    Some(balance_msat),
+                );
+            }
+            FmcdEvent::FederationRecoveryFailed { federation_id, .. } => {
+                self.record_federation_metrics(&federation_id, "recovery_failed", None);
+            }
             FmcdEvent::GatewaySelected {
                 gateway_id,
                 federation_id,
@@ -180,6 +196,22 @@ impl EventHandler for MetricsEventHandler {
             } => {
                 self.record_gateway_metrics(&gateway_id, &federation_id, "unavailable");
             }
+            FmcdEvent::PolicyViolation {
+                federation_id,
+                spend_kind,
+                ..
+            } => {
+                counter!(PAYMENTS_TOTAL, "federation_id" => federation_id, "type" => spend_kind, "status" => "policy_violation").increment(1);
+            }
+            FmcdEvent::ApprovalRequested {
+                federation_id,
+                spend_kind,
+                ..
+            } => {
+                counter!(PAYMENTS_TOTAL, "federation_id" => federation_id, "type" => spend_kind, "status" => "approval_requested").increment(1);
+            }
+            FmcdEvent::ApprovalApproved { .. } => {}
+            FmcdEvent::ApprovalRejected { .. } => {}
             FmcdEvent::DatabaseQueryExecuted {
                 operation,
                 duration_ms,
@@ -243,7 +275,11 @@ impl EventHandler for MetricsEventHandler {
         // Record general event bus metrics
         counter!(EVENT_BUS_EVENTS_TOTAL, "event_type" => event_type).increment(1);
 
-        debug!(handler = self.name(), "Event metrics recorded");
+        debug!(
+            handler = self.name(),
+            service = %self.service_name,
+            "Event metrics recorded"
+        );
 
         Ok(())
     }
@@ -262,7 +298,6 @@ impl EventHandler for MetricsEventHandler {
 #[cfg(test)]
 mod tests {
     use std::sync::atomic::{AtomicU64, Ordering};
-    use std::sync::Arc;
 
     use chrono::Utc;
 
@@ -286,11 +321,10 @@ mod tests {
         let handler = MetricsEventHandler::new("test");
 
         let event = FmcdEvent::PaymentSucceeded {
-            payment_id: "test_payment_id".to_string(),
+            operation_id: "test_payment_id".to_string(),
             federation_id: "test_federation_id".to_string(),
+            amount_msat: 1000,
             preimage: "test_preimage".to_string(),
-            fee_msat: 100,
-            correlation_id: Some("test_correlation".to_string()),
             timestamp: Utc::now(),
         };
 
@@ -0,0 +1,415 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use fedimint_core::db::{Database, IDatabaseTransactionOpsCoreTyped};
+use futures_util::{future, Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{broadcast, Mutex};
+use tokio::time::interval;
+use tracing::{error, info, warn};
+
+use super::{EventHandler, FmcdEvent};
+use crate::core::multimint::db::{EventJournalKey, EventJournalKeyPrefix, EventJournalRecord};
+
+const JOURNAL_PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const DEFAULT_QUERY_LIMIT: usize = 100;
+const MAX_QUERY_LIMIT: usize = 1000;
+
+/// An event together with its position in the journal
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JournaledEvent {
+    /// Monotonically increasing, never reused even after pruning
+    pub sequence: u64,
+    pub recorded_at: DateTime<Utc>,
+    pub event: FmcdEvent,
+}
+
+/// How long journaled events are kept, whichever limit is hit first
+#[derive(Debug, Clone)]
+pub struct JournalRetention {
+    pub max_age: chrono::Duration,
+    pub max_events: u64,
+}
+
+impl Default for JournalRetention {
+    fn default() -> Self {
+        Self {
+            max_age: chrono::Duration::days(30),
+            max_events: 100_000,
+        }
+    }
+}
+
+/// Filter for reading the journal, events come back in sequence order
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JournalQuery {
+    /// Only events after this sequence number, the cursor of a consumer
+    pub after_sequence: Option<u64>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub event_type: Option<String>,
+    pub federation_id: Option<String>,
+    pub correlation_id: Option<String>,
+    pub limit: Option<usize>,
+}
+
+impl JournalQuery {
+    pub fn matches(&self, entry: &JournaledEvent) -> bool {
+        let timestamp = entry.event.timestamp();
+        self.after_sequence
+            .is_none_or(|after| entry.sequence > after)
+            && self.from.is_none_or(|from| timestamp >= from)
+            && self.to.is_none_or(|to| timestamp <= to)
+            && self
+                .event_type
+                .as_ref()
+                .is_none_or(|t| t == entry.event.event_type())
+            && self
+                .federation_id
+                .as_ref()
+                .is_none_or(|id| entry.event.federation_id() == Some(id))
+            && self
+                .correlation_id
+                .as_ref()
+                .is_none_or(|id| entry.event.correlation_id() == Some(id))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CursorStatus {
+    Valid,
+    /// Events after the cursor are gone, resuming would leave a gap
+    Expired {
+        oldest_sequence: Option<u64>,
+    },
+}
+
+/// A page of journal events
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JournalPage {
+    pub events: Vec<JournaledEvent>,
+    /// Sequence number of the newest event in the journal, 0 if it is empty
+    pub last_sequence: u64,
+}
+
+/// Persistent, sequenced journal of every published event in the multimint
+/// database.
+///
+/// Events are stored as published, including payment preimages and the client
+/// IPs of authentication attempts, and are only removed by the retention
+/// policy.
+///
+/// Registered as a critical handler on the event bus so an event is journaled
+/// before `publish` returns. Journaled events are also broadcast with their
+/// sequence number, consumers that read the journal up to a cursor and then
+/// follow the broadcast see every event exactly once.
+pub struct EventJournal {
+    db: Database,
+    retention: JournalRetention,
+    /// Sequence number of the last appended event, held while appending so
+    /// sequence order matches commit order
+    last_sequence: Mutex<u64>,
+    sender: broadcast::Sender<JournaledEvent>,
+    shutdown_tx: std::sync::Mutex<Option<broadcast::Sender<()>>>,
+}
+
+impl std::fmt::Debug for EventJournal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EventJournal")
+            .field("retention", &self.retention)
+            .finish()
+    }
+}
+
+impl EventJournal {
+    /// Open the journal, continuing the sequence of a previous run
+    pub async fn open(db: Database, retention: JournalRetention) -> Result<Self> {
+        let last_sequence = {
+            let mut dbtx = db.begin_transaction_nc().await;
+            let last = dbtx
+                .find_by_prefix_sorted_descending(&EventJournalKeyPrefix)
+                .await
+                .next()
+                .await;
+            last.map_or(0, |(key, _)| key.sequence)
+        };
+
+        let (sender, _) = broadcast::channel(1000);
+        Ok(Self {
+            db,
+            retention,
+            last_sequence: Mutex::new(last_sequence),
+            sender,
+            shutdown_tx: std::sync::Mutex::new(None),
+        })
+    }
+
+    /// Append an event, returns it with its assigned sequence number
+    pub async fn append(&self, event: FmcdEvent) -> Result<JournaledEvent> {
+        let mut last_sequence = self.last_sequence.lock().await;
+        let entry = JournaledEvent {
+            sequence: *last_sequence + 1,
+            recorded_at: Utc::now(),
+            event,
+        };
+
+        let mut dbtx = self.db.begin_transaction().await;
+        dbtx.insert_entry(
+            &EventJournalKey {
+                sequence: entry.sequence,
+            },
+            &EventJournalRecord {
+                json: serde_json::to_string(&entry)?,
+            },
+        )
+        .await;
+        dbtx.commit_tx_result()
+            .await
+            .map_err(|e| anyhow!("Failed to append event to journal: {:?}", e))?;
+
+        *last_sequence = entry.sequence;
+        // No subscribers is not an error
+        let _ = self.sender.send(entry.clone());
+        Ok(entry)
+    }
+
+    /// Sequence number of the newest journaled event, 0 if none
+    pub async fn last_sequence(&self) -> u64 {
+        *self.last_sequence.lock().await
+    }
+
+    /// Sequence number of the oldest event still retained, if any
+    pub async fn first_sequence(&self) -> Option<u64> {
+        let mut dbtx = self.db.begin_transaction_nc().await;
+        let first = dbtx
+            .find_by_prefix(&EventJournalKeyPrefix)
+            .await
+            .next()
+            .await;
+        first.map(|(key, _)| key.sequence)
+    }
+
+    /// Check whether a consumer can resume after `cursor` without missing
+    /// events, the cursor expires once the events following it are pruned or
+    /// if it was never handed out by this journal
+    pub async fn cursor_status(&self, cursor: u64) -> CursorStatus {
+        let last_sequence = self.last_sequence().await;
+        let first_sequence = self.first_sequence().await;
+
+        let pruned = match first_sequence {
+            Some(first) => cursor.saturating_add(1) < first,
+            None => cursor < last_sequence,
+        };
+        if cursor > last_sequence || pruned {
+            CursorStatus::Expired {
+                oldest_sequence: first_sequence,
+            }
+        } else {
+            CursorStatus::Valid
+        }
+    }
+
+    /// Follow the journal after `cursor`, or from now without one: first the
+    /// journaled events after the cursor, then events as they are appended.
+    /// When the live subscription lags behind, the missed events are read
+    /// back from the journal, so nothing is skipped or sent twice.
+    pub fn follow(
+        self: &Arc<Self>,
+        cursor: Option<u64>,
+    ) -> impl Stream<Item = Result<JournaledEvent>> + Send + 'static {
+        // Subscribe before reading the journal so no event falls between the
+        // replay and the live events
+        let mut receiver = self.subscribe();
+        let journal = self.clone();
+
+        async_stream::try_stream! {
+            let mut cursor = match cursor {
+                Some(cursor) => cursor,
+                None => journal.last_sequence().await,
+            };
+            let mut replay = true;
+
+            loop {
+                // Page by page, so replaying from an old cursor never holds more
+                // than one page in memory
+                while replay {
+                    let page = journal.events_page_after(cursor).await?;
+                    replay = !page.is_empty();
+                    for entry in page {
+                        cursor = entry.sequence;
+                        yield entry;
+                    }
+                }
+
+                match receiver.recv().await {
+                    Ok(entry) if entry.sequence > cursor => {
+                        cursor = entry.sequence;
+                        yield entry;
+                    }
+                    // Already sent by the replay
+                    Ok(_) => {}
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!(skipped = skipped, "Event journal subscriber lagged, replaying");
+                        replay = true;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        }
+    }
+
+    /// The next page of journaled events after the cursor, oldest first, empty
+    /// once the cursor is at the end of the journal
+    pub async fn events_page_after(&self, cursor: u64) -> Result<Vec<JournaledEvent>> {
+        let query = JournalQuery {
+            after_sequence: Some(cursor),
+            limit: Some(MAX_QUERY_LIMIT),
+            ..Default::default()
+        };
+        Ok(self.query(&query).await?.events)
+    }
+
+    /// Subscribe to events as they are journaled
+    pub fn subscribe(&self) -> broadcast::Receiver<JournaledEvent> {
+        self.sender.subscribe()
+    }
+
+    /// Read journaled events matching the query, oldest first
+    pub async fn query(&self, query: &JournalQuery) -> Result<JournalPage> {
+        let limit = query
+            .limit
+            .unwrap_or(DEFAULT_QUERY_LIMIT)
+            .min(MAX_QUERY_LIMIT);
+        let start = query
+            .after_sequence
+            .map_or(0, |after| after.saturating_add(1));
+
+        // Decode and filter as the range is read, so a page only reads up to
+        // its last matching event
+        let mut dbtx = self.db.begin_transaction_nc().await;
+        let events = dbtx
+            .find_by_range(
+                EventJournalKey { sequence: start }..EventJournalKey { sequence: u64::MAX },
+            )
+            .await
+            .filter_map(|(_, record)| future::ready(decode_entry(&record)))
+            .filter(|entry| future::ready(query.matches(entry)))
+            .take(limit)
+            .collect::<Vec<_>>()
+            .await;
+
+        Ok(JournalPage {
+            events,
+            last_sequence: self.last_sequence().await,
+        })
+    }
+
+    /// Drop events older than the retention age and the oldest events beyond
+    /// the retention count. Returns the number of removed events.
+    pub async fn prune(&self) -> Result<usize> {
+        let cutoff = Utc::now() - self.retention.max_age;
+
+        let mut dbtx = self.db.begin_transaction().await;
+        let entries = dbtx
+            .find_by_prefix(&EventJournalKeyPrefix)
+            .await
+            .collect::<Vec<_>>()
+            .await;
+
+        let excess = (entries.len() as u64).saturating_sub(self.retention.max_events);
+        let mut removed = 0;
+        for (index, (key, record)) in entries.iter().enumerate() {
+            let expired = decode_entry(record).is_none_or(|entry| entry.recorded_at < cutoff);
+            if (index as u64) < excess || expired {
+                dbtx.remove_entry(key).await;
+                removed += 1;
+            }
+        }
+        dbtx.commit_tx_result()
+            .await
+            .map_err(|e| anyhow!("Failed to prune event journal: {:?}", e))?;
+
+        Ok(removed)
+    }
+
+    /// Start the background task applying the retention policy
+    pub fn start(self: &Arc<Self>) {
+        let (shutdown_tx, _) = broadcast::channel(1);
+        *self
+            .shutdown_tx
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(shutdown_tx.clone());
+
+        info!(
+            max_age_days = self.retention.max_age.num_days(),
+            max_events = self.retention.max_events,
+            "Starting event journal retention task"
+        );
+
+        let journal = self.clone();
+        tokio::spawn(async move {
+            let mut shutdown_rx = shutdown_tx.subscribe();
+            let mut prune_timer = interval(JOURNAL_PRUNE_INTERVAL);
+
+            loop {
+                tokio::select! {
+                    _ = prune_timer.tick() => {
+                        match journal.prune().await {
+                            Ok(removed) if removed > 0 => {
+                                info!(removed = removed, "Pruned event journal");
+                            }
+                            Ok(_) => {}
+                            Err(e) => error!(error = ?e, "Failed to prune event journal"),
+                        }
+                    }
+                    _ = shutdown_rx.recv() => {
+                        info!("Event journal retention task received shutdown signal");
+                        break;
+                    }
+                }
+            }
+        });
+    }
+
+    /// Stop the retention task
+    pub fn stop(&self) {
+        let tx_guard = self
+            .shutdown_tx
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(shutdown_tx) = tx_guard.as_ref() {
+            let _ = shutdown_tx.send(());
+        }
+    }
+}
+
+#[async_trait]
+impl EventHandler for EventJournal {
+    async fn handle(&self, event: FmcdEvent) -> Result<()> {
+        self.append(event).await.map(|_| ())
+    }
+
+    fn name(&self) -> &str {
+        "event_journal"
+    }
+
+    fn is_critical(&self) -> bool {
+        true
+    }
+}
+
+fn decode_entry(record: &EventJournalRecord) -> Option<JournaledEvent> {
+    match serde_json::from_str::<JournaledEvent>(&record.json) {
+        Ok(entry) => Some(entry),
+        Err(e) => {
+            warn!(error = ?e, "Skipping undecodable event journal entry");
+            None
+        }
+    }
+}
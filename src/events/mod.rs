@@ -3,11 +3,13 @@ use std::sync::Arc;
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use tokio::sync::{broadcast, RwLock};
 use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
 pub mod handlers;
+pub mod journal;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
@@ -84,6 +86,37 @@ pub enum FmcdEvent {
         timestamp: DateTime<Utc>,
     },
 
+    // Restore events
+    RestoreStarted {
+        restore_id: String,
+        federation_ids: Vec<String>,
+        correlation_id: Option<String>,
+        timestamp: DateTime<Utc>,
+    },
+    FederationRecoveryProgress {
+        restore_id: String,
+        federation_id: String,
+        module_id: u16,
+        complete: u32,
+        total: u32,
+        correlation_id: Option<String>,
+        timestamp: DateTime<Utc>,
+    },
+    FederationRecoveryCompleted {
+        restore_id: String,
+        federation_id: String,
+        balance_msat: u64,
+        correlation_id: Option<String>,
+        timestamp: DateTime<Utc>,
+    },
+    FederationRecoveryFailed {
+        restore_id: String,
+        federation_id: String,
+        reason: String,
+        correlation_id: Option<String>,
+        timestamp: DateTime<Utc>,
+    },
+
     // Onchain events
     DepositAddressGenerated {
         operation_id: String,
@@ -149,6 +182,42 @@ pub enum FmcdEvent {
         timestamp: DateTime<Utc>,
     },
 
+    // Policy events
+    PolicyViolation {
+        federation_id: String,
+        spend_kind: String,
+        rule: String,
+        amount_msat: u64,
+        reason: String,
+        correlation_id: Option<String>,
+        timestamp: DateTime<Utc>,
+    },
+
+    // Approval events
+    ApprovalRequested {
+        approval_id: String,
+        federation_id: String,
+        spend_kind: String,
+        amount_msat: u64,
+        destination: String,
+        expires_at: DateTime<Utc>,
+        correlation_id: Option<String>,
+        timestamp: DateTime<Utc>,
+    },
+    ApprovalApproved {
+        approval_id: String,
+        federation_id: String,
+        correlation_id: Option<String>,
+        timestamp: DateTime<Utc>,
+    },
+    ApprovalRejected {
+        approval_id: String,
+        federation_id: String,
+        reason: Option<String>,
+        correlation_id: Option<String>,
+        timestamp: DateTime<Utc>,
+    },
+
     // Database events
     DatabaseQueryExecuted {
         operation: String,
@@ -173,9 +242,13 @@ pub enum FmcdEvent {
 }
 
 impl FmcdEvent {
-    /// Generate a unique event ID
+    /// Id of the event, derived from its content so the journal, webhook
+    /// deliveries and their replays all agree on it
     pub fn event_id(&self) -> String {
-        Uuid::new_v4().to_string()
+        let digest = Sha256::digest(serde_json::to_vec(self).unwrap_or_default());
+        let mut bytes = [0u8; 16];
+        bytes.copy_from_slice(&digest[..16]);
+        Uuid::from_bytes(bytes).to_string()
     }
 
     /// Get the event timestamp
@@ -191,6 +264,10 @@ impl FmcdEvent {
             FmcdEvent::FederationConnected { timestamp, .. } => *timestamp,
             FmcdEvent::FederationDisconnected { timestamp, .. } => *timestamp,
             FmcdEvent::FederationBalanceUpdated { timestamp, .. } => *timestamp,
+            FmcdEvent::RestoreStarted { timestamp, .. } => *timestamp,
+            FmcdEvent::FederationRecoveryProgress { timestamp, .. } => *timestamp,
+            FmcdEvent::FederationRecoveryCompleted { timestamp, .. } => *timestamp,
+            FmcdEvent::FederationRecoveryFailed { timestamp, .. } => *timestamp,
             FmcdEvent::DepositAddressGenerated { timestamp, .. } => *timestamp,
             FmcdEvent::DepositDetected { timestamp, .. } => *timestamp,
             FmcdEvent::DepositClaimed { timestamp, .. } => *timestamp,
@@ -199,6 +276,10 @@ impl FmcdEvent {
             FmcdEvent::WithdrawalFailed { timestamp, .. } => *timestamp,
             FmcdEvent::GatewaySelected { timestamp, .. } => *timestamp,
             FmcdEvent::GatewayUnavailable { timestamp, .. } => *timestamp,
+            FmcdEvent::PolicyViolation { timestamp, .. } => *timestamp,
+            FmcdEvent::ApprovalRequested { timestamp, .. } => *timestamp,
+            FmcdEvent::ApprovalApproved { timestamp, .. } => *timestamp,
+            FmcdEvent::ApprovalRejected { timestamp, .. } => *timestamp,
             FmcdEvent::DatabaseQueryExecuted { timestamp, .. } => *timestamp,
             FmcdEvent::AuthenticationAttempt { timestamp, .. } => *timestamp,
         }
@@ -217,6 +298,12 @@ impl FmcdEvent {
             FmcdEvent::FederationConnected { correlation_id, .. } => correlation_id.as_ref(),
             FmcdEvent::FederationDisconnected { correlation_id, .. } => correlation_id.as_ref(),
             FmcdEvent::FederationBalanceUpdated { correlation_id, .. } => correlation_id.as_ref(),
+            FmcdEvent::RestoreStarted { correlation_id, .. } => correlation_id.as_ref(),
+            FmcdEvent::FederationRecoveryProgress { correlation_id, .. } => correlation_id.as_ref(),
+            FmcdEvent::FederationRecoveryCompleted { correlation_id, .. } => {
+                correlation_id.as_ref()
+            }
+            FmcdEvent::FederationRecoveryFailed { correlation_id, .. } => correlation_id.as_ref(),
             FmcdEvent::DepositAddressGenerated { correlation_id, .. } => correlation_id.as_ref(),
             FmcdEvent::DepositDetected { correlation_id, .. } => correlation_id.as_ref(),
             FmcdEvent::DepositClaimed { correlation_id, .. } => correlation_id.as_ref(),
@@ -225,11 +312,49 @@ impl FmcdEvent {
             FmcdEvent::WithdrawalFailed { correlation_id, .. } => correlation_id.as_ref(),
             FmcdEvent::GatewaySelected { correlation_id, .. } => correlation_id.as_ref(),
             FmcdEvent::GatewayUnavailable { correlation_id, .. } => correlation_id.as_ref(),
+            FmcdEvent::PolicyViolation { correlation_id, .. } => correlation_id.as_ref(),
+            FmcdEvent::ApprovalRequested { correlation_id, .. } => correlation_id.as_ref(),
+            FmcdEvent::ApprovalApproved { correlation_id, .. } => correlation_id.as_ref(),
+            FmcdEvent::ApprovalRejected { correlation_id, .. } => correlation_id.as_ref(),
             FmcdEvent::DatabaseQueryExecuted { correlation_id, .. } => correlation_id.as_ref(),
             FmcdEvent::AuthenticationAttempt { correlation_id, .. } => correlation_id.as_ref(),
         }
     }
 
+    /// Get the federation the event belongs to, if it belongs to a single one
+    pub fn federation_id(&self) -> Option<&String> {
+        match self {
+            FmcdEvent::PaymentInitiated { federation_id, .. } => Some(federation_id),
+            FmcdEvent::PaymentSucceeded { federation_id, .. } => Some(federation_id),
+            FmcdEvent::PaymentRefunded { federation_id, .. } => Some(federation_id),
+            FmcdEvent::PaymentFailed { federation_id, .. } => Some(federation_id),
+            FmcdEvent::InvoiceCreated { federation_id, .. } => Some(federation_id),
+            FmcdEvent::InvoicePaid { federation_id, .. } => Some(federation_id),
+            FmcdEvent::InvoiceExpired { federation_id, .. } => Some(federation_id),
+            FmcdEvent::FederationConnected { federation_id, .. } => Some(federation_id),
+            FmcdEvent::FederationDisconnected { federation_id, .. } => Some(federation_id),
+            FmcdEvent::FederationBalanceUpdated { federation_id, .. } => Some(federation_id),
+            FmcdEvent::RestoreStarted { .. } => None,
+            FmcdEvent::FederationRecoveryProgress { federation_id, .. } => Some(federation_id),
+            FmcdEvent::FederationRecoveryCompleted { federation_id, .. } => Some(federation_id),
+            FmcdEvent::FederationRecoveryFailed { federation_id, .. } => Some(federation_id),
+            FmcdEvent::DepositAddressGenerated { federation_id, .. } => Some(federation_id),
+            FmcdEvent::DepositDetected { federation_id, .. } => Some(federation_id),
+            FmcdEvent::DepositClaimed { federation_id, .. } => Some(federation_id),
+            FmcdEvent::WithdrawalInitiated { federation_id, .. } => Some(federation_id),
+            FmcdEvent::WithdrawalSucceeded { federation_id, .. } => Some(federation_id),
+            FmcdEvent::WithdrawalFailed { federation_id, .. } => Some(federation_id),
+            FmcdEvent::GatewaySelected { federation_id, .. } => Some(federation_id),
+            FmcdEvent::GatewayUnavailable { federation_id, .. } => Some(federation_id),
+            FmcdEvent::PolicyViolation { federation_id, .. } => Some(federation_id),
+            FmcdEvent::ApprovalRequested { federation_id, .. } => Some(federation_id),
+            FmcdEvent::ApprovalApproved { federation_id, .. } => Some(federation_id),
+            FmcdEvent::ApprovalRejected { federation_id, .. } => Some(federation_id),
+            FmcdEvent::DatabaseQueryExecuted { .. } => None,
+            FmcdEvent::AuthenticationAttempt { .. } => None,
+        }
+    }
+
     /// Get the event type as a string
     pub fn event_type(&self) -> &'static str {
         match self {
@@ -243,6 +368,10 @@ impl FmcdEvent {
             FmcdEvent::FederationConnected { .. } => "federation_connected",
             FmcdEvent::FederationDisconnected { .. } => "federation_disconnected",
             FmcdEvent::FederationBalanceUpdated { .. } => "federation_balance_updated",
+            FmcdEvent::RestoreStarted { .. } => "restore_started",
+            FmcdEvent::FederationRecoveryProgress { .. } => "federation_recovery_progress",
+            FmcdEvent::FederationRecoveryCompleted { .. } => "federation_recovery_completed",
+            FmcdEvent::FederationRecoveryFailed { .. } => "federation_recovery_failed",
             FmcdEvent::DepositAddressGenerated { .. } => "deposit_address_generated",
             FmcdEvent::DepositDetected { .. } => "deposit_detected",
             FmcdEvent::DepositClaimed { .. } => "deposit_claimed",
@@ -251,6 +380,10 @@ impl FmcdEvent {
             FmcdEvent::WithdrawalFailed { .. } => "withdrawal_failed",
             FmcdEvent::GatewaySelected { .. } => "gateway_selected",
             FmcdEvent::GatewayUnavailable { .. } => "gateway_unavailable",
+            FmcdEvent::PolicyViolation { .. } => "policy_violation",
+            FmcdEvent::ApprovalRequested { .. } => "approval_requested",
+            FmcdEvent::ApprovalApproved { .. } => "approval_approved",
+            FmcdEvent::ApprovalRejected { .. } => "approval_rejected",
             FmcdEvent::DatabaseQueryExecuted { .. } => "database_query_executed",
             FmcdEvent::AuthenticationAttempt { .. } => "authentication_attempt",
         }
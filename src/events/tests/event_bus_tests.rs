@@ -111,11 +111,10 @@ mod tests {
         event_bus.register_handler(handler2).await;
 
         let event = FmcdEvent::PaymentSucceeded {
-            payment_id: "test_payment_id".to_string(),
+            operation_id: "test_payment_id".to_string(),
             federation_id: "test_federation_id".to_string(),
+            amount_msat: 1000,
             preimage: "test_preimage".to_string(),
-            fee_msat: 100,
-            correlation_id: Some("test_correlation_id".to_string()),
             timestamp: Utc::now(),
         };
 
@@ -203,7 +202,7 @@ mod tests {
             FmcdEvent::InvoiceCreated { invoice_id, .. } => {
                 assert_eq!(invoice_id, "test_invoice_id");
             }
-            _ => assert!(false, "Expected InvoiceCreated event"),
+            _ => panic!("Expected InvoiceCreated event"),
         }
     }
 
@@ -0,0 +1,211 @@
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::sync::Arc;
+
+    use chrono::Utc;
+    use futures_util::StreamExt;
+    use tempfile::TempDir;
+
+    use crate::core::tests::open_db;
+    use crate::events::journal::*;
+    use crate::events::FmcdEvent;
+
+    fn invoice_paid(federation_id: &str, correlation_id: Option<&str>) -> FmcdEvent {
+        FmcdEvent::InvoicePaid {
+            operation_id: "op-1".to_string(),
+            federation_id: federation_id.to_string(),
+            amount_msat: 1000,
+            correlation_id: correlation_id.map(str::to_string),
+            timestamp: Utc::now(),
+        }
+    }
+
+    fn balance_updated(federation_id: &str) -> FmcdEvent {
+        FmcdEvent::FederationBalanceUpdated {
+            federation_id: federation_id.to_string(),
+            balance_msat: 5000,
+            correlation_id: None,
+            timestamp: Utc::now(),
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_sequence_continues_after_reopen() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = open_db(&temp_dir).await;
+
+        let journal = EventJournal::open(db.clone(), JournalRetention::default())
+            .await
+            .unwrap();
+        assert_eq!(
+            journal
+                .append(balance_updated("fed-a"))
+                .await
+                .unwrap()
+                .sequence,
+            1
+        );
+        assert_eq!(
+            journal
+                .append(balance_updated("fed-a"))
+                .await
+                .unwrap()
+                .sequence,
+            2
+        );
+        drop(journal);
+
+        let journal = EventJournal::open(db, JournalRetention::default())
+            .await
+            .unwrap();
+        assert_eq!(journal.last_sequence().await, 2);
+        assert_eq!(
+            journal
+                .append(balance_updated("fed-a"))
+                .await
+                .unwrap()
+                .sequence,
+            3
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_query_filters_and_cursor() {
+        let temp_dir = TempDir::new().unwrap();
+        let journal = EventJournal::open(open_db(&temp_dir).await, JournalRetention::default())
+            .await
+            .unwrap();
+
+        journal
+            .append(invoice_paid("fed-a", Some("req-1")))
+            .await
+            .unwrap();
+        journal.append(balance_updated("fed-b")).await.unwrap();
+        journal
+            .append(invoice_paid("fed-b", Some("req-2")))
+            .await
+            .unwrap();
+
+        let page = journal
+            .query(&JournalQuery {
+                after_sequence: Some(1),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(page.last_sequence, 3);
+        let sequences = page.events.iter().map(|e| e.sequence).collect::<Vec<_>>();
+        assert_eq!(sequences, vec![2, 3]);
+
+        let page = journal
+            .query(&JournalQuery {
+                event_type: Some("invoice_paid".to_string()),
+                federation_id: Some("fed-b".to_string()),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(page.events.len(), 1);
+        assert_eq!(page.events[0].sequence, 3);
+
+        let page = journal
+            .query(&JournalQuery {
+                correlation_id: Some("req-1".to_string()),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(page.events.len(), 1);
+        assert_eq!(page.events[0].sequence, 1);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_prune_keeps_newest_events_and_sequence() {
+        let temp_dir = TempDir::new().unwrap();
+        let retention = JournalRetention {
+            max_events: 2,
+            ..Default::default()
+        };
+        let journal = EventJournal::open(open_db(&temp_dir).await, retention)
+            .await
+            .unwrap();
+
+        for _ in 0..5 {
+            journal.append(balance_updated("fed-a")).await.unwrap();
+        }
+        assert_eq!(journal.prune().await.unwrap(), 3);
+
+        let page = journal.query(&JournalQuery::default()).await.unwrap();
+        let sequences = page.events.iter().map(|e| e.sequence).collect::<Vec<_>>();
+        assert_eq!(sequences, vec![4, 5]);
+        assert_eq!(
+            journal
+                .append(balance_updated("fed-a"))
+                .await
+                .unwrap()
+                .sequence,
+            6
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_cursor_expires_once_events_are_pruned() {
+        let temp_dir = TempDir::new().unwrap();
+        let retention = JournalRetention {
+            max_events: 2,
+            ..Default::default()
+        };
+        let journal = EventJournal::open(open_db(&temp_dir).await, retention)
+            .await
+            .unwrap();
+
+        for _ in 0..4 {
+            journal.append(balance_updated("fed-a")).await.unwrap();
+        }
+        assert_eq!(journal.cursor_status(1).await, CursorStatus::Valid);
+
+        journal.prune().await.unwrap();
+        assert_eq!(journal.cursor_status(2).await, CursorStatus::Valid);
+        assert_eq!(journal.cursor_status(4).await, CursorStatus::Valid);
+        assert_eq!(
+            journal.cursor_status(1).await,
+            CursorStatus::Expired {
+                oldest_sequence: Some(3)
+            }
+        );
+        assert_eq!(
+            journal.cursor_status(9).await,
+            CursorStatus::Expired {
+                oldest_sequence: Some(3)
+            }
+        );
+
+        let missed = journal.events_page_after(2).await.unwrap();
+        let sequences = missed.iter().map(|e| e.sequence).collect::<Vec<_>>();
+        assert_eq!(sequences, vec![3, 4]);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_follow_replays_more_than_one_page() {
+        let temp_dir = TempDir::new().unwrap();
+        let journal = Arc::new(
+            EventJournal::open(open_db(&temp_dir).await, JournalRetention::default())
+                .await
+                .unwrap(),
+        );
+
+        for _ in 0..1_005 {
+            journal.append(balance_updated("fed-a")).await.unwrap();
+        }
+
+        let replayed = journal
+            .follow(Some(0))
+            .take(1_005)
+            .map(|entry| entry.unwrap().sequence)
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(replayed, (1..=1_005).collect::<Vec<_>>());
+        assert!(journal.events_page_after(1_005).await.unwrap().is_empty());
+    }
+}
@@ -1 +1,2 @@
 mod event_bus_tests;
+mod journal_tests;
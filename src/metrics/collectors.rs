@@ -1,5 +1,4 @@
 use std::sync::Arc;
-use std::time::Instant;
 
 use async_trait::async_trait;
 use lazy_static::lazy_static;
@@ -31,6 +30,9 @@ pub const DATABASE_QUERIES_TOTAL: &str = "fmcd_database_queries_total";
 pub const DATABASE_QUERY_DURATION_SECONDS: &str = "fmcd_database_query_duration_seconds";
 
 pub const AUTH_ATTEMPTS_TOTAL: &str = "fmcd_auth_attempts_total";
+pub const AUTH_LOCKOUTS_TOTAL: &str = "fmcd_auth_lockouts_total";
+pub const AUTH_BLOCKED_REQUESTS_TOTAL: &str = "fmcd_auth_blocked_requests_total";
+pub const RATE_LIMITED_REQUESTS_TOTAL: &str = "fmcd_rate_limited_requests_total";
 
 pub const WEBHOOK_DELIVERIES_TOTAL: &str = "fmcd_webhook_deliveries_total";
 pub const WEBHOOK_DELIVERY_DURATION_SECONDS: &str = "fmcd_webhook_delivery_duration_seconds";
@@ -69,15 +71,11 @@ pub async fn get_prometheus_handle() -> Option<PrometheusHandle> {
 }
 
 /// Metrics collector that implements the EventHandler trait
-pub struct MetricsCollector {
-    start_time: Instant,
-}
+pub struct MetricsCollector;
 
 impl MetricsCollector {
     pub fn new() -> Self {
-        Self {
-            start_time: Instant::now(),
-        }
+        Self
     }
 
     /// Record payment metrics from payment events
@@ -273,61 +271,57 @@ impl MetricsCollector {
 
     /// Record database metrics from database events
     fn record_database_metrics(&self, event: &FmcdEvent) {
-        match event {
-            FmcdEvent::DatabaseQueryExecuted {
-                operation,
-                duration_ms,
-                success,
-                ..
-            } => {
-                let status = if *success { "success" } else { "error" };
-
-                counter!(
-                    DATABASE_QUERIES_TOTAL,
-                    "operation" => operation.clone(),
-                    "status" => status
-                )
-                .increment(1);
-
-                histogram!(
-                    DATABASE_QUERY_DURATION_SECONDS,
-                    "operation" => operation.clone()
-                )
-                .record(*duration_ms as f64 / 1000.0);
-
-                debug!(
-                    operation = %operation,
-                    duration_ms = duration_ms,
-                    success = success,
-                    "Recorded database query metrics"
-                );
-            }
-            _ => {}
+        if let FmcdEvent::DatabaseQueryExecuted {
+            operation,
+            duration_ms,
+            success,
+            ..
+        } = event
+        {
+            let status = if *success { "success" } else { "error" };
+
+            counter!(
+                DATABASE_QUERIES_TOTAL,
+                "operation" => operation.clone(),
+                "status" => status
+            )
+            .increment(1);
+
+            histogram!(
+                DATABASE_QUERY_DURATION_SECONDS,
+                "operation" => operation.clone()
+            )
+            .record(*duration_ms as f64 / 1000.0);
+
+            debug!(
+                operation = %operation,
+                duration_ms = duration_ms,
+                success = success,
+                "Recorded database query metrics"
+            );
         }
     }
 
     /// Record authentication metrics from auth events
     fn record_auth_metrics(&self, event: &FmcdEvent) {
-        match event {
-            FmcdEvent::AuthenticationAttempt {
-                success, endpoint, ..
-            } => {
-                let status = if *success { "success" } else { "failure" };
-
-                counter!(
-                    AUTH_ATTEMPTS_TOTAL,
-                    "endpoint" => endpoint.clone(),
-                    "status" => status
-                )
-                .increment(1);
-
-                debug!(
-                    endpoint = %endpoint,
-                    success = success,
-                    "Recorded authentication attempt metrics"
-                );
-            }
-            _ => {}
+        if let FmcdEvent::AuthenticationAttempt {
+            success, endpoint, ..
+        } = event
+        {
+            let status = if *success { "success" } else { "failure" };
+
+            counter!(
+                AUTH_ATTEMPTS_TOTAL,
+                "endpoint" => endpoint.clone(),
+                "status" => status
+            )
+            .increment(1);
+
+            debug!(
+                endpoint = %endpoint,
+                success = success,
+                "Recorded authentication attempt metrics"
+            );
         }
     }
 
@@ -418,6 +412,40 @@ pub mod api_metrics {
     }
 }
 
+/// Utility functions for recording brute-force protection metrics
+pub mod auth_metrics {
+    use super::*;
+
+    /// Record a client being locked out, by `ip` or `credential`
+    pub fn record_lockout(kind: &str) {
+        counter!(AUTH_LOCKOUTS_TOTAL, "kind" => kind.to_string()).increment(1);
+    }
+
+    /// Record a request refused before authenticating it
+    pub fn record_blocked_request(reason: &str) {
+        counter!(AUTH_BLOCKED_REQUESTS_TOTAL, "reason" => reason.to_string()).increment(1);
+
+        debug!(reason = %reason, "Recorded blocked request metrics");
+    }
+}
+
+/// Utility functions for recording rate limiting metrics
+pub mod rate_limit_metrics {
+    use super::*;
+
+    /// Record a request refused by the rate limiter
+    pub fn record_limited(class: &str, reason: &str) {
+        counter!(
+            RATE_LIMITED_REQUESTS_TOTAL,
+            "class" => class.to_string(),
+            "reason" => reason.to_string()
+        )
+        .increment(1);
+
+        debug!(class = %class, reason = %reason, "Recorded rate limited request metrics");
+    }
+}
+
 /// Utility functions for recording webhook metrics
 pub mod webhook_metrics {
     use std::time::Duration;
@@ -1,7 +1,3 @@
-use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
-use std::time::{Duration, Instant};
-
 use axum::extract::Request;
 use axum::http::{HeaderValue, StatusCode};
 use axum::middleware::Next;
@@ -13,43 +9,69 @@ use uuid::Uuid;
 pub const CORRELATION_ID_HEADER: &str = "X-Correlation-Id";
 pub const REQUEST_ID_HEADER: &str = "X-Request-Id";
 
-/// Configuration for correlation ID rate limiting
+/// Configuration for correlation ID validation and per-client rate limiting
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct RateLimitConfig {
     /// Maximum length allowed for correlation IDs
     pub max_correlation_id_length: usize,
-    /// Maximum requests per correlation ID per time window
-    pub max_requests_per_correlation_id: usize,
-    /// Time window for rate limiting in seconds
-    pub rate_limit_window_secs: u64,
     /// Enable rate limiting (can be disabled for testing/debugging)
     pub enabled: bool,
+    /// Budget of routes that don't move funds, per API identity and per IP
+    pub read: TokenBucketConfig,
+    /// Budget of payments, withdrawals and ecash spends, per API identity and
+    /// per IP
+    pub spend: TokenBucketConfig,
+    /// Payments, withdrawals and ecash spends running at once, across all
+    /// clients
+    pub max_in_flight_spends: usize,
+    /// Clients tracked at most, idle ones are forgotten first beyond it
+    pub max_tracked_clients: usize,
+}
+
+/// A token bucket refilled at `requests_per_sec`, holding at most `burst`
+/// requests
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct TokenBucketConfig {
+    pub requests_per_sec: f64,
+    pub burst: u32,
 }
 
 impl Default for RateLimitConfig {
     fn default() -> Self {
         Self {
             max_correlation_id_length: 200,
-            max_requests_per_correlation_id: 100,
-            rate_limit_window_secs: 60,
             enabled: true,
+            read: TokenBucketConfig {
+                requests_per_sec: 20.0,
+                burst: 100,
+            },
+            spend: TokenBucketConfig {
+                requests_per_sec: 1.0,
+                burst: 10,
+            },
+            max_in_flight_spends: 32,
+            max_tracked_clients: 10_000,
         }
     }
 }
 
 impl RateLimitConfig {
-    /// Get the rate limit window as a Duration
-    pub fn rate_limit_window(&self) -> Duration {
-        Duration::from_secs(self.rate_limit_window_secs)
-    }
-
     /// Create a permissive config for testing environments
     pub fn permissive() -> Self {
         Self {
             max_correlation_id_length: 500,
-            max_requests_per_correlation_id: 10000,
-            rate_limit_window_secs: 1,
             enabled: false,
+            read: TokenBucketConfig {
+                requests_per_sec: 10_000.0,
+                burst: 10_000,
+            },
+            spend: TokenBucketConfig {
+                requests_per_sec: 10_000.0,
+                burst: 10_000,
+            },
+            max_in_flight_spends: 10_000,
+            max_tracked_clients: 10_000,
         }
     }
 
@@ -57,23 +79,21 @@ impl RateLimitConfig {
     pub fn strict() -> Self {
         Self {
             max_correlation_id_length: 100,
-            max_requests_per_correlation_id: 50,
-            rate_limit_window_secs: 60,
             enabled: true,
+            read: TokenBucketConfig {
+                requests_per_sec: 5.0,
+                burst: 50,
+            },
+            spend: TokenBucketConfig {
+                requests_per_sec: 0.2,
+                burst: 5,
+            },
+            max_in_flight_spends: 8,
+            max_tracked_clients: 10_000,
         }
     }
 }
 
-#[derive(Debug, Clone)]
-struct RateLimitEntry {
-    count: usize,
-    window_start: Instant,
-}
-
-// Simple in-memory rate limiter for correlation IDs
-static CORRELATION_RATE_LIMITER: std::sync::OnceLock<Arc<Mutex<HashMap<String, RateLimitEntry>>>> =
-    std::sync::OnceLock::new();
-
 #[derive(Debug, Clone)]
 pub struct RequestContext {
     pub correlation_id: String,
@@ -113,56 +133,6 @@ fn validate_correlation_id(
     Ok(())
 }
 
-/// Check rate limit for correlation ID
-fn check_rate_limit(correlation_id: &str, config: &RateLimitConfig) -> Result<(), &'static str> {
-    // Skip rate limiting if disabled
-    if !config.enabled {
-        return Ok(());
-    }
-
-    let rate_limiter =
-        CORRELATION_RATE_LIMITER.get_or_init(|| Arc::new(Mutex::new(HashMap::new())));
-
-    let mut limiter = match rate_limiter.lock() {
-        Ok(limiter) => limiter,
-        Err(_) => {
-            // If mutex is poisoned, we still reject to be safe
-            return Err("Rate limiter unavailable");
-        }
-    };
-    let now = Instant::now();
-    let rate_limit_window = config.rate_limit_window();
-
-    // Clean up expired entries
-    limiter.retain(|_, entry| now.duration_since(entry.window_start) < rate_limit_window);
-
-    match limiter.get_mut(correlation_id) {
-        Some(entry) => {
-            if now.duration_since(entry.window_start) >= rate_limit_window {
-                // Reset window
-                entry.count = 1;
-                entry.window_start = now;
-                Ok(())
-            } else if entry.count >= config.max_requests_per_correlation_id {
-                Err("Rate limit exceeded for correlation ID")
-            } else {
-                entry.count += 1;
-                Ok(())
-            }
-        }
-        None => {
-            limiter.insert(
-                correlation_id.to_string(),
-                RateLimitEntry {
-                    count: 1,
-                    window_start: now,
-                },
-            );
-            Ok(())
-        }
-    }
-}
-
 pub fn create_request_id_middleware(
     config: RateLimitConfig,
 ) -> impl Fn(
@@ -206,16 +176,6 @@ async fn request_id_middleware_impl(
                 return Err(StatusCode::BAD_REQUEST);
             }
 
-            // Check rate limit
-            if let Err(reason) = check_rate_limit(&id, &config) {
-                warn!(
-                    correlation_id = %id,
-                    reason = %reason,
-                    "Correlation ID rate limit exceeded"
-                );
-                return Err(StatusCode::TOO_MANY_REQUESTS);
-            }
-
             Some(id)
         }
         None => None,
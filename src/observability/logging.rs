@@ -1,11 +1,16 @@
 use std::fs::{self, Permissions};
 use std::os::unix::fs::PermissionsExt;
 use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
 use tracing_appender::rolling::{RollingFileAppender, Rotation};
 use tracing_subscriber::prelude::*;
 use tracing_subscriber::{fmt, EnvFilter, Registry};
 
+/// How often log file permissions are re-applied, catching rotated files
+const PERMISSION_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
 pub struct LoggingConfig {
     pub level: String,
     pub console_output: bool,
@@ -54,8 +59,12 @@ pub fn init_logging(config: LoggingConfig) -> anyhow::Result<()> {
     // Apply layers based on what's enabled
     match (config.console_output, config.file_output) {
         (true, true) => {
-            let file_appender =
-                RollingFileAppender::new(config.rotation, &config.log_dir, "fmcd.log");
+            let file_appender = Mutex::new(SecureFileAppender::new(
+                config.rotation,
+                config.log_dir.clone(),
+                "fmcd.log",
+                config.file_permissions,
+            ));
 
             let file_layer = fmt::layer()
                 .json()
@@ -79,8 +88,12 @@ pub fn init_logging(config: LoggingConfig) -> anyhow::Result<()> {
             subscriber.with(console_layer).init();
         }
         (false, true) => {
-            let file_appender =
-                RollingFileAppender::new(config.rotation, &config.log_dir, "fmcd.log");
+            let file_appender = Mutex::new(SecureFileAppender::new(
+                config.rotation,
+                config.log_dir.clone(),
+                "fmcd.log",
+                config.file_permissions,
+            ));
 
             let file_layer = fmt::layer()
                 .json()
@@ -108,7 +121,7 @@ fn cleanup_old_log_files(log_dir: &PathBuf, max_files: usize) -> anyhow::Result<
             let path = entry.path();
 
             // Only process .log files
-            if path.is_file() && path.extension().map_or(false, |ext| ext == "log") {
+            if path.is_file() && path.extension().is_some_and(|ext| ext == "log") {
                 let metadata = entry.metadata().ok()?;
                 let modified = metadata.modified().ok()?;
                 Some((path, modified))
@@ -119,7 +132,7 @@ fn cleanup_old_log_files(log_dir: &PathBuf, max_files: usize) -> anyhow::Result<
         .collect();
 
     // Sort by modification time, newest first
-    log_files.sort_by(|a, b| b.1.cmp(&a.1));
+    log_files.sort_by_key(|(_, modified)| std::cmp::Reverse(*modified));
 
     // Remove files beyond the retention limit
     if log_files.len() > max_files {
@@ -145,6 +158,9 @@ struct SecureFileAppender {
     inner: RollingFileAppender,
     permissions: u32,
     log_dir: PathBuf,
+    /// Last time permissions were checked, so rotated files are caught
+    /// without scanning the directory on every write
+    last_checked: Option<Instant>,
 }
 
 impl SecureFileAppender {
@@ -159,6 +175,7 @@ impl SecureFileAppender {
             inner,
             permissions,
             log_dir: directory,
+            last_checked: None,
         }
     }
 
@@ -168,7 +185,7 @@ impl SecureFileAppender {
         if let Ok(entries) = fs::read_dir(&self.log_dir) {
             for entry in entries.flatten() {
                 let path = entry.path();
-                if path.is_file() && path.extension().map_or(false, |ext| ext == "log") {
+                if path.is_file() && path.extension().is_some_and(|ext| ext == "log") {
                     if let Err(e) = set_file_permissions(&path, self.permissions) {
                         eprintln!("Failed to set permissions on log file {:?}: {}", path, e);
                     }
@@ -183,7 +200,13 @@ impl std::io::Write for SecureFileAppender {
     fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
         let result = self.inner.write(buf);
         // Try to ensure permissions after write (best effort)
-        let _ = self.ensure_file_permissions();
+        let due = self
+            .last_checked
+            .is_none_or(|checked| checked.elapsed() >= PERMISSION_CHECK_INTERVAL);
+        if due {
+            self.last_checked = Some(Instant::now());
+            let _ = self.ensure_file_permissions();
+        }
         result
     }
 
@@ -8,6 +8,8 @@ mod tests;
 #[cfg(test)]
 pub mod test_integration;
 
-pub use correlation::{create_request_id_middleware, RateLimitConfig, RequestContext};
+pub use correlation::{
+    create_request_id_middleware, RateLimitConfig, RequestContext, TokenBucketConfig,
+};
 pub use logging::{init_logging, LoggingConfig};
-pub use sanitization::{sanitize_invoice, sanitize_preimage};
+pub use sanitization::{sanitize_invoice, sanitize_json, sanitize_preimage};
@@ -1,5 +1,6 @@
 use std::fmt;
 
+use serde_json::Value;
 use tracing::warn;
 
 /// Configuration for sensitive data sanitization
@@ -151,6 +152,56 @@ pub fn sanitize_payment_hash<T: fmt::Display>(hash: T) -> SensitiveData<T> {
     SensitiveData::new(hash, SensitiveDataType::PaymentHash)
 }
 
+/// Sensitive data type of a JSON field, by its name
+fn sensitive_field(name: &str) -> Option<SensitiveDataType> {
+    match name.to_ascii_lowercase().as_str() {
+        "invoice" | "paymentinfo" | "paymentrequest" | "bolt11" => {
+            Some(SensitiveDataType::LightningInvoice)
+        }
+        "preimage" => Some(SensitiveDataType::PaymentPreimage),
+        "notes" | "oobnotes" => Some(SensitiveDataType::UserToken),
+        "mnemonic" | "words" | "secret" | "manualsecret" | "privatekey" | "password" | "key"
+        | "apikey" | "token" => Some(SensitiveDataType::PrivateKey),
+        _ => None,
+    }
+}
+
+/// Copy of a JSON request with sensitive fields redacted, for storing or
+/// logging it. Secrets are redacted entirely, invoices, preimages and notes
+/// keep their first and last characters.
+pub fn sanitize_json(value: &Value) -> Value {
+    match value {
+        Value::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(name, value)| {
+                    let value = match sensitive_field(name) {
+                        Some(data_type) => sanitize_json_value(value, data_type),
+                        None => sanitize_json(value),
+                    };
+                    (name.clone(), value)
+                })
+                .collect(),
+        ),
+        Value::Array(values) => Value::Array(values.iter().map(sanitize_json).collect()),
+        value => value.clone(),
+    }
+}
+
+fn sanitize_json_value(value: &Value, data_type: SensitiveDataType) -> Value {
+    match value {
+        Value::Null => Value::Null,
+        // Partial redaction slices by bytes, so only ASCII is shown in part
+        Value::String(s) if s.is_ascii() && !matches!(data_type, SensitiveDataType::PrivateKey) => {
+            Value::String(SensitiveData::new(s, data_type).to_string())
+        }
+        _ => Value::String(format!(
+            "[REDACTED_{}]",
+            data_type.display_name().to_uppercase()
+        )),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -162,7 +213,7 @@ mod tests {
         let result = sanitized.to_string();
 
         assert!(result.contains("lnbc1u"));
-        assert!(result.contains("xqzpua"));
+        assert!(result.ends_with("lgmg8d"));
         assert!(result.contains("[REDACTED_INVOICE_"));
         assert!(!result.contains(
             "qqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqypqdq5xysxxatsyp3k7enxv4jsxqzpu"
@@ -200,6 +251,28 @@ mod tests {
         assert_eq!(result, hash);
     }
 
+    #[test]
+    fn test_json_sanitization() {
+        let request = serde_json::json!({
+            "paymentInfo": "lnbc1u1p3xnhl2pp5qqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqypqdq5xysxxatsyp3k7enxv4jsxqzpuaxtlgmg8d",
+            "mnemonic": "abandon ability able about above absent absorb abstract absurd abuse access accident",
+            "webhook": { "url": "https://example.com", "secret": "s3cr3t" },
+            "notes": null,
+            "amountMsat": 1000,
+        });
+        let sanitized = sanitize_json(&request);
+
+        assert!(sanitized["paymentInfo"]
+            .as_str()
+            .unwrap_or_default()
+            .contains("[REDACTED_INVOICE_"));
+        assert_eq!(sanitized["mnemonic"], "[REDACTED_PRIVATE_KEY]");
+        assert_eq!(sanitized["webhook"]["secret"], "[REDACTED_PRIVATE_KEY]");
+        assert_eq!(sanitized["webhook"]["url"], "https://example.com");
+        assert!(sanitized["notes"].is_null());
+        assert_eq!(sanitized["amountMsat"], 1000);
+    }
+
     #[test]
     fn test_private_key_always_sanitized() {
         let config = SanitizationConfig {
@@ -3,8 +3,10 @@ mod tests {
     use axum::body::Body;
     use axum::extract::Request;
     use axum::http::{Method, StatusCode};
-    use axum::middleware::Next;
+    use axum::middleware::from_fn;
     use axum::response::Response;
+    use axum::routing::any;
+    use axum::Router;
     use tower::ServiceExt;
 
     use crate::observability::correlation::{
@@ -22,20 +24,25 @@ mod tests {
         Ok(Response::new(Body::empty()))
     }
 
+    async fn run_middleware(request: Request) -> Response {
+        let app = Router::new()
+            .route("/*path", any(mock_next))
+            .layer(from_fn(request_id_middleware));
+        app.oneshot(request)
+            .await
+            .expect("Router should be infallible")
+    }
+
     #[tokio::test]
     async fn test_request_id_middleware_with_correlation_id() {
-        let mut request = Request::builder()
+        let request = Request::builder()
             .method(Method::GET)
             .uri("/test")
             .header(CORRELATION_ID_HEADER, "test-correlation-123")
             .body(Body::empty())
             .expect("Failed to build test request");
 
-        let next = Next::new(mock_next);
-        let result = request_id_middleware(request, next).await;
-
-        assert!(result.is_ok());
-        let response = result.expect("Middleware should succeed");
+        let response = run_middleware(request).await;
         assert_eq!(response.status(), StatusCode::OK);
 
         // Check headers are set
@@ -60,11 +67,7 @@ mod tests {
             .body(Body::empty())
             .expect("Failed to build test request");
 
-        let next = Next::new(mock_next);
-        let result = request_id_middleware(request, next).await;
-
-        assert!(result.is_ok());
-        let response = result.expect("Middleware should succeed");
+        let response = run_middleware(request).await;
         assert_eq!(response.status(), StatusCode::OK);
 
         // Both headers should be present
@@ -92,11 +95,8 @@ mod tests {
             .body(Body::empty())
             .expect("Failed to build test request");
 
-        let next = Next::new(mock_next);
-        let result = request_id_middleware(request, next).await;
-
-        assert!(result.is_err());
-        assert_eq!(result.unwrap_err(), StatusCode::BAD_REQUEST);
+        let response = run_middleware(request).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
     }
 
     #[tokio::test]
@@ -109,10 +109,7 @@ mod tests {
             .body(Body::empty())
             .expect("Failed to build test request");
 
-        let next = Next::new(mock_next);
-        let result = request_id_middleware(request, next).await;
-
-        assert!(result.is_err());
-        assert_eq!(result.unwrap_err(), StatusCode::BAD_REQUEST);
+        let response = run_middleware(request).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
     }
 }
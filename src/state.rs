@@ -6,26 +6,52 @@ use anyhow::Result;
 use fedimint_client::ClientHandleArc;
 use fedimint_core::config::{FederationId, FederationIdPrefix};
 
+use crate::api::rate_limit::RateLimiter;
+use crate::auth::{ApiKeyStore, AuthGuard, AuthProtectionConfig};
 use crate::core::multimint::MultiMint;
 use crate::core::services::{BalanceMonitor, DepositMonitor, PaymentLifecycleManager};
 use crate::core::FmcdCore;
 use crate::error::AppError;
 use crate::events::EventBus;
+use crate::observability::RateLimitConfig;
 use crate::webhooks::WebhookConfig;
 
 #[cfg(test)]
 #[path = "state_tests.rs"]
-mod tests;
+mod state_tests;
 #[derive(Clone)]
 pub struct AppState {
     pub core: Arc<FmcdCore>,
+    pub api_keys: Arc<ApiKeyStore>,
+    pub auth_guard: Arc<AuthGuard>,
+    pub rate_limiter: Arc<RateLimiter>,
 }
 
 impl AppState {
     /// Create AppState with FmcdCore (preferred method)
     pub async fn new_with_core(core: FmcdCore) -> Result<Self> {
+        let api_keys = Arc::new(ApiKeyStore::load(core.multimint.db().clone()).await?);
+        let auth_guard = Arc::new(AuthGuard::new(AuthProtectionConfig::default())?);
+        let rate_limiter = Arc::new(RateLimiter::new(RateLimitConfig::default()));
         let core = Arc::new(core);
-        Ok(Self { core })
+        Ok(Self {
+            core,
+            api_keys,
+            auth_guard,
+            rate_limiter,
+        })
+    }
+
+    /// Replace the default brute-force protection settings
+    pub fn with_auth_protection(mut self, config: AuthProtectionConfig) -> Result<Self> {
+        self.auth_guard = Arc::new(AuthGuard::new(config)?);
+        Ok(self)
+    }
+
+    /// Replace the default per-client rate limits
+    pub fn with_rate_limiting(mut self, config: RateLimitConfig) -> Self {
+        self.rate_limiter = Arc::new(RateLimiter::new(config));
+        self
     }
 
     pub async fn new(fm_db_path: PathBuf) -> Result<Self> {
@@ -87,7 +113,7 @@ impl AppState {
 
     // Convenience accessors for backward compatibility
     pub fn multimint(&self) -> &MultiMint {
-        &*self.core.multimint
+        &self.core.multimint
     }
 
     pub fn start_time(&self) -> Instant {
@@ -1,8 +1,12 @@
-use std::time::Duration;
+use std::sync::Arc;
 
 use chrono::{DateTime, Utc};
+use fedimint_core::config::FederationId;
+use fedimint_core::core::OperationId;
 use serde::Serialize;
-use tracing::{error, info, warn};
+use tracing::error;
+
+use super::notifier::WebhookNotifier;
 
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -15,104 +19,82 @@ pub struct InvoiceWebhookEvent {
     pub data: serde_json::Value,
 }
 
-/// Send webhook notification for invoice events
-/// Uses exponential backoff with retries for reliability
-pub async fn send_invoice_webhook(
-    webhook_url: &str,
-    event: &InvoiceWebhookEvent,
-) -> anyhow::Result<()> {
-    const MAX_RETRIES: u32 = 3;
-    const BASE_DELAY: Duration = Duration::from_millis(100);
-    const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
-
-    let client = reqwest::Client::builder()
-        .timeout(REQUEST_TIMEOUT)
-        .build()?;
-
-    for attempt in 0..MAX_RETRIES {
-        // Use bit shifting for efficient exponential backoff: 2^n = 1 << n
-        let delay = BASE_DELAY * (1 << attempt);
-
-        if attempt > 0 {
-            info!(
-                webhook_url = %webhook_url,
-                attempt = attempt + 1,
-                delay_ms = delay.as_millis(),
-                "Retrying webhook request after delay"
-            );
-            tokio::time::sleep(delay).await;
-        }
-
-        match send_webhook_request(&client, webhook_url, event).await {
-            Ok(()) => {
-                info!(
-                    webhook_url = %webhook_url,
-                    event_type = %event.event_type,
-                    operation_id = %event.operation_id,
-                    attempt = attempt + 1,
-                    "Webhook sent successfully"
-                );
-                return Ok(());
-            }
-            Err(e) => {
-                warn!(
-                    webhook_url = %webhook_url,
-                    event_type = %event.event_type,
-                    operation_id = %event.operation_id,
-                    attempt = attempt + 1,
-                    error = ?e,
-                    "Webhook request failed"
-                );
-
-                if attempt == MAX_RETRIES - 1 {
-                    error!(
-                        webhook_url = %webhook_url,
-                        event_type = %event.event_type,
-                        operation_id = %event.operation_id,
-                        max_retries = MAX_RETRIES,
-                        "Webhook delivery failed after all retries"
-                    );
-                    return Err(anyhow::anyhow!(
-                        "Webhook delivery failed after {} retries: {}",
-                        MAX_RETRIES,
-                        e
-                    ));
-                }
-            }
+impl InvoiceWebhookEvent {
+    pub fn new(
+        event_type: &str,
+        operation_id: OperationId,
+        invoice_id: &str,
+        federation_id: FederationId,
+        amount_msat: u64,
+        metadata: Option<&serde_json::Value>,
+    ) -> Self {
+        Self {
+            event_type: event_type.to_string(),
+            operation_id: format!("{:?}", operation_id),
+            invoice_id: invoice_id.to_string(),
+            federation_id: federation_id.to_string(),
+            timestamp: Utc::now(),
+            data: serde_json::json!({
+                "amountMsat": amount_msat,
+                "metadata": metadata,
+            }),
         }
     }
+}
 
-    Ok(())
+/// Event id of an invoice callback, the same for every notification of one
+/// outcome so receivers can deduplicate a notification sent again after a
+/// restart
+pub fn callback_event_id(operation_id: OperationId, event_type: &str) -> String {
+    format!("{}-{}", operation_id.fmt_full(), event_type)
 }
 
-async fn send_webhook_request(
-    client: &reqwest::Client,
-    webhook_url: &str,
-    event: &InvoiceWebhookEvent,
-) -> anyhow::Result<()> {
-    let response = client
-        .post(webhook_url)
-        .header("Content-Type", "application/json")
-        .header("User-Agent", "fmcd-webhook/1.0")
-        .header("X-Fmcd-Event-Type", &event.event_type)
-        .header("X-Fmcd-Operation-Id", &event.operation_id)
-        .header("X-Fmcd-Timestamp", event.timestamp.to_rfc3339())
-        .json(event)
-        .send()
-        .await?;
+/// Callback URL given on invoice creation, notified once when that invoice
+/// settles or expires. Notifications go through the webhook outbox, so they
+/// are signed, retried and survive restarts like endpoint deliveries.
+#[derive(Clone)]
+pub struct InvoiceCallback {
+    pub webhook_url: String,
+    pub federation_id: FederationId,
+    pub metadata: Option<serde_json::Value>,
+    pub notifier: Arc<WebhookNotifier>,
+}
 
-    let status = response.status();
-    if status.is_success() {
-        Ok(())
-    } else {
-        let error_body = response
-            .text()
-            .await
-            .unwrap_or_else(|_| "Unknown error".to_string());
-        anyhow::bail!(
-            "Webhook request failed with status {}: {}",
-            status,
-            error_body
+impl InvoiceCallback {
+    /// Queue the notification, failures are logged since there is no one left
+    /// to return them to
+    pub async fn notify(
+        &self,
+        event_type: &str,
+        operation_id: OperationId,
+        invoice_id: &str,
+        amount_msat: u64,
+    ) {
+        let event = InvoiceWebhookEvent::new(
+            event_type,
+            operation_id,
+            invoice_id,
+            self.federation_id,
+            amount_msat,
+            self.metadata.as_ref(),
         );
+        let event_id = callback_event_id(operation_id, event_type);
+        let result = match serde_json::to_string(&event) {
+            Ok(payload) => self
+                .notifier
+                .enqueue_callback(&self.webhook_url, &event_id, event_type, payload)
+                .await
+                .map(|_| ()),
+            Err(e) => Err(e.into()),
+        };
+
+        if let Err(e) = result {
+            error!(
+                invoice_id = %invoice_id,
+                event_type = %event_type,
+                error = ?e,
+                "Failed to queue invoice callback"
+            );
+        }
     }
 }
@@ -1,7 +1,15 @@
 pub mod invoice;
 pub mod notifier;
+pub mod outbox;
 
 #[cfg(test)]
 mod tests;
 
-pub use notifier::{RetryConfig, WebhookConfig, WebhookEndpoint, WebhookNotifier};
+pub use notifier::{
+    ReplaySelector, RetryConfig, SignatureVerificationError, WebhookConfig, WebhookEndpoint,
+    WebhookEndpointInfo, WebhookNotifier, CALLBACK_ENDPOINT_ID, DEFAULT_SIGNATURE_TOLERANCE,
+    DELIVERY_ID_HEADER, SIGNATURE_HEADER,
+};
+pub use outbox::{
+    DeliveryLogQuery, DeliveryState, WebhookDelivery, WebhookDeliveryLogEntry, WebhookOutbox,
+};
@@ -1,7 +1,8 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::net::IpAddr;
 use std::str::FromStr;
+use std::sync::{Arc, Mutex, RwLock};
 use std::time::Duration;
 
 use async_trait::async_trait;
@@ -11,11 +12,47 @@ use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use sha2::Sha256;
-use tokio::time::sleep;
+use tokio::sync::broadcast;
+use tokio::time::{interval, sleep};
 use tracing::{debug, error, info, warn};
 
+use super::outbox::{DeliveryLogQuery, WebhookDelivery, WebhookDeliveryLogEntry, WebhookOutbox};
 use crate::events::{EventHandler, FmcdEvent};
 
+/// Header carrying the timestamped signature, `t=<unix seconds>,v1=<hex>`
+/// where the HMAC-SHA256 covers `<t>.<delivery id>.<body>`
+pub const SIGNATURE_HEADER: &str = "X-Fmcd-Signature";
+
+/// Header carrying the delivery id, which stays the same across retries of a
+/// delivery so receivers can drop duplicates
+pub const DELIVERY_ID_HEADER: &str = "X-Fmcd-Delivery-Id";
+
+/// Default tolerance between the signature timestamp and the receiver's clock
+pub const DEFAULT_SIGNATURE_TOLERANCE: Duration = Duration::from_secs(5 * 60);
+
+/// Why a timestamped webhook signature was rejected
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignatureVerificationError {
+    /// The header is not in the `t=...,v1=...` format
+    Malformed,
+    /// The timestamp is outside the tolerance window
+    Expired,
+    /// No signature in the header matches the payload
+    Mismatch,
+}
+
+impl fmt::Display for SignatureVerificationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Malformed => write!(f, "malformed signature header"),
+            Self::Expired => write!(f, "signature timestamp outside the tolerance window"),
+            Self::Mismatch => write!(f, "signature does not match the payload"),
+        }
+    }
+}
+
+impl std::error::Error for SignatureVerificationError {}
+
 /// Configuration for webhook retry behavior
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RetryConfig {
@@ -43,6 +80,16 @@ impl Default for RetryConfig {
     }
 }
 
+impl RetryConfig {
+    /// Delay before the retry that follows the given (1-based) attempt
+    pub fn delay_after_attempt(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1) as i32;
+        let delay_ms = (self.initial_delay_ms as f64 * self.backoff_multiplier.powi(exponent))
+            .min(self.max_delay_ms as f64);
+        Duration::from_millis(delay_ms as u64)
+    }
+}
+
 /// Configuration for a single webhook endpoint
 #[derive(Clone, Serialize, Deserialize)]
 pub struct WebhookEndpoint {
@@ -93,7 +140,7 @@ impl WebhookEndpoint {
     }
 
     /// Validate webhook URL to prevent SSRF attacks
-    fn validate_webhook_url(url: &str) -> anyhow::Result<()> {
+    pub fn validate_webhook_url(url: &str) -> anyhow::Result<()> {
         let parsed_url =
             reqwest::Url::parse(url).map_err(|e| anyhow::anyhow!("Invalid URL format: {}", e))?;
 
@@ -192,7 +239,7 @@ impl WebhookEndpoint {
     }
 
     /// Validate HMAC secret for cryptographic security
-    fn validate_hmac_secret(secret: &str) -> anyhow::Result<()> {
+    pub fn validate_hmac_secret(secret: &str) -> anyhow::Result<()> {
         // Minimum length for HMAC-SHA256 security (32 bytes = 256 bits)
         const MIN_SECRET_LENGTH: usize = 32;
 
@@ -272,15 +319,72 @@ impl WebhookEndpoint {
     pub fn should_receive_event(&self, event_type: &str) -> bool {
         self.enabled && (self.events.is_empty() || self.events.contains(&event_type.to_string()))
     }
+
+    /// Run the URL and secret checks, for endpoints that were not built with
+    /// `new` and `with_secret`
+    pub fn validate(&self) -> anyhow::Result<()> {
+        Self::validate_webhook_url(&self.url)?;
+        if let Some(secret) = &self.secret {
+            Self::validate_hmac_secret(secret)?;
+        }
+        Ok(())
+    }
 }
 
+/// An endpoint as returned by the admin API, without its secret
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookEndpointInfo {
+    pub id: String,
+    pub url: String,
+    pub has_secret: bool,
+    pub events: Vec<String>,
+    pub retry_config: RetryConfig,
+    pub enabled: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+impl From<&WebhookEndpoint> for WebhookEndpointInfo {
+    fn from(endpoint: &WebhookEndpoint) -> Self {
+        Self {
+            id: endpoint.id.clone(),
+            url: endpoint.url.clone(),
+            has_secret: endpoint.secret.is_some(),
+            events: endpoint.events.clone(),
+            retry_config: endpoint.retry_config.clone(),
+            enabled: endpoint.enabled,
+            description: endpoint.description.clone(),
+        }
+    }
+}
+
+/// Endpoint id under which deliveries to per-invoice callback URLs are kept
+pub const CALLBACK_ENDPOINT_ID: &str = "invoice-callback";
+
 /// Configuration for the webhook system
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct WebhookConfig {
     /// List of webhook endpoints
     pub endpoints: Vec<WebhookEndpoint>,
     /// Whether webhook delivery is enabled globally
     pub enabled: bool,
+    /// Optional secret for signing deliveries to per-invoice callback URLs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub callback_secret: Option<String>,
+}
+
+impl fmt::Debug for WebhookConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WebhookConfig")
+            .field("endpoints", &self.endpoints)
+            .field("enabled", &self.enabled)
+            .field(
+                "callback_secret",
+                &self.callback_secret.as_ref().map(|_| "[REDACTED]"),
+            )
+            .finish()
+    }
 }
 
 impl Default for WebhookConfig {
@@ -288,15 +392,80 @@ impl Default for WebhookConfig {
         Self {
             endpoints: Vec::new(),
             enabled: true,
+            callback_secret: None,
+        }
+    }
+}
+
+impl WebhookConfig {
+    /// The endpoint a per-invoice callback is delivered to, signed with the
+    /// callback secret and retried with the default retry config
+    pub fn callback_endpoint(&self, url: &str) -> WebhookEndpoint {
+        WebhookEndpoint {
+            id: CALLBACK_ENDPOINT_ID.to_string(),
+            url: url.to_string(),
+            secret: self.callback_secret.clone(),
+            events: Vec::new(),
+            retry_config: RetryConfig::default(),
+            enabled: true,
+            description: None,
         }
     }
+
+    pub fn endpoint(&self, id: &str) -> Option<&WebhookEndpoint> {
+        self.endpoints.iter().find(|endpoint| endpoint.id == id)
+    }
+
+    pub fn endpoint_mut(&mut self, id: &str) -> Option<&mut WebhookEndpoint> {
+        self.endpoints.iter_mut().find(|endpoint| endpoint.id == id)
+    }
+
+    /// Remove an endpoint, returns it if it was configured
+    pub fn remove_endpoint(&mut self, id: &str) -> Option<WebhookEndpoint> {
+        let index = self
+            .endpoints
+            .iter()
+            .position(|endpoint| endpoint.id == id)?;
+        Some(self.endpoints.remove(index))
+    }
+}
+
+/// How often the outbox worker looks for deliveries that are due
+const OUTBOX_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How often the outbox worker prunes the delivery log
+const DELIVERY_LOG_PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// How long finished deliveries stay in the delivery log
+const DELIVERY_LOG_RETENTION: chrono::Duration = chrono::Duration::days(30);
+
+/// Which past events to replay to an endpoint, a single event or every event
+/// delivered in a time window
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplaySelector {
+    pub event_id: Option<String>,
+    pub from: Option<chrono::DateTime<chrono::Utc>>,
+    pub to: Option<chrono::DateTime<chrono::Utc>>,
 }
 
 /// Webhook notifier that implements the EventHandler trait
+///
+/// With an outbox attached, every (endpoint, event) delivery is persisted
+/// before it is attempted and retried by a background worker until the
+/// endpoint acknowledges it, so deliveries survive restarts and long outages.
 #[derive(Clone)]
 pub struct WebhookNotifier {
     client: Client,
-    config: WebhookConfig,
+    config: Arc<RwLock<WebhookConfig>>,
+    outbox: Option<WebhookOutbox>,
+    /// Deliveries currently being attempted, so the worker and the initial
+    /// attempt don't send the same delivery twice
+    in_flight: Arc<Mutex<HashSet<String>>>,
+    /// Endpoints the outbox worker is currently delivering to, so a slow
+    /// endpoint only holds up its own deliveries
+    busy_endpoints: Arc<Mutex<HashSet<String>>>,
+    shutdown_tx: Arc<Mutex<Option<broadcast::Sender<()>>>>,
 }
 
 impl WebhookNotifier {
@@ -304,49 +473,77 @@ impl WebhookNotifier {
     pub fn new(config: WebhookConfig) -> anyhow::Result<Self> {
         let client = Client::builder().timeout(Duration::from_secs(30)).build()?;
 
-        Ok(Self { client, config })
+        Ok(Self {
+            client,
+            config: Arc::new(RwLock::new(config)),
+            outbox: None,
+            in_flight: Arc::new(Mutex::new(HashSet::new())),
+            busy_endpoints: Arc::new(Mutex::new(HashSet::new())),
+            shutdown_tx: Arc::new(Mutex::new(None)),
+        })
+    }
+
+    /// Persist deliveries in the given outbox instead of retrying them in
+    /// memory
+    pub fn with_outbox(mut self, outbox: WebhookOutbox) -> Self {
+        self.outbox = Some(outbox);
+        self
     }
 
     /// Update the webhook configuration
-    pub fn update_config(&mut self, config: WebhookConfig) {
-        self.config = config;
+    pub fn update_config(&self, config: WebhookConfig) {
+        let endpoint_count = config.endpoints.len();
+        let enabled = config.enabled;
+        *self
+            .config
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = config;
         info!(
-            endpoint_count = self.config.endpoints.len(),
-            enabled = self.config.enabled,
+            endpoint_count = endpoint_count,
+            enabled = enabled,
             "Webhook configuration updated"
         );
     }
 
     /// Get the current webhook configuration
-    pub fn config(&self) -> &WebhookConfig {
-        &self.config
+    pub fn config(&self) -> WebhookConfig {
+        self.config
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
     }
 
     /// Send webhook notification for an event to all matching endpoints
     pub async fn notify(&self, event: &FmcdEvent) -> anyhow::Result<()> {
-        if !self.config.enabled {
+        let config = self.config();
+        if !config.enabled {
             debug!("Webhook notifications disabled, skipping event");
             return Ok(());
         }
 
         let event_type = event.event_type();
         let event_id = event.event_id();
-        let _timestamp = event.timestamp();
 
         debug!(
             event_id = %event_id,
             event_type = %event_type,
-            endpoint_count = self.config.endpoints.len(),
+            endpoint_count = config.endpoints.len(),
             "Processing webhook notifications for event"
         );
 
-        let payload = self.create_webhook_payload(event)?;
+        let payload = self.create_webhook_payload(event, &event_id)?;
         let payload_str = serde_json::to_string(&payload)?;
 
+        if let Some(outbox) = &self.outbox {
+            return self
+                .enqueue_deliveries(outbox, &config, &event_id, event_type, &payload_str)
+                .await;
+        }
+
         // Send to all matching endpoints concurrently
         let mut tasks = Vec::new();
 
-        for endpoint in &self.config.endpoints {
+        for endpoint in &config.endpoints {
             if endpoint.should_receive_event(event_type) {
                 let client = self.client.clone();
                 let endpoint = endpoint.clone();
@@ -390,8 +587,431 @@ impl WebhookNotifier {
         Ok(())
     }
 
+    /// Persist one delivery per matching endpoint and make a first attempt for
+    /// each in the background
+    async fn enqueue_deliveries(
+        &self,
+        outbox: &WebhookOutbox,
+        config: &WebhookConfig,
+        event_id: &str,
+        event_type: &str,
+        payload: &str,
+    ) -> anyhow::Result<()> {
+        let mut enqueued = 0;
+        for endpoint in &config.endpoints {
+            if !endpoint.should_receive_event(event_type) {
+                continue;
+            }
+
+            let delivery = WebhookDelivery::new(
+                endpoint.id.clone(),
+                event_id.to_string(),
+                event_type.to_string(),
+                payload.to_string(),
+            );
+            outbox.enqueue(&delivery).await?;
+            enqueued += 1;
+
+            let notifier = self.clone();
+            tokio::spawn(async move {
+                notifier.attempt_delivery(delivery).await;
+            });
+        }
+
+        debug!(
+            event_id = %event_id,
+            event_type = %event_type,
+            enqueued = enqueued,
+            "Webhook deliveries added to outbox"
+        );
+
+        Ok(())
+    }
+
+    /// Queue a delivery to a one-off callback URL, such as the one given on
+    /// invoice creation, and make a first attempt in the background. It is
+    /// signed with the callback secret and retried like endpoint deliveries.
+    pub async fn enqueue_callback(
+        &self,
+        url: &str,
+        event_id: &str,
+        event_type: &str,
+        payload: String,
+    ) -> anyhow::Result<WebhookDelivery> {
+        let Some(outbox) = &self.outbox else {
+            return Err(anyhow::anyhow!("Webhook outbox is not enabled"));
+        };
+
+        let mut delivery = WebhookDelivery::new(
+            CALLBACK_ENDPOINT_ID.to_string(),
+            event_id.to_string(),
+            event_type.to_string(),
+            payload,
+        );
+        delivery.callback_url = Some(url.to_string());
+        outbox.enqueue(&delivery).await?;
+
+        let notifier = self.clone();
+        let first_attempt = delivery.clone();
+        tokio::spawn(async move {
+            notifier.attempt_delivery(first_attempt).await;
+        });
+
+        debug!(
+            event_id = %event_id,
+            event_type = %event_type,
+            delivery_id = %delivery.delivery_id,
+            "Callback delivery added to outbox"
+        );
+
+        Ok(delivery)
+    }
+
+    /// Start the outbox worker, which redelivers pending deliveries including
+    /// the ones left over from a previous run
+    pub async fn start(&self) -> anyhow::Result<()> {
+        let Some(outbox) = self.outbox.clone() else {
+            return Ok(());
+        };
+
+        let (shutdown_tx, _) = broadcast::channel(1);
+        {
+            let mut tx_guard = self
+                .shutdown_tx
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            *tx_guard = Some(shutdown_tx.clone());
+        }
+
+        let pending = outbox.pending().await?.len();
+        info!(
+            pending_deliveries = pending,
+            poll_interval_secs = OUTBOX_POLL_INTERVAL.as_secs(),
+            "Starting webhook outbox worker"
+        );
+
+        let notifier = self.clone();
+        tokio::spawn(async move {
+            let mut shutdown_rx = shutdown_tx.subscribe();
+            let mut poll_timer = interval(OUTBOX_POLL_INTERVAL);
+            let mut prune_timer = interval(DELIVERY_LOG_PRUNE_INTERVAL);
+
+            loop {
+                tokio::select! {
+                    _ = poll_timer.tick() => {
+                        if let Err(e) = notifier.process_outbox(&outbox).await {
+                            error!(error = ?e, "Error while processing webhook outbox");
+                        }
+                    }
+                    _ = prune_timer.tick() => {
+                        let cutoff = chrono::Utc::now() - DELIVERY_LOG_RETENTION;
+                        match outbox.prune_delivery_log(cutoff).await {
+                            Ok(removed) if removed > 0 => {
+                                info!(removed = removed, "Pruned webhook delivery log");
+                            }
+                            Ok(_) => {}
+                            Err(e) => error!(error = ?e, "Failed to prune webhook delivery log"),
+                        }
+                    }
+                    _ = shutdown_rx.recv() => {
+                        info!("Webhook outbox worker received shutdown signal");
+                        break;
+                    }
+                }
+            }
+
+            info!("Webhook outbox worker stopped");
+        });
+
+        Ok(())
+    }
+
+    /// Stop the outbox worker
+    pub fn stop(&self) {
+        let tx_guard = self
+            .shutdown_tx
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(shutdown_tx) = tx_guard.as_ref() {
+            let _ = shutdown_tx.send(());
+        }
+    }
+
+    /// Attempt every pending delivery that is due. Each endpoint gets its own
+    /// task that works through its deliveries in order, and an endpoint whose
+    /// task is still running from an earlier poll is skipped.
+    async fn process_outbox(&self, outbox: &WebhookOutbox) -> anyhow::Result<()> {
+        let now = chrono::Utc::now();
+        let mut due: HashMap<String, Vec<WebhookDelivery>> = HashMap::new();
+        for delivery in outbox.pending().await? {
+            if delivery.next_attempt_at <= now {
+                due.entry(delivery.endpoint_id.clone())
+                    .or_default()
+                    .push(delivery);
+            }
+        }
+
+        for (endpoint_id, deliveries) in due {
+            if !self
+                .busy_endpoints
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .insert(endpoint_id.clone())
+            {
+                continue;
+            }
+
+            let notifier = self.clone();
+            tokio::spawn(async move {
+                for delivery in deliveries {
+                    notifier.attempt_delivery(delivery).await;
+                }
+                notifier
+                    .busy_endpoints
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner())
+                    .remove(&endpoint_id);
+            });
+        }
+        Ok(())
+    }
+
+    /// Make one delivery attempt and record the outcome in the outbox: remove
+    /// the delivery once acknowledged, schedule the next retry, or dead-letter
+    /// it when its retries are exhausted
+    async fn attempt_delivery(&self, delivery: WebhookDelivery) {
+        let Some(outbox) = &self.outbox else {
+            return;
+        };
+
+        if !self
+            .in_flight
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .insert(delivery.delivery_id.clone())
+        {
+            return;
+        }
+
+        // The copy we were handed may be stale: another attempt can have
+        // acknowledged or retried the delivery since it was read
+        let delivery_id = delivery.delivery_id;
+        match outbox.get(&delivery_id).await {
+            Ok(Some(current)) => self.attempt_claimed(outbox, current).await,
+            Ok(None) => debug!(
+                delivery_id = %delivery_id,
+                "Webhook delivery already left the outbox, skipping"
+            ),
+            Err(e) => error!(
+                delivery_id = %delivery_id,
+                error = ?e,
+                "Failed to read webhook delivery from outbox"
+            ),
+        }
+
+        self.in_flight
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .remove(&delivery_id);
+    }
+
+    /// Send a delivery this task has claimed and record the outcome
+    async fn attempt_claimed(&self, outbox: &WebhookOutbox, mut delivery: WebhookDelivery) {
+        let config = self.config();
+        let endpoint = match &delivery.callback_url {
+            Some(url) => Some(config.callback_endpoint(url)),
+            None => config.endpoint(&delivery.endpoint_id).cloned(),
+        };
+
+        let result = match endpoint {
+            None => {
+                warn!(
+                    endpoint_id = %delivery.endpoint_id,
+                    delivery_id = %delivery.delivery_id,
+                    "Webhook endpoint no longer configured, dead-lettering delivery"
+                );
+                delivery.last_error = Some("Endpoint no longer configured".to_string());
+                outbox.dead_letter(&delivery).await
+            }
+            // Keep deliveries for disabled endpoints until they are enabled again
+            Some(endpoint) if !config.enabled || !endpoint.enabled => Ok(()),
+            Some(endpoint) => {
+                delivery.attempts += 1;
+                let started = std::time::Instant::now();
+                let (status, outcome) = Self::send_webhook_request_with_status(
+                    &self.client,
+                    &endpoint,
+                    &delivery.payload,
+                    &delivery.event_id,
+                    &delivery.delivery_id,
+                )
+                .await;
+                delivery.last_status = status;
+                delivery.last_latency_ms = Some(started.elapsed().as_millis() as u64);
+
+                match outcome {
+                    Ok(()) => {
+                        info!(
+                            endpoint_id = %endpoint.id,
+                            event_id = %delivery.event_id,
+                            delivery_id = %delivery.delivery_id,
+                            attempt = delivery.attempts,
+                            "Webhook delivered successfully"
+                        );
+                        delivery.last_error = None;
+                        outbox.acknowledge(&delivery).await
+                    }
+                    Err(e) => {
+                        delivery.last_error = Some(e.to_string());
+                        if delivery.attempts >= endpoint.retry_config.max_attempts {
+                            error!(
+                                endpoint_id = %endpoint.id,
+                                event_id = %delivery.event_id,
+                                delivery_id = %delivery.delivery_id,
+                                attempts = delivery.attempts,
+                                error = %e,
+                                "Webhook delivery failed after all retry attempts, moved to dead-letter list"
+                            );
+                            outbox.dead_letter(&delivery).await
+                        } else {
+                            let delay =
+                                endpoint.retry_config.delay_after_attempt(delivery.attempts);
+                            warn!(
+                                endpoint_id = %endpoint.id,
+                                event_id = %delivery.event_id,
+                                delivery_id = %delivery.delivery_id,
+                                attempt = delivery.attempts,
+                                max_attempts = endpoint.retry_config.max_attempts,
+                                retry_in_ms = delay.as_millis(),
+                                error = %e,
+                                "Webhook delivery attempt failed"
+                            );
+                            delivery.next_attempt_at = chrono::Utc::now()
+                                + chrono::Duration::from_std(delay).unwrap_or_default();
+                            outbox.reschedule(&delivery).await.map(|_| ())
+                        }
+                    }
+                }
+            }
+        };
+
+        if let Err(e) = result {
+            error!(
+                delivery_id = %delivery.delivery_id,
+                error = ?e,
+                "Failed to record webhook delivery outcome"
+            );
+        }
+    }
+
+    /// Delivery history of an endpoint, newest first
+    pub async fn delivery_log(
+        &self,
+        endpoint_id: &str,
+        query: &DeliveryLogQuery,
+    ) -> anyhow::Result<Vec<WebhookDeliveryLogEntry>> {
+        match &self.outbox {
+            Some(outbox) => outbox.delivery_log(endpoint_id, query).await,
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Send past events from the delivery log to an endpoint again, as new
+    /// deliveries that are signed when they are sent. Each event is replayed
+    /// once even if it was delivered several times.
+    pub async fn replay(
+        &self,
+        endpoint_id: &str,
+        selector: &ReplaySelector,
+    ) -> anyhow::Result<Vec<WebhookDelivery>> {
+        let Some(outbox) = &self.outbox else {
+            return Err(anyhow::anyhow!("Webhook outbox is not enabled"));
+        };
+
+        let query = DeliveryLogQuery {
+            event_id: selector.event_id.clone(),
+            from: selector.from,
+            to: selector.to,
+            ..Default::default()
+        };
+        let mut entries = outbox.delivery_log(endpoint_id, &query).await?;
+        // Oldest first so the endpoint sees events in their original order
+        entries.reverse();
+
+        let mut seen = HashSet::new();
+        let mut replayed = Vec::new();
+        for entry in entries {
+            if !seen.insert(entry.delivery.event_id.clone()) {
+                continue;
+            }
+
+            let mut delivery = WebhookDelivery::new(
+                endpoint_id.to_string(),
+                entry.delivery.event_id,
+                entry.delivery.event_type,
+                entry.delivery.payload,
+            );
+            delivery.callback_url = entry.delivery.callback_url;
+            outbox.enqueue(&delivery).await?;
+            replayed.push(delivery);
+        }
+
+        for delivery in replayed.iter().cloned() {
+            let notifier = self.clone();
+            tokio::spawn(async move {
+                notifier.attempt_delivery(delivery).await;
+            });
+        }
+
+        info!(
+            endpoint_id = %endpoint_id,
+            replayed = replayed.len(),
+            "Replaying webhook events"
+        );
+
+        Ok(replayed)
+    }
+
+    /// List the deliveries that exhausted their retries
+    pub async fn dead_letters(&self) -> anyhow::Result<Vec<WebhookDelivery>> {
+        match &self.outbox {
+            Some(outbox) => outbox.dead_letters().await,
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Move dead-lettered deliveries back to the outbox, all of them if no ids
+    /// are given, and attempt them right away
+    pub async fn requeue(
+        &self,
+        delivery_ids: Option<&[String]>,
+    ) -> anyhow::Result<Vec<WebhookDelivery>> {
+        let Some(outbox) = &self.outbox else {
+            return Err(anyhow::anyhow!("Webhook outbox is not enabled"));
+        };
+
+        let requeued = outbox.requeue(delivery_ids).await?;
+        for delivery in requeued.iter().cloned() {
+            let notifier = self.clone();
+            tokio::spawn(async move {
+                notifier.attempt_delivery(delivery).await;
+            });
+        }
+
+        info!(
+            requeued = requeued.len(),
+            "Requeued dead-lettered webhook deliveries"
+        );
+
+        Ok(requeued)
+    }
+
     /// Create the webhook payload for an event with sensitive data sanitized
-    fn create_webhook_payload(&self, event: &FmcdEvent) -> anyhow::Result<serde_json::Value> {
+    pub(crate) fn create_webhook_payload(
+        &self,
+        event: &FmcdEvent,
+        event_id: &str,
+    ) -> anyhow::Result<serde_json::Value> {
         // First serialize the full event to JSON
         let event_json = serde_json::to_value(event)?;
 
@@ -399,7 +1019,7 @@ impl WebhookNotifier {
         let sanitized_data = self.sanitize_event_data(event_json)?;
 
         let payload = serde_json::json!({
-            "id": event.event_id(),
+            "id": event_id,
             "type": event.event_type(),
             "timestamp": event.timestamp(),
             "correlation_id": event.correlation_id(),
@@ -483,6 +1103,7 @@ impl WebhookNotifier {
     ) -> anyhow::Result<()> {
         let mut attempt = 0;
         let mut delay_ms = endpoint.retry_config.initial_delay_ms;
+        let delivery_id = uuid::Uuid::new_v4().to_string();
 
         while attempt < endpoint.retry_config.max_attempts {
             attempt += 1;
@@ -495,7 +1116,9 @@ impl WebhookNotifier {
                 "Attempting webhook delivery"
             );
 
-            match Self::send_webhook_request(&client, &endpoint, &payload, event_id).await {
+            match Self::send_webhook_request(&client, &endpoint, &payload, event_id, &delivery_id)
+                .await
+            {
                 Ok(()) => {
                     info!(
                         endpoint_id = %endpoint.id,
@@ -547,49 +1170,82 @@ impl WebhookNotifier {
         endpoint: &WebhookEndpoint,
         payload: &str,
         event_id: &str,
+        delivery_id: &str,
     ) -> anyhow::Result<()> {
+        Self::send_webhook_request_with_status(client, endpoint, payload, event_id, delivery_id)
+            .await
+            .1
+    }
+
+    /// Send a single webhook HTTP request, also returning the response status
+    /// when the endpoint answered
+    async fn send_webhook_request_with_status(
+        client: &Client,
+        endpoint: &WebhookEndpoint,
+        payload: &str,
+        event_id: &str,
+        delivery_id: &str,
+    ) -> (Option<u16>, anyhow::Result<()>) {
         let mut request_builder = client
             .post(&endpoint.url)
             .header("Content-Type", "application/json")
             .header("User-Agent", "fmcd-webhook/1.0")
             .header("X-Event-Id", event_id)
+            .header(DELIVERY_ID_HEADER, delivery_id)
             .timeout(Duration::from_secs(endpoint.retry_config.timeout_secs))
             .body(payload.to_string());
 
-        // Add HMAC signature if secret is configured
+        // Add HMAC signatures if secret is configured, the body-only signature is
+        // kept for receivers that don't verify the timestamped one yet
         if let Some(secret) = &endpoint.secret {
-            let signature = Self::calculate_hmac_signature(payload, secret)?;
-            request_builder = request_builder.header("X-Signature-SHA256", signature);
+            let signatures = Self::calculate_hmac_signature(payload, secret).and_then(|legacy| {
+                let timestamp = chrono::Utc::now().timestamp();
+                Self::timestamped_signature(payload, delivery_id, secret, timestamp)
+                    .map(|timestamped| (legacy, timestamped))
+            });
+            let (legacy, timestamped) = match signatures {
+                Ok(signatures) => signatures,
+                Err(e) => return (None, Err(e)),
+            };
+            request_builder = request_builder
+                .header("X-Signature-SHA256", legacy)
+                .header(SIGNATURE_HEADER, timestamped);
         }
 
-        let response = request_builder.send().await?;
+        let response = match request_builder.send().await {
+            Ok(response) => response,
+            Err(e) => return (None, Err(e.into())),
+        };
+        let status = response.status();
 
         // Check if response indicates success
-        if response.status().is_success() {
+        if status.is_success() {
             debug!(
                 endpoint_id = %endpoint.id,
                 event_id = %event_id,
-                status_code = %response.status(),
+                status_code = %status,
                 "Webhook request completed successfully"
             );
-            Ok(())
+            (Some(status.as_u16()), Ok(()))
         } else {
-            let status = response.status();
             let body = response
                 .text()
                 .await
                 .unwrap_or_else(|_| "Unknown error".to_string());
 
-            Err(anyhow::anyhow!(
-                "Webhook request failed with status {}: {}",
-                status,
-                body
-            ))
+            (
+                Some(status.as_u16()),
+                Err(anyhow::anyhow!(
+                    "Webhook request failed with status {}: {}",
+                    status,
+                    body
+                )),
+            )
         }
     }
 
     /// Calculate HMAC-SHA256 signature for webhook payload
-    fn calculate_hmac_signature(payload: &str, secret: &str) -> anyhow::Result<String> {
+    pub(crate) fn calculate_hmac_signature(payload: &str, secret: &str) -> anyhow::Result<String> {
         let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
             .map_err(|e| anyhow::anyhow!("Invalid HMAC key: {}", e))?;
 
@@ -610,6 +1266,92 @@ impl WebhookNotifier {
             Err(_) => false,
         }
     }
+
+    fn timestamped_mac(
+        payload: &str,
+        delivery_id: &str,
+        secret: &str,
+        timestamp: i64,
+    ) -> anyhow::Result<Hmac<Sha256>> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+            .map_err(|e| anyhow::anyhow!("Invalid HMAC key: {}", e))?;
+        mac.update(format!("{}.{}.", timestamp, delivery_id).as_bytes());
+        mac.update(payload.as_bytes());
+        Ok(mac)
+    }
+
+    /// Calculate the `t=...,v1=...` signature header value for a delivery
+    pub fn timestamped_signature(
+        payload: &str,
+        delivery_id: &str,
+        secret: &str,
+        timestamp: i64,
+    ) -> anyhow::Result<String> {
+        let mac = Self::timestamped_mac(payload, delivery_id, secret, timestamp)?;
+        Ok(format!(
+            "t={},v1={}",
+            timestamp,
+            hex::encode(mac.finalize().into_bytes())
+        ))
+    }
+
+    /// Verify a timestamped signature header for incoming webhook validation.
+    ///
+    /// Rejects signatures whose timestamp is further than `tolerance` from the
+    /// local clock, and accepts the header if any of its `v1` signatures
+    /// matches. Returns the signed timestamp. Receivers should also remember
+    /// the delivery ids they processed within the tolerance window to drop
+    /// duplicates.
+    pub fn verify_timestamped_signature(
+        payload: &str,
+        signature_header: &str,
+        delivery_id: &str,
+        secret: &str,
+        tolerance: Duration,
+    ) -> Result<i64, SignatureVerificationError> {
+        let mut timestamp = None;
+        let mut signatures = Vec::new();
+        for part in signature_header.split(',') {
+            match part.trim().split_once('=') {
+                Some(("t", value)) => {
+                    timestamp = Some(
+                        value
+                            .parse::<i64>()
+                            .map_err(|_| SignatureVerificationError::Malformed)?,
+                    );
+                }
+                Some(("v1", value)) => {
+                    signatures.push(
+                        hex::decode(value).map_err(|_| SignatureVerificationError::Malformed)?,
+                    );
+                }
+                // Unknown schemes are ignored so new ones can be added later
+                Some(_) => {}
+                None => return Err(SignatureVerificationError::Malformed),
+            }
+        }
+
+        let timestamp = timestamp.ok_or(SignatureVerificationError::Malformed)?;
+        if signatures.is_empty() {
+            return Err(SignatureVerificationError::Malformed);
+        }
+
+        let age = chrono::Utc::now().timestamp().abs_diff(timestamp);
+        if age > tolerance.as_secs() {
+            return Err(SignatureVerificationError::Expired);
+        }
+
+        let mac = Self::timestamped_mac(payload, delivery_id, secret, timestamp)
+            .map_err(|_| SignatureVerificationError::Mismatch)?;
+        if signatures
+            .iter()
+            .any(|signature| mac.clone().verify_slice(signature).is_ok())
+        {
+            Ok(timestamp)
+        } else {
+            Err(SignatureVerificationError::Mismatch)
+        }
+    }
 }
 
 #[async_trait]
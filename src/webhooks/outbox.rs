@@ -0,0 +1,383 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use fedimint_core::db::{
+    Committable, Database, DatabaseTransaction, IDatabaseTransactionOpsCoreTyped,
+};
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::core::multimint::db::{
+    WebhookDeadLetterKey, WebhookDeadLetterKeyPrefix, WebhookDeliveryLogEndpointPrefix,
+    WebhookDeliveryLogKey, WebhookDeliveryLogKeyPrefix, WebhookDeliveryRecord, WebhookOutboxKey,
+    WebhookOutboxKeyPrefix,
+};
+
+/// A single (endpoint, event) delivery, kept until the endpoint acknowledges it
+/// with a 2xx response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookDelivery {
+    pub delivery_id: String,
+    pub endpoint_id: String,
+    pub event_id: String,
+    pub event_type: String,
+    /// The serialized webhook payload, signed again on every attempt
+    pub payload: String,
+    pub attempts: u32,
+    pub created_at: DateTime<Utc>,
+    pub next_attempt_at: DateTime<Utc>,
+    /// HTTP status of the last attempt, if the endpoint responded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_status: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_latency_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    /// Where a per-invoice callback goes, unset for configured endpoints
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub callback_url: Option<String>,
+}
+
+impl WebhookDelivery {
+    pub fn new(endpoint_id: String, event_id: String, event_type: String, payload: String) -> Self {
+        let now = Utc::now();
+        Self {
+            delivery_id: Uuid::new_v4().to_string(),
+            endpoint_id,
+            event_id,
+            event_type,
+            payload,
+            attempts: 0,
+            created_at: now,
+            next_attempt_at: now,
+            last_status: None,
+            last_latency_ms: None,
+            last_error: None,
+            callback_url: None,
+        }
+    }
+
+    fn to_record(&self) -> Result<WebhookDeliveryRecord> {
+        Ok(WebhookDeliveryRecord {
+            json: serde_json::to_string(self)?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryState {
+    Pending,
+    Delivered,
+    DeadLettered,
+}
+
+/// Delivery history entry, the latest state of a delivery to an endpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookDeliveryLogEntry {
+    pub state: DeliveryState,
+    pub updated_at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub delivery: WebhookDelivery,
+}
+
+/// Filter for the delivery history of an endpoint
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeliveryLogQuery {
+    pub event_id: Option<String>,
+    pub event_type: Option<String>,
+    pub state: Option<DeliveryState>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub limit: Option<usize>,
+}
+
+impl DeliveryLogQuery {
+    fn matches(&self, entry: &WebhookDeliveryLogEntry) -> bool {
+        self.event_id
+            .as_ref()
+            .is_none_or(|id| *id == entry.delivery.event_id)
+            && self
+                .event_type
+                .as_ref()
+                .is_none_or(|t| *t == entry.delivery.event_type)
+            && self.state.is_none_or(|state| state == entry.state)
+            && self
+                .from
+                .is_none_or(|from| entry.delivery.created_at >= from)
+            && self.to.is_none_or(|to| entry.delivery.created_at <= to)
+    }
+}
+
+/// Persistent webhook outbox in the multimint database.
+///
+/// Pending deliveries stay in the outbox until acknowledged, deliveries that
+/// exhaust their retries move to the dead-letter list where they wait to be
+/// inspected and requeued. Every change is also written to the per-endpoint
+/// delivery log.
+#[derive(Debug, Clone)]
+pub struct WebhookOutbox {
+    db: Database,
+}
+
+impl WebhookOutbox {
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+
+    /// Add a delivery to the outbox, replacing a previous version of it
+    pub async fn enqueue(&self, delivery: &WebhookDelivery) -> Result<()> {
+        let record = delivery.to_record()?;
+        let mut dbtx = self.db.begin_transaction().await;
+        dbtx.insert_entry(
+            &WebhookOutboxKey {
+                delivery_id: delivery.delivery_id.clone(),
+            },
+            &record,
+        )
+        .await;
+        log_delivery(&mut dbtx, delivery, DeliveryState::Pending).await?;
+        dbtx.commit_tx_result()
+            .await
+            .map_err(|e| anyhow!("Failed to enqueue webhook delivery: {:?}", e))
+    }
+
+    /// A pending delivery, `None` once it left the outbox
+    pub async fn get(&self, delivery_id: &str) -> Result<Option<WebhookDelivery>> {
+        let mut dbtx = self.db.begin_transaction_nc().await;
+        let record = dbtx
+            .get_value(&WebhookOutboxKey {
+                delivery_id: delivery_id.to_string(),
+            })
+            .await;
+        record
+            .map(|record| serde_json::from_str::<WebhookDelivery>(&record.json))
+            .transpose()
+            .map_err(|e| anyhow!("Failed to decode webhook delivery: {}", e))
+    }
+
+    /// Store the outcome of a failed attempt of a pending delivery. Returns
+    /// false without writing anything if the delivery already left the outbox,
+    /// so a late retry can't bring back an acknowledged delivery.
+    pub async fn reschedule(&self, delivery: &WebhookDelivery) -> Result<bool> {
+        let key = WebhookOutboxKey {
+            delivery_id: delivery.delivery_id.clone(),
+        };
+        let mut dbtx = self.db.begin_transaction().await;
+        if dbtx.get_value(&key).await.is_none() {
+            return Ok(false);
+        }
+        dbtx.insert_entry(&key, &delivery.to_record()?).await;
+        log_delivery(&mut dbtx, delivery, DeliveryState::Pending).await?;
+        dbtx.commit_tx_result()
+            .await
+            .map_err(|e| anyhow!("Failed to reschedule webhook delivery: {:?}", e))?;
+        Ok(true)
+    }
+
+    /// Remove an acknowledged delivery from the outbox
+    pub async fn acknowledge(&self, delivery: &WebhookDelivery) -> Result<()> {
+        let mut dbtx = self.db.begin_transaction().await;
+        dbtx.remove_entry(&WebhookOutboxKey {
+            delivery_id: delivery.delivery_id.clone(),
+        })
+        .await;
+        log_delivery(&mut dbtx, delivery, DeliveryState::Delivered).await?;
+        dbtx.commit_tx_result()
+            .await
+            .map_err(|e| anyhow!("Failed to acknowledge webhook delivery: {:?}", e))
+    }
+
+    /// Move a delivery from the outbox to the dead-letter list
+    pub async fn dead_letter(&self, delivery: &WebhookDelivery) -> Result<()> {
+        let record = delivery.to_record()?;
+        let mut dbtx = self.db.begin_transaction().await;
+        dbtx.remove_entry(&WebhookOutboxKey {
+            delivery_id: delivery.delivery_id.clone(),
+        })
+        .await;
+        dbtx.insert_entry(
+            &WebhookDeadLetterKey {
+                delivery_id: delivery.delivery_id.clone(),
+            },
+            &record,
+        )
+        .await;
+        log_delivery(&mut dbtx, delivery, DeliveryState::DeadLettered).await?;
+        dbtx.commit_tx_result()
+            .await
+            .map_err(|e| anyhow!("Failed to dead-letter webhook delivery: {:?}", e))
+    }
+
+    /// All pending deliveries, oldest first
+    pub async fn pending(&self) -> Result<Vec<WebhookDelivery>> {
+        let mut dbtx = self.db.begin_transaction_nc().await;
+        let records = dbtx
+            .find_by_prefix(&WebhookOutboxKeyPrefix)
+            .await
+            .map(|(_, record)| record)
+            .collect::<Vec<_>>()
+            .await;
+        Ok(decode_sorted(records))
+    }
+
+    /// All dead-lettered deliveries, oldest first
+    pub async fn dead_letters(&self) -> Result<Vec<WebhookDelivery>> {
+        let mut dbtx = self.db.begin_transaction_nc().await;
+        let records = dbtx
+            .find_by_prefix(&WebhookDeadLetterKeyPrefix)
+            .await
+            .map(|(_, record)| record)
+            .collect::<Vec<_>>()
+            .await;
+        Ok(decode_sorted(records))
+    }
+
+    /// Move dead-lettered deliveries back to the outbox with a fresh retry
+    /// budget, all of them if no ids are given. Returns the requeued deliveries.
+    pub async fn requeue(&self, delivery_ids: Option<&[String]>) -> Result<Vec<WebhookDelivery>> {
+        let dead_letters = self.dead_letters().await?;
+        let now = Utc::now();
+
+        let mut requeued = Vec::new();
+        let mut dbtx = self.db.begin_transaction().await;
+        for mut delivery in dead_letters {
+            if let Some(ids) = delivery_ids {
+                if !ids.contains(&delivery.delivery_id) {
+                    continue;
+                }
+            }
+
+            delivery.attempts = 0;
+            delivery.next_attempt_at = now;
+
+            dbtx.remove_entry(&WebhookDeadLetterKey {
+                delivery_id: delivery.delivery_id.clone(),
+            })
+            .await;
+            dbtx.insert_entry(
+                &WebhookOutboxKey {
+                    delivery_id: delivery.delivery_id.clone(),
+                },
+                &delivery.to_record()?,
+            )
+            .await;
+            log_delivery(&mut dbtx, &delivery, DeliveryState::Pending).await?;
+            requeued.push(delivery);
+        }
+        dbtx.commit_tx_result()
+            .await
+            .map_err(|e| anyhow!("Failed to requeue webhook deliveries: {:?}", e))?;
+
+        Ok(requeued)
+    }
+
+    /// Delivery history of an endpoint, newest first
+    pub async fn delivery_log(
+        &self,
+        endpoint_id: &str,
+        query: &DeliveryLogQuery,
+    ) -> Result<Vec<WebhookDeliveryLogEntry>> {
+        let mut dbtx = self.db.begin_transaction_nc().await;
+        let records = dbtx
+            .find_by_prefix(&WebhookDeliveryLogEndpointPrefix {
+                endpoint_id: endpoint_id.to_string(),
+            })
+            .await
+            .map(|(_, record)| record)
+            .collect::<Vec<_>>()
+            .await;
+
+        let mut entries = records
+            .into_iter()
+            .filter_map(|record| decode_log_entry(&record))
+            .filter(|entry| query.matches(entry))
+            .collect::<Vec<_>>();
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.delivery.created_at));
+        if let Some(limit) = query.limit {
+            entries.truncate(limit);
+        }
+        Ok(entries)
+    }
+
+    /// Drop finished history entries last updated before the cutoff, pending
+    /// deliveries are always kept. Returns the number of removed entries.
+    pub async fn prune_delivery_log(&self, cutoff: DateTime<Utc>) -> Result<usize> {
+        let mut dbtx = self.db.begin_transaction().await;
+        let entries = dbtx
+            .find_by_prefix(&WebhookDeliveryLogKeyPrefix)
+            .await
+            .collect::<Vec<_>>()
+            .await;
+
+        let mut removed = 0;
+        for (key, record) in entries {
+            let Some(entry) = decode_log_entry(&record) else {
+                continue;
+            };
+            if entry.state != DeliveryState::Pending && entry.updated_at < cutoff {
+                dbtx.remove_entry(&key).await;
+                removed += 1;
+            }
+        }
+        dbtx.commit_tx_result()
+            .await
+            .map_err(|e| anyhow!("Failed to prune webhook delivery log: {:?}", e))?;
+
+        Ok(removed)
+    }
+}
+
+async fn log_delivery(
+    dbtx: &mut DatabaseTransaction<'_, Committable>,
+    delivery: &WebhookDelivery,
+    state: DeliveryState,
+) -> Result<()> {
+    let entry = WebhookDeliveryLogEntry {
+        state,
+        updated_at: Utc::now(),
+        delivery: delivery.clone(),
+    };
+    dbtx.insert_entry(
+        &WebhookDeliveryLogKey {
+            endpoint_id: delivery.endpoint_id.clone(),
+            delivery_id: delivery.delivery_id.clone(),
+        },
+        &WebhookDeliveryRecord {
+            json: serde_json::to_string(&entry)?,
+        },
+    )
+    .await;
+    Ok(())
+}
+
+fn decode_log_entry(record: &WebhookDeliveryRecord) -> Option<WebhookDeliveryLogEntry> {
+    match serde_json::from_str::<WebhookDeliveryLogEntry>(&record.json) {
+        Ok(entry) => Some(entry),
+        Err(e) => {
+            warn!(error = ?e, "Skipping undecodable webhook delivery log entry");
+            None
+        }
+    }
+}
+
+fn decode_sorted(records: Vec<WebhookDeliveryRecord>) -> Vec<WebhookDelivery> {
+    let mut deliveries = records
+        .into_iter()
+        .filter_map(
+            |record| match serde_json::from_str::<WebhookDelivery>(&record.json) {
+                Ok(delivery) => Some(delivery),
+                Err(e) => {
+                    warn!(error = ?e, "Skipping undecodable webhook delivery record");
+                    None
+                }
+            },
+        )
+        .collect::<Vec<_>>();
+    deliveries.sort_by_key(|d| d.created_at);
+    deliveries
+}
@@ -1,9 +1,17 @@
 #![allow(clippy::unwrap_used)]
 
+use std::sync::Arc;
+
 use chrono::Utc;
+use fedimint_core::config::FederationId;
+use fedimint_core::core::OperationId;
 use serde_json::json;
+use tempfile::TempDir;
 
-use crate::webhooks::invoice::InvoiceWebhookEvent;
+use crate::core::tests::open_db;
+use crate::webhooks::invoice::{callback_event_id, InvoiceCallback, InvoiceWebhookEvent};
+use crate::webhooks::notifier::{WebhookConfig, WebhookNotifier, CALLBACK_ENDPOINT_ID};
+use crate::webhooks::outbox::WebhookOutbox;
 
 #[tokio::test]
 async fn test_webhook_event_serialization() {
@@ -24,3 +32,62 @@ async fn test_webhook_event_serialization() {
     assert!(serialized.contains("test-op-123"));
     assert!(serialized.contains("1000000"));
 }
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_callback_is_queued_in_outbox() {
+    let temp_dir = TempDir::new().unwrap();
+    let outbox = WebhookOutbox::new(open_db(&temp_dir).await);
+    let notifier = WebhookNotifier::new(WebhookConfig::default())
+        .unwrap()
+        .with_outbox(outbox.clone());
+
+    let callback = InvoiceCallback {
+        webhook_url: "https://example.com/callback".to_string(),
+        federation_id: FederationId::dummy(),
+        metadata: Some(json!({ "orderId": "order-1" })),
+        notifier: Arc::new(notifier),
+    };
+    let operation_id = OperationId::new_random();
+    callback
+        .notify("invoice_expired", operation_id, "inv_1", 1_000)
+        .await;
+
+    // The first attempt can't reach the URL, the delivery stays pending
+    let pending = outbox.pending().await.unwrap();
+    assert_eq!(pending.len(), 1);
+    let delivery = &pending[0];
+    assert_eq!(delivery.endpoint_id, CALLBACK_ENDPOINT_ID);
+    assert_eq!(
+        delivery.callback_url.as_deref(),
+        Some("https://example.com/callback")
+    );
+    assert_eq!(delivery.event_type, "invoice_expired");
+    assert_eq!(
+        delivery.event_id,
+        callback_event_id(operation_id, "invoice_expired")
+    );
+
+    let payload: serde_json::Value = serde_json::from_str(&delivery.payload).unwrap();
+    assert_eq!(payload["eventType"], "invoice_expired");
+    assert_eq!(payload["invoiceId"], "inv_1");
+    assert_eq!(payload["data"]["amountMsat"], 1_000);
+    assert_eq!(payload["data"]["metadata"]["orderId"], "order-1");
+}
+
+#[test]
+fn test_callback_event_id_is_stable_per_outcome() {
+    let operation_id = OperationId::new_random();
+
+    assert_eq!(
+        callback_event_id(operation_id, "invoice_paid"),
+        callback_event_id(operation_id, "invoice_paid")
+    );
+    assert_ne!(
+        callback_event_id(operation_id, "invoice_paid"),
+        callback_event_id(operation_id, "invoice_expired")
+    );
+    assert_ne!(
+        callback_event_id(operation_id, "invoice_paid"),
+        callback_event_id(OperationId::new_random(), "invoice_paid")
+    );
+}
@@ -2,3 +2,5 @@
 mod invoice_tests;
 #[cfg(test)]
 mod notifier_tests;
+#[cfg(test)]
+mod outbox_tests;
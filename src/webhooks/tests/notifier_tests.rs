@@ -1,9 +1,11 @@
 #![allow(clippy::unwrap_used)]
 use chrono::Utc;
-use serde_json::json;
 
 use crate::events::FmcdEvent;
-use crate::webhooks::notifier::{RetryConfig, WebhookConfig, WebhookEndpoint, WebhookNotifier};
+use crate::webhooks::notifier::{
+    RetryConfig, SignatureVerificationError, WebhookConfig, WebhookEndpoint, WebhookNotifier,
+    CALLBACK_ENDPOINT_ID, DEFAULT_SIGNATURE_TOLERANCE,
+};
 
 #[test]
 fn test_webhook_endpoint_creation() {
@@ -121,21 +123,22 @@ fn test_create_webhook_payload() {
     let notifier = WebhookNotifier::new(config).expect("Failed to create notifier");
 
     let event = FmcdEvent::PaymentSucceeded {
-        payment_id: "test-payment".to_string(),
+        operation_id: "test-payment".to_string(),
         federation_id: "test-fed".to_string(),
+        amount_msat: 1000,
         preimage: "test-preimage".to_string(),
-        fee_msat: 1000,
-        correlation_id: Some("test-correlation".to_string()),
         timestamp: Utc::now(),
     };
 
     let payload = notifier
-        .create_webhook_payload(&event)
+        .create_webhook_payload(&event, &event.event_id())
         .expect("Failed to create payload");
 
     assert_eq!(payload["type"], "payment_succeeded");
-    assert_eq!(payload["correlation_id"], "test-correlation");
-    assert!(payload["id"].is_string());
+    assert!(payload["correlation_id"].is_null());
+    // The id is the same however often it is asked for, so deliveries and
+    // their history agree on it
+    assert_eq!(payload["id"], event.event_id());
     assert!(payload["timestamp"].is_string());
     assert!(payload["data"].is_object());
 
@@ -195,16 +198,15 @@ fn test_sensitive_data_sanitization() {
 
     // Test with an event containing sensitive data
     let event = FmcdEvent::PaymentSucceeded {
-        payment_id: "test-payment".to_string(),
+        operation_id: "test-payment".to_string(),
         federation_id: "test-fed".to_string(),
+        amount_msat: 1000,
         preimage: "sensitive-preimage-data".to_string(),
-        fee_msat: 1000,
-        correlation_id: Some("test-correlation".to_string()),
         timestamp: Utc::now(),
     };
 
     let payload = notifier
-        .create_webhook_payload(&event)
+        .create_webhook_payload(&event, &event.event_id())
         .expect("Failed to create payload");
 
     // Verify that sensitive fields are redacted
@@ -214,9 +216,9 @@ fn test_sensitive_data_sanitization() {
     assert_eq!(data.get("preimage").unwrap(), "[REDACTED]");
 
     // Non-sensitive fields should remain
-    assert_eq!(data.get("payment_id").unwrap(), "test-payment");
+    assert_eq!(data.get("operation_id").unwrap(), "test-payment");
     assert_eq!(data.get("federation_id").unwrap(), "test-fed");
-    assert_eq!(data.get("fee_msat").unwrap(), 1000);
+    assert_eq!(data.get("amount_msat").unwrap(), 1000);
 }
 
 #[test]
@@ -234,6 +236,25 @@ fn test_debug_does_not_leak_secrets() {
     assert!(debug_str.contains("[REDACTED]"));
 }
 
+#[test]
+fn test_callback_endpoint_signs_with_callback_secret() {
+    let secret = "CallbackSecret456!@#WithEnoughEntropyForHMAC".to_string();
+    let config = WebhookConfig {
+        callback_secret: Some(secret.clone()),
+        ..Default::default()
+    };
+
+    let endpoint = config.callback_endpoint("https://example.com/callback");
+    assert_eq!(endpoint.id, CALLBACK_ENDPOINT_ID);
+    assert_eq!(endpoint.url, "https://example.com/callback");
+    assert_eq!(endpoint.secret, Some(secret.clone()));
+    assert!(endpoint.enabled);
+
+    let debug_str = format!("{:?}", config);
+    assert!(!debug_str.contains(&secret));
+    assert!(debug_str.contains("[REDACTED]"));
+}
+
 #[test]
 fn test_hmac_secret_validation() {
     let base_endpoint = WebhookEndpoint::new(
@@ -284,3 +305,134 @@ fn test_hmac_secret_validation() {
         .with_secret("MySecure123!Random@WordsWithGoodEntropy#".to_string())
         .is_ok());
 }
+
+#[test]
+fn test_runtime_config_update() {
+    let notifier =
+        WebhookNotifier::new(WebhookConfig::default()).expect("Failed to create notifier");
+
+    let mut config = notifier.config();
+    config.endpoints.push(
+        WebhookEndpoint::new("orders".to_string(), "https://example.com/hook".to_string()).unwrap(),
+    );
+    notifier.update_config(config);
+
+    let mut config = notifier.config();
+    assert!(config.endpoint("orders").is_some());
+    config.endpoint_mut("orders").unwrap().enabled = false;
+    notifier.update_config(config);
+    assert!(!notifier.config().endpoint("orders").unwrap().enabled);
+
+    let mut config = notifier.config();
+    assert!(config.remove_endpoint("orders").is_some());
+    assert!(config.remove_endpoint("orders").is_none());
+}
+
+#[test]
+fn test_endpoint_validate_checks_url_and_secret() {
+    let mut endpoint =
+        WebhookEndpoint::new("orders".to_string(), "https://example.com/hook".to_string()).unwrap();
+    assert!(endpoint.validate().is_ok());
+
+    endpoint.secret = Some("short".to_string());
+    assert!(endpoint.validate().is_err());
+
+    endpoint.secret = None;
+    endpoint.url = "http://127.0.0.1/hook".to_string();
+    assert!(endpoint.validate().is_err());
+}
+
+#[test]
+fn test_timestamped_signature_verification() {
+    let secret = "MySecureSecret123!@#WithEnoughEntropyForHMAC";
+    let payload = r#"{"type":"invoice_paid"}"#;
+    let delivery_id = "0b6f3c1e-delivery";
+    let now = Utc::now().timestamp();
+
+    let header = WebhookNotifier::timestamped_signature(payload, delivery_id, secret, now).unwrap();
+    assert!(header.starts_with(&format!("t={},v1=", now)));
+
+    assert_eq!(
+        WebhookNotifier::verify_timestamped_signature(
+            payload,
+            &header,
+            delivery_id,
+            secret,
+            DEFAULT_SIGNATURE_TOLERANCE,
+        ),
+        Ok(now)
+    );
+
+    // The signature covers the body and the delivery id
+    assert_eq!(
+        WebhookNotifier::verify_timestamped_signature(
+            r#"{"type":"invoice_expired"}"#,
+            &header,
+            delivery_id,
+            secret,
+            DEFAULT_SIGNATURE_TOLERANCE,
+        ),
+        Err(SignatureVerificationError::Mismatch)
+    );
+    assert_eq!(
+        WebhookNotifier::verify_timestamped_signature(
+            payload,
+            &header,
+            "other-delivery",
+            secret,
+            DEFAULT_SIGNATURE_TOLERANCE,
+        ),
+        Err(SignatureVerificationError::Mismatch)
+    );
+
+    // Any matching v1 signature is accepted, e.g. during secret rotation
+    let rotated = format!("{},v1={}", header, "00".repeat(32));
+    assert!(WebhookNotifier::verify_timestamped_signature(
+        payload,
+        &rotated,
+        delivery_id,
+        secret,
+        DEFAULT_SIGNATURE_TOLERANCE,
+    )
+    .is_ok());
+}
+
+#[test]
+fn test_timestamped_signature_rejects_stale_and_malformed() {
+    let secret = "MySecureSecret123!@#WithEnoughEntropyForHMAC";
+    let payload = r#"{"type":"invoice_paid"}"#;
+    let stale = Utc::now().timestamp() - 10 * 60;
+
+    let header = WebhookNotifier::timestamped_signature(payload, "d1", secret, stale).unwrap();
+    assert_eq!(
+        WebhookNotifier::verify_timestamped_signature(
+            payload,
+            &header,
+            "d1",
+            secret,
+            DEFAULT_SIGNATURE_TOLERANCE,
+        ),
+        Err(SignatureVerificationError::Expired)
+    );
+
+    for header in [
+        "",
+        "v1=abcd",
+        "t=123",
+        "t=abc,v1=abcd",
+        "t=123,v1=zz",
+        "garbage",
+    ] {
+        assert_eq!(
+            WebhookNotifier::verify_timestamped_signature(
+                payload,
+                header,
+                "d1",
+                secret,
+                DEFAULT_SIGNATURE_TOLERANCE,
+            ),
+            Err(SignatureVerificationError::Malformed),
+            "header {header:?} should be malformed"
+        );
+    }
+}
@@ -0,0 +1,191 @@
+#![allow(clippy::unwrap_used)]
+use std::time::Duration;
+
+use tempfile::TempDir;
+
+use crate::core::tests::open_db;
+use crate::webhooks::notifier::RetryConfig;
+use crate::webhooks::outbox::{DeliveryLogQuery, DeliveryState, WebhookDelivery, WebhookOutbox};
+
+async fn test_outbox(temp_dir: &TempDir) -> WebhookOutbox {
+    WebhookOutbox::new(open_db(temp_dir).await)
+}
+
+fn test_delivery(event_id: &str) -> WebhookDelivery {
+    WebhookDelivery::new(
+        "endpoint-1".to_string(),
+        event_id.to_string(),
+        "invoice_paid".to_string(),
+        r#"{"type":"invoice_paid"}"#.to_string(),
+    )
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_outbox_keeps_pending_until_acknowledged() {
+    let temp_dir = TempDir::new().unwrap();
+    let outbox = test_outbox(&temp_dir).await;
+
+    let first = test_delivery("event-1");
+    let second = test_delivery("event-2");
+    outbox.enqueue(&first).await.unwrap();
+    outbox.enqueue(&second).await.unwrap();
+
+    let pending = outbox.pending().await.unwrap();
+    assert_eq!(pending.len(), 2);
+    assert_eq!(pending[0].event_id, "event-1");
+
+    outbox.acknowledge(&first).await.unwrap();
+    let pending = outbox.pending().await.unwrap();
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].delivery_id, second.delivery_id);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_outbox_dead_letter_and_requeue() {
+    let temp_dir = TempDir::new().unwrap();
+    let outbox = test_outbox(&temp_dir).await;
+
+    let mut delivery = test_delivery("event-1");
+    outbox.enqueue(&delivery).await.unwrap();
+
+    delivery.attempts = 3;
+    delivery.last_error = Some("status 500".to_string());
+    outbox.dead_letter(&delivery).await.unwrap();
+
+    assert!(outbox.pending().await.unwrap().is_empty());
+    let dead_letters = outbox.dead_letters().await.unwrap();
+    assert_eq!(dead_letters.len(), 1);
+    assert_eq!(dead_letters[0].attempts, 3);
+
+    // Requeueing an unknown id leaves the dead-letter list alone
+    let requeued = outbox
+        .requeue(Some(&["unknown".to_string()]))
+        .await
+        .unwrap();
+    assert!(requeued.is_empty());
+    assert_eq!(outbox.dead_letters().await.unwrap().len(), 1);
+
+    let requeued = outbox.requeue(None).await.unwrap();
+    assert_eq!(requeued.len(), 1);
+    assert!(outbox.dead_letters().await.unwrap().is_empty());
+
+    let pending = outbox.pending().await.unwrap();
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].attempts, 0);
+    assert_eq!(pending[0].last_error.as_deref(), Some("status 500"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_reschedule_does_not_resurrect_acknowledged_delivery() {
+    let temp_dir = TempDir::new().unwrap();
+    let outbox = test_outbox(&temp_dir).await;
+
+    let mut delivery = test_delivery("event-1");
+    outbox.enqueue(&delivery).await.unwrap();
+    delivery.attempts = 1;
+    delivery.last_error = Some("status 500".to_string());
+    assert!(outbox.reschedule(&delivery).await.unwrap());
+    let current = outbox.get(&delivery.delivery_id).await.unwrap().unwrap();
+    assert_eq!(current.attempts, 1);
+
+    // A stale attempt finishing after the delivery was acknowledged
+    outbox.acknowledge(&delivery).await.unwrap();
+    delivery.attempts = 2;
+    assert!(!outbox.reschedule(&delivery).await.unwrap());
+    assert!(outbox.get(&delivery.delivery_id).await.unwrap().is_none());
+    assert!(outbox.pending().await.unwrap().is_empty());
+
+    let log = outbox
+        .delivery_log("endpoint-1", &DeliveryLogQuery::default())
+        .await
+        .unwrap();
+    assert_eq!(log.len(), 1);
+    assert_eq!(log[0].state, DeliveryState::Delivered);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_outbox_survives_reopen() {
+    let temp_dir = TempDir::new().unwrap();
+    let delivery = test_delivery("event-1");
+
+    {
+        let outbox = test_outbox(&temp_dir).await;
+        outbox.enqueue(&delivery).await.unwrap();
+    }
+
+    let outbox = test_outbox(&temp_dir).await;
+    let pending = outbox.pending().await.unwrap();
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].delivery_id, delivery.delivery_id);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_delivery_log_tracks_latest_state() {
+    let temp_dir = TempDir::new().unwrap();
+    let outbox = test_outbox(&temp_dir).await;
+
+    let mut delivered = test_delivery("event-1");
+    outbox.enqueue(&delivered).await.unwrap();
+    delivered.attempts = 1;
+    delivered.last_status = Some(200);
+    delivered.last_latency_ms = Some(12);
+    outbox.acknowledge(&delivered).await.unwrap();
+
+    let mut failed = test_delivery("event-2");
+    outbox.enqueue(&failed).await.unwrap();
+    failed.attempts = 3;
+    failed.last_status = Some(503);
+    failed.last_error = Some("status 503".to_string());
+    outbox.dead_letter(&failed).await.unwrap();
+
+    let log = outbox
+        .delivery_log("endpoint-1", &DeliveryLogQuery::default())
+        .await
+        .unwrap();
+    assert_eq!(log.len(), 2);
+    // Newest first
+    assert_eq!(log[0].delivery.event_id, "event-2");
+    assert_eq!(log[0].state, DeliveryState::DeadLettered);
+    assert_eq!(log[0].delivery.last_status, Some(503));
+    assert_eq!(log[1].state, DeliveryState::Delivered);
+    assert_eq!(log[1].delivery.last_latency_ms, Some(12));
+
+    let query = DeliveryLogQuery {
+        event_id: Some("event-1".to_string()),
+        ..Default::default()
+    };
+    let log = outbox.delivery_log("endpoint-1", &query).await.unwrap();
+    assert_eq!(log.len(), 1);
+    assert_eq!(log[0].delivery.attempts, 1);
+
+    assert!(outbox
+        .delivery_log("endpoint-2", &DeliveryLogQuery::default())
+        .await
+        .unwrap()
+        .is_empty());
+
+    // Finished entries are pruned, pending ones are kept
+    let pending = test_delivery("event-3");
+    outbox.enqueue(&pending).await.unwrap();
+    let removed = outbox
+        .prune_delivery_log(chrono::Utc::now() + chrono::Duration::seconds(1))
+        .await
+        .unwrap();
+    assert_eq!(removed, 2);
+    let log = outbox
+        .delivery_log("endpoint-1", &DeliveryLogQuery::default())
+        .await
+        .unwrap();
+    assert_eq!(log.len(), 1);
+    assert_eq!(log[0].state, DeliveryState::Pending);
+}
+
+#[test]
+fn test_retry_delay_backs_off_up_to_max() {
+    let config = RetryConfig::default();
+
+    assert_eq!(config.delay_after_attempt(1), Duration::from_millis(1000));
+    assert_eq!(config.delay_after_attempt(2), Duration::from_millis(2000));
+    assert_eq!(config.delay_after_attempt(3), Duration::from_millis(4000));
+    assert_eq!(config.delay_after_attempt(10), Duration::from_millis(30000));
+}
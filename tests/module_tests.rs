@@ -0,0 +1,58 @@
+use fedimint_core::core::ModuleKind;
+use fmcd::api::rest::admin::module::validate_args;
+use fmcd::error::ErrorCategory;
+
+fn args(args: &[&str]) -> Vec<String> {
+    args.iter().map(|arg| arg.to_string()).collect()
+}
+
+#[test]
+fn test_valid_module_commands_pass() {
+    let wallet = ModuleKind::from_static_str("wallet");
+    assert!(validate_args(&wallet, &args(&["get-consensus-block-count"])).is_ok());
+    assert!(validate_args(&wallet, &args(&["await-deposit", "--tweak-idx", "3"])).is_ok());
+
+    let ln = ModuleKind::from_static_str("ln");
+    assert!(validate_args(&ln, &args(&["lnurl", "list"])).is_ok());
+}
+
+#[test]
+fn test_bad_module_commands_are_validation_errors() {
+    let wallet = ModuleKind::from_static_str("wallet");
+    for bad in [
+        args(&["--help"]),
+        args(&["new-deposit-address", "-h"]),
+        args(&["--version"]),
+        args(&["help"]),
+        args(&["drain-wallet"]),
+        args(&["get-bitcoin-rpc-kind", "not-a-peer"]),
+        args(&["await-deposit", "--unknown"]),
+        args(&[]),
+    ] {
+        let err = validate_args(&wallet, &bad).unwrap_err();
+        assert!(
+            matches!(err.category, ErrorCategory::ValidationError),
+            "{:?}",
+            bad
+        );
+    }
+
+    let mint = ModuleKind::from_static_str("mint");
+    let err = validate_args(&mint, &args(&["reissue", "not-notes"])).unwrap_err();
+    assert!(matches!(err.category, ErrorCategory::ValidationError));
+}
+
+#[test]
+fn test_unknown_module_kind_is_rejected() {
+    let kind = ModuleKind::from_static_str("meta");
+    let err = validate_args(&kind, &args(&["get"])).unwrap_err();
+    assert!(matches!(err.category, ErrorCategory::ValidationError));
+}
+
+#[test]
+fn test_ln_pay_is_rejected() {
+    let ln = ModuleKind::from_static_str("ln");
+    let err = validate_args(&ln, &args(&["pay", "lnbc1invoice"])).unwrap_err();
+    assert!(matches!(err.category, ErrorCategory::ValidationError));
+    assert!(err.message.contains("/v2/ln/pay"));
+}
@@ -0,0 +1,185 @@
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+
+use fmcd::api::rate_limit::{Limited, RateLimiter, RouteClass};
+use fmcd::observability::{RateLimitConfig, TokenBucketConfig};
+
+fn ip(ip: &str) -> Option<IpAddr> {
+    Some(ip.parse().unwrap())
+}
+
+fn limiter() -> RateLimiter {
+    RateLimiter::new(RateLimitConfig {
+        read: TokenBucketConfig {
+            requests_per_sec: 1.0,
+            burst: 3,
+        },
+        spend: TokenBucketConfig {
+            requests_per_sec: 0.5,
+            burst: 1,
+        },
+        max_in_flight_spends: 2,
+        ..Default::default()
+    })
+}
+
+#[test]
+fn test_route_classes() {
+    assert_eq!(RouteClass::of_path("/v2/ln/pay"), RouteClass::Spend);
+    assert_eq!(
+        RouteClass::of_path("/v2/onchain/withdraw/"),
+        RouteClass::Spend
+    );
+    assert_eq!(RouteClass::of_path("/v2/mint/spend"), RouteClass::Spend);
+    assert_eq!(
+        RouteClass::of_path("/v2/admin/approvals/abc/approve"),
+        RouteClass::Spend
+    );
+    assert_eq!(
+        RouteClass::of_path("/v2/onchain/withdraw/quote"),
+        RouteClass::Read
+    );
+    assert_eq!(RouteClass::of_path("/v2/ln/invoice"), RouteClass::Read);
+}
+
+#[test]
+fn test_buckets_refill_and_are_separate_per_client() {
+    let limiter = limiter();
+    let now = Instant::now();
+
+    for _ in 0..3 {
+        assert!(limiter
+            .acquire(Some("key"), ip("10.0.0.1"), RouteClass::Read, now)
+            .is_ok());
+    }
+    assert_eq!(
+        limiter
+            .acquire(Some("key"), ip("10.0.0.1"), RouteClass::Read, now)
+            .unwrap_err(),
+        Limited::Budget {
+            class: RouteClass::Read,
+            retry_after_secs: 1
+        }
+    );
+    // The key is limited from any IP, the IP with any key
+    assert!(limiter
+        .acquire(Some("key"), ip("10.0.0.2"), RouteClass::Read, now)
+        .is_err());
+    assert!(limiter
+        .acquire(Some("other"), ip("10.0.0.1"), RouteClass::Read, now)
+        .is_err());
+    assert!(limiter
+        .acquire(Some("other"), ip("10.0.0.2"), RouteClass::Read, now)
+        .is_ok());
+
+    // Spends have their own budget
+    assert!(limiter
+        .acquire(Some("key"), ip("10.0.0.1"), RouteClass::Spend, now)
+        .is_ok());
+    assert_eq!(
+        limiter
+            .acquire(Some("key"), ip("10.0.0.1"), RouteClass::Spend, now)
+            .unwrap_err()
+            .retry_after_secs(),
+        2
+    );
+
+    let later = now + Duration::from_secs(1);
+    assert!(limiter
+        .acquire(Some("key"), ip("10.0.0.1"), RouteClass::Read, later)
+        .is_ok());
+    assert!(limiter
+        .acquire(Some("key"), ip("10.0.0.1"), RouteClass::Read, later)
+        .is_err());
+}
+
+#[test]
+fn test_in_flight_spends_are_capped() {
+    let limiter = limiter();
+    let now = Instant::now();
+
+    let first = limiter
+        .acquire(Some("a"), None, RouteClass::Spend, now)
+        .unwrap();
+    let _second = limiter
+        .acquire(Some("b"), None, RouteClass::Spend, now)
+        .unwrap();
+    assert_eq!(
+        limiter
+            .acquire(Some("c"), None, RouteClass::Spend, now)
+            .unwrap_err(),
+        Limited::InFlight
+    );
+    // Reads aren't capped
+    assert!(limiter
+        .acquire(Some("c"), None, RouteClass::Read, now)
+        .is_ok());
+
+    drop(first);
+    assert!(limiter
+        .acquire(Some("c"), None, RouteClass::Spend, now)
+        .is_ok());
+}
+
+#[test]
+fn test_tracked_clients_are_bounded() {
+    let limiter = RateLimiter::new(RateLimitConfig {
+        max_tracked_clients: 2,
+        read: TokenBucketConfig {
+            requests_per_sec: 1.0,
+            burst: 1,
+        },
+        ..Default::default()
+    });
+    let now = Instant::now();
+
+    assert!(limiter
+        .acquire(None, ip("10.0.0.1"), RouteClass::Read, now)
+        .is_ok());
+    assert!(limiter
+        .acquire(
+            None,
+            ip("10.0.0.2"),
+            RouteClass::Read,
+            now + Duration::from_millis(10)
+        )
+        .is_ok());
+    // Tracking a third client forgets the one seen longest ago
+    assert!(limiter
+        .acquire(
+            None,
+            ip("10.0.0.3"),
+            RouteClass::Read,
+            now + Duration::from_millis(20)
+        )
+        .is_ok());
+    assert!(limiter
+        .acquire(
+            None,
+            ip("10.0.0.1"),
+            RouteClass::Read,
+            now + Duration::from_millis(30)
+        )
+        .is_ok());
+    assert!(limiter
+        .acquire(
+            None,
+            ip("10.0.0.3"),
+            RouteClass::Read,
+            now + Duration::from_millis(40)
+        )
+        .is_err());
+}
+
+#[test]
+fn test_disabled_limiter_allows_everything() {
+    let limiter = RateLimiter::new(RateLimitConfig::permissive());
+    let now = Instant::now();
+
+    for _ in 0..100 {
+        assert!(limiter
+            .acquire(Some("key"), None, RouteClass::Spend, now)
+            .unwrap()
+            .is_none());
+    }
+}